@@ -12,11 +12,15 @@ struct Parameter<'a> {
     name: &'a str,
 }
 
-static PARAMETERS: [Parameter; 4] = [
+static PARAMETERS: [Parameter; 5] = [
     Parameter {
         line: include_str!("rfc5424/with_structured_data.txt"),
         name: "with_structured_data",
     },
+    Parameter {
+        line: include_str!("rfc5424/with_many_structured_data.txt"),
+        name: "with_many_structured_data",
+    },
     Parameter {
         line: include_str!("rfc5424/with_structured_data_long_msg.txt"),
         name: "with_structured_data_long_message",
@@ -31,6 +35,7 @@ static PARAMETERS: [Parameter; 4] = [
     },
 ];
 
+/// Run with and without `--features smallvec` to compare how the params are gathered.
 fn parse_bench_rfc5424(c: &mut Criterion<CyclesPerByte>) {
     let mut group = c.benchmark_group("RFC5424");
     for param in &PARAMETERS {
@@ -46,9 +51,68 @@ fn parse_bench_rfc5424(c: &mut Criterion<CyclesPerByte>) {
     group.finish();
 }
 
+/// Short messages, where the time goes on the PRI, version and the rest of the header.
+static HEADERS: [Parameter; 3] = [
+    Parameter {
+        line: "<34>1 - - - - - -",
+        name: "rfc5424_nil",
+    },
+    Parameter {
+        line: "<165>1 2003-10-11T22:14:15.003Z host app 1234 ID47 - hi",
+        name: "rfc5424_short",
+    },
+    Parameter {
+        line: "<34>Oct 11 22:14:15 host su: hi",
+        name: "rfc3164_short",
+    },
+];
+
+fn parse_bench_headers(c: &mut Criterion<CyclesPerByte>) {
+    let mut group = c.benchmark_group("Header");
+    for param in &HEADERS {
+        let bytes = param.line.len().try_into().unwrap();
+
+        group.throughput(Throughput::Bytes(bytes));
+        group.bench_with_input(
+            BenchmarkId::new(param.name, bytes),
+            param.line,
+            |b, line| b.iter(|| syslog_loose::parse_message(line, Variant::Either)),
+        );
+    }
+    group.finish();
+}
+
+/// Each variant on a message of each protocol, to show the cost of `Either` trying
+/// RFC5424 first and of a message being sent with the other protocol.
+fn parse_bench_variants(c: &mut Criterion<CyclesPerByte>) {
+    let lines = [
+        ("rfc5424", include_str!("rfc5424/with_structured_data.txt")),
+        ("rfc3164", include_str!("rfc3164/with_procid.txt")),
+        ("rfc3164_with_year", include_str!("rfc3164/with_year.txt")),
+    ];
+    let variants = [
+        ("either", Variant::Either),
+        ("rfc5424", Variant::RFC5424),
+        ("rfc3164", Variant::RFC3164),
+    ];
+
+    let mut group = c.benchmark_group("Variant");
+    for (line_name, line) in lines.iter() {
+        group.throughput(Throughput::Bytes(line.len().try_into().unwrap()));
+        for (variant_name, variant) in variants.iter() {
+            group.bench_with_input(
+                BenchmarkId::new(*variant_name, line_name),
+                line,
+                |b, line| b.iter(|| syslog_loose::parse_message(line, *variant)),
+            );
+        }
+    }
+    group.finish();
+}
+
 criterion_group!(
     name = benches;
     config = Criterion::default().with_measurement(CyclesPerByte);
-    targets = parse_bench_rfc5424
+    targets = parse_bench_rfc5424, parse_bench_headers, parse_bench_variants
 );
 criterion_main!(benches);
@@ -2,7 +2,7 @@ extern crate syslog_loose;
 
 use chrono::prelude::*;
 use std::net::UdpSocket;
-use syslog_loose::Variant;
+use syslog_loose::{ParseOptions, Variant};
 
 fn resolve_year((month, _date, _hour, _min, _sec): syslog_loose::IncompleteDate) -> i32 {
     let now = Utc::now();
@@ -16,14 +16,17 @@ fn resolve_year((month, _date, _hour, _min, _sec): syslog_loose::IncompleteDate)
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     let socket = UdpSocket::bind("127.0.0.1:9000")?;
     println!("Listening on port 9000...");
+    let options = ParseOptions {
+        get_year: resolve_year,
+        ..ParseOptions::new(Variant::Either)
+    };
     let mut buf = [0u8; 2048];
     loop {
         let (data_read, _) = socket.recv_from(&mut buf)?;
-        let line = std::str::from_utf8(&buf[0..data_read])?;
-        println!("{}", line);
+        println!("{}", String::from_utf8_lossy(&buf[0..data_read]));
         println!(
             "{:#?}",
-            syslog_loose::parse_message_with_year(line, resolve_year, Variant::Either)
+            syslog_loose::parse_datagram(&buf[0..data_read], &options)?
         );
     }
 }
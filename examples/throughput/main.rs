@@ -0,0 +1,107 @@
+// Measures how many messages a second can be parsed, for each of the sample
+// corpora used by the benchmarks and each variant, to help size a collector.
+//
+//     cargo run --release --example throughput [FILE]
+//
+// If FILE is given its lines are measured as well.
+use std::time::{Duration, Instant};
+use syslog_loose::{ParseOptions, Parser, Variant};
+
+const CORPORA: [(&str, &str); 6] = [
+    (
+        "rfc5424 with structured data",
+        include_str!("../../benches/rfc5424/with_structured_data.txt"),
+    ),
+    (
+        "rfc5424 with many structured data",
+        include_str!("../../benches/rfc5424/with_many_structured_data.txt"),
+    ),
+    (
+        "rfc5424 long message",
+        include_str!("../../benches/rfc5424/with_structured_data_long_msg.txt"),
+    ),
+    (
+        "rfc5424 without structured data",
+        include_str!("../../benches/rfc5424/without_structured_data.txt"),
+    ),
+    (
+        "rfc3164 with procid",
+        include_str!("../../benches/rfc3164/with_procid.txt"),
+    ),
+    (
+        "rfc3164 with year",
+        include_str!("../../benches/rfc3164/with_year.txt"),
+    ),
+];
+
+const VARIANTS: [(&str, Variant); 3] = [
+    ("either", Variant::Either),
+    ("rfc5424", Variant::RFC5424),
+    ("rfc3164", Variant::RFC3164),
+];
+
+/// How long each measurement runs for.
+const RUN_FOR: Duration = Duration::from_millis(500);
+
+/// Parse the lines over and over for `RUN_FOR`, returning the messages per second.
+fn measure(lines: &[&str], variant: Variant) -> f64 {
+    let start = Instant::now();
+    let mut count = 0_u64;
+    while start.elapsed() < RUN_FOR {
+        for line in lines {
+            std::hint::black_box(syslog_loose::parse_message(
+                std::hint::black_box(line),
+                variant,
+            ));
+        }
+        count += lines.len() as u64;
+    }
+    count as f64 / start.elapsed().as_secs_f64()
+}
+
+/// Feed the lines as a stream to a [`Parser`], returning the messages per second.
+fn measure_stream(lines: &[&str]) -> f64 {
+    let mut stream = lines.join("\n").into_bytes();
+    stream.push(b'\n');
+
+    let start = Instant::now();
+    let mut count = 0_u64;
+    while start.elapsed() < RUN_FOR {
+        let mut parser = Parser::new(ParseOptions::default());
+        parser.feed(&stream);
+        while let Some(message) = parser.next_message() {
+            std::hint::black_box(message.ok());
+            count += 1;
+        }
+    }
+    count as f64 / start.elapsed().as_secs_f64()
+}
+
+fn report(name: &str, lines: &[&str]) {
+    print!("{:<36}", name);
+    for (_, variant) in VARIANTS {
+        print!("{:>12.0}", measure(lines, variant));
+    }
+    println!("{:>12.0}", measure_stream(lines));
+}
+
+fn main() {
+    print!("{:<36}", "messages/sec");
+    for (name, _) in VARIANTS {
+        print!("{:>12}", name);
+    }
+    println!("{:>12}", "parser");
+
+    for (name, line) in CORPORA {
+        report(name, &[line.trim_end()]);
+    }
+
+    if let Some(path) = std::env::args().nth(1) {
+        let file = std::fs::read_to_string(&path).expect("unable to read file");
+        let lines = file
+            .lines()
+            .filter(|line| !line.is_empty())
+            .collect::<Vec<_>>();
+        report(&path, &lines);
+    }
+}
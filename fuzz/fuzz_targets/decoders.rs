@@ -0,0 +1,72 @@
+//! Feed arbitrary bytes through each of the stream decoders, split into chunks
+//! at arbitrary points.
+#![no_main]
+use arbitrary::Arbitrary;
+use bytes::BytesMut;
+use libfuzzer_sys::fuzz_target;
+use syslog_loose::{
+    BytesDecoder, ControlChars, Framing, MessageReader, Oversize, ParseOptions, Parser,
+    SliceReader, Utf8Policy,
+};
+
+#[derive(Arbitrary, Debug)]
+struct Input<'a> {
+    framing: u8,
+    strict: bool,
+    oversize: u8,
+    control_chars: u8,
+    max_frame_len: u16,
+    chunk_len: u8,
+    data: &'a [u8],
+}
+
+fuzz_target!(|input: Input<'_>| {
+    let options = ParseOptions {
+        framing: match input.framing % 3 {
+            0 => Framing::NonTransparent,
+            1 => Framing::OctetCounting,
+            _ => Framing::Auto,
+        },
+        utf8: if input.strict {
+            Utf8Policy::Strict
+        } else {
+            Utf8Policy::Lossy
+        },
+        oversize: match input.oversize % 3 {
+            0 => Oversize::Error,
+            1 => Oversize::Drop,
+            _ => Oversize::Truncate,
+        },
+        control_chars: match input.control_chars % 3 {
+            0 => ControlChars::Preserve,
+            1 => ControlChars::Strip,
+            _ => ControlChars::Escape,
+        },
+        max_frame_len: usize::from(input.max_frame_len).max(1),
+        ..Default::default()
+    };
+    let chunk_len = usize::from(input.chunk_len).max(1);
+
+    let mut parser = Parser::new(options.clone()).with_parse_stats();
+    for chunk in input.data.chunks(chunk_len) {
+        parser.feed(chunk);
+        while parser.next_message().is_some() {}
+    }
+    parser.finish();
+    while parser.next_message().is_some() {}
+
+    let mut decoder = BytesDecoder::new(options.clone());
+    let mut buf = BytesMut::new();
+    for chunk in input.data.chunks(chunk_len) {
+        buf.extend_from_slice(chunk);
+        while decoder.decode(&mut buf).is_some() {}
+    }
+    while decoder.decode_eof(&mut buf).is_some() {}
+
+    // Every message is found whichever way the input is read.
+    let read = MessageReader::new(input.data, options.clone()).count();
+    let sliced = SliceReader::new(input.data, options.clone()).count();
+    if options.utf8 == Utf8Policy::Strict {
+        assert_eq!(read, sliced);
+    }
+});
@@ -0,0 +1,31 @@
+//! Parse arbitrary bytes as a single message with every entry point.
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+use syslog_loose::{ParseOptions, Variant, parse_datagram, parse_message, try_parse_message};
+
+fuzz_target!(|data: &[u8]| {
+    let input = String::from_utf8_lossy(data);
+
+    for variant in [Variant::Either, Variant::RFC3164, Variant::RFC5424] {
+        let message = parse_message(&input, variant);
+        // Whatever was parsed can be printed.
+        let _ = message.to_string();
+
+        if let Err(error) = try_parse_message(&input, variant) {
+            assert!(error.offset <= input.len());
+            let _ = error.to_string();
+        }
+
+        let options = ParseOptions::new(variant);
+        let (diagnosed, warnings) = options.parse_with_diagnostics(&input);
+        assert_eq!(diagnosed, message);
+        for warning in warnings {
+            let _ = warning.to_string();
+        }
+        if let Err(failure) = options.try_parse(&input) {
+            assert!(failure.error.offset <= input.len());
+        }
+        let _ = options.extract(&message);
+        let _ = parse_datagram(data, &options);
+    }
+});
@@ -0,0 +1,74 @@
+//! Print and encode arbitrary messages, then parse them back.
+#![no_main]
+use arbitrary::Arbitrary;
+use chrono::prelude::*;
+use libfuzzer_sys::fuzz_target;
+use syslog_loose::{
+    Framing, Message, MessageEncoder, Parser, ProcId, Protocol, StructuredElement, Variant,
+    decompose_pri, parse_message,
+};
+
+#[derive(Arbitrary, Debug)]
+struct Input<'a> {
+    pri: Option<u8>,
+    rfc5424: bool,
+    secs: Option<i32>,
+    hostname: Option<&'a str>,
+    appname: Option<&'a str>,
+    pid: Option<i32>,
+    procid: Option<&'a str>,
+    msgid: Option<&'a str>,
+    structured_data: Vec<(&'a str, Vec<(&'a str, &'a str)>)>,
+    msg: &'a str,
+}
+
+fuzz_target!(|input: Input<'_>| {
+    let (facility, severity) = match input.pri {
+        Some(pri) => decompose_pri(pri),
+        None => (None, None),
+    };
+    let message = Message {
+        protocol: if input.rfc5424 {
+            Protocol::RFC5424(1)
+        } else {
+            Protocol::RFC3164
+        },
+        facility,
+        severity,
+        timestamp: input
+            .secs
+            .and_then(|secs| Utc.timestamp_opt(secs.into(), 0).single())
+            .map(Into::into),
+        hostname: input.hostname,
+        appname: input.appname,
+        procid: input
+            .pid
+            .map(ProcId::PID)
+            .or_else(|| input.procid.map(ProcId::Name)),
+        msgid: input.msgid,
+        structured_data: input
+            .structured_data
+            .into_iter()
+            .map(|(id, params)| StructuredElement {
+                id,
+                params: params.into_iter().collect(),
+            })
+            .collect(),
+        msg: input.msg,
+    };
+
+    let text = message.to_string();
+    let _ = parse_message(&text, Variant::Either);
+
+    let mut buf = Vec::new();
+    for framing in [Framing::NonTransparent, Framing::OctetCounting] {
+        MessageEncoder::new(framing).encode(&message, &mut buf);
+    }
+    let owned: Message<String> = message.into();
+    MessageEncoder::new(Framing::OctetCounting).encode(&owned, &mut buf);
+
+    let mut parser = Parser::new(Default::default());
+    parser.feed(&buf);
+    parser.finish();
+    while parser.next_message().is_some() {}
+});
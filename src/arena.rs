@@ -0,0 +1,44 @@
+//! Copying messages into storage that is freed all at once.
+use crate::message::Message;
+
+impl<'a> Message<&'a str> {
+    /// Copy each of the string fields with `copy`, which would typically allocate
+    /// them in an arena. A batch of messages copied into the same arena can then be
+    /// freed together rather than dropping a `String` for every field.
+    ///
+    /// The structured data elements are still held in a `Vec`.
+    pub fn copy_strings<'b, F>(self, copy: F) -> Message<&'b str>
+    where
+        F: FnMut(&'a str) -> &'b str,
+    {
+        self.map(copy)
+    }
+}
+
+#[cfg(feature = "bumpalo")]
+impl<S: AsRef<str> + Ord + PartialEq + Clone> Message<S> {
+    /// Copy the message into `arena`. See [`Message::copy_strings`].
+    pub fn in_arena<'b>(&self, arena: &'b bumpalo::Bump) -> Message<&'b str> {
+        self.to_borrowed().copy_strings(|s| &*arena.alloc_str(s))
+    }
+}
+
+#[cfg(all(test, feature = "bumpalo"))]
+mod tests {
+    use crate::{Variant, parse_message};
+
+    #[test]
+    fn copy_into_arena() {
+        let arena = bumpalo::Bump::new();
+        let line = String::from(
+            "<34>1 2003-10-11T22:14:15.003Z mymachine su - ID47 [meta seq=\"1\"] message",
+        );
+        let message = parse_message(&line, Variant::RFC5424).in_arena(&arena);
+        drop(line);
+
+        assert_eq!(message.hostname, Some("mymachine"));
+        assert_eq!(message.structured_data[0].params[0], ("seq", "1"));
+        assert_eq!(message.msg, "message");
+        assert!(arena.allocated_bytes() > 0);
+    }
+}
@@ -0,0 +1,120 @@
+//! Normalising the logs of Aruba and HPE switches. AOS-S can put an event number
+//! before the mnemonic, as in `00076 ports: port 1 is now on-line`, and AOS-CX
+//! sends `Event|ID|severity|module|slot|text` bodies. Both put the year after the
+//! time, so parse them with [`ParseOptions::year_after_time`](crate::ParseOptions::year_after_time)
+//! set.
+use crate::message::Message;
+
+/// A log from an Aruba switch.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ArubaEvent<'a> {
+    /// The event number from AOS-S, or the event ID from AOS-CX.
+    pub event_id: Option<u32>,
+    /// The part of the switch that logged the message, such as `ports`. This is
+    /// a better appname than the one in the header.
+    pub mnemonic: &'a str,
+    /// The severity from AOS-CX, such as `LOG_INFO`.
+    pub severity: Option<&'a str>,
+    /// The slot from AOS-CX, such as `1/1`.
+    pub slot: Option<&'a str>,
+    /// The text following the mnemonic.
+    pub text: &'a str,
+}
+
+/// Whether a message has the marks of an Aruba switch log - an AOS-CX body, or an
+/// AOS-S event number taken as the appname. This is quicker than [`Message::aruba`].
+pub(crate) fn has_signature(appname: Option<&str>, msg: &str) -> bool {
+    msg.starts_with("Event|")
+        || appname.is_some_and(|appname| {
+            !appname.is_empty() && appname.bytes().all(|b| b.is_ascii_digit())
+        })
+}
+
+impl<S: AsRef<str> + Ord + PartialEq + Clone> Message<S> {
+    /// Parse the message as an Aruba switch log. Returns None unless the body is
+    /// in the AOS-CX form, or the appname is an AOS-S event number.
+    pub fn aruba(&self) -> Option<ArubaEvent<'_>> {
+        let appname = self.appname.as_ref()?.as_ref();
+        let msg = self.msg.as_ref();
+
+        if let Some(fields) = msg.strip_prefix("Event|") {
+            let mut fields = fields.splitn(5, '|');
+            return Some(ArubaEvent {
+                event_id: Some(fields.next()?.parse().ok()?),
+                severity: Some(fields.next()?),
+                mnemonic: fields.next()?,
+                slot: Some(fields.next()?),
+                text: fields.next()?,
+            });
+        }
+
+        // RFC3164 parsing takes an AOS-S event number to be the appname.
+        let event_id = appname.parse().ok()?;
+        let (mnemonic, text) = msg.split_once(": ")?;
+        if mnemonic.is_empty() || mnemonic.contains(' ') {
+            return None;
+        }
+        Some(ArubaEvent {
+            event_id: Some(event_id),
+            mnemonic,
+            severity: None,
+            slot: None,
+            text,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ParseOptions, Variant};
+    use chrono::prelude::*;
+
+    fn parse(input: &str) -> Message<&str> {
+        ParseOptions {
+            year_after_time: true,
+            ..ParseOptions::new(Variant::RFC3164)
+        }
+        .parse(input)
+    }
+
+    #[test]
+    fn parse_aos_s() {
+        let message = parse("<14>Oct 11 22:14:15 2023 switch1 00076 ports: port 1 is now on-line");
+        assert_eq!(message.timestamp.unwrap().year(), 2023);
+        assert_eq!(message.hostname, Some("switch1"));
+
+        assert_eq!(
+            message.aruba().unwrap(),
+            ArubaEvent {
+                event_id: Some(76),
+                mnemonic: "ports",
+                severity: None,
+                slot: None,
+                text: "port 1 is now on-line",
+            }
+        );
+    }
+
+    #[test]
+    fn parse_aos_cx() {
+        let message = parse(
+            "<14>Oct 11 22:14:15 2023 switch1 hpe-sysmond[1234]: Event|2601|LOG_INFO|AMM|1/1|Fan 1 is OK",
+        );
+        let event = message.aruba().unwrap();
+
+        assert_eq!(event.event_id, Some(2601));
+        assert_eq!(event.mnemonic, "AMM");
+        assert_eq!(event.severity, Some("LOG_INFO"));
+        assert_eq!(event.slot, Some("1/1"));
+        assert_eq!(event.text, "Fan 1 is OK");
+    }
+
+    #[test]
+    fn not_aruba() {
+        let message = parse("<14>Oct 11 22:14:15 2023 10.0.0.5 ports: port 1 is now on-line");
+        // Already has the mnemonic as the appname.
+        assert_eq!(message.appname, Some("ports"));
+        assert_eq!(message.aruba(), None);
+    }
+}
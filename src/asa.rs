@@ -0,0 +1,130 @@
+//! Recognising Cisco ASA and Firepower Threat Defense message IDs, and pulling the
+//! fields out of the most common connection messages.
+use crate::{cisco::CiscoMnemonic, pattern::Pattern};
+use std::{collections::BTreeMap, sync::OnceLock};
+
+/// Patterns for the messages logged for each connection and translation.
+const TEMPLATES: &[(u32, &str)] = &[
+    (
+        302013,
+        "Built %{WORD:direction} %{WORD:protocol} connection %{INT:connection_id} for %{DATA:src_interface}:%{IP:src_ip}/%{INT:src_port} (%{IP:src_mapped_ip}/%{INT:src_mapped_port})%{DATA} to %{DATA:dst_interface}:%{IP:dst_ip}/%{INT:dst_port} (%{IP:dst_mapped_ip}/%{INT:dst_mapped_port})%{GREEDYDATA}",
+    ),
+    (
+        302014,
+        "Teardown %{WORD:protocol} connection %{INT:connection_id} for %{DATA:src_interface}:%{IP:src_ip}/%{INT:src_port}%{DATA} to %{DATA:dst_interface}:%{IP:dst_ip}/%{INT:dst_port}%{DATA} duration %{NOTSPACE:duration} bytes %{INT:bytes} %{GREEDYDATA:reason}",
+    ),
+    (
+        302015,
+        "Built %{WORD:direction} %{WORD:protocol} connection %{INT:connection_id} for %{DATA:src_interface}:%{IP:src_ip}/%{INT:src_port} (%{IP:src_mapped_ip}/%{INT:src_mapped_port})%{DATA} to %{DATA:dst_interface}:%{IP:dst_ip}/%{INT:dst_port} (%{IP:dst_mapped_ip}/%{INT:dst_mapped_port})%{GREEDYDATA}",
+    ),
+    (
+        302016,
+        "Teardown %{WORD:protocol} connection %{INT:connection_id} for %{DATA:src_interface}:%{IP:src_ip}/%{INT:src_port}%{DATA} to %{DATA:dst_interface}:%{IP:dst_ip}/%{INT:dst_port}%{DATA} duration %{NOTSPACE:duration} bytes %{INT:bytes}%{GREEDYDATA}",
+    ),
+    (
+        106023,
+        "Deny %{WORD:protocol} src %{DATA:src_interface}:%{IP:src_ip}/%{INT:src_port} dst %{DATA:dst_interface}:%{IP:dst_ip}/%{INT:dst_port}%{DATA} by access-group \"%{DATA:acl}\"%{GREEDYDATA}",
+    ),
+    (
+        305011,
+        "Built %{WORD:translation} %{WORD:protocol} translation from %{DATA:src_interface}:%{IP:src_ip}/%{INT:src_port} to %{DATA:dst_interface}:%{IP:dst_ip}/%{INT:dst_port}%{GREEDYDATA}",
+    ),
+    (
+        305012,
+        "Teardown %{WORD:translation} %{WORD:protocol} translation from %{DATA:src_interface}:%{IP:src_ip}/%{INT:src_port} to %{DATA:dst_interface}:%{IP:dst_ip}/%{INT:dst_port} duration %{NOTSPACE:duration}%{GREEDYDATA}",
+    ),
+];
+
+fn templates() -> &'static [(u32, Pattern)] {
+    static COMPILED: OnceLock<Vec<(u32, Pattern)>> = OnceLock::new();
+    COMPILED.get_or_init(|| {
+        TEMPLATES
+            .iter()
+            .map(|(id, pattern)| (*id, Pattern::new(pattern).expect("valid ASA template")))
+            .collect()
+    })
+}
+
+impl<'a> CiscoMnemonic<'a> {
+    /// The message ID if this is an ASA or FTD message, such as 302013 for
+    /// `%ASA-6-302013`.
+    pub fn asa_message_id(&self) -> Option<u32> {
+        match self.facility {
+            "ASA" | "FTD" => self.mnemonic.parse().ok(),
+            _ => None,
+        }
+    }
+
+    /// The fields of an ASA message, for the built and teardown messages of
+    /// connections (302013 to 302016) and translations (305011 and 305012), and for
+    /// denials by an access group (106023). Returns None for other messages, or if
+    /// the text isn't in the expected form.
+    pub fn asa_fields(&self) -> Option<BTreeMap<&'static str, &'a str>> {
+        let id = self.asa_message_id()?;
+        let (_, pattern) = templates()
+            .iter()
+            .find(|(template_id, _)| *template_id == id)?;
+        pattern.matches(self.text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Variant, parse_message};
+
+    #[test]
+    fn built_connection() {
+        let message = parse_message(
+            "<166>Oct 11 2023 22:14:15: %ASA-6-302013: Built outbound TCP connection 1234 for outside:203.0.113.5/443 (203.0.113.5/443) to inside:192.168.1.2/51234 (198.51.100.7/51234)",
+            Variant::RFC3164,
+        );
+        let cisco = message.cisco().unwrap();
+        assert_eq!(cisco.asa_message_id(), Some(302013));
+
+        let fields = cisco.asa_fields().unwrap();
+        assert_eq!(fields["direction"], "outbound");
+        assert_eq!(fields["protocol"], "TCP");
+        assert_eq!(fields["connection_id"], "1234");
+        assert_eq!(fields["src_interface"], "outside");
+        assert_eq!(fields["src_port"], "443");
+        assert_eq!(fields["dst_ip"], "192.168.1.2");
+        assert_eq!(fields["dst_mapped_ip"], "198.51.100.7");
+    }
+
+    #[test]
+    fn teardown_and_deny() {
+        let message = parse_message(
+            "<166>Oct 11 2023 22:14:45: %ASA-6-302014: Teardown TCP connection 1234 for outside:203.0.113.5/443 to inside:192.168.1.2/51234 duration 0:00:30 bytes 5120 TCP FINs",
+            Variant::RFC3164,
+        );
+        let fields = message.cisco().unwrap().asa_fields().unwrap();
+        assert_eq!(fields["duration"], "0:00:30");
+        assert_eq!(fields["bytes"], "5120");
+        assert_eq!(fields["reason"], "TCP FINs");
+
+        let message = parse_message(
+            r#"<164>Oct 11 2023 22:14:45: %ASA-4-106023: Deny tcp src outside:203.0.113.5/1234 dst inside:192.168.1.2/80 by access-group "outside_in" [0x0, 0x0]"#,
+            Variant::RFC3164,
+        );
+        let fields = message.cisco().unwrap().asa_fields().unwrap();
+        assert_eq!(fields["acl"], "outside_in");
+        assert_eq!(fields["dst_port"], "80");
+    }
+
+    #[test]
+    fn other_messages() {
+        let message = parse_message(
+            "<166>Oct 11 2023 22:14:15: %ASA-6-605005: Login permitted from 10.0.0.1/1234 to inside:10.0.0.2/ssh for user admin",
+            Variant::RFC3164,
+        );
+        let cisco = message.cisco().unwrap();
+        assert_eq!(cisco.asa_message_id(), Some(605005));
+        assert_eq!(cisco.asa_fields(), None);
+
+        let message = parse_message(
+            "<189>Oct 11 22:14:15 router1 %SYS-5-CONFIG_I: Configured from console by admin",
+            Variant::RFC3164,
+        );
+        assert_eq!(message.cisco().unwrap().asa_message_id(), None);
+    }
+}
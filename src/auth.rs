@@ -0,0 +1,177 @@
+//! Recognising the authentication messages logged by sshd, sudo and PAM.
+use crate::{message::Message, pattern::PatternSet};
+use std::{net::IpAddr, sync::OnceLock};
+
+/// The patterns for each event, in the order they are tried. The PAM patterns apply
+/// to any appname since they are logged by whichever program uses PAM.
+const PATTERNS: &[(Option<&str>, &str, &str)] = &[
+    (
+        Some("sshd"),
+        "accepted",
+        "Accepted %{NOTSPACE:method} for %{NOTSPACE:user} from %{IP:ip} port %{INT:port}%{GREEDYDATA}",
+    ),
+    (
+        Some("sshd"),
+        "failed",
+        "Failed %{NOTSPACE:method} for invalid user %{NOTSPACE:user} from %{IP:ip} port %{INT:port}%{GREEDYDATA}",
+    ),
+    (
+        Some("sshd"),
+        "failed",
+        "Failed %{NOTSPACE:method} for %{NOTSPACE:user} from %{IP:ip} port %{INT:port}%{GREEDYDATA}",
+    ),
+    (
+        Some("sshd"),
+        "invalid_user",
+        "Invalid user %{NOTSPACE:user} from %{IP:ip} port %{INT:port}%{GREEDYDATA}",
+    ),
+    (
+        Some("sshd"),
+        "invalid_user",
+        "Invalid user %{NOTSPACE:user} from %{IP:ip}",
+    ),
+    (
+        Some("sudo"),
+        "sudo",
+        "%{NOTSPACE:user} : %{DATA}USER=%{NOTSPACE:target_user} ; COMMAND=%{GREEDYDATA:command}",
+    ),
+    (
+        None,
+        "session_opened",
+        "pam_unix(%{DATA:service}:session): session opened for user %{DATA:user}(uid=%{INT}) by%{GREEDYDATA}",
+    ),
+    (
+        None,
+        "session_opened",
+        "pam_unix(%{DATA:service}:session): session opened for user %{NOTSPACE:user}%{GREEDYDATA}",
+    ),
+    (
+        None,
+        "session_closed",
+        "pam_unix(%{DATA:service}:session): session closed for user %{NOTSPACE:user}",
+    ),
+];
+
+fn patterns() -> &'static PatternSet {
+    static COMPILED: OnceLock<PatternSet> = OnceLock::new();
+    COMPILED.get_or_init(|| {
+        let mut patterns = PatternSet::new();
+        for (appname, name, pattern) in PATTERNS {
+            patterns
+                .add(*appname, name, pattern)
+                .expect("valid auth pattern");
+        }
+        patterns
+    })
+}
+
+/// An authentication event.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AuthEvent<'a> {
+    /// What happened, one of `accepted`, `failed` and `invalid_user` from sshd,
+    /// `sudo`, and `session_opened` and `session_closed` from PAM.
+    pub event: &'static str,
+    pub user: Option<&'a str>,
+    pub source_ip: Option<IpAddr>,
+    pub port: Option<u16>,
+    /// The sshd authentication method, such as `password` or `publickey`.
+    pub method: Option<&'a str>,
+    /// The user that sudo ran the command as.
+    pub target_user: Option<&'a str>,
+    /// The command run by sudo.
+    pub command: Option<&'a str>,
+    /// The service that opened or closed a PAM session, such as `sshd` or `cron`.
+    pub service: Option<&'a str>,
+}
+
+impl<S: AsRef<str> + Ord + PartialEq + Clone> Message<S> {
+    /// Recognise an authentication event from sshd, sudo or PAM.
+    pub fn auth_event(&self) -> Option<AuthEvent<'_>> {
+        let extracted = patterns().extract(self)?;
+        let field = |name: &str| extracted.fields.get(name).copied();
+
+        Some(AuthEvent {
+            event: extracted.pattern,
+            user: field("user"),
+            source_ip: field("ip").and_then(|ip| ip.parse().ok()),
+            port: field("port").and_then(|port| port.parse().ok()),
+            method: field("method"),
+            target_user: field("target_user"),
+            command: field("command"),
+            service: field("service"),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Variant, parse_message};
+
+    fn event(input: &str) -> Option<AuthEvent<'_>> {
+        // Leak the message so the event can borrow it for the test.
+        let message = Box::leak(Box::new(parse_message(input, Variant::RFC3164)));
+        message.auth_event()
+    }
+
+    #[test]
+    fn sshd_events() {
+        assert_eq!(
+            event(
+                "<38>Oct 11 22:14:15 host sshd[123]: Accepted publickey for bob from 10.0.0.1 port 51234 ssh2: RSA SHA256:abc"
+            ),
+            Some(AuthEvent {
+                event: "accepted",
+                user: Some("bob"),
+                source_ip: Some("10.0.0.1".parse().unwrap()),
+                port: Some(51234),
+                method: Some("publickey"),
+                target_user: None,
+                command: None,
+                service: None,
+            })
+        );
+
+        let failed = event("<38>Oct 11 22:14:15 host sshd[123]: Failed password for invalid user admin from 10.0.0.1 port 2222 ssh2").unwrap();
+        assert_eq!(failed.event, "failed");
+        assert_eq!(failed.user, Some("admin"));
+
+        let invalid =
+            event("<38>Oct 11 22:14:15 host sshd[123]: Invalid user admin from 10.0.0.1 port 2222")
+                .unwrap();
+        assert_eq!(invalid.event, "invalid_user");
+        assert_eq!(invalid.port, Some(2222));
+    }
+
+    #[test]
+    fn sudo_command() {
+        let sudo = event("<85>Oct 11 22:14:15 host sudo:      bob : TTY=pts/0 ; PWD=/home/bob ; USER=root ; COMMAND=/usr/bin/systemctl restart nginx").unwrap();
+
+        assert_eq!(sudo.event, "sudo");
+        assert_eq!(sudo.user, Some("bob"));
+        assert_eq!(sudo.target_user, Some("root"));
+        assert_eq!(sudo.command, Some("/usr/bin/systemctl restart nginx"));
+    }
+
+    #[test]
+    fn pam_sessions() {
+        let opened = event("<86>Oct 11 22:14:15 host sshd[123]: pam_unix(sshd:session): session opened for user bob(uid=1000) by (uid=0)").unwrap();
+        assert_eq!(opened.event, "session_opened");
+        assert_eq!(opened.user, Some("bob"));
+        assert_eq!(opened.service, Some("sshd"));
+
+        let opened = event("<86>Oct 11 22:14:15 host CRON[99]: pam_unix(cron:session): session opened for user root by (uid=0)").unwrap();
+        assert_eq!(opened.user, Some("root"));
+
+        let closed = event(
+            "<86>Oct 11 22:14:15 host su[9]: pam_unix(su:session): session closed for user root",
+        )
+        .unwrap();
+        assert_eq!(closed.event, "session_closed");
+
+        assert_eq!(
+            event("<86>Oct 11 22:14:15 host sshd[123]: Connection closed"),
+            None
+        );
+    }
+}
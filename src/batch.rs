@@ -0,0 +1,129 @@
+//! Parsing many messages in one go.
+use crate::{error::ParseError, message::Message, options::ParseOptions};
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+
+///
+/// Parse each of the lines exactly, returning a result for every line in the same
+/// order they were given. Any error records the index of the line that failed so
+/// they can be reported after filtering out the successes.
+///
+/// # Arguments
+///
+/// * lines - the messages to parse, one per item.
+/// * options - the options used to parse every message.
+///
+pub fn parse_many<'a, I>(
+    lines: I,
+    options: &ParseOptions,
+) -> Vec<Result<Message<&'a str>, ParseError<'a>>>
+where
+    I: IntoIterator<Item = &'a str>,
+{
+    lines
+        .into_iter()
+        .enumerate()
+        .map(|(index, line)| {
+            options
+                .parse_exact(line)
+                .map_err(|err| ParseError { index, ..err })
+        })
+        .collect()
+}
+
+///
+/// Parse a slice of lines exactly. See [`parse_many`].
+///
+pub fn parse_batch<'a>(
+    lines: &[&'a str],
+    options: &ParseOptions,
+) -> Vec<Result<Message<&'a str>, ParseError<'a>>> {
+    parse_many(lines.iter().copied(), options)
+}
+
+///
+/// Parse a slice of lines exactly, spreading the work across the rayon thread pool.
+/// The results are in the same order as the lines. See [`parse_many`].
+///
+#[cfg(feature = "rayon")]
+pub fn parse_batch_parallel<'a>(
+    lines: &[&'a str],
+    options: &ParseOptions,
+) -> Vec<Result<Message<&'a str>, ParseError<'a>>> {
+    lines
+        .par_iter()
+        .enumerate()
+        .map(|(index, line)| {
+            options
+                .parse_exact(line)
+                .map_err(|err| ParseError { index, ..err })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Variant;
+
+    #[test]
+    fn parse_many_indexes_errors() {
+        let lines = vec![
+            "<34>1 2003-10-11T22:14:15.003Z mymachine su - ID47 - one",
+            "not a syslog message",
+            "<34>1 2003-10-11T22:14:15.003Z mymachine su - ID47 - three",
+        ];
+        let results = parse_batch(&lines, &ParseOptions::new(Variant::RFC5424));
+
+        assert_eq!(results.len(), 3);
+        assert_eq!(results[0].as_ref().unwrap().msg, "one");
+        assert_eq!(
+            results[1],
+            Err(ParseError {
+                index: 1,
+                input: "not a syslog message",
+                offset: 0,
+                field: crate::Field::Pri,
+                expected: "a priority such as <34>",
+                rule: crate::Rule {
+                    rfc: 5424,
+                    id: "HEADER/PRI",
+                    section: "6.2.1",
+                },
+            })
+        );
+        assert_eq!(results[2].as_ref().unwrap().msg, "three");
+    }
+
+    #[test]
+    fn parse_many_iterator() {
+        let log = "<34>Oct 11 22:14:15 mymachine su: one\n<34>Oct 11 22:14:15 mymachine su: two";
+        let messages = parse_many(log.lines(), &ParseOptions::new(Variant::RFC3164))
+            .into_iter()
+            .map(|result| result.unwrap().msg)
+            .collect::<Vec<_>>();
+
+        assert_eq!(messages, vec!["one", "two"]);
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn parse_batch_parallel_keeps_order() {
+        let lines = (0..1000)
+            .map(|idx| {
+                if idx % 7 == 0 {
+                    format!("not syslog {}", idx)
+                } else {
+                    format!("<34>Oct 11 22:14:15 mymachine su: {}", idx)
+                }
+            })
+            .collect::<Vec<_>>();
+        let lines = lines.iter().map(String::as_str).collect::<Vec<_>>();
+        let options = ParseOptions::new(Variant::RFC3164);
+
+        assert_eq!(
+            parse_batch_parallel(&lines, &options),
+            parse_batch(&lines, &options)
+        );
+    }
+}
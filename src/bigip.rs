@@ -0,0 +1,150 @@
+//! Normalising the logs of F5 BIG-IP devices. These have a `slot1/hostname` style
+//! hostname on chassis systems, the level where the tag is expected, and the
+//! process and a message code at the start of the body, as in
+//! `info tmm[12345]: 01070417:6: AUDIT - user admin`.
+use crate::{message::Message, pri::SyslogSeverity, procid::ProcId};
+
+const LEVELS: &[&str] = &[
+    "emerg", "alert", "crit", "err", "warning", "notice", "info", "debug",
+];
+
+/// The parts of a BIG-IP log.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BigIp<'a> {
+    /// The blade, such as `slot1`, if the hostname included one.
+    pub slot: Option<&'a str>,
+    /// The hostname without any slot.
+    pub host: Option<&'a str>,
+    /// The level, such as `info`, if it was sent in place of the tag.
+    pub level: Option<&'a str>,
+    /// The process that logged the message, such as `tmm` or `mcpd`.
+    pub module: &'a str,
+    pub pid: Option<i32>,
+    /// The message code, such as `01070417`, which identifies the message.
+    pub code: Option<&'a str>,
+    /// The severity that follows the message code.
+    pub severity: Option<SyslogSeverity>,
+    /// The text following the code.
+    pub text: &'a str,
+}
+
+/// Split `code:severity: ` off the front of `input`.
+fn code(input: &str) -> Option<(&str, SyslogSeverity, &str)> {
+    let (code, rest) = input.split_once(':')?;
+    if code.len() != 8 || !code.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return None;
+    }
+    let (severity, rest) = rest.split_once(':')?;
+    let severity = SyslogSeverity::from_int(severity.parse().ok()?)?;
+    Some((code, severity, rest.trim_start()))
+}
+
+/// Whether a message has the marks of a BIG-IP log - a level in place of the tag
+/// followed by `process[pid]:`, or a body starting with a message code. This is
+/// quicker than [`Message::bigip`].
+pub(crate) fn has_signature(appname: Option<&str>, msg: &str) -> bool {
+    (appname.is_some_and(|appname| LEVELS.contains(&appname)) && msg.contains("]: "))
+        || code(msg).is_some()
+}
+
+impl<S: AsRef<str> + Ord + PartialEq + Clone> Message<S> {
+    /// Parse the message as a BIG-IP log. Returns None unless either the level
+    /// takes the place of the tag, followed by `process[pid]:`, or the body starts
+    /// with a message code.
+    pub fn bigip(&self) -> Option<BigIp<'_>> {
+        let appname = self.appname.as_ref().map(AsRef::as_ref);
+        let msg = self.msg.as_ref();
+
+        let (level, module, pid, rest) = match appname {
+            Some(level) if LEVELS.contains(&level) => {
+                let (tag, rest) = msg.split_once(": ")?;
+                let (module, pid) = tag.strip_suffix(']')?.split_once('[')?;
+                (Some(level), module, pid.parse().ok(), rest)
+            }
+            Some(module) => {
+                let pid = match &self.procid {
+                    Some(ProcId::PID(pid)) => Some(*pid),
+                    _ => None,
+                };
+                code(msg)?;
+                (None, module, pid, msg)
+            }
+            None => return None,
+        };
+
+        let (code, severity, text) = match code(rest) {
+            Some((code, severity, text)) => (Some(code), Some(severity), text),
+            None => (None, None, rest),
+        };
+
+        let hostname = self.hostname.as_ref().map(AsRef::as_ref);
+        let (slot, host) = match hostname.and_then(|hostname| hostname.split_once('/')) {
+            Some((slot, host)) if slot.starts_with("slot") => (Some(slot), Some(host)),
+            _ => (None, hostname),
+        };
+
+        Some(BigIp {
+            slot,
+            host,
+            level,
+            module,
+            pid,
+            code,
+            severity,
+            text,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Variant, parse_message};
+
+    #[test]
+    fn parse_chassis() {
+        let message = parse_message(
+            "<182>Oct 11 22:14:15 slot1/bigip1 info tmm[12345]: 01070417:6: AUDIT - user admin - transaction #123",
+            Variant::RFC3164,
+        );
+
+        assert_eq!(
+            message.bigip().unwrap(),
+            BigIp {
+                slot: Some("slot1"),
+                host: Some("bigip1"),
+                level: Some("info"),
+                module: "tmm",
+                pid: Some(12345),
+                code: Some("01070417"),
+                severity: Some(SyslogSeverity::SEV_INFO),
+                text: "AUDIT - user admin - transaction #123",
+            }
+        );
+    }
+
+    #[test]
+    fn parse_without_level() {
+        let message = parse_message(
+            "<182>Oct 11 22:14:15 bigip1.example.com mcpd[5678]: 01070638:5: Pool /Common/p1 member /Common/10.0.0.1:80 monitor status down.",
+            Variant::RFC3164,
+        );
+        let bigip = message.bigip().unwrap();
+
+        assert_eq!(bigip.slot, None);
+        assert_eq!(bigip.host, Some("bigip1.example.com"));
+        assert_eq!(bigip.module, "mcpd");
+        assert_eq!(bigip.pid, Some(5678));
+        assert_eq!(bigip.code, Some("01070638"));
+        assert_eq!(bigip.severity, Some(SyslogSeverity::SEV_NOTICE));
+    }
+
+    #[test]
+    fn not_bigip() {
+        let message = parse_message(
+            "<34>Oct 11 22:14:15 mymachine su: 'su root' failed",
+            Variant::RFC3164,
+        );
+        assert_eq!(message.bigip(), None);
+    }
+}
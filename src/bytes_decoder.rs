@@ -0,0 +1,287 @@
+//! Decoding messages straight out of a `BytesMut` receive buffer without copying.
+use crate::{
+    error::{Error, InvalidUtf8},
+    framing::{FrameLocator, Step},
+    message::Message,
+    options::{ParseOptions, Utf8Policy, replacements},
+    stats::DecoderStats,
+};
+use bytes::{Buf, Bytes, BytesMut};
+use std::{borrow::Borrow, fmt, hash, ops::Deref};
+
+/// A string backed by a reference counted `Bytes` buffer. The fields of messages
+/// returned by [`BytesDecoder`] all point into the frame they were parsed from, so
+/// the frame is only freed once every field has been dropped.
+#[derive(Clone, Default)]
+pub struct BytesStr(Bytes);
+
+impl BytesStr {
+    /// Returns the underlying bytes.
+    pub fn into_bytes(self) -> Bytes {
+        self.0
+    }
+
+    pub fn as_str(&self) -> &str {
+        // The bytes are only ever taken from a validated str.
+        std::str::from_utf8(&self.0).unwrap_or_default()
+    }
+
+    /// Slice `s` out of `bytes` if it lies within it, otherwise copy it.
+    fn slice_of(bytes: &Bytes, s: &str) -> Self {
+        let range = bytes.as_ptr_range();
+        let sub = s.as_bytes().as_ptr_range();
+        if range.start <= sub.start && sub.end <= range.end {
+            BytesStr(bytes.slice_ref(s.as_bytes()))
+        } else {
+            BytesStr(Bytes::copy_from_slice(s.as_bytes()))
+        }
+    }
+}
+
+impl AsRef<str> for BytesStr {
+    fn as_ref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl Deref for BytesStr {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl Borrow<str> for BytesStr {
+    fn borrow(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl PartialEq for BytesStr {
+    fn eq(&self, other: &Self) -> bool {
+        self.0 == other.0
+    }
+}
+
+impl Eq for BytesStr {}
+
+impl PartialEq<str> for BytesStr {
+    fn eq(&self, other: &str) -> bool {
+        self.as_str() == other
+    }
+}
+
+impl PartialEq<&str> for BytesStr {
+    fn eq(&self, other: &&str) -> bool {
+        self.as_str() == *other
+    }
+}
+
+impl PartialOrd for BytesStr {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for BytesStr {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.0.cmp(&other.0)
+    }
+}
+
+impl hash::Hash for BytesStr {
+    fn hash<H: hash::Hasher>(&self, state: &mut H) {
+        self.as_str().hash(state)
+    }
+}
+
+impl fmt::Debug for BytesStr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self.as_str(), f)
+    }
+}
+
+impl fmt::Display for BytesStr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self.as_str(), f)
+    }
+}
+
+impl Message<&str> {
+    /// Convert the message to an owned one that keeps `buffer` alive rather than
+    /// copying each field, when the message was parsed from a str borrowed from
+    /// `buffer`. Any fields that don't lie within `buffer` are copied.
+    pub fn into_owned_with(self, buffer: &Bytes) -> Message<BytesStr> {
+        self.map(|field| BytesStr::slice_of(buffer, field))
+    }
+}
+
+/// Splits frames off the front of a `BytesMut` buffer and parses them without
+/// copying the payloads.
+///
+/// This follows the shape of a tokio `Decoder`: data is read into a `BytesMut`,
+/// then `decode` is called until it returns None, at which point more data is needed.
+/// Frames that are not valid UTF-8 are copied when using [`Utf8Policy::Lossy`].
+#[derive(Clone, Debug)]
+pub struct BytesDecoder {
+    options: ParseOptions,
+    locator: FrameLocator,
+    truncated: bool,
+}
+
+impl BytesDecoder {
+    pub fn new(options: ParseOptions) -> Self {
+        BytesDecoder {
+            locator: FrameLocator::new(&options),
+            options,
+            truncated: false,
+        }
+    }
+
+    /// The counts of what has been decoded so far.
+    pub fn stats(&self) -> DecoderStats {
+        self.locator.stats
+    }
+
+    /// Was the last frame returned cut short by [`Oversize::Truncate`](crate::Oversize::Truncate)?
+    pub fn was_truncated(&self) -> bool {
+        self.truncated
+    }
+
+    /// Split the next frame off the front of `buf`.
+    /// If `eof` is set, no more data is coming so a trailing unterminated frame is returned.
+    pub fn decode_frame(&mut self, buf: &mut BytesMut, eof: bool) -> Option<Result<Bytes, Error>> {
+        loop {
+            match self.locator.locate(buf, eof) {
+                Step::Frame { range, consumed } => {
+                    self.truncated = false;
+                    return Some(Ok(buf.split_to(consumed).freeze().slice(range)));
+                }
+                Step::Truncated { range, consumed } => {
+                    self.truncated = true;
+                    return Some(Ok(buf.split_to(consumed).freeze().slice(range)));
+                }
+                Step::Skip { consumed, error } => {
+                    buf.advance(consumed);
+                    if let Some(error) = error {
+                        return Some(Err(error.into()));
+                    }
+                }
+                Step::Incomplete => return None,
+            }
+        }
+    }
+
+    /// Split the next frame off the front of `buf` and parse it.
+    pub fn decode(&mut self, buf: &mut BytesMut) -> Option<Result<Message<BytesStr>, Error>> {
+        self.decode_message(buf, false)
+    }
+
+    /// Split the next frame off the front of `buf` and parse it, taking the remaining
+    /// data as the final frame if it isn't terminated.
+    pub fn decode_eof(&mut self, buf: &mut BytesMut) -> Option<Result<Message<BytesStr>, Error>> {
+        self.decode_message(buf, true)
+    }
+
+    fn decode_message(
+        &mut self,
+        buf: &mut BytesMut,
+        eof: bool,
+    ) -> Option<Result<Message<BytesStr>, Error>> {
+        loop {
+            let frame = match self.decode_frame(buf, eof)? {
+                Ok(frame) => frame,
+                Err(err) => return Some(Err(err)),
+            };
+
+            let raw = frame.clone();
+            let frame = match (std::str::from_utf8(&frame), self.options.utf8) {
+                (Ok(_), _) => frame,
+                (Err(err), Utf8Policy::Strict) => {
+                    return Some(Err(InvalidUtf8::new(&frame, err).into()));
+                }
+                (Err(_), Utf8Policy::Lossy) => {
+                    self.locator.stats.replacements += replacements(&frame);
+                    Bytes::from(String::from_utf8_lossy(&frame).into_owned())
+                }
+            };
+
+            // Checked above, or made valid by the lossy conversion.
+            let input = std::str::from_utf8(&frame).unwrap_or_default();
+            match self
+                .options
+                .parse_raw(&raw, input, &mut self.locator.last_variant)
+            {
+                Some((message, fallback)) => {
+                    if fallback {
+                        self.locator.stats.fallbacks += 1;
+                    }
+                    return Some(Ok(self
+                        .options
+                        .clean_msg(message, |message| message.into_owned_with(&frame))));
+                }
+                None => self.locator.stats.filtered += 1,
+            };
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{StructuredElement, Variant};
+
+    #[test]
+    fn decode_shares_buffer() {
+        let mut buf = BytesMut::from(
+            &b"<34>1 2003-10-11T22:14:15.003Z mymachine su - ID47 [id a=\"b\"] one\n<34>1 2003"[..],
+        );
+        let mut decoder = BytesDecoder::new(ParseOptions::new(Variant::RFC5424));
+        let message = decoder.decode(&mut buf).unwrap().unwrap();
+
+        assert_eq!(message.hostname.as_ref().unwrap(), "mymachine");
+        assert_eq!(message.msg, "one");
+        assert_eq!(
+            message.structured_data,
+            vec![StructuredElement {
+                id: BytesStr(Bytes::from_static(b"id")),
+                params: vec![(
+                    BytesStr(Bytes::from_static(b"a")),
+                    BytesStr(Bytes::from_static(b"b"))
+                )]
+            }]
+        );
+        assert!(decoder.decode(&mut buf).is_none());
+        assert_eq!(&buf[..], b"<34>1 2003");
+    }
+
+    #[test]
+    fn into_owned_with_shares_buffer() {
+        let buffer =
+            Bytes::from_static(b"<34>1 2003-10-11T22:14:15.003Z mymachine su - ID47 - one");
+        let input = std::str::from_utf8(&buffer).unwrap();
+        let mut message = ParseOptions::new(Variant::RFC5424).parse(input);
+        message.appname = Some("elsewhere");
+        let owned = message.into_owned_with(&buffer);
+
+        let range = buffer.as_ptr_range();
+        let hostname = owned.hostname.unwrap();
+        assert_eq!(hostname, "mymachine");
+        assert!(range.contains(&hostname.as_ptr()));
+        let appname = owned.appname.unwrap();
+        assert_eq!(appname, "elsewhere");
+        assert!(!range.contains(&appname.as_ptr()));
+    }
+
+    #[test]
+    fn decode_lossy_frame() {
+        let mut buf = BytesMut::from(&b"bad \xff byte"[..]);
+        let mut decoder = BytesDecoder::new(ParseOptions::default());
+
+        assert!(decoder.decode(&mut buf).is_none());
+        let message = decoder.decode_eof(&mut buf).unwrap().unwrap();
+        assert_eq!(message.msg, "bad \u{fffd} byte");
+        assert!(buf.is_empty());
+    }
+}
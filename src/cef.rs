@@ -0,0 +1,223 @@
+//! Parsing [Common Event Format](https://www.microfocus.com/documentation/arcsight/arcsight-smartconnectors/pdfdoc/cef-implementation-standard/cef-implementation-standard.pdf)
+//! messages carried in the body of a syslog message.
+use crate::message::Message;
+use std::borrow::Cow;
+
+/// A CEF event, `CEF:Version|Device Vendor|Device Product|Device Version|Device Event Class ID|Name|Severity|Extension`.
+///
+/// Escaped characters are replaced, so the fields only allocate if they contain
+/// escapes.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Cef<'a> {
+    pub version: u32,
+    pub device_vendor: Cow<'a, str>,
+    pub device_product: Cow<'a, str>,
+    pub device_version: Cow<'a, str>,
+    pub signature_id: Cow<'a, str>,
+    pub name: Cow<'a, str>,
+    /// Either 0 to 10, or one of Unknown, Low, Medium, High and Very-High.
+    pub severity: Cow<'a, str>,
+    /// The `key=value` extension fields in the order they were given.
+    pub extensions: Vec<(&'a str, Cow<'a, str>)>,
+}
+
+/// Replace each `\` followed by a character with the result of `escape`.
+fn unescape(s: &str, escape: fn(char) -> Option<char>) -> Cow<'_, str> {
+    if !s.contains('\\') {
+        return Cow::Borrowed(s);
+    }
+
+    let mut unescaped = String::with_capacity(s.len());
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some(next) => match escape(next) {
+                    Some(replaced) => unescaped.push(replaced),
+                    // Not a known escape, so leave it as it is.
+                    None => {
+                        unescaped.push('\\');
+                        unescaped.push(next);
+                    }
+                },
+                None => unescaped.push('\\'),
+            },
+            c => unescaped.push(c),
+        }
+    }
+    Cow::Owned(unescaped)
+}
+
+fn header_escape(c: char) -> Option<char> {
+    match c {
+        '|' | '\\' => Some(c),
+        _ => None,
+    }
+}
+
+fn extension_escape(c: char) -> Option<char> {
+    match c {
+        '=' | '\\' => Some(c),
+        'n' => Some('\n'),
+        'r' => Some('\r'),
+        _ => None,
+    }
+}
+
+fn is_key_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || matches!(c, '_' | '.' | '-' | '[' | ']')
+}
+
+/// Split the extension into its `key=value` pairs. A key starts either at the start
+/// of the extension or after a space and ends at an unescaped `=`, values run up to
+/// the space before the next key.
+fn extensions(extension: &str) -> Vec<(&str, Cow<'_, str>)> {
+    let bytes = extension.as_bytes();
+    let mut keys = Vec::new();
+    let mut escaped = false;
+    for (pos, &b) in bytes.iter().enumerate() {
+        if escaped {
+            escaped = false;
+        } else if b == b'\\' {
+            escaped = true;
+        } else if b == b'=' {
+            let start = extension[..pos]
+                .rfind(|c| !is_key_char(c))
+                .map_or(0, |space| space + 1);
+            if start < pos && (start == 0 || bytes[start - 1] == b' ') {
+                keys.push((start, pos));
+            }
+        }
+    }
+
+    keys.iter()
+        .enumerate()
+        .map(|(idx, &(start, eq))| {
+            let end = keys.get(idx + 1).map_or(extension.len(), |next| next.0);
+            let value = extension[eq + 1..end].trim_end_matches(' ');
+            (&extension[start..eq], unescape(value, extension_escape))
+        })
+        .collect()
+}
+
+impl<'a> Cef<'a> {
+    /// Parse a CEF event, `input` must start with `CEF:`.
+    pub fn parse(input: &'a str) -> Option<Self> {
+        Self::parse_body(input.trim_start().strip_prefix("CEF:")?)
+    }
+
+    /// Parse the event following the `CEF:` prefix.
+    fn parse_body(body: &'a str) -> Option<Self> {
+        // Split on the unescaped pipes. The extension is everything after the 7th.
+        let mut fields = Vec::with_capacity(7);
+        let mut start = 0;
+        let mut escaped = false;
+        for (pos, c) in body.char_indices() {
+            if fields.len() == 7 {
+                break;
+            }
+            if escaped {
+                escaped = false;
+            } else if c == '\\' {
+                escaped = true;
+            } else if c == '|' {
+                fields.push(&body[start..pos]);
+                start = pos + 1;
+            }
+        }
+
+        if fields.len() < 7 {
+            return None;
+        }
+
+        let header = |idx: usize| unescape(fields[idx], header_escape);
+        Some(Cef {
+            version: fields[0].trim().parse().ok()?,
+            device_vendor: header(1),
+            device_product: header(2),
+            device_version: header(3),
+            signature_id: header(4),
+            name: header(5),
+            severity: header(6),
+            extensions: extensions(body[start..].trim_end()),
+        })
+    }
+
+    /// The value of the first extension field named `key`.
+    pub fn extension(&self, key: &str) -> Option<&str> {
+        self.extensions
+            .iter()
+            .find(|(name, _)| *name == key)
+            .map(|(_, value)| value.as_ref())
+    }
+}
+
+impl<S: AsRef<str> + Ord + PartialEq + Clone> Message<S> {
+    /// Parse the body of the message as a CEF event if it is one.
+    ///
+    /// RFC3164 parsing takes the `CEF` prefix to be the tag, so the message is also
+    /// checked as a CEF event when the appname is `CEF`.
+    pub fn cef(&self) -> Option<Cef<'_>> {
+        let msg = self.msg.as_ref();
+        match &self.appname {
+            Some(appname) if appname.as_ref() == "CEF" && !msg.starts_with("CEF:") => {
+                Cef::parse_body(msg)
+            }
+            _ => Cef::parse(msg),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Variant, parse_message};
+
+    #[test]
+    fn parse_cef() {
+        let cef = Cef::parse(
+            r"CEF:0|Security|threat\|manager|1.0|100|worm successfully stopped|10|src=10.0.0.1 dst=2.1.2.2 msg=Detected a\=b in C:\\temp spt=1232",
+        )
+        .unwrap();
+
+        assert_eq!(cef.version, 0);
+        assert_eq!(cef.device_vendor, "Security");
+        assert_eq!(cef.device_product, "threat|manager");
+        assert_eq!(cef.signature_id, "100");
+        assert_eq!(cef.name, "worm successfully stopped");
+        assert_eq!(cef.severity, "10");
+        assert_eq!(
+            cef.extensions,
+            vec![
+                ("src", Cow::Borrowed("10.0.0.1")),
+                ("dst", Cow::Borrowed("2.1.2.2")),
+                ("msg", Cow::Owned(r"Detected a=b in C:\temp".to_string())),
+                ("spt", Cow::Borrowed("1232")),
+            ]
+        );
+        assert_eq!(cef.extension("spt"), Some("1232"));
+    }
+
+    #[test]
+    fn not_cef() {
+        assert_eq!(Cef::parse("an ordinary message"), None);
+        assert_eq!(Cef::parse("CEF:0|too|few|fields"), None);
+    }
+
+    #[test]
+    fn cef_from_message() {
+        let message = parse_message(
+            "<134>Feb 14 19:04:54 host CEF:0|Security|threatmanager|1.0|100|worm stopped|10|src=10.0.0.1 request=http://example.com/?a=b",
+            Variant::RFC3164,
+        );
+        let cef = message.cef().unwrap();
+        assert_eq!(cef.name, "worm stopped");
+        assert_eq!(cef.extension("request"), Some("http://example.com/?a=b"));
+
+        let message = parse_message(
+            "<134>1 2003-10-11T22:14:15.003Z host app - - - CEF:0|Security|threatmanager|1.0|100|worm stopped|10|",
+            Variant::RFC5424,
+        );
+        assert!(message.cef().unwrap().extensions.is_empty());
+    }
+}
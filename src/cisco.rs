@@ -0,0 +1,155 @@
+//! Parsing the `%FACILITY-SEVERITY-MNEMONIC:` block that Cisco IOS, NX-OS and ASA
+//! devices put at the start of their messages.
+use crate::{message::Message, pri::SyslogSeverity};
+
+/// The parts of a Cisco message, such as
+/// `%LINK-3-UPDOWN: Interface GigabitEthernet0/1, changed state to down`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CiscoMnemonic<'a> {
+    /// The part of the device the message came from, such as `LINK` or `SYS`.
+    /// Any sub facility is kept, as in `SPANTREE-SP`.
+    pub facility: &'a str,
+    pub severity: SyslogSeverity,
+    /// Identifies the message, such as `UPDOWN`.
+    pub mnemonic: &'a str,
+    /// The text following the mnemonic block.
+    pub text: &'a str,
+    /// Whether the severity agrees with the severity from the PRI of the message.
+    /// None if there was no PRI.
+    pub pri_severity_agrees: Option<bool>,
+}
+
+impl<'a> CiscoMnemonic<'a> {
+    /// Parse a message that starts with the mnemonic block, with or without the
+    /// leading `%`.
+    pub fn parse(input: &'a str) -> Option<Self> {
+        let input = input.trim_start();
+        let (block, text) = input.split_once(':')?;
+        let mut cisco = Self::parse_block(block)?;
+        cisco.text = text.trim_start();
+        Some(cisco)
+    }
+
+    /// Parse `FACILITY-SEVERITY-MNEMONIC`.
+    fn parse_block(block: &'a str) -> Option<Self> {
+        let block = block.strip_prefix('%').unwrap_or(block);
+        let mut parts = block.rsplitn(3, '-');
+        let mnemonic = parts.next()?;
+        let severity = parts.next()?;
+        let facility = parts.next()?;
+
+        let valid = |part: &str, extra: char| {
+            !part.is_empty()
+                && part
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == extra)
+        };
+        if !valid(facility, '-') || !valid(mnemonic, '_') || severity.len() != 1 {
+            return None;
+        }
+
+        Some(CiscoMnemonic {
+            facility,
+            severity: SyslogSeverity::from_int(severity.parse().ok()?)?,
+            mnemonic,
+            text: "",
+            pri_severity_agrees: None,
+        })
+    }
+}
+
+impl<S: AsRef<str> + Ord + PartialEq + Clone> Message<S> {
+    /// Find the Cisco mnemonic block in the message.
+    ///
+    /// Depending on what comes before it, RFC3164 parsing can take the block to be
+    /// the appname or, for ASA devices, the hostname, so these are checked first.
+    /// Otherwise the block is looked for in the body, after any sequence number
+    /// and timestamp that the device added.
+    pub fn cisco(&self) -> Option<CiscoMnemonic<'_>> {
+        let header = [&self.appname, &self.hostname]
+            .iter()
+            .filter_map(|field| field.as_ref())
+            .map(AsRef::as_ref)
+            .find(|field| field.starts_with('%'));
+
+        let mut cisco = match header {
+            Some(block) => {
+                let mut cisco = CiscoMnemonic::parse_block(block)?;
+                cisco.text = self.msg.as_ref().trim_start();
+                cisco
+            }
+            None => {
+                let msg = self.msg.as_ref();
+                msg.match_indices('%')
+                    .filter(|(pos, _)| *pos == 0 || msg[..*pos].ends_with(' '))
+                    .find_map(|(pos, _)| CiscoMnemonic::parse(&msg[pos..]))?
+            }
+        };
+
+        cisco.pri_severity_agrees = self.severity.map(|severity| severity == cisco.severity);
+        Some(cisco)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Variant, parse_message};
+
+    #[test]
+    fn parse_from_appname() {
+        let message = parse_message(
+            "<189>Oct 11 22:14:15 router1 %SYS-5-CONFIG_I: Configured from console by admin",
+            Variant::RFC3164,
+        );
+
+        assert_eq!(
+            message.cisco().unwrap(),
+            CiscoMnemonic {
+                facility: "SYS",
+                severity: SyslogSeverity::SEV_NOTICE,
+                mnemonic: "CONFIG_I",
+                text: "Configured from console by admin",
+                pri_severity_agrees: Some(true),
+            }
+        );
+    }
+
+    #[test]
+    fn parse_from_hostname() {
+        let message = parse_message(
+            "<166>Oct 11 2023 22:14:15: %ASA-6-302013: Built outbound TCP connection",
+            Variant::RFC3164,
+        );
+        let cisco = message.cisco().unwrap();
+
+        assert_eq!(cisco.facility, "ASA");
+        assert_eq!(cisco.mnemonic, "302013");
+        assert_eq!(cisco.text, "Built outbound TCP connection");
+    }
+
+    #[test]
+    fn parse_from_body() {
+        let message = parse_message(
+            "<187>123: *Mar  1 18:46:11.123: %SPANTREE-SP-2-BLOCK_BPDUGUARD: Received BPDU on port Gi0/1",
+            Variant::RFC3164,
+        );
+        let cisco = message.cisco().unwrap();
+
+        assert_eq!(cisco.facility, "SPANTREE-SP");
+        assert_eq!(cisco.severity, SyslogSeverity::SEV_CRIT);
+        assert_eq!(cisco.mnemonic, "BLOCK_BPDUGUARD");
+        assert_eq!(cisco.text, "Received BPDU on port Gi0/1");
+        // The message couldn't be parsed, so there is no PRI to check.
+        assert_eq!(cisco.pri_severity_agrees, None);
+
+        let cisco = CiscoMnemonic::parse("%LINK-3-UPDOWN: down").unwrap();
+        assert_eq!(cisco.severity, SyslogSeverity::SEV_ERR);
+    }
+
+    #[test]
+    fn not_cisco() {
+        assert_eq!(CiscoMnemonic::parse("disk 90% full: warning"), None);
+        assert_eq!(CiscoMnemonic::parse("%LINK-9-UPDOWN: down"), None);
+    }
+}
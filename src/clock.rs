@@ -0,0 +1,103 @@
+//! Spotting senders whose clock has gone backwards.
+use crate::message::Message;
+use chrono::Duration;
+use chrono::prelude::*;
+use std::collections::HashMap;
+
+/// The last timestamp seen from each host, to flag messages timestamped earlier
+/// than the one before them from the same host.
+///
+/// Messages from one host should arrive roughly in order, so a timestamp that jumps
+/// back further than the threshold is a sign the host's clock was reset or that old
+/// messages are being replayed. Messages without a hostname are treated as coming
+/// from the same host, and messages without a timestamp are ignored.
+///
+/// ```
+/// use chrono::Duration;
+/// use syslog_loose::{ClockCheck, Variant, parse_message};
+///
+/// let mut clock = ClockCheck::new(Duration::seconds(60));
+/// let first = parse_message("<34>1 2003-10-11T22:14:15Z host su - - - one", Variant::RFC5424);
+/// let second = parse_message("<34>1 2003-10-11T21:14:15Z host su - - - two", Variant::RFC5424);
+/// assert_eq!(clock.check(&first), None);
+/// assert_eq!(clock.check(&second), Some(Duration::hours(1)));
+/// ```
+#[derive(Clone, Debug)]
+pub struct ClockCheck {
+    threshold: Duration,
+    last: HashMap<String, DateTime<FixedOffset>>,
+}
+
+impl ClockCheck {
+    /// Flag timestamps that are more than `threshold` earlier than the last one.
+    pub fn new(threshold: Duration) -> Self {
+        ClockCheck {
+            threshold,
+            last: HashMap::new(),
+        }
+    }
+
+    /// Record the timestamp of `message`, returning how far it went back if it is
+    /// more than the threshold earlier than the last message from the same host.
+    pub fn check<S: AsRef<str> + Ord + PartialEq + Clone>(
+        &mut self,
+        message: &Message<S>,
+    ) -> Option<Duration> {
+        let timestamp = message.timestamp?;
+        // Keyed by the empty string when there is no hostname, so looking up a host
+        // already seen doesn't allocate.
+        let hostname = message.hostname.as_ref().map_or("", AsRef::as_ref);
+        let last = match self.last.get_mut(hostname) {
+            Some(last) => last,
+            None => {
+                self.last.insert(hostname.to_string(), timestamp);
+                return None;
+            }
+        };
+
+        let back = *last - timestamp;
+        *last = timestamp;
+        Some(back).filter(|back| *back > self.threshold)
+    }
+
+    /// The number of hosts seen.
+    pub fn hosts(&self) -> usize {
+        self.last.len()
+    }
+
+    /// Forget the timestamps seen so far.
+    pub fn clear(&mut self) {
+        self.last.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Variant, parse_message};
+
+    #[test]
+    fn flags_per_host() {
+        let mut clock = ClockCheck::new(Duration::seconds(5));
+        let lines = [
+            ("<34>1 2003-10-11T22:14:15Z a su - - - one", None),
+            ("<34>1 2003-10-11T22:10:00Z b su - - - two", None),
+            ("<34>1 2003-10-11T22:14:12Z a su - - - three", None),
+            ("<34>1 2003-10-11T22:14:00Z a su - - - four", Some(12)),
+            ("<34>1 2003-10-11T22:14:01Z a su - - - five", None),
+            ("<34>1 - a su - - - six", None),
+            ("<34>1 2003-10-11T22:09:00Z b su - - - seven", Some(60)),
+        ];
+
+        for (line, back) in lines.iter() {
+            let message = parse_message(line, Variant::RFC5424);
+            assert_eq!(
+                clock.check(&message),
+                back.map(Duration::seconds),
+                "{}",
+                line
+            );
+        }
+        assert_eq!(clock.hosts(), 2);
+    }
+}
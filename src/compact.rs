@@ -0,0 +1,33 @@
+//! Owned messages holding their strings inline where they are short enough.
+use crate::message::Message;
+use compact_str::CompactString;
+
+/// Hostnames, appnames, msgids and most structured data values fit within the 24
+/// bytes a `CompactString` stores inline, so only the longer fields, usually just
+/// the body, need allocating.
+impl From<Message<&str>> for Message<CompactString> {
+    fn from(message: Message<&str>) -> Self {
+        message.map(CompactString::from)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Variant, parse_message};
+
+    #[test]
+    fn short_fields_are_inline() {
+        let message: Message<CompactString> = parse_message(
+            r#"<34>1 2003-10-11T22:14:15.003Z mymachine su - ID47 [meta seq="1"] a message long enough to need allocating"#,
+            Variant::RFC5424,
+        )
+        .into();
+
+        let hostname = message.hostname.as_ref().unwrap();
+        assert_eq!(hostname, "mymachine");
+        assert!(!hostname.is_heap_allocated());
+        assert!(!message.structured_data[0].params[0].1.is_heap_allocated());
+        assert!(message.msg.is_heap_allocated());
+    }
+}
@@ -0,0 +1,155 @@
+//! Recognising the tags that container runtimes give to the logs of containers,
+//! and the klog header that Kubernetes components put at the start of the body.
+use crate::{message::Message, pri::SyslogSeverity};
+
+/// The runtimes whose tags have the form `runtime/name`.
+const RUNTIMES: &[&str] = &["docker", "podman", "containerd", "cri-o"];
+
+/// The runtime and container name from a tag such as `docker/nginx`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ContainerTag<'a> {
+    pub runtime: &'a str,
+    /// The container name, or the container ID if the runtime was configured to
+    /// use that in the tag.
+    pub name: &'a str,
+}
+
+impl<'a> ContainerTag<'a> {
+    /// Parse a tag of the form `runtime/name`.
+    pub fn parse(tag: &'a str) -> Option<Self> {
+        let (runtime, name) = tag.split_once('/')?;
+        if !RUNTIMES.contains(&runtime) || name.is_empty() {
+            return None;
+        }
+        Some(ContainerTag { runtime, name })
+    }
+}
+
+/// The header of a klog formatted message,
+/// `Lmmdd hh:mm:ss.uuuuuu threadid file:line] msg`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Klog<'a> {
+    /// One of `I`, `W`, `E` and `F`.
+    pub level: char,
+    pub month: u32,
+    pub day: u32,
+    /// The time, such as `10:00:00.000000`.
+    pub time: &'a str,
+    pub thread_id: u64,
+    pub file: &'a str,
+    pub line: u32,
+    /// The text following the header.
+    pub text: &'a str,
+}
+
+impl<'a> Klog<'a> {
+    /// Parse a message starting with a klog header.
+    pub fn parse(input: &'a str) -> Option<Self> {
+        let input = input.trim_start();
+        let mut chars = input.chars();
+        let level = chars.next().filter(|level| "IWEF".contains(*level))?;
+        let rest = chars.as_str();
+
+        let date = rest
+            .get(..4)
+            .filter(|date| date.bytes().all(|b| b.is_ascii_digit()))?;
+        let (time, rest) = rest[4..].strip_prefix(' ')?.split_once(' ')?;
+        let (thread_id, rest) = rest.trim_start().split_once(' ')?;
+        let (location, text) = match rest.split_once("] ") {
+            Some(split) => split,
+            None => (rest.strip_suffix(']')?, ""),
+        };
+        let (file, line) = location.rsplit_once(':')?;
+
+        if time.len() < 8 || time.as_bytes()[2] != b':' || time.as_bytes()[5] != b':' {
+            return None;
+        }
+
+        Some(Klog {
+            level,
+            month: date[..2].parse().ok()?,
+            day: date[2..].parse().ok()?,
+            time,
+            thread_id: thread_id.parse().ok()?,
+            file,
+            line: line.parse().ok()?,
+            text,
+        })
+    }
+
+    /// The syslog severity corresponding to the level.
+    pub fn severity(&self) -> SyslogSeverity {
+        match self.level {
+            'I' => SyslogSeverity::SEV_INFO,
+            'W' => SyslogSeverity::SEV_WARNING,
+            'E' => SyslogSeverity::SEV_ERR,
+            _ => SyslogSeverity::SEV_CRIT,
+        }
+    }
+}
+
+impl<S: AsRef<str> + Ord + PartialEq + Clone> Message<S> {
+    /// The runtime and container name if the appname is a container tag.
+    pub fn container(&self) -> Option<ContainerTag<'_>> {
+        ContainerTag::parse(self.appname.as_ref()?.as_ref())
+    }
+
+    /// The klog header if the body starts with one.
+    pub fn klog(&self) -> Option<Klog<'_>> {
+        Klog::parse(self.msg.as_ref())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Variant, parse_message};
+
+    #[test]
+    fn parse_container_tag() {
+        let message = parse_message(
+            "<30>Oct 11 22:14:15 node1 docker/nginx[1234]: 10.0.0.1 - - GET /",
+            Variant::RFC3164,
+        );
+        assert_eq!(
+            message.container(),
+            Some(ContainerTag {
+                runtime: "docker",
+                name: "nginx"
+            })
+        );
+
+        assert_eq!(ContainerTag::parse("kubelet"), None);
+        assert_eq!(ContainerTag::parse("systemd/nginx"), None);
+    }
+
+    #[test]
+    fn parse_klog() {
+        let message = parse_message(
+            "<30>Oct 11 22:14:15 node1 kubelet[987]: E0102 10:00:00.000000    987 kubelet.go:123] Failed to start",
+            Variant::RFC3164,
+        );
+
+        let klog = message.klog().unwrap();
+        assert_eq!(
+            klog,
+            Klog {
+                level: 'E',
+                month: 1,
+                day: 2,
+                time: "10:00:00.000000",
+                thread_id: 987,
+                file: "kubelet.go",
+                line: 123,
+                text: "Failed to start",
+            }
+        );
+        assert_eq!(klog.severity(), SyslogSeverity::SEV_ERR);
+    }
+
+    #[test]
+    fn not_klog() {
+        assert_eq!(Klog::parse("Info about something"), None);
+        assert_eq!(Klog::parse("I0102 somewhere else"), None);
+    }
+}
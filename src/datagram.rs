@@ -0,0 +1,91 @@
+//! Parsing messages received over UDP.
+use crate::{error::Error, framing::is_blank, message::Message, options::ParseOptions};
+
+/// Is this byte padding that some senders append to the end of a datagram?
+fn is_padding(b: &u8) -> bool {
+    matches!(b, b'\0' | b'\n' | b'\r')
+}
+
+///
+/// Parse a single UDP datagram.
+///
+/// Trailing NUL and newline padding is removed. A datagram normally carries a single
+/// message, but some senders batch several newline separated messages into one, so
+/// each line is parsed as a separate message. An empty datagram gives an empty Vec,
+/// as do blank lines and lines skipped by the filter.
+///
+/// # Arguments
+///
+/// * datagram - the contents of the datagram.
+/// * options - the options to parse with, the framing options are not used.
+///
+pub fn parse_datagram(
+    datagram: &[u8],
+    options: &ParseOptions,
+) -> Result<Vec<Message<String>>, Error> {
+    // The lines of a datagram all come from the same sender.
+    let mut last = None;
+    let end = datagram.len() - datagram.iter().rev().take_while(|b| is_padding(b)).count();
+
+    datagram[..end]
+        .split(|&b| b == b'\n')
+        .map(|line| line.strip_suffix(b"\r").unwrap_or(line))
+        .filter(|line| !is_blank(line))
+        .filter_map(|line| {
+            options
+                .parse_frame(line, &mut last)
+                .map(|parsed| parsed.map(|(message, _)| message))
+                .transpose()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Utf8Policy, Variant};
+
+    #[test]
+    fn datagram_strips_padding() {
+        let messages = parse_datagram(
+            b"<34>1 2003-10-11T22:14:15.003Z mymachine su - ID47 - message\n\0\0",
+            &ParseOptions::new(Variant::RFC5424),
+        )
+        .unwrap();
+
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0].msg, "message");
+    }
+
+    #[test]
+    fn datagram_multiple_messages() {
+        let messages = parse_datagram(
+            b"<34>Oct 11 22:14:15 mymachine su: one\r\n<34>Oct 11 22:14:16 mymachine su: two",
+            &ParseOptions::new(Variant::RFC3164),
+        )
+        .unwrap();
+
+        assert_eq!(
+            messages
+                .iter()
+                .map(|message| message.msg.as_str())
+                .collect::<Vec<_>>(),
+            vec!["one", "two"]
+        );
+    }
+
+    #[test]
+    fn datagram_strict_utf8() {
+        let options = ParseOptions {
+            utf8: Utf8Policy::Strict,
+            ..Default::default()
+        };
+
+        assert!(matches!(
+            parse_datagram(b"<34>Oct 11 22:14:15 host \xff", &options),
+            Err(Error::InvalidUtf8(_))
+        ));
+        assert!(parse_datagram(b"\0\0\0", &options).unwrap().is_empty());
+        assert!(parse_datagram(b" \t\n", &options).unwrap().is_empty());
+    }
+}
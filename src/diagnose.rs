@@ -0,0 +1,584 @@
+//! Working out where and why a message failed to parse exactly, and which of the
+//! lenient fallbacks were taken.
+use crate::{
+    Variant,
+    error::{Field, ParseError, ParseFailure, Rule},
+    message::{Message, Protocol},
+    options::{ParseOptions, find_control},
+    parsers::{appname, hostname, msgid, procid},
+    pri::{Priority, pri},
+    rfc5424,
+    structured_data::{invalid_elements, unterminated_element},
+    timestamp::{timestamp_3164, timestamp_3339},
+};
+use chrono::FixedOffset;
+use nom::{
+    IResult, Parser as _,
+    character::complete::{space0, space1},
+    sequence::terminated,
+};
+use std::{error, fmt};
+
+/// Where parsing stopped: the remaining input, the field it was expecting and a
+/// description of what that should look like.
+type Failure<'a> = (&'a str, Field, &'static str);
+
+const PRI: &str = "a priority such as <34>";
+const EMPTY: &str = "a message, not blank input";
+
+/// Run `parser` over the start of `input`, which should hold `field`.
+fn field<'a, O>(
+    input: &mut &'a str,
+    field: Field,
+    expected: &'static str,
+    mut parser: impl FnMut(&'a str) -> IResult<&'a str, O>,
+) -> Result<O, Failure<'a>> {
+    let (remaining, output) = parser(input).map_err(|_| (*input, field, expected))?;
+    *input = remaining;
+    Ok(output)
+}
+
+/// Skip the PRI, also returning whether it was missing. Both variants parse messages
+/// without a PRI, so it is only reported as missing if the next field fails too.
+fn skip_pri(input: &str) -> (&str, bool) {
+    match pri(input) {
+        Ok((remaining, _)) => (remaining, remaining.len() == input.len()),
+        Err(_) => (input, true),
+    }
+}
+
+/// A message holding the PRI of `input`, if it has one, with the rest of `input` as
+/// the body, to be filled in with the fields that parse.
+fn partial(input: &str, protocol: Protocol) -> Message<&str> {
+    let priority = pri(input).ok().and_then(|(_, priority)| priority);
+    Message {
+        protocol,
+        facility: priority.map(Priority::facility),
+        severity: priority.map(Priority::severity),
+        timestamp: None,
+        hostname: None,
+        appname: None,
+        procid: None,
+        msgid: None,
+        structured_data: vec![],
+        msg: input,
+    }
+}
+
+fn fields_5424<'a>(input: &mut &'a str, partial: &mut Message<&'a str>) -> Result<(), Failure<'a>> {
+    partial.protocol =
+        Protocol::rfc5424(field(input, Field::Version, "a version number", |input| {
+            terminated(rfc5424::version, space1).parse(input)
+        })?);
+    partial.timestamp = Some(field(
+        input,
+        Field::Timestamp,
+        "an RFC3339 timestamp",
+        |input| terminated(timestamp_3339, space1).parse(input),
+    )?);
+    partial.hostname = field(input, Field::Hostname, "a hostname or -", |input| {
+        terminated(hostname, space1).parse(input)
+    })?;
+    partial.appname = field(input, Field::Appname, "an appname or -", |input| {
+        terminated(appname, space1).parse(input)
+    })?;
+    partial.procid = field(input, Field::ProcId, "a procid or -", |input| {
+        terminated(procid, space1).parse(input)
+    })?
+    .map(Into::into);
+    partial.msgid = field(input, Field::MsgId, "a msgid or -", |input| {
+        terminated(msgid, space0).parse(input)
+    })?;
+    field(
+        input,
+        Field::StructuredData,
+        "structured data or -",
+        rfc5424::body,
+    )?;
+    Ok(())
+}
+
+fn failure_5424(input: &str) -> Option<(Failure<'_>, Message<&str>)> {
+    let (mut remaining, missing_pri) = skip_pri(input);
+    let mut partial = partial(input, Protocol::RFC5424(1));
+    let failure = match fields_5424(&mut remaining, &mut partial) {
+        Ok(()) => return None,
+        Err((_, Field::Version, _)) if missing_pri => (input, Field::Pri, PRI),
+        Err(failure) => failure,
+    };
+    partial.msg = failure.0;
+    Some((failure, partial))
+}
+
+fn failure_3164(input: &str) -> Option<(Failure<'_>, Message<&str>)> {
+    let (remaining, missing_pri) = skip_pri(input);
+    let mut remaining = remaining.trim_start_matches([' ', '\t']);
+    // Everything after the timestamp is optional.
+    let failure = field(
+        &mut remaining,
+        Field::Timestamp,
+        "an RFC3164 or RFC3339 timestamp",
+        timestamp_3164::<_, FixedOffset>(|_| 1970, None),
+    )
+    .err()?;
+    let failure = if missing_pri {
+        (input, Field::Pri, PRI)
+    } else {
+        failure
+    };
+    Some((
+        failure,
+        Message {
+            msg: failure.0,
+            ..partial(input, Protocol::RFC3164)
+        },
+    ))
+}
+
+/// The rule broken by structured data `sd` that couldn't be parsed. Anything
+/// between brackets is accepted, so it is either missing or an element isn't closed,
+/// and the rule is the part of the element the input ended in.
+fn sd_rule(sd: &str) -> Rule {
+    let element = match sd.strip_prefix('[') {
+        Some(element) => element,
+        None => return Rule::rfc5424("STRUCTURED-DATA", "6.3"),
+    };
+    let mut params = match element.split_once(' ') {
+        Some((_, params)) => params,
+        None => return Rule::rfc5424("SD-ELEMENT/SD-ID", "6.3.2"),
+    };
+    loop {
+        let value = match params.trim_start_matches(' ').split_once('=') {
+            Some((_, value)) => value,
+            None => return Rule::rfc5424("SD-PARAM/PARAM-NAME", "6.3.3"),
+        };
+        let mut escaped = false;
+        let close = value.strip_prefix('"').and_then(|value| {
+            value.char_indices().find_map(|(i, c)| match c {
+                _ if escaped => {
+                    escaped = false;
+                    None
+                }
+                '\\' => {
+                    escaped = true;
+                    None
+                }
+                '"' => Some(&value[i + 1..]),
+                _ => None,
+            })
+        });
+        params = match close {
+            Some(rest) if !rest.is_empty() => rest,
+            Some(_) => return Rule::rfc5424("SD-ELEMENT", "6.3.1"),
+            None => return Rule::rfc5424("SD-PARAM/PARAM-VALUE", "6.3.3"),
+        };
+    }
+}
+
+/// The rule broken by `field`, which failed to parse at `remaining`.
+fn rule(protocol: &Protocol, field: Field, remaining: &str) -> Rule {
+    match (protocol, field) {
+        (Protocol::RFC3164, Field::Timestamp) => Rule::rfc3164("HEADER/TIMESTAMP", "4.1.2"),
+        (Protocol::RFC3164, _) => Rule::rfc3164("PRI", "4.1.1"),
+        (_, Field::Pri) => Rule::rfc5424("HEADER/PRI", "6.2.1"),
+        (_, Field::Version) => Rule::rfc5424("HEADER/VERSION", "6.2.2"),
+        (_, Field::Timestamp) => Rule::rfc5424("HEADER/TIMESTAMP", "6.2.3"),
+        (_, Field::Hostname) => Rule::rfc5424("HEADER/HOSTNAME", "6.2.4"),
+        (_, Field::Appname) => Rule::rfc5424("HEADER/APP-NAME", "6.2.5"),
+        (_, Field::ProcId) => Rule::rfc5424("HEADER/PROCID", "6.2.6"),
+        (_, Field::MsgId) => Rule::rfc5424("HEADER/MSGID", "6.2.7"),
+        (_, Field::StructuredData) => sd_rule(remaining),
+        (_, Field::Empty) => Rule::rfc5424("SYSLOG-MSG", "6"),
+    }
+}
+
+impl<'a> ParseError<'a> {
+    /// Describe why `input` couldn't be parsed as `variant`. With [`Variant::Either`]
+    /// the variant that got furthest is reported.
+    pub(crate) fn diagnose(input: &'a str, variant: Variant) -> Self {
+        Self::diagnose_partial(input, variant).0
+    }
+
+    /// Like [`ParseError::diagnose`], also returning the fields that were parsed
+    /// before the one that failed, with the rest of the input as the body.
+    pub(crate) fn diagnose_partial(input: &'a str, variant: Variant) -> (Self, Message<&'a str>) {
+        let trimmed = input.trim();
+        let failure = match variant {
+            _ if trimmed.is_empty() => None,
+            Variant::RFC3164 => failure_3164(trimmed),
+            Variant::RFC5424 => failure_5424(trimmed),
+            Variant::Either => match (failure_5424(trimmed), failure_3164(trimmed)) {
+                // Less of the input remaining means it got further.
+                (Some(rfc5424), Some(rfc3164)) if rfc5424.0.0.len() < rfc3164.0.0.len() => {
+                    Some(rfc5424)
+                }
+                (_, Some(rfc3164)) => Some(rfc3164),
+                (rfc5424, None) => rfc5424,
+            },
+        };
+        let (first, expected_first) = if trimmed.is_empty() {
+            (Field::Empty, EMPTY)
+        } else {
+            (Field::Pri, PRI)
+        };
+        let ((remaining, field, expected), partial) = failure.unwrap_or_else(|| {
+            (
+                (trimmed, first, expected_first),
+                partial(trimmed, Protocol::RFC3164),
+            )
+        });
+
+        let error = ParseError {
+            index: 0,
+            input,
+            offset: remaining.as_ptr() as usize - input.as_ptr() as usize,
+            field,
+            expected,
+            rule: rule(&partial.protocol, field, remaining),
+        };
+        (error, partial)
+    }
+}
+
+/// Something about a message that was accepted by the lenient parser but wouldn't
+/// have been by the exact one, see [`ParseOptions::parse_with_diagnostics`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ParseWarning<'a> {
+    /// There was no PRI, or it wasn't valid, so the facility and severity are unknown.
+    MissingPri,
+    /// The timestamp couldn't be parsed, so the whole input was taken as the message.
+    BadTimestamp { offset: usize },
+    /// Some other field couldn't be parsed, so the whole input was taken as the message.
+    Unparsed { field: Field, offset: usize },
+    /// A structured data element that couldn't be parsed was dropped.
+    InvalidSdElement { raw: &'a str },
+    /// The structured data element starting at `offset` is never closed, so the
+    /// message looks to have been cut short. Octet counted frames that end early
+    /// are reported as a [`FrameError::Truncated`](crate::FrameError::Truncated) instead.
+    Truncated { offset: usize },
+    /// The message contains a control character other than tab, the first of which
+    /// is at `offset`, see [`ParseOptions::control_chars`].
+    ControlCharacter { offset: usize },
+}
+
+impl<'a> fmt::Display for ParseWarning<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseWarning::MissingPri => write!(f, "missing priority"),
+            ParseWarning::BadTimestamp { offset } => {
+                write!(f, "invalid timestamp at byte {}", offset)
+            }
+            ParseWarning::Unparsed { field, offset } => {
+                write!(f, "invalid {} at byte {}", field, offset)
+            }
+            ParseWarning::InvalidSdElement { raw } => {
+                write!(f, "dropped invalid structured data element {}", raw)
+            }
+            ParseWarning::ControlCharacter { offset } => {
+                write!(f, "control character at byte {}", offset)
+            }
+            ParseWarning::Truncated { offset } => {
+                write!(
+                    f,
+                    "message truncated in the structured data at byte {}",
+                    offset
+                )
+            }
+        }
+    }
+}
+
+impl<'a> error::Error for ParseWarning<'a> {}
+
+impl ParseOptions {
+    /// Parse a single message exactly like [`ParseOptions::parse_exact`], but when it
+    /// can't be parsed also return the fields that were, so a broken message can
+    /// still be shown with its priority and timestamp.
+    ///
+    /// ```
+    /// use syslog_loose::{Field, ParseOptions, Variant};
+    ///
+    /// let failure = ParseOptions::new(Variant::RFC5424)
+    ///     .try_parse("<34>1 2003-10-11T22:14:15.003Z host su 1 ID47 [meta x")
+    ///     .unwrap_err();
+    /// assert_eq!(failure.error.field, Field::StructuredData);
+    /// assert_eq!(failure.partial.msgid, Some("ID47"));
+    /// assert_eq!(failure.partial.msg, "[meta x");
+    /// ```
+    pub fn try_parse<'a>(&self, input: &'a str) -> Result<Message<&'a str>, ParseFailure<'a>> {
+        crate::parse(input, self.get_year, self.tz, self.variant)
+            .map(|(_, message)| message)
+            .map_err(|_| {
+                let (error, partial) = ParseError::diagnose_partial(input, self.variant);
+                ParseFailure {
+                    partial: Box::new(partial),
+                    error,
+                }
+            })
+    }
+
+    /// Parse a single message like [`ParseOptions::parse`], also returning a warning
+    /// for each of the lenient fallbacks taken, so the quality of what each sender
+    /// sends can be tracked.
+    ///
+    /// ```
+    /// use syslog_loose::{ParseOptions, ParseWarning};
+    ///
+    /// let (message, warnings) = ParseOptions::default()
+    ///     .parse_with_diagnostics("<34>1 2003-10-11T22:14:15.003Z host su - - [a b=\"c\"][d e=] msg");
+    /// assert_eq!(message.structured_data.len(), 1);
+    /// assert_eq!(warnings, vec![ParseWarning::InvalidSdElement { raw: "[d e=]" }]);
+    /// ```
+    pub fn parse_with_diagnostics<'a>(
+        &self,
+        input: &'a str,
+    ) -> (Message<&'a str>, Vec<ParseWarning<'a>>) {
+        let message = self.parse(input);
+        let mut found = Vec::new();
+        warnings(input, self.variant, &message, |warning| found.push(warning));
+        (message, found)
+    }
+}
+
+/// Call `f` with a warning for each of the lenient fallbacks taken parsing `message`
+/// from `input`.
+pub(crate) fn warnings<'a>(
+    input: &'a str,
+    variant: Variant,
+    message: &Message<&str>,
+    mut f: impl FnMut(ParseWarning<'a>),
+) {
+    let trimmed = input.trim();
+    if skip_pri(trimmed).1 {
+        f(ParseWarning::MissingPri);
+    }
+
+    if let Some(offset) = find_control(trimmed) {
+        f(ParseWarning::ControlCharacter {
+            offset: offset + (trimmed.as_ptr() as usize - input.as_ptr() as usize),
+        });
+    }
+
+    let truncated = |sd: &str| {
+        unterminated_element(sd).map(|rest| ParseWarning::Truncated {
+            offset: rest.as_ptr() as usize - input.as_ptr() as usize,
+        })
+    };
+
+    // Both variants need a timestamp, so only the fallback is missing one.
+    if message.timestamp.is_none() {
+        let error = ParseError::diagnose(input, variant);
+        f(match error.field {
+            Field::Timestamp => ParseWarning::BadTimestamp {
+                offset: error.offset,
+            },
+            Field::StructuredData => {
+                truncated(&input[error.offset..]).unwrap_or(ParseWarning::Unparsed {
+                    field: Field::StructuredData,
+                    offset: error.offset,
+                })
+            }
+            field => ParseWarning::Unparsed {
+                field,
+                offset: error.offset,
+            },
+        });
+    } else if message.protocol != Protocol::RFC3164 {
+        if let Some((_, rest)) = rfc5424::parse_header(trimmed) {
+            let rest = rest.trim_start();
+            for raw in invalid_elements(rest) {
+                f(ParseWarning::InvalidSdElement { raw });
+            }
+            if let Some(warning) = truncated(rest) {
+                f(warning);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn diagnoses_failures() {
+        let cases = [
+            (
+                "<34>1 2003-10-11T22:14:15 mymachine su - ID47 - bad timestamp",
+                Variant::RFC5424,
+                (6, Field::Timestamp),
+            ),
+            (
+                "<34>x 2003-10-11T22:14:15.003Z mymachine su - ID47 - bad version",
+                Variant::RFC5424,
+                (4, Field::Version),
+            ),
+            (
+                "<34>1 2003-10-11T22:14:15.003Z mymachine su - ID47 [meta x",
+                Variant::Either,
+                (51, Field::StructuredData),
+            ),
+            (
+                "  <34>Oct 41 22:14:15 mymachine su: bad day",
+                Variant::Either,
+                (6, Field::Timestamp),
+            ),
+            ("not syslog at all", Variant::Either, (0, Field::Pri)),
+        ];
+
+        for (input, variant, (offset, field)) in cases.iter() {
+            let error = ParseError::diagnose(input, *variant);
+            assert_eq!((error.offset, error.field), (*offset, *field), "{}", input);
+        }
+
+        let rules = [
+            (
+                "<34>1 2003-10-11T22:14:15.003Z mymachine su - ID47 x",
+                "STRUCTURED-DATA",
+            ),
+            (
+                "<34>1 2003-10-11T22:14:15.003Z mymachine su - ID47 [meta",
+                "SD-ELEMENT/SD-ID",
+            ),
+            (
+                "<34>1 2003-10-11T22:14:15.003Z mymachine su - ID47 [meta x",
+                "SD-PARAM/PARAM-NAME",
+            ),
+            (
+                r#"<34>1 2003-10-11T22:14:15.003Z mymachine su - ID47 [meta x="\"y"#,
+                "SD-PARAM/PARAM-VALUE",
+            ),
+            (
+                r#"<34>1 2003-10-11T22:14:15.003Z mymachine su - ID47 [meta x="y" "#,
+                "SD-ELEMENT",
+            ),
+            (
+                "<34>1 2003-10-11T22:14:15 mymachine su - ID47 -",
+                "HEADER/TIMESTAMP",
+            ),
+        ];
+        for (input, id) in rules.iter() {
+            assert_eq!(
+                ParseError::diagnose(input, Variant::RFC5424).rule.id,
+                *id,
+                "{}",
+                input
+            );
+        }
+        let rule = ParseError::diagnose(cases[3].0, Variant::Either).rule;
+        assert_eq!(rule, Rule::rfc3164("HEADER/TIMESTAMP", "4.1.2"));
+        assert_eq!(
+            rule.url(),
+            "https://www.rfc-editor.org/rfc/rfc3164#section-4.1.2"
+        );
+
+        let error = ParseError::diagnose(" \r\n", Variant::RFC5424);
+        assert_eq!((error.field, error.expected), (Field::Empty, EMPTY));
+        assert_eq!(
+            error.to_string(),
+            "invalid message at byte 0: expected a message, not blank input"
+        );
+        assert_ne!(
+            ParseError::diagnose(cases[0].0, Variant::RFC5424).field,
+            Field::Empty
+        );
+
+        assert_eq!(
+            ParseError::diagnose(cases[0].0, Variant::RFC5424).to_string(),
+            "invalid timestamp at byte 6: expected an RFC3339 timestamp"
+        );
+    }
+
+    #[test]
+    fn keeps_partial_messages() {
+        let options = ParseOptions::default();
+        let failure = options
+            .try_parse("<34>1 2003-10-11T22:14:15.003Z mymachine su - ID47 [meta x")
+            .unwrap_err();
+        assert_eq!(failure.error.field, Field::StructuredData);
+        assert_eq!(
+            failure.partial.severity,
+            Some(crate::SyslogSeverity::SEV_CRIT)
+        );
+        assert_eq!(failure.partial.protocol, Protocol::RFC5424(1));
+        assert!(failure.partial.timestamp.is_some());
+        assert_eq!(failure.partial.hostname, Some("mymachine"));
+        assert_eq!(failure.partial.procid, None);
+        assert_eq!(failure.partial.msg, "[meta x");
+
+        let failure = options
+            .try_parse("<34>Oct 41 22:14:15 mymachine su: bad day")
+            .unwrap_err();
+        assert_eq!(failure.error.field, Field::Timestamp);
+        assert_eq!(
+            failure.partial.facility,
+            Some(crate::SyslogFacility::LOG_AUTH)
+        );
+        assert_eq!(failure.partial.timestamp, None);
+        assert_eq!(failure.partial.msg, "Oct 41 22:14:15 mymachine su: bad day");
+
+        assert!(
+            options
+                .try_parse("<34>Oct 11 22:14:15 mymachine su: ok")
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn warns_of_fallbacks() {
+        let options = ParseOptions::default();
+        let cases = [
+            (
+                "<34>1 2003-10-11T22:14:15.003Z mymachine su - ID47 - fine",
+                vec![],
+            ),
+            (
+                "Oct 11 22:14:15 mymachine su: no pri",
+                vec![ParseWarning::MissingPri],
+            ),
+            (
+                "<34>Oct 41 22:14:15 mymachine su: bad day",
+                vec![ParseWarning::BadTimestamp { offset: 4 }],
+            ),
+            (
+                "<34>1 2003-10-11T22:14:15.003Z mymachine su - ID47 [x][incorrect x] [ignored]",
+                vec![ParseWarning::InvalidSdElement {
+                    raw: "[incorrect x]",
+                }],
+            ),
+            (
+                "<34>1 2003-10-11T22:14:15.003Z mymachine su - ID47 [meta x=\"cut",
+                vec![ParseWarning::Truncated { offset: 51 }],
+            ),
+            (
+                "<34>1 2003-10-11T22:14:15.003Z mymachine su - ID47 [x][meta x=\"c]ut",
+                vec![
+                    ParseWarning::InvalidSdElement {
+                        raw: "[meta x=\"c]",
+                    },
+                    ParseWarning::Truncated { offset: 54 },
+                ],
+            ),
+            (
+                "<34>Oct 11 22:14:15 mymachine su: \x1b[31mred",
+                vec![ParseWarning::ControlCharacter { offset: 34 }],
+            ),
+            (
+                "junk",
+                vec![
+                    ParseWarning::MissingPri,
+                    ParseWarning::Unparsed {
+                        field: Field::Pri,
+                        offset: 0,
+                    },
+                ],
+            ),
+        ];
+
+        for (input, expected) in cases.iter() {
+            let (message, warnings) = options.parse_with_diagnostics(input);
+            assert_eq!(&warnings, expected, "{}", input);
+            assert_eq!(message, options.parse(input));
+        }
+    }
+}
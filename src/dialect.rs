@@ -0,0 +1,609 @@
+//! Pluggable extraction of vendor specific fields from the body of a message.
+use crate::{aruba, bigip, message::Message};
+use std::{fmt, sync::Arc};
+
+/// Extracts fields from the body of messages in a vendor's format, see
+/// [`crate::ParseOptions::dialects`].
+///
+/// ```
+/// use syslog_loose::{Dialect, ParseOptions, Message, Variant};
+///
+/// struct Sudo;
+///
+/// impl Dialect for Sudo {
+///     fn name(&self) -> &'static str {
+///         "sudo"
+///     }
+///
+///     fn matches(&self, message: &Message<&str>) -> bool {
+///         message.appname == Some("sudo")
+///     }
+///
+///     fn extract(&self, message: &Message<&str>) -> Option<Vec<(String, String)>> {
+///         let (user, command) = message.msg.split_once(" : ")?;
+///         Some(vec![
+///             ("user".to_string(), user.trim().to_string()),
+///             ("command".to_string(), command.to_string()),
+///         ])
+///     }
+/// }
+///
+/// let options = ParseOptions::new(Variant::RFC3164).with_dialect(Sudo);
+/// let message = options.parse("<85>Oct 11 22:14:15 host sudo: bob : COMMAND=/bin/ls");
+/// let extracted = options.extract(&message).unwrap();
+///
+/// assert_eq!(extracted.dialect, "sudo");
+/// assert_eq!(extracted.fields[0], ("user".to_string(), "bob".to_string()));
+/// ```
+pub trait Dialect: Send + Sync {
+    /// Identifies the dialect, such as `cef`.
+    fn name(&self) -> &'static str;
+
+    /// Whether the message looks like it is in this dialect. This should be quick
+    /// to check, such as comparing the appname.
+    fn matches(&self, message: &Message<&str>) -> bool;
+
+    /// Extract the fields from a message that matched. Returns None if the body
+    /// turns out not to be in this dialect, and the next dialect is tried.
+    fn extract(&self, message: &Message<&str>) -> Option<Vec<(String, String)>>;
+}
+
+impl fmt::Debug for dyn Dialect {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Dialect").field(&self.name()).finish()
+    }
+}
+
+/// Lets the dialects in a static, such as [`BUILTIN_DIALECTS`], be registered.
+impl<D: Dialect + ?Sized> Dialect for &'static D {
+    fn name(&self) -> &'static str {
+        (**self).name()
+    }
+
+    fn matches(&self, message: &Message<&str>) -> bool {
+        (**self).matches(message)
+    }
+
+    fn extract(&self, message: &Message<&str>) -> Option<Vec<(String, String)>> {
+        (**self).extract(message)
+    }
+}
+
+/// The dialects tried in turn by [`ParseOptions::extract`](crate::ParseOptions::extract),
+/// starting with the [`BUILTIN_DIALECTS`].
+pub(crate) fn builtin() -> Vec<Arc<dyn Dialect>> {
+    BUILTIN_DIALECTS
+        .iter()
+        .map(|dialect| Arc::new(*dialect) as Arc<dyn Dialect>)
+        .collect()
+}
+
+/// The fields extracted from a message by a [`Dialect`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DialectFields {
+    /// The name of the dialect that extracted the fields.
+    pub dialect: &'static str,
+    /// The fields in the order the dialect gave them.
+    pub fields: Vec<(String, String)>,
+}
+
+/// Run each of the dialects that match the message in turn, returning the fields
+/// from the first that extracts any.
+pub(crate) fn extract<'d, S: AsRef<str> + Ord + PartialEq + Clone>(
+    dialects: impl IntoIterator<Item = &'d dyn Dialect>,
+    message: &Message<S>,
+) -> Option<DialectFields> {
+    let message = message.to_borrowed();
+    dialects
+        .into_iter()
+        // Called this way so it isn't taken as the impl for `&'static D`.
+        .filter(|dialect| Dialect::matches(*dialect, &message))
+        .find_map(|dialect| {
+            dialect.extract(&message).map(|fields| DialectFields {
+                dialect: dialect.name(),
+                fields,
+            })
+        })
+}
+
+type Fields = Vec<(String, String)>;
+
+/// The dialects built into the crate.
+struct Builtin {
+    name: &'static str,
+    matches: fn(&Message<&str>) -> bool,
+    extract: fn(&Message<&str>) -> Option<Fields>,
+}
+
+impl Dialect for Builtin {
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn matches(&self, message: &Message<&str>) -> bool {
+        (self.matches)(message)
+    }
+
+    fn extract(&self, message: &Message<&str>) -> Option<Vec<(String, String)>> {
+        (self.extract)(message)
+    }
+}
+
+fn appname_is(message: &Message<&str>, name: &str) -> bool {
+    message.appname == Some(name)
+}
+
+fn pairs<K, V>(pairs: impl IntoIterator<Item = (K, V)>) -> Fields
+where
+    K: fmt::Display,
+    V: fmt::Display,
+{
+    pairs
+        .into_iter()
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .collect()
+}
+
+fn join<T: fmt::Display>(values: &[T]) -> String {
+    values
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+static CEF: Builtin = Builtin {
+    name: "cef",
+    matches: |message| appname_is(message, "CEF") || message.msg.starts_with("CEF:"),
+    extract: |message| {
+        let cef = message.cef()?;
+        let mut fields = pairs([
+            ("version", cef.version.to_string()),
+            ("device_vendor", cef.device_vendor.to_string()),
+            ("device_product", cef.device_product.to_string()),
+            ("device_version", cef.device_version.to_string()),
+            ("signature_id", cef.signature_id.to_string()),
+            ("name", cef.name.to_string()),
+            ("severity", cef.severity.to_string()),
+        ]);
+        fields.extend(pairs(cef.extensions));
+        Some(fields)
+    },
+};
+
+static LEEF: Builtin = Builtin {
+    name: "leef",
+    matches: |message| appname_is(message, "LEEF") || message.msg.starts_with("LEEF:"),
+    extract: |message| {
+        let leef = message.leef()?;
+        let mut fields = pairs([
+            ("version", leef.version),
+            ("vendor", leef.vendor),
+            ("product", leef.product),
+            ("product_version", leef.product_version),
+            ("event_id", leef.event_id),
+        ]);
+        fields.extend(pairs(leef.attributes));
+        Some(fields)
+    },
+};
+
+static SNARE: Builtin = Builtin {
+    name: "snare",
+    matches: |message| {
+        appname_is(message, "MSWinEventLog")
+            || message.msg.trim_start().starts_with("MSWinEventLog")
+    },
+    extract: |message| {
+        let event = message.snare()?;
+        Some(pairs([
+            ("criticality", event.criticality),
+            ("log", event.log),
+            ("counter", event.counter),
+            ("time", event.time),
+            ("event_id", event.event_id),
+            ("source", event.source),
+            ("user", event.user),
+            ("sid_type", event.sid_type),
+            ("event_type", event.event_type),
+            ("computer", event.computer),
+            ("category", event.category),
+            ("data", event.data),
+        ]))
+    },
+};
+
+static FILTERLOG: Builtin = Builtin {
+    name: "filterlog",
+    matches: |message| appname_is(message, "filterlog"),
+    extract: |message| {
+        let log = message.filterlog()?;
+        let mut fields = pairs([
+            ("rule", log.rule),
+            ("sub_rule", log.sub_rule),
+            ("anchor", log.anchor),
+            ("tracker", log.tracker),
+            ("interface", log.interface),
+            ("reason", log.reason),
+            ("action", log.action),
+            ("direction", log.direction),
+            ("protocol", log.protocol),
+        ]);
+        fields.extend(pairs([
+            ("ip_version", log.ip_version.to_string()),
+            ("src", log.src.to_string()),
+            ("dst", log.dst.to_string()),
+        ]));
+        fields.extend(
+            vec![
+                ("protocol_id", log.protocol_id.map(|id| id.to_string())),
+                ("length", log.length.map(|length| length.to_string())),
+                ("src_port", log.src_port.map(|port| port.to_string())),
+                ("dst_port", log.dst_port.map(|port| port.to_string())),
+                ("tcp_flags", log.tcp_flags.map(str::to_string)),
+            ]
+            .into_iter()
+            .filter_map(|(key, value)| Some((key.to_string(), value?))),
+        );
+        Some(fields)
+    },
+};
+
+static HAPROXY: Builtin = Builtin {
+    name: "haproxy",
+    matches: |message| appname_is(message, "haproxy"),
+    extract: |message| {
+        let log = message.haproxy()?;
+        let mut fields = pairs([
+            ("client_ip", log.client_ip.to_string()),
+            ("client_port", log.client_port.to_string()),
+            ("accept_date", log.accept_date.to_string()),
+            ("frontend", log.frontend.to_string()),
+            ("backend", log.backend.to_string()),
+            ("server", log.server.to_string()),
+            ("timers", join(&log.timers)),
+            ("bytes_read", log.bytes_read.to_string()),
+            ("termination_state", log.termination_state.to_string()),
+            ("connections", join(&log.connections)),
+            ("srv_queue", log.srv_queue.to_string()),
+            ("backend_queue", log.backend_queue.to_string()),
+        ]);
+        if let Some(status) = log.status {
+            fields.push(("status".to_string(), status.to_string()));
+        }
+        if let Some(request) = log.request {
+            fields.push(("request".to_string(), request.to_string()));
+        }
+        Some(fields)
+    },
+};
+
+static MAIL: Builtin = Builtin {
+    name: "mail",
+    matches: |message| message.mail().is_some(),
+    extract: |message| {
+        let event = message.mail()?;
+        let mut fields = Vec::new();
+        if let Some(queue_id) = event.queue_id {
+            fields.push(("queue_id".to_string(), queue_id.to_string()));
+        }
+        fields.extend(pairs(event.fields.iter().copied()));
+        if let Some(status) = event.status() {
+            // Sendmail calls this `stat`.
+            fields.retain(|(key, _)| key != "status");
+            fields.push(("status".to_string(), status.to_string()));
+        }
+        if let Some(detail) = event.status_detail() {
+            fields.push(("status_detail".to_string(), detail.to_string()));
+        }
+        Some(fields)
+    },
+};
+
+static AUTH: Builtin = Builtin {
+    name: "auth",
+    matches: |message| {
+        matches!(message.appname, Some("sshd") | Some("sudo")) || message.msg.starts_with("pam_")
+    },
+    extract: |message| {
+        let event = message.auth_event()?;
+        let mut fields = pairs([("event", event.event)]);
+        fields.extend(
+            vec![
+                ("user", event.user.map(str::to_string)),
+                ("source_ip", event.source_ip.map(|ip| ip.to_string())),
+                ("port", event.port.map(|port| port.to_string())),
+                ("method", event.method.map(str::to_string)),
+                ("target_user", event.target_user.map(str::to_string)),
+                ("command", event.command.map(str::to_string)),
+                ("service", event.service.map(str::to_string)),
+            ]
+            .into_iter()
+            .filter_map(|(key, value)| Some((key.to_string(), value?))),
+        );
+        Some(fields)
+    },
+};
+
+static CISCO: Builtin = Builtin {
+    name: "cisco",
+    matches: |message| {
+        message.msg.contains('%')
+            || [message.appname, message.hostname]
+                .iter()
+                .flatten()
+                .any(|field| field.starts_with('%'))
+    },
+    extract: |message| {
+        let cisco = message.cisco()?;
+        let mut fields = pairs([
+            ("facility", cisco.facility.to_string()),
+            ("severity", cisco.severity.code().to_string()),
+            ("mnemonic", cisco.mnemonic.to_string()),
+            ("text", cisco.text.to_string()),
+        ]);
+        if let Some(agrees) = cisco.pri_severity_agrees {
+            fields.push(("pri_severity_agrees".to_string(), agrees.to_string()));
+        }
+        if let Some(id) = cisco.asa_message_id() {
+            fields.push(("message_id".to_string(), id.to_string()));
+        }
+        fields.extend(pairs(cisco.asa_fields().unwrap_or_default()));
+        Some(fields)
+    },
+};
+
+static ROUTEROS: Builtin = Builtin {
+    name: "routeros",
+    matches: |message| {
+        message
+            .appname
+            .unwrap_or(message.msg)
+            .split(' ')
+            .next()
+            .is_some_and(|topics| topics.contains(','))
+    },
+    extract: |message| {
+        let event = message.routeros()?;
+        Some(pairs([
+            ("topics", event.topics.join(",")),
+            ("level", event.level.to_string()),
+            ("text", event.text.to_string()),
+        ]))
+    },
+};
+
+static CONTAINER: Builtin = Builtin {
+    name: "container",
+    matches: |message| message.container().is_some() || message.klog().is_some(),
+    extract: |message| {
+        let mut fields = Vec::new();
+        if let Some(tag) = message.container() {
+            fields.extend(pairs([("runtime", tag.runtime), ("container", tag.name)]));
+        }
+        if let Some(klog) = message.klog() {
+            fields.extend(pairs([
+                ("klog_level", klog.level.to_string()),
+                ("klog_month", klog.month.to_string()),
+                ("klog_day", klog.day.to_string()),
+                ("klog_time", klog.time.to_string()),
+                ("klog_thread_id", klog.thread_id.to_string()),
+                ("klog_file", klog.file.to_string()),
+                ("klog_line", klog.line.to_string()),
+                ("text", klog.text.to_string()),
+            ]));
+        }
+        Some(fields)
+    },
+};
+
+static ESXI: Builtin = Builtin {
+    name: "esxi",
+    matches: |message| message.esxi().is_some(),
+    extract: |message| {
+        let esxi = message.esxi()?;
+        let mut fields = Vec::new();
+        if let Some(level) = esxi.level {
+            fields.push(("level".to_string(), level.to_string()));
+        }
+        // The component is the real appname.
+        if let Some(component) = esxi.component {
+            fields.push(("appname".to_string(), component.to_string()));
+        }
+        if let Some(pid) = esxi.pid {
+            fields.push(("procid".to_string(), pid.to_string()));
+        }
+        fields.extend(pairs(esxi.originator.iter().copied()));
+        if let (Some(cpu), Some(world_id)) = (esxi.cpu, esxi.world_id) {
+            fields.extend(pairs([("cpu", cpu), ("world_id", world_id)]));
+        }
+        fields.push(("text".to_string(), esxi.text.to_string()));
+        Some(fields)
+    },
+};
+
+static UBIQUITI: Builtin = Builtin {
+    name: "ubiquiti",
+    matches: |message| message.msg.contains(']') || message.appname == Some(""),
+    extract: |message| {
+        let firewall = message.ubiquiti_firewall()?;
+        let mut fields = pairs([("rule", firewall.rule)]);
+        if let Some(action) = firewall.action() {
+            fields.push(("action".to_string(), action.to_string()));
+        }
+        fields.extend(pairs(firewall.fields));
+        Some(fields)
+    },
+};
+
+static NAS: Builtin = Builtin {
+    name: "nas",
+    matches: |message| message.msg.contains(": ") || message.msg.contains(" ["),
+    extract: |message| {
+        let event = message.nas_event()?;
+        let mut fields = pairs([("vendor", event.vendor), ("category", event.category)]);
+        if let Some(user) = event.user {
+            fields.push(("user".to_string(), user.to_string()));
+        }
+        if let Some(ip) = event.ip {
+            fields.push(("ip".to_string(), ip.to_string()));
+        }
+        fields.extend(pairs(event.fields.iter().copied()));
+        Some(fields)
+    },
+};
+
+static BIGIP: Builtin = Builtin {
+    name: "bigip",
+    matches: |message| bigip::has_signature(message.appname, message.msg),
+    extract: |message| {
+        let bigip = message.bigip()?;
+        let mut fields = pairs([("module", bigip.module)]);
+        fields.extend(
+            vec![
+                ("slot", bigip.slot.map(str::to_string)),
+                ("host", bigip.host.map(str::to_string)),
+                ("level", bigip.level.map(str::to_string)),
+                ("pid", bigip.pid.map(|pid| pid.to_string())),
+                ("code", bigip.code.map(str::to_string)),
+                (
+                    "severity",
+                    bigip.severity.map(|severity| severity.code().to_string()),
+                ),
+            ]
+            .into_iter()
+            .filter_map(|(key, value)| Some((key.to_string(), value?))),
+        );
+        fields.push(("text".to_string(), bigip.text.to_string()));
+        Some(fields)
+    },
+};
+
+static ARUBA: Builtin = Builtin {
+    name: "aruba",
+    matches: |message| aruba::has_signature(message.appname, message.msg),
+    extract: |message| {
+        let event = message.aruba()?;
+        // The mnemonic is the real appname.
+        let mut fields = pairs([("appname", event.mnemonic)]);
+        fields.extend(
+            vec![
+                ("event_id", event.event_id.map(|id| id.to_string())),
+                ("severity", event.severity.map(str::to_string)),
+                ("slot", event.slot.map(str::to_string)),
+            ]
+            .into_iter()
+            .filter_map(|(key, value)| Some((key.to_string(), value?))),
+        );
+        fields.push(("text".to_string(), event.text.to_string()));
+        Some(fields)
+    },
+};
+
+static FORTIGATE: Builtin = Builtin {
+    name: "fortigate",
+    matches: |message| message.msg.contains("logid=") || message.msg.contains("devid="),
+    extract: |message| Some(pairs(message.fortigate()?.fields)),
+};
+
+static PANOS: Builtin = Builtin {
+    name: "panos",
+    matches: |message| {
+        message.appname.is_some_and(|appname| appname.contains(','))
+            || message.msg.matches(',').count() >= 6
+    },
+    extract: |message| Some(pairs(message.panos()?.fields)),
+};
+
+/// The dialects for the formats the crate understands - CEF, LEEF, Snare, pfSense
+/// filterlog, HAProxy, Postfix and sendmail, sshd, sudo and PAM, Cisco, RouterOS,
+/// container and klog, ESXi, Ubiquiti firewall, Synology and QNAP, BIG-IP, Aruba,
+/// FortiGate and PAN-OS, tried in that order. These are registered by default.
+pub static BUILTIN_DIALECTS: &[&dyn Dialect] = &[
+    &CEF, &LEEF, &SNARE, &FILTERLOG, &HAPROXY, &MAIL, &AUTH, &CISCO, &ROUTEROS, &CONTAINER, &ESXI,
+    &UBIQUITI, &NAS, &BIGIP, &ARUBA, &FORTIGATE, &PANOS,
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ParseOptions, Variant, parse_message};
+
+    #[test]
+    fn extract_builtin() {
+        let message = parse_message(
+            "<134>Feb  6 12:14:14 localhost haproxy[14389]: 10.0.1.2:33317 [06/Feb/2009:12:12:51.443] fnt bck/srv1 0/0/5007 212 -- 0/0/0/0/3 0/0",
+            Variant::RFC3164,
+        );
+        let extracted = extract(BUILTIN_DIALECTS.iter().copied(), &message).unwrap();
+
+        assert_eq!(extracted.dialect, "haproxy");
+        assert!(
+            extracted
+                .fields
+                .contains(&("timers".to_string(), "0/0/5007".to_string()))
+        );
+
+        let message = parse_message(
+            "<134>Oct 11 22:14:15 mymachine CEF:0|Security|threatmanager|1.0|100|worm stopped|10|src=10.0.0.1",
+            Variant::RFC3164,
+        );
+        let extracted = extract(BUILTIN_DIALECTS.iter().copied(), &message).unwrap();
+        assert_eq!(extracted.dialect, "cef");
+        assert_eq!(
+            extracted.fields.last(),
+            Some(&("src".to_string(), "10.0.0.1".to_string()))
+        );
+    }
+
+    #[test]
+    fn extract_routeros() {
+        let message = parse_message(
+            "<30>Oct 11 22:14:15 MikroTik firewall,info input: in:ether1 out:(unknown 0), proto TCP (SYN), 1.2.3.4:5678->5.6.7.8:22, len 60",
+            Variant::RFC3164,
+        );
+        let extracted = extract(BUILTIN_DIALECTS.iter().copied(), &message).unwrap();
+
+        assert_eq!(extracted.dialect, "routeros");
+        assert_eq!(
+            extracted.fields[0],
+            ("topics".to_string(), "firewall".to_string())
+        );
+    }
+
+    #[test]
+    fn vendor_signatures() {
+        let message = parse_message(
+            "<34>1 2003-10-11T22:14:15.003Z mymachine su - ID47 - 'su root' failed: 01070417:6",
+            Variant::RFC5424,
+        );
+        assert!(!BIGIP.matches(&message));
+        assert!(!ARUBA.matches(&message));
+
+        let message = parse_message(
+            "<182>Oct 11 22:14:15 bigip1 mcpd[5678]: 01070638:5: Pool /Common/p1 monitor status down.",
+            Variant::RFC3164,
+        );
+        assert!(BIGIP.matches(&message));
+        assert!(!ARUBA.matches(&message));
+
+        let options = ParseOptions {
+            year_after_time: true,
+            ..ParseOptions::new(Variant::RFC3164)
+        };
+        let message =
+            options.parse("<14>Oct 11 22:14:15 2023 switch1 00076 ports: port 1 is now on-line");
+        assert!(ARUBA.matches(&message));
+        assert!(!BIGIP.matches(&message));
+    }
+
+    #[test]
+    fn no_dialect() {
+        let message = parse_message(
+            "<34>Oct 11 22:14:15 mymachine su: 'su root' failed",
+            Variant::RFC3164,
+        );
+        assert_eq!(extract(BUILTIN_DIALECTS.iter().copied(), &message), None);
+        assert_eq!(extract([], &message), None);
+    }
+}
@@ -0,0 +1,156 @@
+//! Mapping to [Elastic Common Schema](https://www.elastic.co/guide/en/ecs/current/ecs-syslog.html) fields.
+use crate::{message::Message, procid::ProcId};
+use chrono::{SecondsFormat, Utc};
+use serde_json::{Map, Value};
+
+/// Insert `value` at the dotted `path` within `object`, creating the objects
+/// along the way.
+fn insert(object: &mut Map<String, Value>, path: &str, value: Value) {
+    match path.split_once('.') {
+        Some((head, rest)) => {
+            let child = object
+                .entry(head)
+                .or_insert_with(|| Value::Object(Map::new()));
+            if let Value::Object(child) = child {
+                insert(child, rest, value);
+            }
+        }
+        None => {
+            object.insert(path.to_string(), value);
+        }
+    }
+}
+
+impl<S: AsRef<str> + Ord + PartialEq + Clone> Message<S> {
+    /// The message as an ECS document, ready to be indexed into Elasticsearch.
+    /// `event.created` is set to the current time.
+    pub fn to_ecs(&self) -> Value {
+        let mut ecs = Map::new();
+
+        if let Some(timestamp) = self.timestamp {
+            insert(
+                &mut ecs,
+                "@timestamp",
+                timestamp
+                    .to_rfc3339_opts(SecondsFormat::AutoSi, true)
+                    .into(),
+            );
+        }
+        insert(
+            &mut ecs,
+            "event.created",
+            Utc::now()
+                .to_rfc3339_opts(SecondsFormat::AutoSi, true)
+                .into(),
+        );
+        insert(&mut ecs, "message", self.msg.as_ref().into());
+
+        if let Some(hostname) = &self.hostname {
+            insert(&mut ecs, "host.hostname", hostname.as_ref().into());
+        }
+        if let Some(appname) = &self.appname {
+            insert(&mut ecs, "log.syslog.appname", appname.as_ref().into());
+            insert(&mut ecs, "process.name", appname.as_ref().into());
+        }
+        match &self.procid {
+            Some(ProcId::PID(pid)) => {
+                insert(&mut ecs, "log.syslog.procid", pid.to_string().into());
+                insert(&mut ecs, "process.pid", (*pid).into());
+            }
+            Some(ProcId::Name(name)) => {
+                insert(&mut ecs, "log.syslog.procid", name.as_ref().into());
+            }
+            None => {}
+        }
+        if let Some(msgid) = &self.msgid {
+            insert(&mut ecs, "log.syslog.msgid", msgid.as_ref().into());
+        }
+        if let Some(version) = self.protocol.version() {
+            insert(&mut ecs, "log.syslog.version", version.to_string().into());
+        }
+
+        if let Some(facility) = self.facility {
+            insert(&mut ecs, "log.syslog.facility.code", facility.code().into());
+            insert(
+                &mut ecs,
+                "log.syslog.facility.name",
+                facility.as_str().into(),
+            );
+        }
+        if let Some(severity) = self.severity {
+            insert(&mut ecs, "log.syslog.severity.code", severity.code().into());
+            insert(
+                &mut ecs,
+                "log.syslog.severity.name",
+                severity.as_str().into(),
+            );
+            insert(&mut ecs, "log.level", severity.as_str().into());
+        }
+        if let Some(value) = self.priority().and_then(|priority| priority.value()) {
+            insert(&mut ecs, "log.syslog.priority", value.into());
+        }
+
+        if !self.structured_data.is_empty() {
+            let structured_data = self
+                .structured_data
+                .iter()
+                .map(|element| {
+                    let params = element
+                        .params()
+                        .map(|(name, value)| (name.as_ref().to_string(), Value::from(value)))
+                        .collect::<Map<_, _>>();
+                    (element.id.as_ref().to_string(), Value::Object(params))
+                })
+                .collect::<Map<_, _>>();
+            insert(
+                &mut ecs,
+                "log.syslog.structured_data",
+                Value::Object(structured_data),
+            );
+        }
+
+        Value::Object(ecs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Variant, parse_message};
+    use serde_json::json;
+
+    #[test]
+    fn message_to_ecs() {
+        let mut ecs = parse_message(
+            r#"<165>1 2003-10-11T22:14:15.003Z mymachine.example.com evntslog 12 ID47 [exampleSDID@32473 iut="3"] An application event"#,
+            Variant::RFC5424,
+        )
+        .to_ecs();
+
+        // Depends on when the test is run.
+        assert!(ecs["event"]["created"].is_string());
+        ecs.as_object_mut().unwrap().remove("event");
+
+        assert_eq!(
+            ecs,
+            json!({
+                "@timestamp": "2003-10-11T22:14:15.003Z",
+                "message": "An application event",
+                "host": { "hostname": "mymachine.example.com" },
+                "process": { "name": "evntslog", "pid": 12 },
+                "log": {
+                    "level": "notice",
+                    "syslog": {
+                        "appname": "evntslog",
+                        "procid": "12",
+                        "msgid": "ID47",
+                        "version": "1",
+                        "priority": 165,
+                        "facility": { "code": 20, "name": "local4" },
+                        "severity": { "code": 5, "name": "notice" },
+                        "structured_data": { "exampleSDID@32473": { "iut": "3" } },
+                    }
+                }
+            })
+        );
+    }
+}
@@ -0,0 +1,155 @@
+//! Chainable setters for adjusting a message before writing it out again.
+use crate::{
+    message::Message,
+    pri::{Priority, SyslogFacility, SyslogSeverity},
+    procid::ProcId,
+    structured_data::StructuredElement,
+};
+use chrono::{DateTime, FixedOffset};
+
+/// Each setter returns the message with the one field changed. The strings are
+/// converted into the message's string type, so to set them to strings built at
+/// runtime on a parsed message, which borrows from its input, first convert it
+/// with [`Message::into_owned`].
+///
+/// ```
+/// use syslog_loose::{Message, SyslogSeverity, Variant, parse_message};
+///
+/// let message: Message<String> = parse_message(
+///     "<34>1 2003-10-11T22:14:15.003Z mymachine su - ID47 - message",
+///     Variant::RFC5424,
+/// )
+/// .into_owned();
+///
+/// let message = message
+///     .with_severity(SyslogSeverity::SEV_NOTICE)
+///     .with_hostname(format!("{}.example.com", "mymachine"))
+///     .with_sd_param("origin", "ip", "192.0.2.1");
+/// assert_eq!(
+///     message.to_string(),
+///     r#"<37>1 2003-10-11T22:14:15.003+00:00 mymachine.example.com su - ID47 [origin ip="192.0.2.1"] message"#
+/// );
+/// ```
+impl<S: AsRef<str> + Ord + PartialEq + Clone> Message<S> {
+    pub fn with_facility(self, facility: SyslogFacility) -> Self {
+        Message {
+            facility: Some(facility),
+            ..self
+        }
+    }
+
+    pub fn with_severity(self, severity: SyslogSeverity) -> Self {
+        Message {
+            severity: Some(severity),
+            ..self
+        }
+    }
+
+    /// Set both the facility and the severity.
+    pub fn with_priority(mut self, priority: Priority) -> Self {
+        self.set_priority(Some(priority));
+        self
+    }
+
+    pub fn with_timestamp(self, timestamp: DateTime<FixedOffset>) -> Self {
+        Message {
+            timestamp: Some(timestamp),
+            ..self
+        }
+    }
+
+    pub fn with_hostname(self, hostname: impl Into<S>) -> Self {
+        Message {
+            hostname: Some(hostname.into()),
+            ..self
+        }
+    }
+
+    pub fn with_appname(self, appname: impl Into<S>) -> Self {
+        Message {
+            appname: Some(appname.into()),
+            ..self
+        }
+    }
+
+    pub fn with_procid(self, procid: ProcId<S>) -> Self {
+        Message {
+            procid: Some(procid),
+            ..self
+        }
+    }
+
+    pub fn with_msgid(self, msgid: impl Into<S>) -> Self {
+        Message {
+            msgid: Some(msgid.into()),
+            ..self
+        }
+    }
+
+    pub fn with_msg(self, msg: impl Into<S>) -> Self {
+        Message {
+            msg: msg.into(),
+            ..self
+        }
+    }
+
+    /// Set the param `name` of the structured data element `id` to `value`,
+    /// replacing the first param of that name or adding the element if there isn't
+    /// one. The value is written out as it is given, so must already be escaped.
+    pub fn with_sd_param(mut self, id: &str, name: &str, value: impl Into<S>) -> Self
+    where
+        S: for<'b> From<&'b str>,
+    {
+        let value = value.into();
+        let element = match self
+            .structured_data
+            .iter()
+            .position(|element| element.id.as_ref() == id)
+        {
+            Some(pos) => &mut self.structured_data[pos],
+            None => {
+                self.structured_data.push(StructuredElement {
+                    id: id.into(),
+                    params: Default::default(),
+                });
+                self.structured_data.last_mut().unwrap()
+            }
+        };
+
+        match element
+            .params
+            .iter_mut()
+            .find(|(param, _)| param.as_ref() == name)
+        {
+            Some((_, old)) => *old = value,
+            None => element.params.push((name.into(), value)),
+        }
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Message, ProcId, Variant, parse_message};
+
+    #[test]
+    fn sets_fields() {
+        let message: Message<String> = parse_message(
+            r#"<34>1 2003-10-11T22:14:15.003Z mymachine su - ID47 [meta a="1"] message"#,
+            Variant::RFC5424,
+        )
+        .into_owned();
+
+        let message = message
+            .with_appname("sshd")
+            .with_procid(ProcId::PID(22))
+            .with_msgid("ID48")
+            .with_msg("changed")
+            .with_sd_param("meta", "a", "2")
+            .with_sd_param("meta", "b", "3");
+        assert_eq!(
+            message.to_string(),
+            r#"<34>1 2003-10-11T22:14:15.003+00:00 mymachine sshd 22 ID48 [meta a="2" b="3"] changed"#
+        );
+    }
+}
@@ -0,0 +1,116 @@
+//! The encoding half of the stream codecs, for relaying messages on.
+use crate::{framing::Framing, message::Message};
+
+/// Frames messages for sending over a stream, the counterpart to [`Parser`](crate::Parser).
+///
+/// Octet counted frames can hold any message. Non transparent frames are ended
+/// with the delimiter, so any delimiters within the message will split it when it
+/// is received. [`Framing::Auto`] encodes as octet counted frames.
+///
+/// ```
+/// use syslog_loose::{Framing, MessageEncoder};
+///
+/// let mut buf = Vec::new();
+/// MessageEncoder::new(Framing::OctetCounting).encode_str("<34>1 - - - - - - one", &mut buf);
+/// MessageEncoder::new(Framing::NonTransparent).encode_str("<34>1 - - - - - - two", &mut buf);
+/// assert_eq!(buf, b"21 <34>1 - - - - - - one<34>1 - - - - - - two\n");
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MessageEncoder {
+    framing: Framing,
+    delimiter: u8,
+}
+
+impl MessageEncoder {
+    pub fn new(framing: Framing) -> Self {
+        MessageEncoder {
+            framing,
+            delimiter: b'\n',
+        }
+    }
+
+    /// Use `delimiter` to end non transparent frames instead of a newline.
+    pub fn with_delimiter(self, delimiter: u8) -> Self {
+        MessageEncoder { delimiter, ..self }
+    }
+
+    /// Append a frame holding the raw message to `dst`.
+    pub fn encode_str(&self, msg: &str, dst: &mut Vec<u8>) {
+        match self.framing {
+            Framing::NonTransparent => {
+                dst.extend_from_slice(msg.as_bytes());
+                dst.push(self.delimiter);
+            }
+            Framing::OctetCounting | Framing::Auto => {
+                dst.extend_from_slice(msg.len().to_string().as_bytes());
+                dst.push(b' ');
+                dst.extend_from_slice(msg.as_bytes());
+            }
+        }
+    }
+
+    /// Format the message and append it to `dst` as a single frame.
+    pub fn encode<S: AsRef<str> + Ord + PartialEq + Clone>(
+        &self,
+        message: &Message<S>,
+        dst: &mut Vec<u8>,
+    ) {
+        self.encode_str(&message.to_string(), dst)
+    }
+
+    /// Format the message and append it to `dst` as a single frame.
+    #[cfg(feature = "bytes")]
+    pub fn encode_bytes<S: AsRef<str> + Ord + PartialEq + Clone>(
+        &self,
+        message: &Message<S>,
+        dst: &mut bytes::BytesMut,
+    ) {
+        let mut buf = Vec::new();
+        self.encode(message, &mut buf);
+        dst.extend_from_slice(&buf);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ParseOptions, Parser, Variant, parse_message};
+
+    #[test]
+    fn encode_round_trip() {
+        let message = parse_message(
+            "<34>1 2003-10-11T22:14:15.003Z mymachine su - ID47 - one",
+            Variant::RFC5424,
+        );
+
+        for framing in [Framing::OctetCounting, Framing::NonTransparent] {
+            let mut buf = Vec::new();
+            let encoder = MessageEncoder::new(framing);
+            encoder.encode(&message, &mut buf);
+            encoder.encode(&message, &mut buf);
+
+            let mut parser = Parser::new(ParseOptions {
+                framing,
+                ..ParseOptions::new(Variant::RFC5424)
+            });
+            parser.feed(&buf);
+            parser.finish();
+            for _ in 0..2 {
+                assert_eq!(
+                    parser.next_message().unwrap().unwrap(),
+                    message.clone().into()
+                );
+            }
+            assert!(parser.next_message().is_none());
+        }
+    }
+
+    #[test]
+    fn encode_with_delimiter() {
+        let mut buf = Vec::new();
+        MessageEncoder::new(Framing::NonTransparent)
+            .with_delimiter(0)
+            .encode_str("one", &mut buf);
+        assert_eq!(buf, b"one\0");
+    }
+}
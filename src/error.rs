@@ -1,14 +1,313 @@
-use std::{error, fmt};
+use crate::message::Message;
+use std::{error, fmt, io, str::Utf8Error};
 
-/// Wrap nom errors with our own
-#[derive(Debug)]
-pub struct ParseError<'a>(pub nom::Err<(&'a str, nom::error::ErrorKind)>);
+/// The parts of a message, used to say where a message failed to parse.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Field {
+    Pri,
+    Version,
+    Timestamp,
+    Hostname,
+    Appname,
+    ProcId,
+    MsgId,
+    StructuredData,
+    /// Not a part of the message: the input was empty or nothing but whitespace,
+    /// such as a keepalive.
+    Empty,
+}
+
+impl fmt::Display for Field {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Field::Pri => "priority",
+            Field::Version => "version",
+            Field::Timestamp => "timestamp",
+            Field::Hostname => "hostname",
+            Field::Appname => "appname",
+            Field::ProcId => "procid",
+            Field::MsgId => "msgid",
+            Field::StructuredData => "structured data",
+            Field::Empty => "message",
+        })
+    }
+}
+
+/// A rule from the ABNF of RFC5424, or from the format described by RFC3164, so a
+/// conformance report can cite the section of the RFC that a message breaks.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Rule {
+    /// The RFC, 5424 or 3164.
+    pub rfc: u16,
+    /// The rule, within the rule it is part of, such as `HEADER/HOSTNAME`.
+    pub id: &'static str,
+    /// The section of the RFC that describes the rule, such as `6.2.4`.
+    pub section: &'static str,
+}
+
+impl Rule {
+    pub(crate) const fn rfc5424(id: &'static str, section: &'static str) -> Self {
+        Rule {
+            rfc: 5424,
+            id,
+            section,
+        }
+    }
+
+    pub(crate) const fn rfc3164(id: &'static str, section: &'static str) -> Self {
+        Rule {
+            rfc: 3164,
+            id,
+            section,
+        }
+    }
+
+    /// A link to the section of the RFC.
+    pub fn url(&self) -> String {
+        format!(
+            "https://www.rfc-editor.org/rfc/rfc{}#section-{}",
+            self.rfc, self.section
+        )
+    }
+}
+
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "RFC{} section {} {}", self.rfc, self.section, self.id)
+    }
+}
+
+/// A message that could not be parsed exactly.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseError<'a> {
+    /// The position of the input within the batch being parsed.
+    /// Zero when a single message is parsed.
+    pub index: usize,
+    /// The input that failed to parse.
+    pub input: &'a str,
+    /// The byte offset within `input` of the field that couldn't be parsed.
+    pub offset: usize,
+    /// The field that couldn't be parsed.
+    pub field: Field,
+    /// What the field should have looked like, such as `an RFC3339 timestamp`.
+    pub expected: &'static str,
+    /// The rule of the RFC that the message breaks.
+    pub rule: Rule,
+}
 
 impl<'a> fmt::Display for ParseError<'a> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let ParseError(err) = self;
-        write!(f, "{:#?}", err)
+        write!(
+            f,
+            "invalid {} at byte {}: expected {}",
+            self.field, self.offset, self.expected
+        )
     }
 }
 
 impl<'a> error::Error for ParseError<'a> {}
+
+/// A message that could not be parsed exactly, along with what could be parsed of
+/// it, see [`ParseOptions::try_parse`](crate::ParseOptions::try_parse).
+#[derive(Clone, Debug, PartialEq)]
+pub struct ParseFailure<'a> {
+    /// The fields parsed before the one that failed, with the rest of the input,
+    /// starting from the field that failed, as the body.
+    pub partial: Box<Message<&'a str>>,
+    /// Why the message couldn't be parsed.
+    pub error: ParseError<'a>,
+}
+
+impl<'a> fmt::Display for ParseFailure<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.error.fmt(f)
+    }
+}
+
+impl<'a> error::Error for ParseFailure<'a> {}
+
+/// Problems encountered splitting a stream into individual messages.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum FrameError {
+    /// The frame is longer than the configured maximum and has been discarded.
+    /// `len` is the length given by an octet counted or RELP frame. With non
+    /// transparent framing the frame may be thrown away before its end arrives, so
+    /// it is always `max + 1`.
+    TooLong { len: usize, max: usize },
+    /// An octet counted frame did not start with a valid length.
+    InvalidLength,
+    /// The stream ended part way through an octet counted frame.
+    Truncated { expected: usize, actual: usize },
+    /// A RELP frame was malformed.
+    InvalidRelp,
+}
+
+impl fmt::Display for FrameError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FrameError::TooLong { len, max } => {
+                write!(f, "frame of {} bytes exceeds the maximum of {}", len, max)
+            }
+            FrameError::InvalidLength => write!(f, "invalid octet count"),
+            FrameError::Truncated { expected, actual } => write!(
+                f,
+                "stream ended after {} of {} bytes of the frame",
+                actual, expected
+            ),
+            FrameError::InvalidRelp => write!(f, "invalid RELP frame"),
+        }
+    }
+}
+
+impl error::Error for FrameError {}
+
+/// A protocol buffer encoded message could not be decoded.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ProtoError;
+
+impl fmt::Display for ProtoError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid protocol buffer encoded message")
+    }
+}
+
+impl error::Error for ProtoError {}
+
+/// A pattern that couldn't be compiled, see [`crate::Pattern`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PatternError(pub String);
+
+impl fmt::Display for PatternError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid pattern {}", self.0)
+    }
+}
+
+impl error::Error for PatternError {}
+
+/// A value that RFC5424 doesn't allow in a field, see [`Message::validate`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct InvalidField {
+    pub field: Field,
+    /// What the field should look like, such as `at most 48 printable ASCII characters`.
+    pub expected: &'static str,
+    /// The rule of the RFC that the value breaks.
+    pub rule: Rule,
+}
+
+impl fmt::Display for InvalidField {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "invalid {}, expected {} ({})",
+            self.field, self.expected, self.rule
+        )
+    }
+}
+
+impl error::Error for InvalidField {}
+
+/// A name or number that isn't a syslog facility, see [`crate::SyslogFacility`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FacilityError(pub String);
+
+impl fmt::Display for FacilityError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "unknown facility {}", self.0)
+    }
+}
+
+impl error::Error for FacilityError {}
+
+/// A name or number that isn't a syslog severity, see [`crate::SyslogSeverity`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SeverityError(pub String);
+
+impl fmt::Display for SeverityError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "unknown severity {}", self.0)
+    }
+}
+
+impl error::Error for SeverityError {}
+
+/// A frame that was not valid UTF-8 when the policy is [`crate::Utf8Policy::Strict`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct InvalidUtf8 {
+    /// The byte offset within the frame of the first invalid sequence.
+    pub offset: usize,
+    /// The invalid sequence, or the incomplete character the frame ends with.
+    pub bytes: Vec<u8>,
+}
+
+impl InvalidUtf8 {
+    pub(crate) fn new(frame: &[u8], err: Utf8Error) -> Self {
+        let offset = err.valid_up_to();
+        let len = err.error_len().unwrap_or(frame.len() - offset);
+        InvalidUtf8 {
+            offset,
+            bytes: frame[offset..offset + len].to_vec(),
+        }
+    }
+}
+
+impl fmt::Display for InvalidUtf8 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "invalid utf-8 sequence {:02x?} at byte {}",
+            self.bytes, self.offset
+        )
+    }
+}
+
+impl error::Error for InvalidUtf8 {}
+
+/// Errors returned when reading messages from a stream.
+#[derive(Debug)]
+pub enum Error {
+    /// The underlying reader failed.
+    Io(io::Error),
+    /// The stream could not be split into frames.
+    Frame(FrameError),
+    /// The frame was not valid UTF-8 and the policy is [`crate::Utf8Policy::Strict`].
+    InvalidUtf8(InvalidUtf8),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io(err) => write!(f, "io error: {}", err),
+            Error::Frame(err) => write!(f, "framing error: {}", err),
+            Error::InvalidUtf8(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Error::Io(err) => Some(err),
+            Error::Frame(err) => Some(err),
+            Error::InvalidUtf8(err) => Some(err),
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Self {
+        Error::Io(err)
+    }
+}
+
+impl From<FrameError> for Error {
+    fn from(err: FrameError) -> Self {
+        Error::Frame(err)
+    }
+}
+
+impl From<InvalidUtf8> for Error {
+    fn from(err: InvalidUtf8) -> Self {
+        Error::InvalidUtf8(err)
+    }
+}
@@ -0,0 +1,176 @@
+//! Normalising the messages sent by VMware ESXi hosts. ESXi puts the level, the
+//! component and its process ID, and an `[Originator@6876 ...]` block of key value
+//! pairs at the start of the body, rather than in the syslog header.
+use crate::message::Message;
+
+/// The levels ESXi components log at.
+const LEVELS: &[&str] = &[
+    "verbose", "info", "warning", "error", "trivia", "debug", "panic", "critical",
+];
+
+/// The parts of the body of an ESXi message, such as
+/// `info hostd[2099896] [Originator@6876 sub=Vimsvc opID=a1-b2] Task Created`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Esxi<'a> {
+    /// Such as `info`, or `In(166)` from ESXi 8.
+    pub level: Option<&'a str>,
+    /// The component that logged the message, such as `hostd`. This is a better
+    /// appname than the one in the header.
+    pub component: Option<&'a str>,
+    pub pid: Option<u32>,
+    /// The pairs from the `[Originator@6876 ...]` block.
+    pub originator: Vec<(&'a str, &'a str)>,
+    /// The CPU and world ID that prefix vmkernel messages, as in `cpu3:2097)`.
+    pub cpu: Option<u32>,
+    pub world_id: Option<u32>,
+    /// The text following the prefixes.
+    pub text: &'a str,
+}
+
+impl<'a> Esxi<'a> {
+    /// Parse the body of an ESXi message. Returns None unless there is at least one
+    /// of the prefixes that ESXi adds.
+    pub fn parse(input: &'a str) -> Option<Self> {
+        let mut esxi = Esxi {
+            level: None,
+            component: None,
+            pid: None,
+            originator: Vec::new(),
+            cpu: None,
+            world_id: None,
+            text: input.trim_start(),
+        };
+
+        let (word, rest) = esxi.text.split_once(' ').unwrap_or((esxi.text, ""));
+        if LEVELS.contains(&word) || is_numbered_level(word) {
+            esxi.level = Some(word);
+            esxi.text = rest.trim_start();
+        }
+
+        let (word, rest) = esxi.text.split_once(' ').unwrap_or((esxi.text, ""));
+        if let Some((component, pid)) = word
+            .trim_end_matches(':')
+            .strip_suffix(']')
+            .and_then(|word| word.split_once('['))
+        {
+            if let Ok(pid) = pid.parse() {
+                esxi.component = Some(component);
+                esxi.pid = Some(pid);
+                esxi.text = rest.trim_start();
+            }
+        }
+
+        if let Some(block) = esxi.text.strip_prefix("[Originator@") {
+            let end = block.find(']')?;
+            esxi.originator = block[..end]
+                .split(' ')
+                .skip(1)
+                .filter_map(|pair| pair.split_once('='))
+                .collect();
+            esxi.text = block[end + 1..].trim_start();
+        }
+
+        if let Some((cpu, rest)) = esxi
+            .text
+            .strip_prefix("cpu")
+            .and_then(|text| text.split_once(')'))
+        {
+            if let Some((cpu, world_id)) = cpu.split_once(':') {
+                if let (Ok(cpu), Ok(world_id)) = (cpu.parse(), world_id.parse()) {
+                    esxi.cpu = Some(cpu);
+                    esxi.world_id = Some(world_id);
+                    esxi.text = rest;
+                }
+            }
+        }
+
+        let recognised =
+            esxi.component.is_some() || !esxi.originator.is_empty() || esxi.cpu.is_some();
+        recognised.then_some(esxi)
+    }
+
+    /// The value of a pair from the originator block.
+    pub fn originator_field(&self, key: &str) -> Option<&'a str> {
+        self.originator
+            .iter()
+            .find(|(name, _)| *name == key)
+            .map(|(_, value)| *value)
+    }
+
+    /// The operation ID, used to follow a task across hosts and vCenter.
+    pub fn op_id(&self) -> Option<&'a str> {
+        self.originator_field("opID")
+    }
+}
+
+/// Is this an ESXi 8 level, two letters followed by the PRI, as in `In(166)`?
+fn is_numbered_level(word: &str) -> bool {
+    match word.split_once('(') {
+        Some((level, pri)) => {
+            level.len() == 2
+                && level.bytes().all(|b| b.is_ascii_alphabetic())
+                && pri
+                    .strip_suffix(')')
+                    .is_some_and(|pri| !pri.is_empty() && pri.bytes().all(|b| b.is_ascii_digit()))
+        }
+        None => false,
+    }
+}
+
+impl<S: AsRef<str> + Ord + PartialEq + Clone> Message<S> {
+    /// Parse the ESXi prefixes from the body of the message.
+    pub fn esxi(&self) -> Option<Esxi<'_>> {
+        Esxi::parse(self.msg.as_ref())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Variant, parse_message};
+
+    #[test]
+    fn parse_hostd() {
+        let message = parse_message(
+            "<166>1 2023-10-11T22:14:15.123Z esx01 Hostd 2099896 - - In(166) Hostd[2099896]: [Originator@6876 sub=Vimsvc opID=a1-b2 user=vpxuser] Task Created",
+            Variant::RFC5424,
+        );
+
+        assert_eq!(
+            message.esxi().unwrap(),
+            Esxi {
+                level: Some("In(166)"),
+                component: Some("Hostd"),
+                pid: Some(2099896),
+                originator: vec![("sub", "Vimsvc"), ("opID", "a1-b2"), ("user", "vpxuser")],
+                cpu: None,
+                world_id: None,
+                text: "Task Created",
+            }
+        );
+    }
+
+    #[test]
+    fn parse_vpxa() {
+        let message = parse_message(
+            "<166>2023-10-11T22:14:15.123Z esx01.example.com Vpxa: verbose vpxa[2098] [Originator@6876 sub=vpxLro opID=abc-123] [VpxLRO] -- BEGIN task",
+            Variant::RFC3164,
+        );
+        let esxi = message.esxi().unwrap();
+
+        assert_eq!(esxi.level, Some("verbose"));
+        assert_eq!(esxi.component, Some("vpxa"));
+        assert_eq!(esxi.op_id(), Some("abc-123"));
+        assert_eq!(esxi.text, "[VpxLRO] -- BEGIN task");
+    }
+
+    #[test]
+    fn parse_vmkernel() {
+        let esxi = Esxi::parse("cpu3:2097)ScsiDeviceIO: 3068: Cmd failed").unwrap();
+        assert_eq!(esxi.cpu, Some(3));
+        assert_eq!(esxi.world_id, Some(2097));
+        assert_eq!(esxi.text, "ScsiDeviceIO: 3068: Cmd failed");
+
+        assert_eq!(Esxi::parse("info nothing special"), None);
+    }
+}
@@ -0,0 +1,325 @@
+//! Matching messages against a set of conditions, for routing them.
+use crate::{
+    lazy::LazyMessage,
+    message::Message,
+    pri::{SyslogFacility, SyslogSeverity},
+    structured_data::StructuredElement,
+};
+use std::ops::{Bound, RangeBounds};
+
+/// Does `text` match `glob`, where `*` matches any run of characters and `?` any
+/// single character?
+fn glob_match(glob: &str, text: &str) -> bool {
+    let (glob, text) = (glob.as_bytes(), text.as_bytes());
+    let (mut g, mut t) = (0, 0);
+    // Where to carry on from if the text after the last `*` doesn't match.
+    let mut star = None;
+    while t < text.len() {
+        match glob.get(g) {
+            Some(b'*') => {
+                star = Some((g, t));
+                g += 1;
+            }
+            Some(b'?') => {
+                // Step over a whole character.
+                g += 1;
+                t += 1;
+                while t < text.len() && text[t] & 0xc0 == 0x80 {
+                    t += 1;
+                }
+            }
+            Some(c) if *c == text[t] => {
+                g += 1;
+                t += 1;
+            }
+            _ => match star {
+                Some((star_g, star_t)) => {
+                    // Let the `*` take one more character.
+                    t = star_t + 1;
+                    while t < text.len() && text[t] & 0xc0 == 0x80 {
+                        t += 1;
+                    }
+                    g = star_g + 1;
+                    star = Some((star_g, t));
+                }
+                None => return false,
+            },
+        }
+    }
+    glob[g..].iter().all(|c| *c == b'*')
+}
+
+/// A structured data param that must be present.
+#[derive(Clone, Debug)]
+struct ParamCondition {
+    id: String,
+    name: String,
+    /// The value it must have, escaped as it is in the message, if any.
+    value: Option<String>,
+}
+
+/// A set of conditions on the fields of a message, all of which must hold for the
+/// message to match. A filter with no conditions matches everything.
+///
+/// ```
+/// use syslog_loose::{MessageFilter, SyslogFacility, SyslogSeverity, Variant, parse_message};
+///
+/// let filter = MessageFilter::new()
+///     .with_severity(..=SyslogSeverity::SEV_WARNING)
+///     .with_facilities(vec![SyslogFacility::LOG_AUTH, SyslogFacility::LOG_AUTHPRIV])
+///     .with_hostname("web-*")
+///     .with_msg_containing("failed");
+///
+/// let message = parse_message("<34>Oct 11 22:14:15 web-01 su: login failed", Variant::RFC3164);
+/// assert!(filter.matches(&message));
+/// let message = parse_message("<38>Oct 11 22:14:15 web-01 su: login failed", Variant::RFC3164);
+/// assert!(!filter.matches(&message));
+/// ```
+///
+/// A [`Parser`](crate::Parser) can drop the messages that don't match as it
+/// decodes them, without parsing more than their header where the conditions
+/// allow, see [`Parser::with_message_filter`](crate::Parser::with_message_filter).
+#[derive(Clone, Debug, Default)]
+pub struct MessageFilter {
+    severity: Option<(Bound<SyslogSeverity>, Bound<SyslogSeverity>)>,
+    facilities: Option<Vec<SyslogFacility>>,
+    hostname: Option<String>,
+    appname: Option<String>,
+    params: Vec<ParamCondition>,
+    contains: Vec<String>,
+    #[cfg(feature = "regex")]
+    regex: Option<regex::Regex>,
+}
+
+impl MessageFilter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Only messages with a severity in `range`. Severities are ordered by their
+    /// number, so the most severe come first: `..=SyslogSeverity::SEV_WARNING` is
+    /// warnings and anything more severe.
+    pub fn with_severity(self, range: impl RangeBounds<SyslogSeverity>) -> Self {
+        MessageFilter {
+            severity: Some((range.start_bound().cloned(), range.end_bound().cloned())),
+            ..self
+        }
+    }
+
+    /// Only messages with one of `facilities`.
+    pub fn with_facilities(self, facilities: impl IntoIterator<Item = SyslogFacility>) -> Self {
+        MessageFilter {
+            facilities: Some(facilities.into_iter().collect()),
+            ..self
+        }
+    }
+
+    /// Only messages with a hostname matching `glob`, where `*` matches any run of
+    /// characters and `?` any single character.
+    pub fn with_hostname(self, glob: &str) -> Self {
+        MessageFilter {
+            hostname: Some(glob.to_string()),
+            ..self
+        }
+    }
+
+    /// Only messages with an appname matching `glob`, as for
+    /// [`MessageFilter::with_hostname`].
+    pub fn with_appname(self, glob: &str) -> Self {
+        MessageFilter {
+            appname: Some(glob.to_string()),
+            ..self
+        }
+    }
+
+    /// Only messages with a structured data element `id` with the param `name`
+    /// equal to `value`, which is compared as it is escaped in the message.
+    pub fn with_param(mut self, id: &str, name: &str, value: &str) -> Self {
+        self.params.push(ParamCondition {
+            id: id.to_string(),
+            name: name.to_string(),
+            value: Some(value.to_string()),
+        });
+        self
+    }
+
+    /// Only messages with a structured data element `id` with the param `name`.
+    pub fn with_param_exists(mut self, id: &str, name: &str) -> Self {
+        self.params.push(ParamCondition {
+            id: id.to_string(),
+            name: name.to_string(),
+            value: None,
+        });
+        self
+    }
+
+    /// Only messages with a body containing `text`.
+    pub fn with_msg_containing(mut self, text: &str) -> Self {
+        self.contains.push(text.to_string());
+        self
+    }
+
+    /// Only messages with a body matching `regex`.
+    #[cfg(feature = "regex")]
+    pub fn with_msg_regex(self, regex: regex::Regex) -> Self {
+        MessageFilter {
+            regex: Some(regex),
+            ..self
+        }
+    }
+
+    /// Does `message` meet all of the conditions?
+    pub fn matches<S: AsRef<str> + Ord + PartialEq + Clone>(&self, message: &Message<S>) -> bool {
+        self.matches_header(
+            message.facility,
+            message.severity,
+            message.hostname.as_ref().map(AsRef::as_ref),
+            message.appname.as_ref().map(AsRef::as_ref),
+        ) && self.matches_params(&message.structured_data)
+            && self.matches_msg(message.msg.as_ref())
+    }
+
+    /// Does `message` meet all of the conditions? The structured data is only
+    /// parsed if the header fields match and there are conditions on the structured
+    /// data or the body.
+    pub fn matches_lazy(&self, message: &LazyMessage<'_>) -> bool {
+        self.matches_header(
+            message.facility(),
+            message.severity(),
+            message.hostname(),
+            message.appname(),
+        ) && (self.params.is_empty() || self.matches_params(message.structured_data()))
+            && (!self.has_msg_conditions() || self.matches_msg(message.msg()))
+    }
+
+    fn matches_header(
+        &self,
+        facility: Option<SyslogFacility>,
+        severity: Option<SyslogSeverity>,
+        hostname: Option<&str>,
+        appname: Option<&str>,
+    ) -> bool {
+        let glob = |glob: &Option<String>, field: Option<&str>| match (glob, field) {
+            (None, _) => true,
+            (Some(glob), Some(field)) => glob_match(glob, field),
+            (Some(_), None) => false,
+        };
+
+        self.severity
+            .is_none_or(|range| severity.is_some_and(|severity| range.contains(&severity)))
+            && self.facilities.as_ref().is_none_or(|facilities| {
+                facility.is_some_and(|facility| facilities.contains(&facility))
+            })
+            && glob(&self.hostname, hostname)
+            && glob(&self.appname, appname)
+    }
+
+    fn matches_params<S: AsRef<str> + Ord + Clone>(
+        &self,
+        structured_data: &[StructuredElement<S>],
+    ) -> bool {
+        self.params.iter().all(|condition| {
+            structured_data.iter().any(|element| {
+                element.id.as_ref() == condition.id
+                    && element.params.iter().any(|(name, value)| {
+                        name.as_ref() == condition.name
+                            && condition
+                                .value
+                                .as_ref()
+                                .is_none_or(|expected| value.as_ref() == expected)
+                    })
+            })
+        })
+    }
+
+    fn matches_msg(&self, msg: &str) -> bool {
+        #[cfg(feature = "regex")]
+        if let Some(regex) = &self.regex {
+            if !regex.is_match(msg) {
+                return false;
+            }
+        }
+        self.contains.iter().all(|text| msg.contains(text.as_str()))
+    }
+
+    fn has_msg_conditions(&self) -> bool {
+        #[cfg(feature = "regex")]
+        if self.regex.is_some() {
+            return true;
+        }
+        !self.contains.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ParseOptions, Variant, parse_message};
+
+    #[test]
+    fn globs() {
+        assert!(glob_match("web-*", "web-01"));
+        assert!(glob_match("*.example.com", "a.b.example.com"));
+        assert!(glob_match("web-??", "web-01"));
+        assert!(glob_match("?é", "aé"));
+        assert!(glob_match("*?b", "éb"));
+        assert!(glob_match("*", ""));
+        assert!(glob_match("a*b*c", "aXbYbZc"));
+        assert!(!glob_match("web-?", "web-01"));
+        assert!(!glob_match("*.example.com", "example.com"));
+        assert!(!glob_match("a*b", "ab-"));
+    }
+
+    #[test]
+    fn matches_conditions() {
+        let message = parse_message(
+            r#"<34>1 2003-10-11T22:14:15.003Z web-01 sshd - ID47 [auth user="root" method="pw"] Failed password"#,
+            Variant::RFC5424,
+        );
+        let lazy = ParseOptions::new(Variant::RFC5424).parse_lazy(
+            r#"<34>1 2003-10-11T22:14:15.003Z web-01 sshd - ID47 [auth user="root" method="pw"] Failed password"#,
+        );
+
+        let matching = [
+            MessageFilter::new(),
+            MessageFilter::new().with_severity(SyslogSeverity::SEV_EMERG..=SyslogSeverity::SEV_ERR),
+            MessageFilter::new().with_facilities(vec![SyslogFacility::LOG_AUTH]),
+            MessageFilter::new().with_appname("ssh*"),
+            MessageFilter::new().with_param("auth", "user", "root"),
+            MessageFilter::new().with_param_exists("auth", "method"),
+            MessageFilter::new().with_msg_containing("Failed"),
+        ];
+        for filter in matching.iter() {
+            assert!(filter.matches(&message), "{:?}", filter);
+            assert!(filter.matches_lazy(&lazy), "{:?}", filter);
+        }
+
+        let failing = [
+            MessageFilter::new().with_severity(SyslogSeverity::SEV_ERR..),
+            MessageFilter::new().with_facilities(vec![SyslogFacility::LOG_DAEMON]),
+            MessageFilter::new().with_hostname("db-*"),
+            MessageFilter::new().with_param("auth", "user", "admin"),
+            MessageFilter::new().with_param_exists("meta", "method"),
+            MessageFilter::new().with_msg_containing("Accepted"),
+            MessageFilter::new()
+                .with_appname("sshd")
+                .with_msg_containing("Accepted"),
+        ];
+        for filter in failing.iter() {
+            assert!(!filter.matches(&message), "{:?}", filter);
+            assert!(!filter.matches_lazy(&lazy), "{:?}", filter);
+        }
+    }
+
+    #[cfg(feature = "regex")]
+    #[test]
+    fn matches_regex() {
+        let message = parse_message(
+            "<34>Oct 11 22:14:15 web su: port 22 closed",
+            Variant::RFC3164,
+        );
+        let filter = |regex| MessageFilter::new().with_msg_regex(regex::Regex::new(regex).unwrap());
+        assert!(filter(r"port \d+").matches(&message));
+        assert!(!filter(r"^closed").matches(&message));
+    }
+}
@@ -0,0 +1,150 @@
+//! Parsing the CSV payload of pfSense and OPNsense `filterlog` messages, as
+//! documented in the [pfSense filter log format](https://docs.netgate.com/pfsense/en/latest/monitoring/logs/raw-filter-format.html).
+use crate::message::Message;
+use std::net::IpAddr;
+
+/// A firewall verdict logged by `filterlog`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Filterlog<'a> {
+    pub rule: &'a str,
+    pub sub_rule: &'a str,
+    pub anchor: &'a str,
+    pub tracker: &'a str,
+    /// The interface the packet was seen on, such as `igb1`.
+    pub interface: &'a str,
+    /// Why the packet was logged, usually `match`.
+    pub reason: &'a str,
+    /// `pass`, `block` or `reject`.
+    pub action: &'a str,
+    /// `in` or `out`.
+    pub direction: &'a str,
+    /// 4 or 6.
+    pub ip_version: u8,
+    /// The protocol name in lower case, such as `tcp`.
+    pub protocol: &'a str,
+    pub protocol_id: Option<u8>,
+    /// The length of the packet.
+    pub length: Option<u32>,
+    pub src: IpAddr,
+    pub dst: IpAddr,
+    /// Only given for TCP and UDP.
+    pub src_port: Option<u16>,
+    /// Only given for TCP and UDP.
+    pub dst_port: Option<u16>,
+    /// Only given for TCP, such as `S` or `FA`.
+    pub tcp_flags: Option<&'a str>,
+}
+
+impl<'a> Filterlog<'a> {
+    /// Parse a `filterlog` CSV payload.
+    pub fn parse(input: &'a str) -> Option<Self> {
+        let fields = input.trim().split(',').collect::<Vec<_>>();
+        let field = |idx: usize| fields.get(idx).copied();
+
+        // The position of the protocol details depends on the IP version.
+        let ip_version = field(8)?.parse().ok()?;
+        let (protocol, protocol_id, length, src, dst, rest) = match ip_version {
+            4 => (16, 15, 17, 18, 19, 20),
+            6 => (12, 13, 14, 15, 16, 17),
+            _ => return None,
+        };
+
+        let protocol = field(protocol)?;
+        let ports = match protocol {
+            "tcp" | "udp" => Some((field(rest)?.parse().ok()?, field(rest + 1)?.parse().ok()?)),
+            _ => None,
+        };
+
+        Some(Filterlog {
+            rule: field(0)?,
+            sub_rule: field(1)?,
+            anchor: field(2)?,
+            tracker: field(3)?,
+            interface: field(4)?,
+            reason: field(5)?,
+            action: field(6)?,
+            direction: field(7)?,
+            ip_version,
+            protocol,
+            protocol_id: field(protocol_id)?.parse().ok(),
+            length: field(length)?.parse().ok(),
+            src: field(src)?.parse().ok()?,
+            dst: field(dst)?.parse().ok()?,
+            src_port: ports.map(|(src, _)| src),
+            dst_port: ports.map(|(_, dst)| dst),
+            tcp_flags: if protocol == "tcp" {
+                field(rest + 3)
+            } else {
+                None
+            },
+        })
+    }
+}
+
+impl<S: AsRef<str> + Ord + PartialEq + Clone> Message<S> {
+    /// Parse the body of the message as a firewall verdict if the appname is
+    /// `filterlog`.
+    pub fn filterlog(&self) -> Option<Filterlog<'_>> {
+        match &self.appname {
+            Some(appname) if appname.as_ref() == "filterlog" => Filterlog::parse(self.msg.as_ref()),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Variant, parse_message};
+
+    #[test]
+    fn parse_ipv4_tcp() {
+        let message = parse_message(
+            "<134>Oct 11 22:14:15 firewall filterlog[123]: 5,,,1000000103,igb1,match,block,in,4,0x0,,64,0,0,DF,6,tcp,60,10.0.0.1,10.0.0.2,51234,443,0,S,1234,,64240,,mss",
+            Variant::RFC3164,
+        );
+
+        assert_eq!(
+            message.filterlog().unwrap(),
+            Filterlog {
+                rule: "5",
+                sub_rule: "",
+                anchor: "",
+                tracker: "1000000103",
+                interface: "igb1",
+                reason: "match",
+                action: "block",
+                direction: "in",
+                ip_version: 4,
+                protocol: "tcp",
+                protocol_id: Some(6),
+                length: Some(60),
+                src: "10.0.0.1".parse().unwrap(),
+                dst: "10.0.0.2".parse().unwrap(),
+                src_port: Some(51234),
+                dst_port: Some(443),
+                tcp_flags: Some("S"),
+            }
+        );
+    }
+
+    #[test]
+    fn parse_ipv6_udp_and_icmp() {
+        let log = Filterlog::parse(
+            "9,,,1000000105,em0,match,pass,out,6,0x00,0x00000,64,udp,17,48,fe80::1,ff02::1,546,547,48",
+        )
+        .unwrap();
+        assert_eq!(log.src, "fe80::1".parse::<IpAddr>().unwrap());
+        assert_eq!(log.dst_port, Some(547));
+        assert_eq!(log.tcp_flags, None);
+
+        let log = Filterlog::parse(
+            "5,,,1000000103,igb1,match,block,in,4,0x0,,64,0,0,none,1,icmp,84,10.0.0.1,10.0.0.2,request,1234,1",
+        )
+        .unwrap();
+        assert_eq!(log.protocol, "icmp");
+        assert_eq!(log.src_port, None);
+
+        assert_eq!(Filterlog::parse("not,a,filter,log"), None);
+    }
+}
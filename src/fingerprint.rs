@@ -0,0 +1,131 @@
+//! Fingerprinting messages to spot the same event sent over and over.
+use crate::message::Message;
+
+/// The params of the `meta` element that change with every message sent.
+const VOLATILE_META: [&str; 2] = ["sequenceId", "sysUpTime"];
+
+/// A 64 bit FNV-1a hash, which unlike the std hashers is the same in every
+/// process and Rust version, so fingerprints can be stored or compared between
+/// collectors.
+struct Fnv(u64);
+
+impl Fnv {
+    fn new() -> Self {
+        Fnv(0xcbf2_9ce4_8422_2325)
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 ^= u64::from(*byte);
+            self.0 = self.0.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+
+    /// Write a field, marking where it ends so that moving text from one field to
+    /// the next changes the hash.
+    fn field(&mut self, field: Option<&str>) {
+        match field {
+            Some(field) => {
+                self.write(&[1]);
+                self.write(field.as_bytes());
+                self.write(&[0xff]);
+            }
+            None => self.write(&[0]),
+        }
+    }
+}
+
+impl<S: AsRef<str> + Ord + PartialEq + Clone> Message<S> {
+    /// A hash of the facility, severity, hostname, appname, structured data and
+    /// body, for spotting a storm of duplicates where the same event arrives many
+    /// times with different timestamps.
+    ///
+    /// The timestamp, procid and msgid are left out, as are the `sequenceId` and
+    /// `sysUpTime` params of the `meta` element. Params are hashed regardless of
+    /// their order, as they are compared. The hash is the same across processes and
+    /// versions of Rust.
+    ///
+    /// ```
+    /// use syslog_loose::{Variant, parse_message};
+    ///
+    /// let first = parse_message(
+    ///     r#"<34>1 2003-10-11T22:14:15Z host su - - [meta sequenceId="1"] failed"#,
+    ///     Variant::RFC5424,
+    /// );
+    /// let second = parse_message(
+    ///     r#"<34>1 2003-10-11T22:14:16Z host su - - [meta sequenceId="2"] failed"#,
+    ///     Variant::RFC5424,
+    /// );
+    /// assert_eq!(first.fingerprint(), second.fingerprint());
+    /// ```
+    pub fn fingerprint(&self) -> u64 {
+        let mut hash = Fnv::new();
+        hash.write(&[
+            self.facility.map_or(0xff, |facility| facility.code()),
+            self.severity.map_or(0xff, |severity| severity.code()),
+        ]);
+        hash.field(self.hostname.as_ref().map(AsRef::as_ref));
+        hash.field(self.appname.as_ref().map(AsRef::as_ref));
+
+        let mut params = Vec::new();
+        for element in &self.structured_data {
+            let id = element.id.as_ref();
+            hash.field(Some(id));
+            params.clear();
+            params.extend(
+                element
+                    .params
+                    .iter()
+                    .map(|(name, value)| (name.as_ref(), value.as_ref()))
+                    .filter(|(name, _)| id != "meta" || !VOLATILE_META.contains(name)),
+            );
+            params.sort_unstable();
+            for (name, value) in &params {
+                hash.field(Some(name));
+                hash.field(Some(value));
+            }
+        }
+
+        hash.field(Some(self.msg.as_ref()));
+        hash.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Variant, parse_message};
+
+    #[test]
+    fn ignores_what_changes() {
+        let fingerprint = |msg| parse_message(msg, Variant::RFC5424).fingerprint();
+        let first = fingerprint(
+            r#"<34>1 2003-10-11T22:14:15Z host su 1 ID1 [meta sequenceId="1"][x a="1" b="2"] failed"#,
+        );
+
+        assert_eq!(
+            first,
+            fingerprint(
+                r#"<34>1 2003-10-11T22:14:16Z host su 2 ID2 [meta sequenceId="2"][x b="2" a="1"] failed"#
+            )
+        );
+        assert_ne!(
+            first,
+            fingerprint(r#"<34>1 2003-10-11T22:14:15Z host su 1 ID1 [meta][x a="1" b="3"] failed"#)
+        );
+        assert_ne!(
+            first,
+            fingerprint(
+                r#"<35>1 2003-10-11T22:14:15Z host su 1 ID1 [meta sequenceId="1"][x a="1" b="2"] failed"#
+            )
+        );
+        assert_ne!(
+            fingerprint("<34>1 2003-10-11T22:14:15Z host su - - - failed"),
+            fingerprint("<34>1 2003-10-11T22:14:15Z hostsu - - - - failed"),
+        );
+        // Stays the same from one release to the next.
+        assert_eq!(
+            fingerprint("<34>1 2003-10-11T22:14:15Z host su - - - failed"),
+            15535556197822599202
+        );
+    }
+}
@@ -0,0 +1,152 @@
+//! Parsing the `key=value` bodies of Fortinet FortiGate logs.
+use crate::{logfmt::parse_logfmt, message::Message};
+use chrono::prelude::*;
+use std::{borrow::Cow, collections::BTreeMap};
+
+/// A FortiGate log, such as
+/// `date=2023-10-11 time=22:14:15 devname="FGT60E" logid="0000000013" type="traffic"`.
+///
+/// Values are optionally quoted with `"`, with `\"` standing for a quote.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Fortigate<'a> {
+    pub fields: BTreeMap<&'a str, Cow<'a, str>>,
+}
+
+impl<'a> Fortigate<'a> {
+    /// Parse a FortiGate log body. Returns None unless the body has the `date` and
+    /// `time` fields along with a `logid` or `devid`.
+    ///
+    /// FortiGate can be configured to send logs without a syslog header, so any
+    /// leading `<PRI>` is skipped.
+    pub fn parse(input: &'a str) -> Option<Self> {
+        let input = input.trim_start();
+        let input = input
+            .strip_prefix('<')
+            .and_then(|rest| rest.split_once('>'))
+            .filter(|(pri, _)| !pri.is_empty() && pri.bytes().all(|b| b.is_ascii_digit()))
+            .map_or(input, |(_, rest)| rest);
+
+        Fortigate::from_fields(parse_logfmt(input))
+    }
+
+    fn from_fields(fields: BTreeMap<&'a str, Cow<'a, str>>) -> Option<Self> {
+        let recognised = fields.contains_key("date")
+            && fields.contains_key("time")
+            && (fields.contains_key("logid") || fields.contains_key("devid"));
+        recognised.then_some(Fortigate { fields })
+    }
+
+    /// The value of the field, if present.
+    pub fn field(&self, key: &str) -> Option<&str> {
+        self.fields.get(key).map(|value| value.as_ref())
+    }
+
+    /// The time of the log from the `date` and `time` fields, in the offset given by
+    /// the `tz` field. UTC is assumed if there is no valid `tz`.
+    pub fn timestamp(&self) -> Option<DateTime<FixedOffset>> {
+        let date = NaiveDate::parse_from_str(self.field("date")?, "%Y-%m-%d").ok()?;
+        let time = NaiveTime::parse_from_str(self.field("time")?, "%H:%M:%S").ok()?;
+        let offset = self
+            .field("tz")
+            .and_then(parse_offset)
+            .unwrap_or_else(|| FixedOffset::east_opt(0).unwrap());
+
+        offset.from_local_datetime(&date.and_time(time)).single()
+    }
+}
+
+/// Parse an offset of the form `+0200` or `-05:30`.
+fn parse_offset(tz: &str) -> Option<FixedOffset> {
+    let (sign, digits) = match tz.as_bytes().first()? {
+        b'+' => (1, &tz[1..]),
+        b'-' => (-1, &tz[1..]),
+        _ => return None,
+    };
+    let digits = digits.replace(':', "");
+    if digits.len() != 4 || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let hours: i32 = digits[..2].parse().ok()?;
+    let minutes: i32 = digits[2..].parse().ok()?;
+    FixedOffset::east_opt(sign * (hours * 3600 + minutes * 60))
+}
+
+impl<S: AsRef<str> + Ord + PartialEq + Clone> Message<S> {
+    /// Parse the body of the message as a FortiGate log.
+    ///
+    /// FortiGate doesn't send a tag, so RFC3164 parsing takes the first pair, up to
+    /// the first space, as the appname. When the appname contains a `=` its pair is
+    /// included.
+    pub fn fortigate(&self) -> Option<Fortigate<'_>> {
+        match &self.appname {
+            Some(appname) if appname.as_ref().contains('=') => {
+                let mut fields = parse_logfmt(appname.as_ref());
+                fields.extend(parse_logfmt(self.msg.as_ref()));
+                Fortigate::from_fields(fields)
+            }
+            _ => Fortigate::parse(self.msg.as_ref()),
+        }
+    }
+
+    /// If the message has no timestamp in its header, but is a FortiGate log, take
+    /// the timestamp from the `date` and `time` fields of the log.
+    pub fn with_fortigate_timestamp(mut self) -> Self {
+        if self.timestamp.is_none() {
+            self.timestamp = self.fortigate().and_then(|log| log.timestamp());
+        }
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Variant, parse_message};
+
+    #[test]
+    fn parse_without_header() {
+        let message = parse_message(
+            r#"<189>date=2023-10-11 time=22:14:15 devname="FGT60E" devid="FGT60E0000" logid="0000000013" type="traffic" tz="+0200" srcip=10.0.0.1 msg="a \"b\"""#,
+            Variant::Either,
+        );
+        assert_eq!(message.timestamp, None);
+
+        let log = message.fortigate().unwrap();
+        assert_eq!(log.field("devname"), Some("FGT60E"));
+        assert_eq!(log.field("srcip"), Some("10.0.0.1"));
+        assert_eq!(log.field("msg"), Some(r#"a "b""#));
+
+        let message = message.with_fortigate_timestamp();
+        assert_eq!(
+            message.timestamp,
+            Some(
+                FixedOffset::east_opt(7200)
+                    .unwrap()
+                    .with_ymd_and_hms(2023, 10, 11, 22, 14, 15)
+                    .unwrap()
+            )
+        );
+    }
+
+    #[test]
+    fn parse_with_header() {
+        let message = parse_message(
+            r#"<189>Oct 11 22:14:15 fw date=2023-10-11 time=22:14:15 devname="FGT60E" logid="0000000013""#,
+            Variant::RFC3164,
+        );
+        let log = message.fortigate().unwrap();
+        assert_eq!(log.fields.len(), 4);
+        assert_eq!(log.field("date"), Some("2023-10-11"));
+
+        // The header timestamp is kept.
+        let timestamp = message.timestamp;
+        assert_eq!(message.with_fortigate_timestamp().timestamp, timestamp);
+    }
+
+    #[test]
+    fn not_fortigate() {
+        assert_eq!(Fortigate::parse("level=info msg=hello"), None);
+        assert_eq!(parse_offset("-05:30").unwrap().local_minus_utc(), -19800);
+        assert_eq!(parse_offset("UTC"), None);
+    }
+}
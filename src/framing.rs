@@ -0,0 +1,1005 @@
+//! Locating individual messages within a stream of bytes.
+use crate::{
+    Variant,
+    error::{Error, FrameError},
+    message::Message,
+    options::ParseOptions,
+    stats::DecoderStats,
+};
+use std::ops::Range;
+
+/// How messages are separated from each other in a stream.
+/// See [RFC6587](https://www.rfc-editor.org/rfc/rfc6587#section-3.4).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Framing {
+    /// Each message is terminated by a delimiter, by default a newline.
+    NonTransparent,
+    /// Each message is prefixed by its length in bytes followed by a space.
+    OctetCounting,
+    /// Work out which of the above the sender is using from the start of the first
+    /// frame and use that for the rest of the stream. A frame starting with digits
+    /// followed by a space is taken as octet counting, since a message would start
+    /// with `<`.
+    Auto,
+}
+
+/// The bytes that terminate a frame when using [`Framing::NonTransparent`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Delimiters {
+    /// A frame ends at any one of these bytes.
+    Any(&'static [u8]),
+    /// Work out from the first frame whether the sender terminates frames with a
+    /// newline, a NUL or both, and stick with that for the rest of the stream.
+    Auto,
+}
+
+impl Delimiters {
+    /// Frames are terminated by a newline.
+    pub const NEWLINE: Delimiters = Delimiters::Any(b"\n");
+    /// Frames are terminated by a NUL byte.
+    pub const NUL: Delimiters = Delimiters::Any(b"\0");
+    /// Frames are terminated by either a newline or a NUL byte.
+    pub const NEWLINE_OR_NUL: Delimiters = Delimiters::Any(b"\n\0");
+}
+
+impl Default for Delimiters {
+    fn default() -> Self {
+        Delimiters::NEWLINE
+    }
+}
+
+/// What to do when an octet counted stream contains a frame without a valid length,
+/// meaning we no longer know where the frames start.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Resync {
+    /// Report the error and throw away the rest of the stream.
+    Abort,
+    /// Report the error and scan forward for something that looks like the start
+    /// of a frame - a length following whitespace, followed by a space and `<`.
+    /// Further errors are not reported until a frame is found.
+    Scan,
+}
+
+/// What to do with frames longer than the maximum frame length.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Oversize {
+    /// Throw the frame away and report a [`FrameError::TooLong`].
+    Error,
+    /// Silently throw the frame away.
+    Drop,
+    /// Keep the start of the frame, up to the maximum length, and throw away the rest.
+    /// The message is flagged as being truncated.
+    Truncate,
+}
+
+/// The result of a single attempt at locating a frame.
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) enum Step {
+    /// A complete frame. `consumed` bytes should be removed from the front of the
+    /// buffer once the frame has been dealt with.
+    Frame {
+        range: Range<usize>,
+        consumed: usize,
+    },
+    /// The start of a frame that was too long, the rest of which has been dropped.
+    Truncated {
+        range: Range<usize>,
+        consumed: usize,
+    },
+    /// `consumed` bytes should be thrown away, optionally reporting an error.
+    Skip {
+        consumed: usize,
+        error: Option<FrameError>,
+    },
+    /// More input is needed before anything can be done.
+    Incomplete,
+}
+
+/// The longest octet count we will accept - enough digits for any sensible length.
+const MAX_LENGTH_DIGITS: usize = 10;
+
+/// Splits a buffer into frames. Holds no data itself so it can be driven by any
+/// source of bytes.
+#[derive(Clone, Debug)]
+pub(crate) struct FrameLocator {
+    framing: Framing,
+    delimiters: Delimiters,
+    max_frame_len: usize,
+    oversize: Oversize,
+    /// Remaining bytes of an oversized frame that are to be thrown away.
+    /// For non transparent framing this is `usize::MAX` as we discard up to the
+    /// next delimiter instead.
+    discarding: usize,
+    resync: Resync,
+    /// Set when we are scanning for the next frame after an invalid one.
+    resyncing: bool,
+    /// Set when we have given up on the stream.
+    aborted: bool,
+    pub(crate) stats: DecoderStats,
+    /// The variant of the last message parsed, tried first for the next.
+    pub(crate) last_variant: Option<Variant>,
+}
+
+impl FrameLocator {
+    pub(crate) fn new(options: &ParseOptions) -> Self {
+        FrameLocator {
+            framing: options.framing,
+            delimiters: options.delimiters,
+            max_frame_len: options.max_frame_len,
+            oversize: options.oversize,
+            discarding: 0,
+            resync: options.resync,
+            resyncing: false,
+            aborted: false,
+            stats: DecoderStats::default(),
+            last_variant: None,
+        }
+    }
+
+    /// The bytes that may currently end a frame.
+    fn delimiter_set(&self) -> &'static [u8] {
+        match self.delimiters {
+            Delimiters::Any(delimiters) => delimiters,
+            Delimiters::Auto => b"\n\0",
+        }
+    }
+
+    /// Find the position of the next delimiter, settling on the delimiters
+    /// if they are being detected.
+    fn find_delimiter(&mut self, buf: &[u8], eof: bool) -> Option<usize> {
+        let delimiters = self.delimiter_set();
+        let pos = buf.iter().position(|b| delimiters.contains(b))?;
+
+        if self.delimiters == Delimiters::Auto {
+            self.delimiters = match (buf[pos], buf.get(pos + 1)) {
+                // Some senders terminate with both.
+                (b'\n', Some(b'\0')) | (b'\0', Some(b'\n')) => Delimiters::NEWLINE_OR_NUL,
+                // We can't tell yet if the other follows.
+                (_, None) if !eof => return None,
+                (b'\n', _) => Delimiters::NEWLINE,
+                _ => Delimiters::NUL,
+            };
+        }
+
+        Some(pos)
+    }
+
+    /// Look for the next frame at the start of `buf`.
+    /// If `eof` is set, there is no more data to come, so any trailing partial frame
+    /// is returned as is.
+    pub(crate) fn locate(&mut self, buf: &[u8], eof: bool) -> Step {
+        let step = self.next_step(buf, eof);
+        match step {
+            Step::Frame { .. } => self.stats.frames += 1,
+            Step::Truncated { .. } => {
+                self.stats.frames += 1;
+                self.stats.truncations += 1;
+            }
+            Step::Skip { error: Some(_), .. } => self.stats.errors += 1,
+            Step::Skip { error: None, .. } | Step::Incomplete => {}
+        }
+        step
+    }
+
+    fn next_step(&mut self, buf: &[u8], eof: bool) -> Step {
+        if buf.is_empty() {
+            return Step::Incomplete;
+        }
+
+        if self.aborted {
+            return Step::Skip {
+                consumed: buf.len(),
+                error: None,
+            };
+        }
+
+        if self.discarding > 0 {
+            return self.discard(buf);
+        }
+
+        match self.framing {
+            Framing::NonTransparent => self.non_transparent(buf, eof),
+            Framing::OctetCounting => self.octet_counting(buf, eof),
+            Framing::Auto => match detect_framing(buf, eof) {
+                Some(framing) => {
+                    self.framing = framing;
+                    self.next_step(buf, eof)
+                }
+                None => Step::Incomplete,
+            },
+        }
+    }
+
+    fn discard(&mut self, buf: &[u8]) -> Step {
+        let consumed = if self.discarding == usize::MAX {
+            let delimiters = self.delimiter_set();
+            match buf.iter().position(|b| delimiters.contains(b)) {
+                Some(pos) => {
+                    self.discarding = 0;
+                    pos + 1
+                }
+                None => buf.len(),
+            }
+        } else {
+            let consumed = self.discarding.min(buf.len());
+            self.discarding -= consumed;
+            consumed
+        };
+
+        Step::Skip {
+            consumed,
+            error: None,
+        }
+    }
+
+    fn non_transparent(&mut self, buf: &[u8], eof: bool) -> Step {
+        // How much of an oversized frame has arrived depends on how the input was
+        // split, so it is only reported as being one byte too long.
+        let too_long = self.max_frame_len + 1;
+        let (end, consumed) = match self.find_delimiter(buf, eof) {
+            Some(pos) => (pos, pos + 1),
+            None if buf.len() > self.max_frame_len => {
+                self.discarding = usize::MAX;
+                return self.oversized(buf, 0, too_long, buf.len());
+            }
+            None if eof => (buf.len(), buf.len()),
+            None => return Step::Incomplete,
+        };
+
+        let end = if end > 0 && buf[end - 1] == b'\r' {
+            end - 1
+        } else {
+            end
+        };
+
+        if end == 0 && self.delimiters == Delimiters::NEWLINE_OR_NUL {
+            // The second half of a newline and NUL terminator.
+            Step::Skip {
+                consumed,
+                error: None,
+            }
+        } else if is_blank(&buf[..end]) {
+            self.blank(consumed)
+        } else if end > self.max_frame_len {
+            self.oversized(buf, 0, too_long, consumed)
+        } else {
+            Step::Frame {
+                range: 0..end,
+                consumed,
+            }
+        }
+    }
+
+    /// Skip a frame holding nothing but whitespace, such as a keepalive.
+    fn blank(&mut self, consumed: usize) -> Step {
+        self.stats.empty += 1;
+        Step::Skip {
+            consumed,
+            error: None,
+        }
+    }
+
+    /// Deal with a frame of `len` bytes starting at `start` that is longer than the maximum.
+    /// `consumed` is how much of the buffer the frame takes up.
+    fn oversized(&self, buf: &[u8], start: usize, len: usize, consumed: usize) -> Step {
+        match self.oversize {
+            Oversize::Error => Step::Skip {
+                consumed,
+                error: Some(FrameError::TooLong {
+                    len,
+                    max: self.max_frame_len,
+                }),
+            },
+            Oversize::Drop => Step::Skip {
+                consumed,
+                error: None,
+            },
+            Oversize::Truncate => {
+                let end = char_boundary(&buf[start..consumed], self.max_frame_len);
+                Step::Truncated {
+                    range: start..start + end,
+                    consumed,
+                }
+            }
+        }
+    }
+
+    /// The start of the buffer isn't a valid octet count, so we have lost track of
+    /// where the frames are.
+    fn invalid_length(&mut self, buf: &[u8]) -> Step {
+        // Only report the first error until we are back on track.
+        let error = if self.resyncing {
+            None
+        } else {
+            Some(FrameError::InvalidLength)
+        };
+
+        let consumed = match self.resync {
+            Resync::Abort => {
+                self.aborted = true;
+                buf.len()
+            }
+            Resync::Scan => {
+                if !self.resyncing {
+                    self.stats.resyncs += 1;
+                }
+                self.resyncing = true;
+                next_frame_start(buf)
+            }
+        };
+
+        Step::Skip { consumed, error }
+    }
+
+    fn octet_counting(&mut self, buf: &[u8], eof: bool) -> Step {
+        // Some senders follow each frame with a newline, skip over these.
+        let leading = buf.iter().take_while(|b| b.is_ascii_whitespace()).count();
+        if leading > 0 {
+            return Step::Skip {
+                consumed: leading,
+                error: None,
+            };
+        }
+
+        if self.resyncing {
+            // Only leave off scanning at something that is definitely a frame,
+            // otherwise the frames found would depend on how the input was split.
+            match plausible_frame_start(buf) {
+                Some(true) => {}
+                None if !eof => return Step::Incomplete,
+                _ => return self.invalid_length(buf),
+            }
+        }
+
+        let digits = buf.iter().take_while(|b| b.is_ascii_digit()).count();
+        if digits == buf.len() && digits <= MAX_LENGTH_DIGITS && !eof {
+            return Step::Incomplete;
+        }
+
+        if digits == 0 || digits > MAX_LENGTH_DIGITS || buf.get(digits) != Some(&b' ') {
+            return self.invalid_length(buf);
+        }
+
+        // Only ascii digits, so this can't fail short of overflowing.
+        let len = match std::str::from_utf8(&buf[..digits])
+            .ok()
+            .and_then(|digits| digits.parse::<usize>().ok())
+        {
+            Some(len) => len,
+            None => return self.invalid_length(buf),
+        };
+
+        let start = digits + 1;
+        if len > self.max_frame_len {
+            let available = buf.len() - start;
+            if self.oversize == Oversize::Truncate && available < self.max_frame_len && !eof {
+                // Wait until we have the part of the frame we are keeping.
+                return Step::Incomplete;
+            }
+
+            self.discarding = len.saturating_sub(available);
+            return self.oversized(buf, start, len, start + len.min(available));
+        }
+
+        if buf.len() - start >= len {
+            self.resyncing = false;
+            if is_blank(&buf[start..start + len]) {
+                return self.blank(start + len);
+            }
+            Step::Frame {
+                range: start..start + len,
+                consumed: start + len,
+            }
+        } else if eof {
+            Step::Skip {
+                consumed: buf.len(),
+                error: Some(FrameError::Truncated {
+                    expected: len,
+                    actual: buf.len() - start,
+                }),
+            }
+        } else {
+            Step::Incomplete
+        }
+    }
+}
+
+/// Is the frame empty or nothing but whitespace?
+pub(crate) fn is_blank(frame: &[u8]) -> bool {
+    frame.iter().all(u8::is_ascii_whitespace)
+}
+
+/// The length of the longest prefix of `buf` no longer than `max` that doesn't split
+/// a UTF-8 character.
+fn char_boundary(buf: &[u8], max: usize) -> usize {
+    if buf.len() <= max {
+        return buf.len();
+    }
+
+    // Back off over any continuation bytes, at most 3 for valid UTF-8.
+    (max.saturating_sub(3)..=max)
+        .rev()
+        .find(|&end| buf.get(end).is_none_or(|b| (b & 0xC0) != 0x80))
+        .unwrap_or(max)
+}
+
+/// Could this be the start of an octet counted frame?
+/// Returns None if there isn't enough data to tell.
+fn plausible_frame_start(buf: &[u8]) -> Option<bool> {
+    let digits = buf.iter().take_while(|b| b.is_ascii_digit()).count();
+    if digits == 0 || digits > MAX_LENGTH_DIGITS || buf[0] == b'0' {
+        return if buf.is_empty() { None } else { Some(false) };
+    }
+
+    match (buf.get(digits), buf.get(digits + 1)) {
+        (None, _) | (Some(b' '), None) => None,
+        (Some(b' '), Some(b'<')) => Some(true),
+        _ => Some(false),
+    }
+}
+
+/// Find the next position following whitespace that could be the start of a frame.
+fn next_frame_start(buf: &[u8]) -> usize {
+    (1..buf.len())
+        .find(|&pos| {
+            buf[pos - 1].is_ascii_whitespace() && plausible_frame_start(&buf[pos..]) != Some(false)
+        })
+        .unwrap_or(buf.len())
+}
+
+/// Sniff the framing from the start of the stream, returns None if we can't yet tell.
+fn detect_framing(buf: &[u8], eof: bool) -> Option<Framing> {
+    let start = buf
+        .iter()
+        .position(|b| !b.is_ascii_whitespace() && *b != b'\0');
+    let buf = match start {
+        Some(start) => &buf[start..],
+        None if eof => return Some(Framing::NonTransparent),
+        None => return None,
+    };
+
+    let digits = buf.iter().take_while(|b| b.is_ascii_digit()).count();
+    match buf.get(digits) {
+        Some(b' ') if digits > 0 && buf[0] != b'0' => Some(Framing::OctetCounting),
+        None if !eof && digits <= MAX_LENGTH_DIGITS => None,
+        _ => Some(Framing::NonTransparent),
+    }
+}
+
+/// A buffer of incoming bytes that hands out complete frames as they become available.
+#[derive(Clone, Debug)]
+pub(crate) struct FrameBuffer {
+    locator: FrameLocator,
+    buf: Vec<u8>,
+    /// The start of the data not yet consumed. The consumed bytes are only removed
+    /// when new data is added and they outnumber the rest, so removing them doesn't
+    /// copy the buffer for every frame.
+    start: usize,
+    eof: bool,
+    truncated: bool,
+}
+
+impl FrameBuffer {
+    pub(crate) fn new(options: &ParseOptions) -> Self {
+        FrameBuffer {
+            locator: FrameLocator::new(options),
+            buf: Vec::new(),
+            start: 0,
+            eof: false,
+            truncated: false,
+        }
+    }
+
+    /// Add more data to the end of the buffer.
+    pub(crate) fn extend(&mut self, data: &[u8]) {
+        if self.start > self.buf.len() - self.start {
+            self.buf.drain(..self.start);
+            self.start = 0;
+        }
+        self.buf.extend_from_slice(data);
+    }
+
+    /// Marks that no more data is coming, any partial frame remaining is flushed.
+    pub(crate) fn set_eof(&mut self) {
+        self.eof = true;
+    }
+
+    /// The framing in use, once detected if it is [`Framing::Auto`].
+    pub(crate) fn framing(&self) -> Framing {
+        self.locator.framing
+    }
+
+    /// The counts of what has been decoded so far.
+    pub(crate) fn stats(&self) -> DecoderStats {
+        self.locator.stats
+    }
+
+    /// Was the last frame returned truncated?
+    pub(crate) fn was_truncated(&self) -> bool {
+        self.truncated
+    }
+
+    /// Has the end of the input been reached?
+    pub(crate) fn is_eof(&self) -> bool {
+        self.eof
+    }
+
+    /// Throws away any buffered data.
+    pub(crate) fn clear(&mut self) {
+        self.buf.clear();
+        self.start = 0;
+    }
+
+    /// Calls `f` with the next complete frame, or returns None if more data is needed.
+    pub(crate) fn next_frame<R>(
+        &mut self,
+        f: impl FnOnce(&[u8]) -> R,
+    ) -> Option<Result<R, FrameError>> {
+        loop {
+            let buf = &self.buf[self.start..];
+            match self.locator.locate(buf, self.eof) {
+                Step::Frame { range, consumed } => {
+                    self.truncated = false;
+                    let result = f(&buf[range]);
+                    self.start += consumed;
+                    return Some(Ok(result));
+                }
+                Step::Truncated { range, consumed } => {
+                    self.truncated = true;
+                    let result = f(&buf[range]);
+                    self.start += consumed;
+                    return Some(Ok(result));
+                }
+                Step::Skip { consumed, error } => {
+                    self.start += consumed;
+                    if let Some(error) = error {
+                        return Some(Err(error));
+                    }
+                }
+                Step::Incomplete => return None,
+            }
+        }
+    }
+
+    /// Parse the next complete frame and pass the message, borrowed from the buffer,
+    /// to `f` along with the decoded frame. Frames skipped by the filter are passed
+    /// over. Returns None if more data is needed.
+    pub(crate) fn next_message<R>(
+        &mut self,
+        options: &ParseOptions,
+        mut f: impl FnMut(Message<&str>, &str) -> R,
+    ) -> Option<Result<R, Error>> {
+        loop {
+            let mut last = self.locator.last_variant;
+            let mut replaced = 0;
+            let result = self
+                .next_frame(|frame| {
+                    options.parse_frame_with(frame, &mut last, &mut replaced, &mut f)
+                })
+                .map(|result| result.map_err(Error::from).and_then(|message| message));
+            self.locator.last_variant = last;
+            self.locator.stats.replacements += replaced;
+
+            match result? {
+                Ok(Some((message, fallback))) => {
+                    if fallback {
+                        self.locator.stats.fallbacks += 1;
+                    }
+                    return Some(Ok(message));
+                }
+                Ok(None) => self.locator.stats.filtered += 1,
+                Err(err) => return Some(Err(err)),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn locate_non_transparent() {
+        let mut locator = FrameLocator::new(&ParseOptions {
+            framing: Framing::NonTransparent,
+            max_frame_len: 100,
+            ..Default::default()
+        });
+        assert_eq!(
+            locator.locate(b"one\r\ntwo", false),
+            Step::Frame {
+                range: 0..3,
+                consumed: 5
+            }
+        );
+        assert_eq!(locator.locate(b"two", false), Step::Incomplete);
+        assert_eq!(
+            locator.locate(b"two", true),
+            Step::Frame {
+                range: 0..3,
+                consumed: 3
+            }
+        );
+    }
+
+    #[test]
+    fn skips_blank_frames() {
+        let mut locator = FrameLocator::new(&ParseOptions::default());
+        for blank in [&b"\n"[..], b"\r\n", b" \t\n"] {
+            assert_eq!(
+                locator.locate(blank, false),
+                Step::Skip {
+                    consumed: blank.len(),
+                    error: None
+                }
+            );
+        }
+
+        let mut octets = FrameLocator::new(&ParseOptions {
+            framing: Framing::OctetCounting,
+            ..Default::default()
+        });
+        assert_eq!(
+            octets.locate(b"2   3 one", false),
+            Step::Skip {
+                consumed: 4,
+                error: None
+            }
+        );
+        assert_eq!((locator.stats.empty, octets.stats.empty), (3, 1));
+    }
+
+    #[test]
+    fn locate_non_transparent_too_long() {
+        let mut locator = FrameLocator::new(&ParseOptions {
+            framing: Framing::NonTransparent,
+            max_frame_len: 4,
+            ..Default::default()
+        });
+        assert_eq!(
+            locator.locate(b"abcdef", false),
+            Step::Skip {
+                consumed: 6,
+                error: Some(FrameError::TooLong { len: 5, max: 4 })
+            }
+        );
+        assert_eq!(
+            locator.locate(b"gh\nok\n", false),
+            Step::Skip {
+                consumed: 3,
+                error: None
+            }
+        );
+        assert_eq!(
+            locator.locate(b"ok\n", false),
+            Step::Frame {
+                range: 0..2,
+                consumed: 3
+            }
+        );
+    }
+
+    #[test]
+    fn locate_oversize_policies() {
+        let mut locator = FrameLocator::new(&ParseOptions {
+            max_frame_len: 4,
+            oversize: Oversize::Drop,
+            ..Default::default()
+        });
+        assert_eq!(
+            locator.locate(b"abcdef\nok\n", false),
+            Step::Skip {
+                consumed: 7,
+                error: None
+            }
+        );
+
+        let mut locator = FrameLocator::new(&ParseOptions {
+            max_frame_len: 4,
+            oversize: Oversize::Truncate,
+            ..Default::default()
+        });
+        assert_eq!(
+            locator.locate(b"abcdef\nok\n", false),
+            Step::Truncated {
+                range: 0..4,
+                consumed: 7
+            }
+        );
+        // Don't split the three byte character.
+        assert_eq!(
+            locator.locate("ab\u{20ac}\n".as_bytes(), false),
+            Step::Truncated {
+                range: 0..2,
+                consumed: 6
+            }
+        );
+    }
+
+    #[test]
+    fn locate_octet_counting_truncate() {
+        let mut locator = FrameLocator::new(&ParseOptions {
+            framing: Framing::OctetCounting,
+            max_frame_len: 4,
+            oversize: Oversize::Truncate,
+            ..Default::default()
+        });
+        assert_eq!(locator.locate(b"8 abc", false), Step::Incomplete);
+        assert_eq!(
+            locator.locate(b"8 abcde", false),
+            Step::Truncated {
+                range: 2..6,
+                consumed: 7
+            }
+        );
+        assert_eq!(
+            locator.locate(b"fgh2 ok", false),
+            Step::Skip {
+                consumed: 3,
+                error: None
+            }
+        );
+        assert_eq!(
+            locator.locate(b"2 ok", false),
+            Step::Frame {
+                range: 2..4,
+                consumed: 4
+            }
+        );
+    }
+
+    #[test]
+    fn locate_nul_delimited() {
+        let mut locator = FrameLocator::new(&ParseOptions {
+            delimiters: Delimiters::NEWLINE_OR_NUL,
+            ..Default::default()
+        });
+        assert_eq!(
+            locator.locate(b"one\0two\n", false),
+            Step::Frame {
+                range: 0..3,
+                consumed: 4
+            }
+        );
+        assert_eq!(
+            locator.locate(b"two\n", false),
+            Step::Frame {
+                range: 0..3,
+                consumed: 4
+            }
+        );
+    }
+
+    #[test]
+    fn locate_auto_delimiters() {
+        let mut locator = FrameLocator::new(&ParseOptions {
+            delimiters: Delimiters::Auto,
+            ..Default::default()
+        });
+        assert_eq!(locator.locate(b"one\0", false), Step::Incomplete);
+        assert_eq!(
+            locator.locate(b"one\0two\nthree\0", false),
+            Step::Frame {
+                range: 0..3,
+                consumed: 4
+            }
+        );
+        assert_eq!(locator.delimiters, Delimiters::NUL);
+        assert_eq!(
+            locator.locate(b"two\nthree\0", false),
+            Step::Frame {
+                range: 0..9,
+                consumed: 10
+            }
+        );
+
+        let mut locator = FrameLocator::new(&ParseOptions {
+            delimiters: Delimiters::Auto,
+            ..Default::default()
+        });
+        assert_eq!(
+            locator.locate(b"one\n\0two", false),
+            Step::Frame {
+                range: 0..3,
+                consumed: 4
+            }
+        );
+        assert_eq!(locator.delimiters, Delimiters::NEWLINE_OR_NUL);
+    }
+
+    #[test]
+    fn detect_framing_from_first_frame() {
+        assert_eq!(detect_framing(b"", false), None);
+        assert_eq!(detect_framing(b"12", false), None);
+        assert_eq!(detect_framing(b"12", true), Some(Framing::NonTransparent));
+        assert_eq!(
+            detect_framing(b"12 <34>1", false),
+            Some(Framing::OctetCounting)
+        );
+        assert_eq!(
+            detect_framing(b"\n12 <34>1", false),
+            Some(Framing::OctetCounting)
+        );
+        assert_eq!(
+            detect_framing(b"<34>1 2003", false),
+            Some(Framing::NonTransparent)
+        );
+        assert_eq!(
+            detect_framing(b"2003-10-11 message", false),
+            Some(Framing::NonTransparent)
+        );
+    }
+
+    #[test]
+    fn locate_auto_framing() {
+        let mut locator = FrameLocator::new(&ParseOptions {
+            framing: Framing::Auto,
+            ..Default::default()
+        });
+        assert_eq!(locator.locate(b"5", false), Step::Incomplete);
+        assert_eq!(
+            locator.locate(b"5 <34>1\n", false),
+            Step::Frame {
+                range: 2..7,
+                consumed: 7
+            }
+        );
+        assert_eq!(locator.framing, Framing::OctetCounting);
+    }
+
+    #[test]
+    fn locate_resync_scan() {
+        let mut locator = FrameLocator::new(&ParseOptions {
+            framing: Framing::OctetCounting,
+            resync: Resync::Scan,
+            ..Default::default()
+        });
+        let buf = b"3x <1>garbage 12 mid <frame\n5 <1>ok";
+        assert_eq!(
+            locator.locate(buf, false),
+            Step::Skip {
+                consumed: 28,
+                error: Some(FrameError::InvalidLength)
+            }
+        );
+        assert_eq!(
+            locator.locate(&buf[28..], false),
+            Step::Frame {
+                range: 2..7,
+                consumed: 7
+            }
+        );
+    }
+
+    /// All the frames from feeding `input` to a buffer `chunk` bytes at a time.
+    fn frames(
+        options: &ParseOptions,
+        input: &[u8],
+        chunk: usize,
+    ) -> Vec<Result<String, FrameError>> {
+        let mut buf = FrameBuffer::new(options);
+        let mut frames = Vec::new();
+        for data in input.chunks(chunk) {
+            buf.extend(data);
+            while let Some(frame) =
+                buf.next_frame(|frame| String::from_utf8_lossy(frame).into_owned())
+            {
+                frames.push(frame);
+            }
+        }
+        buf.set_eof();
+        while let Some(frame) = buf.next_frame(|frame| String::from_utf8_lossy(frame).into_owned())
+        {
+            frames.push(frame);
+        }
+        frames
+    }
+
+    #[test]
+    fn resync_independent_of_chunking() {
+        let options = ParseOptions {
+            framing: Framing::OctetCounting,
+            resync: Resync::Scan,
+            ..Default::default()
+        };
+        for input in [
+            &b"<34>Oct 11 22:14:15 host su: one\n"[..],
+            b"3x <1>garbage 12 mid <frame\n5 <1>ok",
+        ] {
+            let whole = frames(&options, input, input.len());
+            for chunk in 1..=8 {
+                assert_eq!(frames(&options, input, chunk), whole, "chunks of {}", chunk);
+            }
+        }
+        assert_eq!(
+            frames(&options, b"<34>Oct 11 22:14:15 host su: one\n", 34),
+            [Err(FrameError::InvalidLength)]
+        );
+    }
+
+    #[test]
+    fn too_long_independent_of_chunking() {
+        let options = ParseOptions {
+            max_frame_len: 4,
+            ..Default::default()
+        };
+        let input = b"abcdefgh\nok\n";
+        let whole = frames(&options, input, input.len());
+        assert_eq!(
+            whole,
+            [
+                Err(FrameError::TooLong { len: 5, max: 4 }),
+                Ok("ok".to_string())
+            ]
+        );
+        for chunk in 1..=8 {
+            assert_eq!(frames(&options, input, chunk), whole, "chunks of {}", chunk);
+        }
+    }
+
+    #[test]
+    fn compacts_consumed_frames() {
+        let mut buf = FrameBuffer::new(&ParseOptions::default());
+        buf.extend(b"one\ntwo\nthree\n");
+        let mut next = || buf.next_frame(<[u8]>::to_vec).map(Result::unwrap);
+        assert_eq!(next(), Some(b"one".to_vec()));
+        assert_eq!(next(), Some(b"two".to_vec()));
+        assert_eq!(buf.start, 8);
+
+        // Most of the buffer has been consumed, so it is moved down.
+        buf.extend(b"four\n");
+        assert_eq!((buf.start, &buf.buf[..]), (0, &b"three\nfour\n"[..]));
+        let mut next = || buf.next_frame(<[u8]>::to_vec).map(Result::unwrap);
+        assert_eq!(next(), Some(b"three".to_vec()));
+        assert_eq!(next(), Some(b"four".to_vec()));
+        assert_eq!(next(), None);
+    }
+
+    #[test]
+    fn locate_resync_abort() {
+        let mut locator = FrameLocator::new(&ParseOptions {
+            framing: Framing::OctetCounting,
+            resync: Resync::Abort,
+            ..Default::default()
+        });
+        assert_eq!(
+            locator.locate(b"3x <1>garbage", false),
+            Step::Skip {
+                consumed: 13,
+                error: Some(FrameError::InvalidLength)
+            }
+        );
+        assert_eq!(
+            locator.locate(b"5 <1>ok", false),
+            Step::Skip {
+                consumed: 7,
+                error: None
+            }
+        );
+    }
+
+    #[test]
+    fn locate_octet_counting() {
+        let mut locator = FrameLocator::new(&ParseOptions {
+            framing: Framing::OctetCounting,
+            max_frame_len: 100,
+            ..Default::default()
+        });
+        assert_eq!(locator.locate(b"11", false), Step::Incomplete);
+        assert_eq!(locator.locate(b"11 <34>1 - -", false), Step::Incomplete);
+        assert_eq!(
+            locator.locate(b"11 <34>1 - - -5 abcde", false),
+            Step::Frame {
+                range: 3..14,
+                consumed: 14
+            }
+        );
+        assert_eq!(
+            locator.locate(b"x1 abc", false),
+            Step::Skip {
+                consumed: 6,
+                error: Some(FrameError::InvalidLength)
+            }
+        );
+    }
+}
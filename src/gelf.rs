@@ -0,0 +1,175 @@
+//! Conversion to the [Graylog Extended Log Format](https://go2docs.graylog.org/current/getting_in_log_data/gelf.html).
+use crate::{message::Message, procid::ProcId};
+use std::collections::BTreeMap;
+
+/// A GELF 1.1 message.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Gelf {
+    pub host: String,
+    /// The first line of the message.
+    pub short_message: String,
+    /// The whole message if it runs over more than one line.
+    pub full_message: Option<String>,
+    /// Seconds since the epoch.
+    pub timestamp: Option<f64>,
+    /// The syslog severity.
+    pub level: Option<u8>,
+    /// The additional fields, keyed without the leading underscore.
+    pub additional: BTreeMap<String, String>,
+}
+
+/// Make `name` a valid additional field name, GELF only allows word characters,
+/// dots and dashes.
+fn field_name(name: &str) -> String {
+    name.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || matches!(c, '_' | '.' | '-') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+impl Gelf {
+    /// The message as a GELF JSON object.
+    pub fn to_json(&self) -> String {
+        let mut object = serde_json::Map::new();
+        object.insert("version".to_string(), "1.1".into());
+        object.insert("host".to_string(), self.host.as_str().into());
+        object.insert(
+            "short_message".to_string(),
+            self.short_message.as_str().into(),
+        );
+        if let Some(full_message) = &self.full_message {
+            object.insert("full_message".to_string(), full_message.as_str().into());
+        }
+        if let Some(timestamp) = self.timestamp {
+            object.insert("timestamp".to_string(), timestamp.into());
+        }
+        if let Some(level) = self.level {
+            object.insert("level".to_string(), level.into());
+        }
+        for (name, value) in &self.additional {
+            // `_id` is reserved.
+            let name = if name == "id" {
+                "__id".to_string()
+            } else {
+                format!("_{}", name)
+            };
+            object.insert(name, value.as_str().into());
+        }
+
+        serde_json::Value::Object(object).to_string()
+    }
+}
+
+impl<S: AsRef<str> + Ord + PartialEq + Clone> From<&Message<S>> for Gelf {
+    fn from(message: &Message<S>) -> Self {
+        let msg = message.msg.as_ref();
+        let short_message = msg.lines().next().unwrap_or_default().to_string();
+        let full_message = if short_message.len() < msg.trim_end().len() {
+            Some(msg.to_string())
+        } else {
+            None
+        };
+
+        let mut additional = BTreeMap::new();
+        if let Some(facility) = message.facility {
+            additional.insert("facility".to_string(), facility.as_str().to_string());
+        }
+        if let Some(appname) = &message.appname {
+            additional.insert("appname".to_string(), appname.as_ref().to_string());
+        }
+        match &message.procid {
+            Some(ProcId::PID(pid)) => {
+                additional.insert("procid".to_string(), pid.to_string());
+            }
+            Some(ProcId::Name(name)) => {
+                additional.insert("procid".to_string(), name.as_ref().to_string());
+            }
+            None => {}
+        }
+        if let Some(msgid) = &message.msgid {
+            additional.insert("msgid".to_string(), msgid.as_ref().to_string());
+        }
+        for element in &message.structured_data {
+            for (param, value) in element.params() {
+                additional.insert(
+                    field_name(&format!("{}_{}", element.id.as_ref(), param.as_ref())),
+                    value,
+                );
+            }
+        }
+
+        Gelf {
+            host: message
+                .hostname
+                .as_ref()
+                .map_or("-", |hostname| hostname.as_ref())
+                .to_string(),
+            short_message,
+            full_message,
+            timestamp: message
+                .timestamp
+                .map(|timestamp| timestamp.timestamp_millis() as f64 / 1000.0),
+            level: message.severity.map(|severity| severity.code()),
+            additional,
+        }
+    }
+}
+
+impl<S: AsRef<str> + Ord + PartialEq + Clone> From<Message<S>> for Gelf {
+    fn from(message: Message<S>) -> Self {
+        Gelf::from(&message)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Variant, parse_message};
+
+    #[test]
+    fn message_to_gelf() {
+        let gelf = Gelf::from(parse_message(
+            r#"<165>1 2003-10-11T22:14:15.003Z mymachine.example.com evntslog 12 ID47 [exampleSDID@32473 iut="3" event.Source="App"] An application event"#,
+            Variant::RFC5424,
+        ));
+
+        assert_eq!(gelf.host, "mymachine.example.com");
+        assert_eq!(gelf.short_message, "An application event");
+        assert_eq!(gelf.full_message, None);
+        assert_eq!(gelf.timestamp, Some(1065910455.003));
+        assert_eq!(gelf.level, Some(5));
+
+        let json: serde_json::Value = serde_json::from_str(&gelf.to_json()).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "version": "1.1",
+                "host": "mymachine.example.com",
+                "short_message": "An application event",
+                "timestamp": 1065910455.003,
+                "level": 5,
+                "_facility": "local4",
+                "_appname": "evntslog",
+                "_procid": "12",
+                "_msgid": "ID47",
+                "_exampleSDID_32473_iut": "3",
+                "_exampleSDID_32473_event.Source": "App",
+            })
+        );
+    }
+
+    #[test]
+    fn multi_line_message() {
+        let gelf = Gelf::from(parse_message(
+            "<34>Oct 11 22:14:15 mymachine su: first\nsecond",
+            Variant::RFC3164,
+        ));
+        assert_eq!(gelf.short_message, "first");
+        assert_eq!(gelf.full_message.as_deref(), Some("first\nsecond"));
+    }
+}
@@ -0,0 +1,184 @@
+//! Parsing HAProxy's default HTTP and TCP log formats, as described in the
+//! [HAProxy configuration manual](https://docs.haproxy.org/2.8/configuration.html#8.2).
+use crate::message::Message;
+use std::net::IpAddr;
+
+/// A request or connection logged by HAProxy.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Haproxy<'a> {
+    pub client_ip: IpAddr,
+    pub client_port: u16,
+    /// The date the connection was accepted, such as `06/Feb/2009:12:14:14.655`.
+    pub accept_date: &'a str,
+    pub frontend: &'a str,
+    pub backend: &'a str,
+    pub server: &'a str,
+    /// `TR/Tw/Tc/Tr/Ta` for HTTP logs and `Tw/Tc/Tt` for TCP logs, in milliseconds.
+    /// A timer is -1 if that stage was never reached.
+    pub timers: Vec<i64>,
+    /// The HTTP status code. None for TCP logs.
+    pub status: Option<u16>,
+    pub bytes_read: u64,
+    /// The session state at disconnection, such as `----` or `SD`.
+    pub termination_state: &'a str,
+    /// `actconn/feconn/beconn/srv_conn/retries`.
+    pub connections: Vec<u64>,
+    pub srv_queue: u64,
+    pub backend_queue: u64,
+    /// The captured request and response headers, without their braces.
+    pub captured_headers: Vec<&'a str>,
+    /// The HTTP request line. None for TCP logs.
+    pub request: Option<&'a str>,
+}
+
+/// Split the next word off the front of `input`.
+fn word<'a>(input: &mut &'a str) -> Option<&'a str> {
+    let trimmed = input.trim_start();
+    let end = trimmed.find(' ').unwrap_or(trimmed.len());
+    *input = &trimmed[end..];
+    Some(&trimmed[..end]).filter(|word| !word.is_empty())
+}
+
+/// Parse a number, which HAProxy prefixes with `+` when logging before the
+/// session has finished.
+fn number<T: std::str::FromStr>(value: &str) -> Option<T> {
+    value.strip_prefix('+').unwrap_or(value).parse().ok()
+}
+
+fn numbers<T: std::str::FromStr>(value: &str) -> Option<Vec<T>> {
+    value.split('/').map(number).collect()
+}
+
+impl<'a> Haproxy<'a> {
+    /// Parse a log in HAProxy's HTTP or TCP format.
+    pub fn parse(input: &'a str) -> Option<Self> {
+        let mut rest = input.trim();
+
+        let (client_ip, client_port) = word(&mut rest)?.rsplit_once(':')?;
+        let accept_date = word(&mut rest)?.strip_prefix('[')?.strip_suffix(']')?;
+        let frontend = word(&mut rest)?;
+        let (backend, server) = word(&mut rest)?.split_once('/')?;
+        let timers = numbers::<i64>(word(&mut rest)?)?;
+        let http = match timers.len() {
+            5 => true,
+            3 => false,
+            _ => return None,
+        };
+        let status = if http {
+            Some(number(word(&mut rest)?)?)
+        } else {
+            None
+        };
+        let bytes_read = number(word(&mut rest)?)?;
+        if http {
+            // The captured request and response cookies.
+            word(&mut rest)?;
+            word(&mut rest)?;
+        }
+        let termination_state = word(&mut rest)?;
+        let connections = numbers(word(&mut rest)?)?;
+        let (srv_queue, backend_queue) = word(&mut rest)?.split_once('/')?;
+
+        let mut captured_headers = Vec::new();
+        let mut request = None;
+        if http {
+            rest = rest.trim_start();
+            while let Some(headers) = rest.strip_prefix('{') {
+                let end = headers.find('}')?;
+                captured_headers.push(&headers[..end]);
+                rest = headers[end + 1..].trim_start();
+            }
+            // Long requests are truncated, losing the closing quote.
+            let line = rest.strip_prefix('"')?;
+            request = Some(line.strip_suffix('"').unwrap_or(line));
+        }
+
+        Some(Haproxy {
+            client_ip: client_ip
+                .trim_start_matches('[')
+                .trim_end_matches(']')
+                .parse()
+                .ok()?,
+            client_port: client_port.parse().ok()?,
+            accept_date,
+            frontend,
+            backend,
+            server,
+            timers,
+            status,
+            bytes_read,
+            termination_state,
+            connections,
+            srv_queue: number(srv_queue)?,
+            backend_queue: number(backend_queue)?,
+            captured_headers,
+            request,
+        })
+    }
+}
+
+impl<S: AsRef<str> + Ord + PartialEq + Clone> Message<S> {
+    /// Parse the body of the message as a HAProxy log if the appname is `haproxy`.
+    pub fn haproxy(&self) -> Option<Haproxy<'_>> {
+        match &self.appname {
+            Some(appname) if appname.as_ref() == "haproxy" => Haproxy::parse(self.msg.as_ref()),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Variant, parse_message};
+
+    #[test]
+    fn parse_http() {
+        let message = parse_message(
+            r#"<134>Feb  6 12:14:14 localhost haproxy[14389]: 10.0.1.2:33317 [06/Feb/2009:12:14:14.655] http-in static/srv1 10/0/30/69/109 200 2750 - - ---- 1/1/1/1/0 0/0 {1wt.eu} {} "GET /index.html HTTP/1.1""#,
+            Variant::RFC3164,
+        );
+
+        assert_eq!(
+            message.haproxy().unwrap(),
+            Haproxy {
+                client_ip: "10.0.1.2".parse().unwrap(),
+                client_port: 33317,
+                accept_date: "06/Feb/2009:12:14:14.655",
+                frontend: "http-in",
+                backend: "static",
+                server: "srv1",
+                timers: vec![10, 0, 30, 69, 109],
+                status: Some(200),
+                bytes_read: 2750,
+                termination_state: "----",
+                connections: vec![1, 1, 1, 1, 0],
+                srv_queue: 0,
+                backend_queue: 0,
+                captured_headers: vec!["1wt.eu", ""],
+                request: Some("GET /index.html HTTP/1.1"),
+            }
+        );
+    }
+
+    #[test]
+    fn parse_tcp() {
+        let log = Haproxy::parse(
+            "[::1]:33313 [06/Feb/2009:12:12:51.443] fnt bck/<NOSRV> 0/-1/+5007 +212 SC 0/0/0/0/3 0/0",
+        )
+        .unwrap();
+
+        assert_eq!(log.client_ip, "::1".parse::<IpAddr>().unwrap());
+        assert_eq!(log.server, "<NOSRV>");
+        assert_eq!(log.timers, vec![0, -1, 5007]);
+        assert_eq!(log.status, None);
+        assert_eq!(log.bytes_read, 212);
+        assert_eq!(log.termination_state, "SC");
+        assert_eq!(log.request, None);
+    }
+
+    #[test]
+    fn not_haproxy() {
+        assert_eq!(Haproxy::parse("Proxy started."), None);
+    }
+}
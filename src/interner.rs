@@ -0,0 +1,127 @@
+//! Sharing the storage of the strings that repeat from message to message.
+use crate::{message::Message, procid::ProcId, structured_data::StructuredElement};
+use std::{
+    collections::HashSet,
+    sync::{Arc, Mutex},
+};
+
+/// A set of strings that messages converted with [`Interner::message`] share.
+///
+/// A collector sees the same few hostnames and appnames over and over, so rather
+/// than allocating them for every message they are looked up here and the stored
+/// copy is reused. Clones of an interner share the same set, so one can be given
+/// to the parser for each connection.
+#[derive(Clone, Debug, Default)]
+pub struct Interner {
+    strings: Arc<Mutex<HashSet<Arc<str>>>>,
+}
+
+impl Interner {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The stored copy of `s`, storing it first if it hasn't been seen before.
+    pub fn intern(&self, s: &str) -> Arc<str> {
+        let mut strings = self.strings.lock().unwrap_or_else(|err| err.into_inner());
+        match strings.get(s) {
+            Some(interned) => interned.clone(),
+            None => {
+                let interned: Arc<str> = Arc::from(s);
+                strings.insert(interned.clone());
+                interned
+            }
+        }
+    }
+
+    /// The number of strings stored.
+    pub fn len(&self) -> usize {
+        self.strings
+            .lock()
+            .unwrap_or_else(|err| err.into_inner())
+            .len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Forget the stored strings. Messages already converted keep their copies.
+    pub fn clear(&self) {
+        self.strings
+            .lock()
+            .unwrap_or_else(|err| err.into_inner())
+            .clear();
+    }
+
+    /// Convert the message, interning the hostname, appname, procid, msgid and the
+    /// structured data ids and param names. The body and the param values rarely
+    /// repeat so are allocated as they are.
+    pub fn message<S: AsRef<str> + Ord + PartialEq + Clone>(
+        &self,
+        message: &Message<S>,
+    ) -> Message<Arc<str>> {
+        let intern = |s: &S| self.intern(s.as_ref());
+        Message {
+            protocol: message.protocol.clone(),
+            facility: message.facility,
+            severity: message.severity,
+            timestamp: message.timestamp,
+            hostname: message.hostname.as_ref().map(intern),
+            appname: message.appname.as_ref().map(intern),
+            procid: message.procid.as_ref().map(|procid| match procid {
+                ProcId::PID(pid) => ProcId::PID(*pid),
+                ProcId::Name(name) => ProcId::Name(intern(name)),
+            }),
+            msgid: message.msgid.as_ref().map(intern),
+            structured_data: message
+                .structured_data
+                .iter()
+                .map(|element| StructuredElement {
+                    id: intern(&element.id),
+                    params: element
+                        .params
+                        .iter()
+                        .map(|(name, value)| (intern(name), Arc::from(value.as_ref())))
+                        .collect(),
+                })
+                .collect(),
+            msg: Arc::from(message.msg.as_ref()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Variant, parse_message};
+
+    #[test]
+    fn shares_repeated_strings() {
+        let interner = Interner::new();
+        let one = interner.message(&parse_message(
+            "<34>1 2003-10-11T22:14:15.003Z mymachine su - ID47 [meta seq=\"1\"] one",
+            Variant::RFC5424,
+        ));
+        let two = interner.clone().message(&parse_message(
+            "<34>1 2003-10-11T22:14:16.003Z mymachine su - ID47 [meta seq=\"2\"] two",
+            Variant::RFC5424,
+        ));
+
+        assert_eq!(two.hostname.as_deref(), Some("mymachine"));
+        assert_eq!(&*two.msg, "two");
+        assert!(Arc::ptr_eq(
+            one.hostname.as_ref().unwrap(),
+            two.hostname.as_ref().unwrap()
+        ));
+        assert!(Arc::ptr_eq(
+            &one.structured_data[0].params[0].0,
+            &two.structured_data[0].params[0].0
+        ));
+        // mymachine, su, ID47, meta and seq.
+        assert_eq!(interner.len(), 5);
+
+        interner.clear();
+        assert!(interner.is_empty());
+    }
+}
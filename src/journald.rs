@@ -0,0 +1,256 @@
+//! Bridging to systemd-journald, using its [field conventions](https://www.freedesktop.org/software/systemd/man/latest/systemd.journal-fields.html)
+//! and [export format](https://systemd.io/JOURNAL_EXPORT_FORMATS/).
+use crate::{
+    error::Error,
+    message::{Message, Protocol},
+    pri::{SyslogFacility, SyslogSeverity},
+    procid::ProcId,
+};
+use chrono::{DateTime, FixedOffset};
+use std::io::{self, BufRead, Read};
+
+/// A single journal entry, the fields in the order they were given.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct JournalEntry {
+    pub fields: Vec<(String, Vec<u8>)>,
+}
+
+impl JournalEntry {
+    /// The value of the first field called `name`.
+    pub fn field(&self, name: &str) -> Option<&[u8]> {
+        self.fields
+            .iter()
+            .find(|(field, _)| field == name)
+            .map(|(_, value)| value.as_slice())
+    }
+
+    /// The value of the first field called `name`, if it is valid UTF-8.
+    pub fn field_str(&self, name: &str) -> Option<&str> {
+        self.field(name)
+            .and_then(|value| std::str::from_utf8(value).ok())
+    }
+
+    /// The entry as a message. The syslog fields are preferred, falling back to the
+    /// fields journald records about the process that logged the entry.
+    pub fn to_message(&self) -> Message<String> {
+        let number = |name: &str| self.field_str(name).and_then(|value| value.parse().ok());
+        let string = |name: &str| self.field_str(name).map(str::to_string);
+
+        let timestamp = self
+            .field_str("__REALTIME_TIMESTAMP")
+            .and_then(|micros| micros.parse().ok())
+            .and_then(DateTime::from_timestamp_micros)
+            .map(|timestamp| timestamp.with_timezone(&FixedOffset::east_opt(0).unwrap()));
+
+        Message {
+            protocol: Protocol::RFC5424(1),
+            facility: number("SYSLOG_FACILITY").and_then(SyslogFacility::from_int),
+            severity: number("PRIORITY").and_then(SyslogSeverity::from_int),
+            timestamp,
+            hostname: string("_HOSTNAME"),
+            appname: string("SYSLOG_IDENTIFIER").or_else(|| string("_COMM")),
+            procid: self
+                .field_str("SYSLOG_PID")
+                .or_else(|| self.field_str("_PID"))
+                .map(|pid| ProcId::from(pid).map(str::to_string)),
+            msgid: string("MESSAGE_ID"),
+            structured_data: vec![],
+            msg: self
+                .field("MESSAGE")
+                .map(|msg| String::from_utf8_lossy(msg).into_owned())
+                .unwrap_or_default(),
+        }
+    }
+}
+
+impl<S: AsRef<str> + Ord + PartialEq + Clone> Message<S> {
+    /// The message as journald fields, suitable for sending to journald's native
+    /// protocol or writing in the export format.
+    pub fn to_journald_fields(&self) -> Vec<(&'static str, String)> {
+        let mut fields = vec![("MESSAGE", self.msg.as_ref().to_string())];
+        if let Some(severity) = self.severity {
+            fields.push(("PRIORITY", severity.code().to_string()));
+        }
+        if let Some(facility) = self.facility {
+            fields.push(("SYSLOG_FACILITY", facility.code().to_string()));
+        }
+        if let Some(appname) = &self.appname {
+            fields.push(("SYSLOG_IDENTIFIER", appname.as_ref().to_string()));
+        }
+        if let Some(procid) = &self.procid {
+            fields.push(("SYSLOG_PID", procid.to_string()));
+        }
+        if let Some(msgid) = &self.msgid {
+            fields.push(("MESSAGE_ID", msgid.as_ref().to_string()));
+        }
+        if let Some(timestamp) = self.timestamp {
+            fields.push(("SYSLOG_TIMESTAMP", timestamp.to_rfc3339()));
+        }
+        fields
+    }
+}
+
+/// An iterator over the entries in a journal export stream, such as the output of
+/// `journalctl -o export`. An error ends the iteration.
+pub struct JournalExportReader<R> {
+    reader: R,
+    done: bool,
+}
+
+impl<R: BufRead> JournalExportReader<R> {
+    pub fn new(reader: R) -> Self {
+        JournalExportReader {
+            reader,
+            done: false,
+        }
+    }
+
+    /// Returns the underlying reader.
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+
+    fn read_entry(&mut self) -> io::Result<Option<JournalEntry>> {
+        let mut entry = JournalEntry::default();
+        let mut line = Vec::new();
+        loop {
+            line.clear();
+            if self.reader.read_until(b'\n', &mut line)? == 0 {
+                // End of the stream, the last entry needn't be followed by a blank line.
+                return Ok(if entry.fields.is_empty() {
+                    None
+                } else {
+                    Some(entry)
+                });
+            }
+
+            let line = line.strip_suffix(b"\n").unwrap_or(&line);
+            if line.is_empty() {
+                if entry.fields.is_empty() {
+                    continue;
+                }
+                return Ok(Some(entry));
+            }
+
+            let (name, value) = match line.iter().position(|&b| b == b'=') {
+                Some(pos) => (&line[..pos], line[pos + 1..].to_vec()),
+                // A binary field, the name is followed by a little endian length.
+                None => (line, self.read_binary()?),
+            };
+            let name = String::from_utf8(name.to_vec())
+                .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+            entry.fields.push((name, value));
+        }
+    }
+
+    fn read_binary(&mut self) -> io::Result<Vec<u8>> {
+        let mut len = [0; 8];
+        self.reader.read_exact(&mut len)?;
+        let len = u64::from_le_bytes(len);
+
+        let mut value = Vec::new();
+        if (&mut self.reader).take(len).read_to_end(&mut value)? as u64 != len {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+
+        let mut newline = [0];
+        self.reader.read_exact(&mut newline)?;
+        if newline != *b"\n" {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "binary field not followed by a newline",
+            ));
+        }
+        Ok(value)
+    }
+}
+
+impl<R: BufRead> Iterator for JournalExportReader<R> {
+    type Item = Result<JournalEntry, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        match self.read_entry() {
+            Ok(Some(entry)) => Some(Ok(entry)),
+            Ok(None) => {
+                self.done = true;
+                None
+            }
+            Err(err) => {
+                self.done = true;
+                Some(Err(err.into()))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Variant, parse_message};
+
+    #[test]
+    fn read_export_stream() {
+        let mut export = b"__REALTIME_TIMESTAMP=1065910455003000\n_HOSTNAME=mymachine\nSYSLOG_IDENTIFIER=su\n_PID=12\nPRIORITY=2\nSYSLOG_FACILITY=4\nMESSAGE=one\n\n".to_vec();
+        export.extend_from_slice(b"_COMM=app\nMESSAGE\n");
+        export.extend_from_slice(&7u64.to_le_bytes());
+        export.extend_from_slice(b"two\nbin\n");
+
+        let entries = JournalExportReader::new(&export[..])
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[1].field("MESSAGE"), Some(&b"two\nbin"[..]));
+
+        let message = entries[0].to_message();
+        assert_eq!(
+            message,
+            Message {
+                protocol: Protocol::RFC5424(1),
+                facility: Some(SyslogFacility::LOG_AUTH),
+                severity: Some(SyslogSeverity::SEV_CRIT),
+                timestamp: Some(DateTime::parse_from_rfc3339("2003-10-11T22:14:15.003Z").unwrap()),
+                hostname: Some("mymachine".to_string()),
+                appname: Some("su".to_string()),
+                procid: Some(ProcId::PID(12)),
+                msgid: None,
+                structured_data: vec![],
+                msg: "one".to_string(),
+            }
+        );
+        assert_eq!(entries[1].to_message().appname.as_deref(), Some("app"));
+    }
+
+    #[test]
+    fn truncated_binary_field() {
+        let mut export = b"MESSAGE\n".to_vec();
+        export.extend_from_slice(&10u64.to_le_bytes());
+        export.extend_from_slice(b"short");
+
+        let mut reader = JournalExportReader::new(&export[..]);
+        assert!(matches!(reader.next(), Some(Err(Error::Io(_)))));
+        assert!(reader.next().is_none());
+    }
+
+    #[test]
+    fn message_to_fields() {
+        let message = parse_message(
+            "<34>Oct 11 22:14:15 mymachine su[12]: one",
+            Variant::RFC3164,
+        );
+        let fields = message.to_journald_fields();
+        assert_eq!(
+            &fields[..5],
+            &[
+                ("MESSAGE", "one".to_string()),
+                ("PRIORITY", "2".to_string()),
+                ("SYSLOG_FACILITY", "4".to_string()),
+                ("SYSLOG_IDENTIFIER", "su".to_string()),
+                ("SYSLOG_PID", "12".to_string()),
+            ]
+        );
+    }
+}
@@ -0,0 +1,199 @@
+//! Converting messages to JSON, and extracting JSON payloads, such as
+//! [CEE](https://cee.mitre.org/) `@cee:` logs, from the body of a message.
+use crate::{
+    message::{Message, Protocol},
+    procid::ProcId,
+};
+use serde_json::{Map, Value};
+
+/// The message as a JSON object laid out as
+///
+/// ```json
+/// {
+///   "protocol": "rfc5424",
+///   "version": 1,
+///   "facility": "local4",
+///   "severity": "notice",
+///   "timestamp": "2003-10-11T22:14:15.003+00:00",
+///   "hostname": "mymachine",
+///   "appname": "evntslog",
+///   "procid": 12,
+///   "msgid": "ID47",
+///   "structured_data": { "exampleSDID@32473": { "iut": "3" } },
+///   "msg": "An application event"
+/// }
+/// ```
+///
+/// `protocol` is either `rfc3164` or `rfc5424`, `version` is only given for RFC5424.
+/// Missing fields are null, `procid` is a number if it is a pid, otherwise a string.
+/// Escapes in the structured data param values are removed.
+impl<S: AsRef<str> + Ord + PartialEq + Clone> From<&Message<S>> for Value {
+    fn from(message: &Message<S>) -> Self {
+        let string = |s: &Option<S>| s.as_ref().map_or(Value::Null, |s| Value::from(s.as_ref()));
+
+        let mut object = Map::new();
+        match message.protocol {
+            Protocol::RFC3164 => {
+                object.insert("protocol".to_string(), "rfc3164".into());
+            }
+            Protocol::RFC5424(version) | Protocol::Unknown(version) => {
+                object.insert("protocol".to_string(), "rfc5424".into());
+                object.insert("version".to_string(), version.into());
+            }
+        }
+        object.insert(
+            "facility".to_string(),
+            message.facility.map(|facility| facility.as_str()).into(),
+        );
+        object.insert(
+            "severity".to_string(),
+            message.severity.map(|severity| severity.as_str()).into(),
+        );
+        object.insert(
+            "timestamp".to_string(),
+            message
+                .timestamp
+                .map(|timestamp| timestamp.to_rfc3339())
+                .into(),
+        );
+        object.insert("hostname".to_string(), string(&message.hostname));
+        object.insert("appname".to_string(), string(&message.appname));
+        object.insert(
+            "procid".to_string(),
+            match &message.procid {
+                Some(ProcId::PID(pid)) => (*pid).into(),
+                Some(ProcId::Name(name)) => name.as_ref().into(),
+                None => Value::Null,
+            },
+        );
+        object.insert("msgid".to_string(), string(&message.msgid));
+        object.insert(
+            "structured_data".to_string(),
+            Value::Object(
+                message
+                    .structured_data
+                    .iter()
+                    .map(|element| {
+                        let params = element
+                            .params()
+                            .map(|(name, value)| (name.as_ref().to_string(), Value::from(value)))
+                            .collect();
+                        (element.id.as_ref().to_string(), Value::Object(params))
+                    })
+                    .collect(),
+            ),
+        );
+        object.insert("msg".to_string(), message.msg.as_ref().into());
+
+        Value::Object(object)
+    }
+}
+
+impl<S: AsRef<str> + Ord + PartialEq + Clone> From<Message<S>> for Value {
+    fn from(message: Message<S>) -> Self {
+        Value::from(&message)
+    }
+}
+
+impl<S: AsRef<str> + Ord + PartialEq + Clone> Message<S> {
+    /// Parse the JSON in the body of the message. The body is taken as JSON if it
+    /// starts with the `@cee:` cookie or with a `{`. Returns None if it doesn't.
+    ///
+    /// RFC3164 parsing takes the `@cee` cookie to be the tag, so the message is also
+    /// taken as JSON when the appname is `@cee`.
+    pub fn json_payload(&self) -> Option<Result<serde_json::Value, serde_json::Error>> {
+        let msg = self.msg.as_ref().trim_start();
+        let json = match &self.appname {
+            Some(appname) if appname.as_ref() == "@cee" => msg,
+            _ => match msg.strip_prefix("@cee:") {
+                Some(json) => json,
+                None if msg.starts_with('{') => msg,
+                None => return None,
+            },
+        };
+
+        Some(serde_json::from_str(json))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Variant, parse_message};
+    use serde_json::json;
+
+    #[test]
+    fn message_to_value() {
+        let value = Value::from(parse_message(
+            r#"<165>1 2003-10-11T22:14:15.003Z mymachine evntslog 12 ID47 [exampleSDID@32473 iut="3"] An application event"#,
+            Variant::RFC5424,
+        ));
+        assert_eq!(
+            value,
+            json!({
+                "protocol": "rfc5424",
+                "version": 1,
+                "facility": "local4",
+                "severity": "notice",
+                "timestamp": "2003-10-11T22:14:15.003+00:00",
+                "hostname": "mymachine",
+                "appname": "evntslog",
+                "procid": 12,
+                "msgid": "ID47",
+                "structured_data": { "exampleSDID@32473": { "iut": "3" } },
+                "msg": "An application event"
+            })
+        );
+
+        let value = Value::from(&parse_message("hello", Variant::RFC3164));
+        assert_eq!(value["protocol"], "rfc3164");
+        assert!(value.get("version").is_none());
+        assert_eq!(value["hostname"], Value::Null);
+        assert_eq!(value["msg"], "hello");
+    }
+
+    #[test]
+    fn cee_payload() {
+        let message = parse_message(
+            r#"<134>Feb 14 19:04:54 host @cee: {"msg": "hello", "pid": 12}"#,
+            Variant::RFC3164,
+        );
+        assert_eq!(
+            message.json_payload().unwrap().unwrap(),
+            json!({"msg": "hello", "pid": 12})
+        );
+
+        let message = parse_message(
+            r#"<134>1 2003-10-11T22:14:15.003Z host app - - - @cee:{"msg": "hello"}"#,
+            Variant::RFC5424,
+        );
+        assert_eq!(
+            message.json_payload().unwrap().unwrap(),
+            json!({"msg": "hello"})
+        );
+    }
+
+    #[test]
+    fn plain_json_payload() {
+        let message = parse_message(
+            r#"<134>1 2003-10-11T22:14:15.003Z host app - - - {"msg": "hello"}"#,
+            Variant::RFC5424,
+        );
+        assert_eq!(
+            message.json_payload().unwrap().unwrap(),
+            json!({"msg": "hello"})
+        );
+
+        let message = parse_message(
+            "<134>1 2003-10-11T22:14:15.003Z host app - - - {not json",
+            Variant::RFC5424,
+        );
+        assert!(message.json_payload().unwrap().is_err());
+
+        let message = parse_message(
+            "<134>1 2003-10-11T22:14:15.003Z host app - - - hello",
+            Variant::RFC5424,
+        );
+        assert!(message.json_payload().is_none());
+    }
+}
@@ -0,0 +1,141 @@
+//! Extracting `key=value; key=value;` pairs, as sent by appliances such as
+//! Check Point, Sophos and WatchGuard, from the body of a message.
+use crate::message::Message;
+use std::collections::BTreeMap;
+
+/// The characters used to split a body into key value pairs.
+///
+/// Unlike logfmt, pairs are split on the delimiter alone, so unquoted values may
+/// contain spaces. Values may also be wrapped in `"`, in which case they may
+/// contain the delimiter.
+///
+/// ```
+/// use syslog_loose::KeyValues;
+///
+/// let pairs = KeyValues::SEMICOLON.parse("action=Accept; src=10.0.0.1; rule name=Allow web;");
+/// assert_eq!(pairs["action"], "Accept");
+/// assert_eq!(pairs["rule name"], "Allow web");
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct KeyValues {
+    /// Separates one pair from the next.
+    pub delimiter: char,
+    /// Separates the key from the value.
+    pub separator: char,
+}
+
+impl KeyValues {
+    /// `key=value; key=value;`, used by Sophos and WatchGuard.
+    pub const SEMICOLON: KeyValues = KeyValues {
+        delimiter: ';',
+        separator: '=',
+    };
+    /// `key:"value"; key:"value";`, used by Check Point.
+    pub const CHECK_POINT: KeyValues = KeyValues {
+        delimiter: ';',
+        separator: ':',
+    };
+
+    pub fn new(delimiter: char, separator: char) -> Self {
+        KeyValues {
+            delimiter,
+            separator,
+        }
+    }
+
+    /// Parse the pairs in `input`. Surrounding whitespace is trimmed from keys and
+    /// values. Pairs without a separator or with an empty key are skipped. If a key
+    /// is repeated the last value wins.
+    pub fn parse<'a>(&self, input: &'a str) -> BTreeMap<&'a str, &'a str> {
+        let mut pairs = BTreeMap::new();
+        let mut rest = input;
+        while !rest.is_empty() {
+            let pair_end = rest.find(self.delimiter).unwrap_or(rest.len());
+            let Some(key_end) = rest[..pair_end].find(self.separator) else {
+                rest = rest[pair_end..].strip_prefix(self.delimiter).unwrap_or("");
+                continue;
+            };
+
+            let key = rest[..key_end].trim();
+            let value = rest[key_end + self.separator.len_utf8()..].trim_start();
+            let value = match value.strip_prefix('"') {
+                Some(quoted) => {
+                    let end = quoted.find('"').unwrap_or(quoted.len());
+                    rest = &quoted[end..];
+                    rest = rest.strip_prefix('"').unwrap_or(rest);
+                    // Skip anything between the closing quote and the delimiter.
+                    let pair_end = rest.find(self.delimiter).unwrap_or(rest.len());
+                    rest = &rest[pair_end..];
+                    &quoted[..end]
+                }
+                None => {
+                    let end = value.find(self.delimiter).unwrap_or(value.len());
+                    rest = &value[end..];
+                    value[..end].trim_end()
+                }
+            };
+            rest = rest.strip_prefix(self.delimiter).unwrap_or(rest);
+
+            if !key.is_empty() {
+                pairs.insert(key, value);
+            }
+        }
+        pairs
+    }
+}
+
+impl Default for KeyValues {
+    fn default() -> Self {
+        KeyValues::SEMICOLON
+    }
+}
+
+impl<S: AsRef<str> + Ord + PartialEq + Clone> Message<S> {
+    /// Parse the key value pairs out of the body of the message.
+    /// See [`KeyValues::parse`].
+    pub fn kv_pairs(&self, format: KeyValues) -> BTreeMap<&str, &str> {
+        format.parse(self.msg.as_ref())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Variant, parse_message};
+
+    #[test]
+    fn parse_semicolons() {
+        let pairs = KeyValues::SEMICOLON
+            .parse(r#"id=1;  msg=user logged in ; note="a; b" trailing; junk; =x; last="#);
+
+        assert_eq!(
+            pairs.into_iter().collect::<Vec<_>>(),
+            vec![
+                ("id", "1"),
+                ("last", ""),
+                ("msg", "user logged in"),
+                ("note", "a; b"),
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_check_point() {
+        let message = parse_message(
+            r#"<134>Oct 11 22:14:15 gw CheckPoint: time:"1697062455"; action:"Drop"; src:"10.0.0.1"; service:"443";"#,
+            Variant::RFC3164,
+        );
+        let pairs = message.kv_pairs(KeyValues::CHECK_POINT);
+
+        assert_eq!(pairs.len(), 4);
+        assert_eq!(pairs["action"], "Drop");
+        assert_eq!(pairs["service"], "443");
+    }
+
+    #[test]
+    fn parse_custom() {
+        let pairs = KeyValues::new('|', ':').parse("a: 1|b:two words");
+        assert_eq!(pairs["a"], "1");
+        assert_eq!(pairs["b"], "two words");
+    }
+}
@@ -0,0 +1,215 @@
+//! Parsing the header of a message straight away, leaving the structured data until
+//! it is needed.
+use crate::{
+    Variant,
+    error::ParseError,
+    message::{Message, Protocol},
+    options::ParseOptions,
+    pri::{SyslogFacility, SyslogSeverity},
+    procid::ProcId,
+    rfc5424,
+    structured_data::StructuredElement,
+};
+use chrono::{DateTime, FixedOffset};
+use std::cell::OnceCell;
+
+/// A message whose structured data is only parsed when it is first asked for.
+///
+/// Where the body of the message starts depends on where the structured data ends,
+/// so [`LazyMessage::msg`] also parses the structured data. Messages that are
+/// filtered out on the header fields never pay for it.
+#[derive(Clone, Debug)]
+pub struct LazyMessage<'a> {
+    /// The message with the structured data and body left empty.
+    header: Message<&'a str>,
+    /// The unparsed structured data and body.
+    rest: &'a str,
+    parsed: OnceCell<(Vec<StructuredElement<&'a str>>, &'a str)>,
+}
+
+impl<'a> LazyMessage<'a> {
+    /// A message that has already been parsed in full.
+    pub(crate) fn parsed(message: Message<&'a str>) -> Self {
+        let parsed = OnceCell::new();
+        let _ = parsed.set((message.structured_data, message.msg));
+        LazyMessage {
+            header: Message {
+                structured_data: vec![],
+                msg: "",
+                ..message
+            },
+            rest: "",
+            parsed,
+        }
+    }
+
+    pub fn protocol(&self) -> &Protocol {
+        &self.header.protocol
+    }
+
+    pub fn facility(&self) -> Option<SyslogFacility> {
+        self.header.facility
+    }
+
+    pub fn severity(&self) -> Option<SyslogSeverity> {
+        self.header.severity
+    }
+
+    pub fn timestamp(&self) -> Option<DateTime<FixedOffset>> {
+        self.header.timestamp
+    }
+
+    pub fn hostname(&self) -> Option<&'a str> {
+        self.header.hostname
+    }
+
+    pub fn appname(&self) -> Option<&'a str> {
+        self.header.appname
+    }
+
+    pub fn procid(&self) -> Option<&ProcId<&'a str>> {
+        self.header.procid.as_ref()
+    }
+
+    pub fn msgid(&self) -> Option<&'a str> {
+        self.header.msgid
+    }
+
+    /// Has the structured data been parsed yet?
+    pub fn is_parsed(&self) -> bool {
+        self.parsed.get().is_some()
+    }
+
+    fn parse_rest(&self) -> &(Vec<StructuredElement<&'a str>>, &'a str) {
+        self.parsed.get_or_init(|| {
+            rfc5424::body(self.rest)
+                .map(|(_, body)| body)
+                .unwrap_or((vec![], self.rest))
+        })
+    }
+
+    /// The structured data, parsing it if this is the first time it is needed.
+    pub fn structured_data(&self) -> &[StructuredElement<&'a str>] {
+        &self.parse_rest().0
+    }
+
+    /// The body of the message, parsing the structured data if this is the first
+    /// time either is needed.
+    pub fn msg(&self) -> &'a str {
+        self.parse_rest().1
+    }
+
+    /// The message with everything parsed.
+    pub fn into_message(self) -> Message<&'a str> {
+        self.parse_rest();
+        let (structured_data, msg) = self.parsed.into_inner().unwrap_or_default();
+        Message {
+            structured_data,
+            msg,
+            ..self.header
+        }
+    }
+}
+
+impl ParseOptions {
+    /// Parse a single message like [`ParseOptions::parse`], leaving the structured
+    /// data of RFC5424 messages to be parsed when it is first needed.
+    pub fn parse_lazy<'a>(&self, input: &'a str) -> LazyMessage<'a> {
+        self.parse_lazy_with_hint(input, &mut None)
+            .unwrap_or_else(|_| LazyMessage::parsed(self.parse(input)))
+    }
+
+    /// Parse a single message exactly, keeping track of the variant in `last` as
+    /// [`ParseOptions::parse_with_hint`] does, and leaving the structured data of
+    /// RFC5424 messages to be parsed when it is first needed.
+    pub(crate) fn parse_lazy_with_hint<'a>(
+        &self,
+        input: &'a str,
+        last: &mut Option<Variant>,
+    ) -> Result<LazyMessage<'a>, ParseError<'a>> {
+        let rfc5424_first = match self.variant {
+            Variant::RFC3164 => false,
+            Variant::RFC5424 => true,
+            Variant::Either => !matches!(last, Some(Variant::RFC3164)),
+        };
+
+        if rfc5424_first {
+            let trimmed = input.trim();
+            if let Some(message) = rfc5424::parse_canonical(trimmed) {
+                *last = Some(Variant::RFC5424);
+                // There is no structured data to put off parsing.
+                return Ok(LazyMessage::parsed(self.vendor_facility(input, message)));
+            }
+            if let Some((header, rest)) = rfc5424::parse_header(trimmed) {
+                *last = Some(Variant::RFC5424);
+                return Ok(LazyMessage {
+                    header: self.vendor_facility(input, header),
+                    rest,
+                    parsed: OnceCell::new(),
+                });
+            }
+        }
+
+        self.parse_exact_with_hint(input, last)
+            .map(LazyMessage::parsed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_structured_data_when_needed() {
+        let options = ParseOptions::default();
+        let input = r#"<34>1 2003-10-11T22:14:15.003Z mymachine su - ID47 [meta seq="1"][origin ip="::1"] message"#;
+
+        let lazy = options.parse_lazy(input);
+        assert_eq!(lazy.appname(), Some("su"));
+        assert!(!lazy.is_parsed());
+
+        assert_eq!(lazy.structured_data()[1].params[0], ("ip", "::1"));
+        assert!(lazy.is_parsed());
+        assert_eq!(lazy.msg(), "message");
+        assert_eq!(lazy.into_message(), options.parse(input));
+    }
+
+    #[test]
+    fn same_as_eager_parse() {
+        let options = ParseOptions::default();
+        for input in [
+            "<34>1 2003-10-11T22:14:15.003Z mymachine su - ID47 - canonical",
+            "<34>1 2003-10-11T22:14:15.003+01:00 mymachine su - ID47 -",
+            "<34>1 2003-10-11T22:14:15.003Z mymachine su - ID47 [incorrect x] unparsed",
+            "<34>1 2003-10-11T22:14:15.003Z mymachine su - ID47 not structured data",
+            "<34>Oct 11 22:14:15 mymachine su: 3164",
+            "just a message",
+        ] {
+            let lazy = options.parse_lazy(input);
+            let eager = options.parse(input);
+            assert_eq!(lazy.protocol(), &eager.protocol);
+            assert_eq!(lazy.into_message(), eager);
+        }
+    }
+
+    #[test]
+    fn same_as_parse_with_hint() {
+        let options = ParseOptions::default();
+        let (mut lazy_last, mut eager_last) = (None, None);
+        for input in [
+            "<34>Oct 11 22:14:15 mymachine su: 3164",
+            "<34>1 2003-10-11T22:14:15.003Z mymachine su - ID47 [meta seq=\"1\"] after 3164",
+            "<34>1 2003-10-11T22:14:15.003Z mymachine su - ID47 - canonical",
+        ] {
+            let lazy = options.parse_lazy_with_hint(input, &mut lazy_last).unwrap();
+            let eager = options.parse_with_hint(input, &mut eager_last);
+            assert_eq!(lazy.into_message(), eager);
+            assert_eq!(format!("{:?}", lazy_last), format!("{:?}", eager_last));
+        }
+        assert!(
+            options
+                .parse_lazy_with_hint("just a message", &mut None)
+                .is_err()
+        );
+    }
+}
@@ -0,0 +1,163 @@
+//! Parsing IBM [Log Event Extended Format](https://www.ibm.com/docs/en/dsm?topic=overview-leef-event-components)
+//! messages carried in the body of a syslog message.
+use crate::message::Message;
+
+/// A LEEF 1.0 or 2.0 event,
+/// `LEEF:Version|Vendor|Product|Version|EventID|[Delimiter|]Attributes`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Leef<'a> {
+    /// `1.0` or `2.0`.
+    pub version: &'a str,
+    pub vendor: &'a str,
+    pub product: &'a str,
+    pub product_version: &'a str,
+    pub event_id: &'a str,
+    /// The character separating the attributes. Always a tab for LEEF 1.0, LEEF 2.0
+    /// can specify it in the header.
+    pub delimiter: char,
+    /// The `key=value` attributes in the order they were given.
+    pub attributes: Vec<(&'a str, &'a str)>,
+}
+
+/// Parse the delimiter from a LEEF 2.0 header, either a single character or its
+/// code in hex as `0x09` or `x09`.
+fn delimiter(field: &str) -> Option<char> {
+    let mut chars = field.chars();
+    match (chars.next(), chars.next()) {
+        (None, _) => Some('\t'),
+        (Some(c), None) => Some(c),
+        _ => {
+            let hex = field
+                .strip_prefix("0x")
+                .or_else(|| field.strip_prefix("x"))?;
+            char::from_u32(u32::from_str_radix(hex, 16).ok()?)
+        }
+    }
+}
+
+impl<'a> Leef<'a> {
+    /// Parse a LEEF event, `input` must start with `LEEF:`.
+    pub fn parse(input: &'a str) -> Option<Self> {
+        Self::parse_body(input.trim_start().strip_prefix("LEEF:")?)
+    }
+
+    /// Parse the event following the `LEEF:` prefix.
+    fn parse_body(body: &'a str) -> Option<Self> {
+        let mut fields = body.splitn(6, '|');
+        let version = fields.next()?.trim();
+        let vendor = fields.next()?;
+        let product = fields.next()?;
+        let product_version = fields.next()?;
+        let event_id = fields.next()?;
+        let rest = fields.next()?;
+
+        let (delimiter, attributes) = match version {
+            "1.0" => ('\t', rest),
+            // The delimiter field is optional, in which case it is taken to be a tab.
+            "2.0" => match rest.split_once('|') {
+                Some((field, attributes)) => (delimiter(field)?, attributes),
+                None => ('\t', rest),
+            },
+            _ => return None,
+        };
+
+        Some(Leef {
+            version,
+            vendor,
+            product,
+            product_version,
+            event_id,
+            delimiter,
+            attributes: attributes
+                .trim_end_matches(['\r', '\n'])
+                .split(delimiter)
+                .filter_map(|attribute| attribute.split_once('='))
+                .collect(),
+        })
+    }
+
+    /// The value of the first attribute named `key`.
+    pub fn attribute(&self, key: &str) -> Option<&'a str> {
+        self.attributes
+            .iter()
+            .find(|(name, _)| *name == key)
+            .map(|(_, value)| *value)
+    }
+}
+
+impl<S: AsRef<str> + Ord + PartialEq + Clone> Message<S> {
+    /// Parse the body of the message as a LEEF event if it is one.
+    ///
+    /// As with [`Message::cef`], RFC3164 parsing takes the `LEEF` prefix to be the
+    /// tag, so the message is also checked when the appname is `LEEF`.
+    pub fn leef(&self) -> Option<Leef<'_>> {
+        let msg = self.msg.as_ref();
+        match &self.appname {
+            Some(appname) if appname.as_ref() == "LEEF" && !msg.starts_with("LEEF:") => {
+                Leef::parse_body(msg)
+            }
+            _ => Leef::parse(msg),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Variant, parse_message};
+
+    #[test]
+    fn parse_leef_1() {
+        let leef = Leef::parse(
+            "LEEF:1.0|Microsoft|MSExchange|4.0 SP1|15345|src=192.0.2.0\tdst=172.50.123.1\tsev=5\tmsg=a=b",
+        )
+        .unwrap();
+
+        assert_eq!(leef.version, "1.0");
+        assert_eq!(leef.vendor, "Microsoft");
+        assert_eq!(leef.product, "MSExchange");
+        assert_eq!(leef.product_version, "4.0 SP1");
+        assert_eq!(leef.event_id, "15345");
+        assert_eq!(leef.delimiter, '\t');
+        assert_eq!(
+            leef.attributes,
+            vec![
+                ("src", "192.0.2.0"),
+                ("dst", "172.50.123.1"),
+                ("sev", "5"),
+                ("msg", "a=b")
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_leef_2_delimiters() {
+        let leef = Leef::parse("LEEF:2.0|Lancope|StealthWatch|1.0|41|^|src=10.0.1.8^dst=10.0.0.5")
+            .unwrap();
+        assert_eq!(leef.delimiter, '^');
+        assert_eq!(leef.attribute("dst"), Some("10.0.0.5"));
+
+        let leef =
+            Leef::parse("LEEF:2.0|Lancope|StealthWatch|1.0|41|0x7c|src=10.0.1.8|dst=10.0.0.5")
+                .unwrap();
+        assert_eq!(leef.delimiter, '|');
+        assert_eq!(
+            leef.attributes,
+            vec![("src", "10.0.1.8"), ("dst", "10.0.0.5")]
+        );
+
+        assert_eq!(Leef::parse("LEEF:3.0|a|b|c|d|e=f"), None);
+    }
+
+    #[test]
+    fn leef_from_message() {
+        let message = parse_message(
+            "<13>Jan 18 11:07:53 192.168.1.1 LEEF:1.0|QRadar|QRM|1.0|NEW_PORT_DISCOVERED|src=172.5.6.67\tdst=172.50.123.1",
+            Variant::RFC3164,
+        );
+        let leef = message.leef().unwrap();
+        assert_eq!(leef.event_id, "NEW_PORT_DISCOVERED");
+        assert_eq!(leef.attribute("src"), Some("172.5.6.67"));
+        assert!(message.cef().is_none());
+    }
+}
@@ -0,0 +1,60 @@
+//! Mapping syslog severities onto the levels of logging libraries.
+use crate::pri::SyslogSeverity;
+
+/// The level of a logging library to use for each syslog severity, for bridges
+/// that want a different mapping to the default.
+///
+/// Logging libraries have fewer levels than syslog has severities, so some have
+/// to share. By default `emerg`, `alert`, `crit` and `err` are all errors, and
+/// `notice` is info, see [`log_level`](crate::log_level) and
+/// [`tracing_level`](crate::tracing_level). Unknown severities use the level of
+/// `debug`.
+///
+/// ```
+/// # #[cfg(feature = "log")]
+/// # {
+/// use syslog_loose::{SeverityMap, SyslogSeverity};
+///
+/// let levels = SeverityMap::<log::Level>::default()
+///     .with(SyslogSeverity::SEV_NOTICE, log::Level::Warn);
+/// assert_eq!(levels.get(SyslogSeverity::SEV_NOTICE), log::Level::Warn);
+/// assert_eq!(levels.get(SyslogSeverity::SEV_INFO), log::Level::Info);
+/// # }
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SeverityMap<L> {
+    levels: [L; 8],
+}
+
+impl<L: Copy> SeverityMap<L> {
+    /// The mapping given by `f`.
+    pub fn from_fn(f: impl Fn(SyslogSeverity) -> L) -> Self {
+        SeverityMap {
+            levels: [
+                f(SyslogSeverity::SEV_EMERG),
+                f(SyslogSeverity::SEV_ALERT),
+                f(SyslogSeverity::SEV_CRIT),
+                f(SyslogSeverity::SEV_ERR),
+                f(SyslogSeverity::SEV_WARNING),
+                f(SyslogSeverity::SEV_NOTICE),
+                f(SyslogSeverity::SEV_INFO),
+                f(SyslogSeverity::SEV_DEBUG),
+            ],
+        }
+    }
+
+    /// Map `severity` to `level` instead.
+    pub fn with(mut self, severity: SyslogSeverity, level: L) -> Self {
+        self.levels[index(severity)] = level;
+        self
+    }
+
+    /// The level for `severity`.
+    pub fn get(&self, severity: SyslogSeverity) -> L {
+        self.levels[index(severity)]
+    }
+}
+
+fn index(severity: SyslogSeverity) -> usize {
+    usize::from(severity.code()).min(7)
+}
@@ -2,24 +2,179 @@
 #![deny(clippy::cargo)]
 extern crate nom;
 
+/// Emit a `tracing` event about how a message is being parsed when the `tracing`
+/// feature is enabled, and nothing at all otherwise.
+macro_rules! trace_parse {
+    ($level:ident, $($fields:tt)*) => {
+        #[cfg(feature = "tracing")]
+        tracing::$level!(target: "syslog_loose", $($fields)*);
+    };
+}
+
+mod arena;
+mod aruba;
+mod asa;
+mod auth;
+mod batch;
+mod bigip;
+#[cfg(feature = "bytes")]
+mod bytes_decoder;
+mod cef;
+mod cisco;
+mod clock;
+#[cfg(feature = "compact_str")]
+mod compact;
+mod container;
+mod datagram;
+mod diagnose;
+mod dialect;
+#[cfg(feature = "ecs")]
+mod ecs;
+mod edit;
+mod encoder;
 mod error;
+mod esxi;
+mod filter;
+mod filterlog;
+mod fingerprint;
+mod fortigate;
+mod framing;
+#[cfg(feature = "gelf")]
+mod gelf;
+mod haproxy;
+mod interner;
+mod journald;
+#[cfg(feature = "json")]
+mod json;
+mod kv;
+mod lazy;
+mod leef;
+mod levels;
+#[cfg(feature = "log")]
+mod log_record;
+mod logfmt;
+mod mail;
 mod message;
+#[cfg(feature = "miette")]
+mod miette_report;
+mod nas;
+mod options;
+#[cfg(feature = "otel")]
+mod otel;
+mod panos;
+mod parser;
 mod parsers;
+mod parts;
+mod pattern;
 mod pri;
 mod procid;
+#[cfg(feature = "proto")]
+mod proto;
+mod reader;
+mod relp;
+mod repeated;
 mod rfc3164;
 mod rfc5424;
+mod rfc5425;
+mod routeros;
+mod signed;
+mod slice;
+mod snare;
+mod stats;
+#[cfg(feature = "futures")]
+mod stream;
 mod structured_data;
+#[cfg(feature = "test-util")]
+mod test_util;
 mod timestamp;
+#[cfg(feature = "tracing")]
+mod tracing_event;
+mod ubiquiti;
+mod validate;
 
 use chrono::prelude::*;
 use nom::{branch::alt, IResult, Parser as _};
 
+pub use aruba::ArubaEvent;
+pub use auth::AuthEvent;
+#[cfg(feature = "rayon")]
+pub use batch::parse_batch_parallel;
+pub use batch::{parse_batch, parse_many};
+pub use bigip::BigIp;
+#[cfg(feature = "bytes")]
+pub use bytes_decoder::{BytesDecoder, BytesStr};
+pub use cef::Cef;
+pub use cisco::CiscoMnemonic;
+pub use clock::ClockCheck;
+pub use container::{ContainerTag, Klog};
+pub use datagram::parse_datagram;
+pub use diagnose::ParseWarning;
+pub use dialect::{BUILTIN_DIALECTS, Dialect, DialectFields};
+pub use encoder::MessageEncoder;
+pub use error::{
+    Error, FacilityError, Field, FrameError, InvalidField, InvalidUtf8, ParseError, ParseFailure,
+    PatternError, ProtoError, Rule, SeverityError,
+};
+pub use esxi::Esxi;
+pub use filter::MessageFilter;
+pub use filterlog::Filterlog;
+pub use fortigate::Fortigate;
+pub use framing::{Delimiters, Framing, Oversize, Resync};
+#[cfg(feature = "gelf")]
+pub use gelf::Gelf;
+pub use haproxy::Haproxy;
+pub use interner::Interner;
+pub use journald::{JournalEntry, JournalExportReader};
+pub use kv::KeyValues;
+pub use lazy::LazyMessage;
+pub use leef::Leef;
+pub use levels::SeverityMap;
+#[cfg(feature = "log")]
+pub use log_record::log_level;
+pub use logfmt::parse_logfmt;
+pub use mail::MailEvent;
 pub use message::{Message, Protocol};
-pub use pri::{decompose_pri, SyslogFacility, SyslogSeverity};
+#[cfg(feature = "miette")]
+pub use miette_report::WarningReport;
+pub use nas::NasEvent;
+pub use options::{
+    ControlChars, DEFAULT_MAX_FRAME_LEN, HeaderFilter, MalformedHook, ParseOptions, Utf8Policy,
+};
+#[cfg(feature = "otel")]
+pub use otel::{OtelLogRecord, otel_severity_number};
+pub use panos::PanOs;
+pub use parser::Parser;
+pub use parts::MessageParts;
+pub use pattern::{Extracted, Pattern, PatternSet};
+pub use pri::{decompose_pri, Priority, SyslogFacility, SyslogSeverity};
 pub use procid::ProcId;
+#[cfg(feature = "proto")]
+pub use proto::{SyslogElement, SyslogMessage, SyslogParam, SyslogProcId};
+pub use reader::MessageReader;
+pub use relp::RelpFrame;
+pub use rfc5425::{FrameReader, FrameWriter};
+pub use routeros::RouterOs;
+#[cfg(feature = "crypto")]
+pub use signed::SignedLog;
+pub use signed::{
+    CertificateAssembler, CertificateBlock, HashAlgorithm, SignatureBlock, SignatureGroup,
+};
+#[cfg(feature = "mmap")]
+pub use slice::MappedLog;
+pub use slice::SliceReader;
+pub use snare::SnareEvent;
+pub use stats::{DecoderStats, ParseStats};
+#[cfg(feature = "futures-io")]
+pub use stream::AsyncMessageReader;
+#[cfg(feature = "futures")]
+pub use stream::MessageStream;
 pub use structured_data::StructuredElement;
+#[cfg(feature = "test-util")]
+pub use test_util::{MalformedText, Rfc3164Text, Rfc5424Text};
 pub use timestamp::IncompleteDate;
+#[cfg(feature = "tracing")]
+pub use tracing_event::tracing_level;
+pub use ubiquiti::UbiquitiFirewall;
 
 /// Used to specify which variant of the RFC message we are expecting.
 #[derive(Clone, Copy, Debug)]
@@ -42,13 +197,51 @@ fn parse<F, Tz: TimeZone + Copy>(
 where
     F: FnOnce(IncompleteDate) -> i32 + Copy,
 {
-    match variant {
-        Variant::Either => {
-            alt((rfc5424::parse, |input| rfc3164::parse(input, get_year, tz))).parse(input.trim())
-        }
-        Variant::RFC3164 => rfc3164::parse(input.trim(), get_year, tz),
+    parse_preferring(input, get_year, tz, variant, None, false)
+}
+
+/// Like `parse`, but with `Variant::Either` 3164 is tried first if that is the
+/// `preferred` variant - typically the variant of the last message from the source.
+/// See [`ParseOptions::year_after_time`] for `year_after_time`.
+pub(crate) fn parse_preferring<F, Tz: TimeZone + Copy>(
+    input: &str,
+    get_year: F,
+    tz: Option<Tz>,
+    variant: Variant,
+    preferred: Option<Variant>,
+    year_after_time: bool,
+) -> IResult<&str, Message<&str>>
+where
+    F: FnOnce(IncompleteDate) -> i32 + Copy,
+{
+    let result = match variant {
+        Variant::Either => match preferred {
+            Some(Variant::RFC3164) => alt((
+                |input| rfc3164::parse_with(input, get_year, tz, year_after_time),
+                rfc5424::parse,
+            ))
+            .parse(input.trim()),
+            _ => alt((rfc5424::parse, |input| {
+                rfc3164::parse_with(input, get_year, tz, year_after_time)
+            }))
+            .parse(input.trim()),
+        },
+        Variant::RFC3164 => rfc3164::parse_with(input.trim(), get_year, tz, year_after_time),
         Variant::RFC5424 => rfc5424::parse(input.trim()),
+    };
+
+    if let Ok((_, message)) = &result {
+        message.debug_check_invariants(input);
     }
+
+    #[cfg(feature = "tracing")]
+    match &result {
+        Ok((_, message)) => {
+            tracing::trace!(target: "syslog_loose", protocol = ?message.protocol, "detected variant")
+        }
+        Err(_) => tracing::trace!(target: "syslog_loose", ?variant, "no variant matched"),
+    }
+    result
 }
 
 ///
@@ -74,22 +267,30 @@ where
 {
     parse(input, get_year, tz, variant)
         .map(|(_, result)| result)
-        .unwrap_or(
-            // If we fail to parse, the entire input becomes the message
-            // the rest of the fields are empty.
-            Message {
-                facility: None,
-                severity: None,
-                timestamp: None,
-                hostname: None,
-                appname: None,
-                procid: None,
-                msgid: None,
-                protocol: Protocol::RFC3164,
-                structured_data: vec![],
-                msg: input,
-            },
-        )
+        .unwrap_or_else(|_| unparsed(input))
+}
+
+/// The message for input that couldn't be parsed.
+pub(crate) fn unparsed(input: &str) -> Message<&str> {
+    trace_parse!(
+        debug,
+        len = input.len(),
+        "taking the whole input as the message"
+    );
+    // If we fail to parse, the entire input becomes the message
+    // the rest of the fields are empty.
+    Message {
+        facility: None,
+        severity: None,
+        timestamp: None,
+        hostname: None,
+        appname: None,
+        procid: None,
+        msgid: None,
+        protocol: Protocol::RFC3164,
+        structured_data: vec![],
+        msg: input,
+    }
 }
 
 ///
@@ -113,6 +314,14 @@ where
 /// For messages where the timestamp doesn't specify a year it just
 /// takes the current year.
 ///
+/// The fields of the message borrow from `input`, so parsing doesn't allocate
+/// except for
+///
+/// * a `Vec` for the structured data elements, and one for the params of each
+///   element.
+/// * timestamps without an offset being converted to local time when no timezone
+///   is given, as chrono looks up the local timezone.
+///
 /// # Arguments
 ///
 /// * input - the string containing the message.
@@ -122,10 +331,30 @@ pub fn parse_message(input: &str, variant: Variant) -> Message<&str> {
     parse_message_with_year(input, |_| Local::now().year(), variant)
 }
 
+/// Parses the message like [`parse_message`], but returns an error if it isn't a
+/// valid message of the given variant rather than taking the whole input as the
+/// body, so that malformed input can be dealt with separately.
+///
+/// ```
+/// use syslog_loose::{Field, Variant, try_parse_message};
+///
+/// let message = try_parse_message("<34>Oct 11 22:14:15 mymachine su: ok", Variant::Either).unwrap();
+/// assert_eq!(message.msg, "ok");
+///
+/// let error = try_parse_message("<34>1 yesterday mymachine su - - - ok", Variant::RFC5424).unwrap_err();
+/// assert_eq!(error.field, Field::Timestamp);
+/// assert_eq!(error.offset, 6);
+/// ```
+pub fn try_parse_message(input: &str, variant: Variant) -> Result<Message<&str>, ParseError<'_>> {
+    parse::<_, Local>(input, |_| Local::now().year(), None, variant)
+        .map(|(_, message)| message)
+        .map_err(|_| ParseError::diagnose(input, variant))
+}
+
 ///
 /// Parse the message exactly. If it can't be parsed, an Error is returned.
-/// Note, since it is hard to locate exactly what is causing the error due to the parser trying
-/// so many different combinations, a simple hardcoded string is returned as the error message.
+/// The error message is that of the [`ParseError`] describing the field that
+/// failed, such as `invalid timestamp at byte 4: expected an RFC3164 or RFC3339 timestamp`.
 ///
 /// # Arguments
 ///
@@ -144,13 +373,13 @@ where
 {
     parse::<_, Local>(input, get_year, None, variant)
         .map(|(_, result)| result)
-        .map_err(|_| "unable to parse input as valid syslog message".to_string())
+        .map_err(|_| ParseError::diagnose(input, variant).to_string())
 }
 
 ///
 /// Parse the message exactly. If it can't be parsed, an Error is returned.
-/// Note, since it is hard to locate exactly what is causing the error due to the parser trying
-/// so many different combinations, a simple hardcoded string is returned as the error message.
+/// The error message is that of the [`ParseError`] describing the field that
+/// failed, such as `invalid timestamp at byte 4: expected an RFC3164 or RFC3339 timestamp`.
 ///
 /// # Arguments
 ///
@@ -171,7 +400,7 @@ where
 {
     parse(input, get_year, tz, variant)
         .map(|(_, result)| result)
-        .map_err(|_| "unable to parse input as valid syslog message".to_string())
+        .map_err(|_| ParseError::diagnose(input, variant).to_string())
 }
 
 ///
@@ -220,5 +449,5 @@ where
 }
 
 pub fn parse_message_with_result(input: &str, variant: Variant) -> IResult<&str, Message<&str>> {
-    parse_message_with_result_year(&input, |_| Local::now().year(), variant)
+    parse_message_with_result_year(input, |_| Local::now().year(), variant)
 }
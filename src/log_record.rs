@@ -0,0 +1,168 @@
+//! Re-emitting messages through the [`log`] crate.
+use crate::{levels::SeverityMap, message::Message, pri::SyslogSeverity};
+
+/// The `log` level for a syslog severity. `log` has no level above error, so
+/// `emerg`, `alert` and `crit` are errors too, and `notice` is info. Use a
+/// [`SeverityMap`] for a different mapping.
+pub fn log_level(severity: SyslogSeverity) -> log::Level {
+    match severity {
+        SyslogSeverity::SEV_EMERG
+        | SyslogSeverity::SEV_ALERT
+        | SyslogSeverity::SEV_CRIT
+        | SyslogSeverity::SEV_ERR => log::Level::Error,
+        SyslogSeverity::SEV_WARNING => log::Level::Warn,
+        SyslogSeverity::SEV_NOTICE | SyslogSeverity::SEV_INFO => log::Level::Info,
+        SyslogSeverity::SEV_DEBUG => log::Level::Debug,
+    }
+}
+
+impl From<SyslogSeverity> for log::Level {
+    fn from(severity: SyslogSeverity) -> Self {
+        log_level(severity)
+    }
+}
+
+impl Default for SeverityMap<log::Level> {
+    fn default() -> Self {
+        SeverityMap::from_fn(log_level)
+    }
+}
+
+impl<S: AsRef<str> + Ord + PartialEq + Clone> Message<S> {
+    /// The header fields and structured data params as key values, the params
+    /// keyed as `<SD-ID>.<PARAM-NAME>`.
+    fn key_values(&self) -> Vec<(String, String)> {
+        let mut key_values = Vec::new();
+        if let Some(hostname) = &self.hostname {
+            key_values.push(("hostname".to_string(), hostname.as_ref().to_string()));
+        }
+        if let Some(appname) = &self.appname {
+            key_values.push(("appname".to_string(), appname.as_ref().to_string()));
+        }
+        if let Some(procid) = &self.procid {
+            key_values.push(("procid".to_string(), procid.to_string()));
+        }
+        if let Some(msgid) = &self.msgid {
+            key_values.push(("msgid".to_string(), msgid.as_ref().to_string()));
+        }
+        for element in &self.structured_data {
+            for (param, value) in element.params() {
+                key_values.push((format!("{}.{}", element.id.as_ref(), param.as_ref()), value));
+            }
+        }
+        key_values
+    }
+
+    /// Send the message to the global logger as a `log::Record`, with the header
+    /// fields and structured data as key values. Messages without a severity are
+    /// logged at info.
+    pub fn emit_log(&self) {
+        self.emit_log_to(log::logger())
+    }
+
+    /// Send the message to the given logger, see [`Message::emit_log`].
+    pub fn emit_log_to(&self, logger: &dyn log::Log) {
+        self.emit_log_mapped(logger, &SeverityMap::default())
+    }
+
+    /// Send the message to the given logger at the level `levels` maps its
+    /// severity to, see [`Message::emit_log`].
+    pub fn emit_log_mapped(&self, logger: &dyn log::Log, levels: &SeverityMap<log::Level>) {
+        let key_values = self.key_values();
+        logger.log(
+            &log::Record::builder()
+                .args(format_args!("{}", self.msg.as_ref()))
+                .level(
+                    self.severity
+                        .map_or(log::Level::Info, |severity| levels.get(severity)),
+                )
+                .target("syslog")
+                .key_values(&key_values)
+                .build(),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{SeverityMap, SyslogSeverity, Variant, parse_message};
+    use std::sync::Mutex;
+
+    type Fields = Vec<(String, String)>;
+
+    /// Records everything logged to it.
+    #[derive(Default)]
+    struct Capture(Mutex<Vec<(log::Level, String, Fields)>>);
+
+    impl log::Log for Capture {
+        fn enabled(&self, _: &log::Metadata<'_>) -> bool {
+            true
+        }
+
+        fn log(&self, record: &log::Record<'_>) {
+            struct Collect(Vec<(String, String)>);
+            impl<'kvs> log::kv::VisitSource<'kvs> for Collect {
+                fn visit_pair(
+                    &mut self,
+                    key: log::kv::Key<'kvs>,
+                    value: log::kv::Value<'kvs>,
+                ) -> Result<(), log::kv::Error> {
+                    self.0.push((key.to_string(), value.to_string()));
+                    Ok(())
+                }
+            }
+
+            let mut collect = Collect(Vec::new());
+            record.key_values().visit(&mut collect).unwrap();
+            self.0
+                .lock()
+                .unwrap()
+                .push((record.level(), record.args().to_string(), collect.0));
+        }
+
+        fn flush(&self) {}
+    }
+
+    #[test]
+    fn emit_record() {
+        let capture = Capture::default();
+        parse_message(
+            r#"<163>1 2003-10-11T22:14:15.003Z mymachine app 12 ID47 [id a="b"] failed"#,
+            Variant::RFC5424,
+        )
+        .emit_log_to(&capture);
+
+        let records = capture.0.into_inner().unwrap();
+        assert_eq!(
+            records,
+            vec![(
+                log::Level::Error,
+                "failed".to_string(),
+                vec![
+                    ("hostname".to_string(), "mymachine".to_string()),
+                    ("appname".to_string(), "app".to_string()),
+                    ("procid".to_string(), "12".to_string()),
+                    ("msgid".to_string(), "ID47".to_string()),
+                    ("id.a".to_string(), "b".to_string()),
+                ]
+            )]
+        );
+    }
+
+    #[test]
+    fn emit_mapped_level() {
+        let capture = Capture::default();
+        let levels = SeverityMap::default().with(SyslogSeverity::SEV_NOTICE, log::Level::Warn);
+        let message = parse_message("<165>Oct 11 22:14:15 host su: notice", Variant::RFC3164);
+        message.emit_log_mapped(&capture, &levels);
+        message.emit_log_to(&capture);
+
+        let records = capture.0.into_inner().unwrap();
+        let levels = records.iter().map(|record| record.0).collect::<Vec<_>>();
+        assert_eq!(levels, vec![log::Level::Warn, log::Level::Info]);
+        assert_eq!(
+            log::Level::from(SyslogSeverity::SEV_EMERG),
+            log::Level::Error
+        );
+    }
+}
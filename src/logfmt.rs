@@ -0,0 +1,137 @@
+//! Extracting [logfmt](https://brandur.org/logfmt) `key=value` pairs from the body
+//! of a message.
+use crate::message::Message;
+use std::{borrow::Cow, collections::BTreeMap};
+
+/// Parse a value quoted with `"`, starting just after the opening quote. Returns the
+/// unescaped value and the rest of the input after the closing quote.
+fn quoted(input: &str) -> (Cow<'_, str>, &str) {
+    let mut escaped = false;
+    let mut has_escapes = false;
+    for (pos, c) in input.char_indices() {
+        if escaped {
+            escaped = false;
+        } else if c == '\\' {
+            escaped = true;
+            has_escapes = true;
+        } else if c == '"' {
+            let value = &input[..pos];
+            let value = if has_escapes {
+                Cow::Owned(unescape(value))
+            } else {
+                Cow::Borrowed(value)
+            };
+            return (value, &input[pos + 1..]);
+        }
+    }
+
+    // Unterminated, so take the rest of the input.
+    (Cow::Owned(unescape(input)), "")
+}
+
+fn unescape(value: &str) -> String {
+    let mut unescaped = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        match (c, chars.clone().next()) {
+            ('\\', Some(next @ ('"' | '\\'))) => {
+                chars.next();
+                unescaped.push(next);
+            }
+            ('\\', Some('n')) => {
+                chars.next();
+                unescaped.push('\n');
+            }
+            (c, _) => unescaped.push(c),
+        }
+    }
+    unescaped
+}
+
+/// Parse the `key=value` pairs in `input`. Values may be quoted with `"`. Words that
+/// aren't followed by an `=` are skipped. If a key is repeated the last value wins.
+pub fn parse_logfmt(input: &str) -> BTreeMap<&str, Cow<'_, str>> {
+    let mut pairs = BTreeMap::new();
+    let mut rest = input;
+    loop {
+        rest = rest.trim_start();
+        if rest.is_empty() {
+            return pairs;
+        }
+
+        let key_end = rest
+            .find(|c: char| c == '=' || c.is_whitespace())
+            .unwrap_or(rest.len());
+        let key = &rest[..key_end];
+        rest = &rest[key_end..];
+
+        let Some(value) = rest.strip_prefix('=') else {
+            // A bare word.
+            continue;
+        };
+
+        let value = if let Some(value) = value.strip_prefix('"') {
+            let (value, remaining) = quoted(value);
+            rest = remaining;
+            value
+        } else {
+            let end = value.find(char::is_whitespace).unwrap_or(value.len());
+            rest = &value[end..];
+            Cow::Borrowed(&value[..end])
+        };
+
+        if !key.is_empty() {
+            pairs.insert(key, value);
+        }
+    }
+}
+
+impl<S: AsRef<str> + Ord + PartialEq + Clone> Message<S> {
+    /// Parse the logfmt `key=value` pairs out of the body of the message.
+    /// See [`parse_logfmt`].
+    pub fn logfmt(&self) -> BTreeMap<&str, Cow<'_, str>> {
+        parse_logfmt(self.msg.as_ref())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Variant, parse_message};
+
+    #[test]
+    fn parse_pairs() {
+        let pairs = parse_logfmt(
+            r#"level=info msg="user \"bob\" logged in" took=3ms  empty= free text path=/a=b"#,
+        );
+
+        assert_eq!(
+            pairs.into_iter().collect::<Vec<_>>(),
+            vec![
+                ("empty", Cow::Borrowed("")),
+                ("level", Cow::Borrowed("info")),
+                ("msg", Cow::Owned(r#"user "bob" logged in"#.to_string())),
+                ("path", Cow::Borrowed("/a=b")),
+                ("took", Cow::Borrowed("3ms")),
+            ]
+        );
+    }
+
+    #[test]
+    fn unterminated_quote() {
+        let pairs = parse_logfmt(r#"a=1 msg="never ends"#);
+        assert_eq!(pairs["a"], "1");
+        assert_eq!(pairs["msg"], "never ends");
+    }
+
+    #[test]
+    fn logfmt_from_message() {
+        let message = parse_message(
+            r#"<34>Oct 11 22:14:15 mymachine app: level=warn msg="disk low" free=10%"#,
+            Variant::RFC3164,
+        );
+        let pairs = message.logfmt();
+        assert_eq!(pairs.len(), 3);
+        assert_eq!(pairs["msg"], "disk low");
+    }
+}
@@ -0,0 +1,166 @@
+//! Extracting the queue ID and delivery details from Postfix and sendmail logs.
+use crate::message::Message;
+
+/// A line logged by Postfix or sendmail, such as
+/// `4F3D2A1B2C: to=<bob@example.com>, relay=mx.example.com[1.2.3.4]:25, dsn=2.0.0, status=sent (250 OK)`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MailEvent<'a> {
+    /// The ID of the message in the mail queue, if the line is about a message.
+    pub queue_id: Option<&'a str>,
+    /// The `key=value` pairs in the order they were given. Values can contain
+    /// commas within brackets, as in `status=sent (250 2.0.0 Ok, queued as 1A2B)`.
+    pub fields: Vec<(&'a str, &'a str)>,
+    /// The text following the queue ID, or the whole text if there isn't one.
+    pub text: &'a str,
+}
+
+/// Split the queue ID off the front of `input`.
+fn queue_id(input: &str) -> Option<(&str, &str)> {
+    let (id, rest) = input.split_once(": ")?;
+    let valid = id.len() >= 6 && id.bytes().all(|b| b.is_ascii_alphanumeric());
+    valid.then_some((id, rest))
+}
+
+/// Split `input` into `key=value` pairs separated by `, `, ignoring separators
+/// within brackets.
+fn pairs(input: &str) -> Vec<(&str, &str)> {
+    let mut items = Vec::new();
+    let mut depth = 0usize;
+    let mut start = 0;
+    let bytes = input.as_bytes();
+    for (pos, &b) in bytes.iter().enumerate() {
+        match b {
+            b'(' | b'<' | b'[' => depth += 1,
+            b')' | b'>' | b']' => depth = depth.saturating_sub(1),
+            b',' if depth == 0 && bytes.get(pos + 1) == Some(&b' ') => {
+                items.push(&input[start..pos]);
+                start = pos + 2;
+            }
+            _ => {}
+        }
+    }
+    items.push(&input[start..]);
+
+    items
+        .into_iter()
+        .filter_map(|item| item.split_once('='))
+        .filter(|(key, _)| !key.is_empty() && !key.contains(' '))
+        .collect()
+}
+
+impl<'a> MailEvent<'a> {
+    /// Parse the body of a Postfix or sendmail log line.
+    pub fn parse(input: &'a str) -> Self {
+        let input = input.trim();
+        let (queue_id, text) = match queue_id(input) {
+            Some((id, rest)) => (Some(id), rest),
+            None => (None, input),
+        };
+
+        MailEvent {
+            queue_id,
+            fields: pairs(text),
+            text,
+        }
+    }
+
+    /// The value of the field, if present.
+    pub fn field(&self, key: &str) -> Option<&'a str> {
+        self.fields
+            .iter()
+            .find(|(name, _)| *name == key)
+            .map(|(_, value)| *value)
+    }
+
+    /// The host the message was handed to.
+    pub fn relay(&self) -> Option<&'a str> {
+        self.field("relay")
+    }
+
+    /// The delivery status code, such as `2.0.0`.
+    pub fn dsn(&self) -> Option<&'a str> {
+        self.field("dsn")
+    }
+
+    /// The delivery status without its explanation, such as `sent` or `deferred`.
+    /// Sendmail calls this `stat`.
+    pub fn status(&self) -> Option<&'a str> {
+        let status = self.field("status").or_else(|| self.field("stat"))?;
+        Some(status.split(" (").next().unwrap_or(status))
+    }
+
+    /// The explanation of the delivery status, from the brackets following it.
+    pub fn status_detail(&self) -> Option<&'a str> {
+        let status = self.field("status").or_else(|| self.field("stat"))?;
+        let (_, detail) = status.split_once(" (")?;
+        Some(detail.strip_suffix(')').unwrap_or(detail))
+    }
+}
+
+impl<S: AsRef<str> + Ord + PartialEq + Clone> Message<S> {
+    /// Parse the body of the message as a mail log line if it comes from Postfix,
+    /// such as `postfix/smtp`, or sendmail.
+    pub fn mail(&self) -> Option<MailEvent<'_>> {
+        let appname = self.appname.as_ref()?.as_ref();
+        let postfix = appname
+            .split('/')
+            .next()
+            .is_some_and(|service| service.starts_with("postfix"))
+            && appname.contains('/');
+        if postfix || appname == "sendmail" || appname == "sm-mta" {
+            Some(MailEvent::parse(self.msg.as_ref()))
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Variant, parse_message};
+
+    #[test]
+    fn parse_postfix() {
+        let message = parse_message(
+            "<22>Oct 11 22:14:15 mail postfix/smtp[1234]: 4F3D2A1B2C: to=<bob@example.com>, relay=mx.example.com[1.2.3.4]:25, delay=0.5, delays=0.1/0/0.2/0.2, dsn=2.0.0, status=sent (250 2.0.0 Ok, queued as 9ABC)",
+            Variant::RFC3164,
+        );
+        let event = message.mail().unwrap();
+
+        assert_eq!(event.queue_id, Some("4F3D2A1B2C"));
+        assert_eq!(event.field("to"), Some("<bob@example.com>"));
+        assert_eq!(event.relay(), Some("mx.example.com[1.2.3.4]:25"));
+        assert_eq!(event.dsn(), Some("2.0.0"));
+        assert_eq!(event.status(), Some("sent"));
+        assert_eq!(event.status_detail(), Some("250 2.0.0 Ok, queued as 9ABC"));
+        assert_eq!(event.fields.len(), 6);
+    }
+
+    #[test]
+    fn parse_sendmail() {
+        let message = parse_message(
+            "<22>Oct 11 22:14:15 mail sendmail[1234]: x9BMEF12345678: to=<bob@example.com>, delay=00:00:01, mailer=esmtp, relay=mx.example.com. [1.2.3.4], dsn=4.0.0, stat=Deferred: Connection refused",
+            Variant::RFC3164,
+        );
+        let event = message.mail().unwrap();
+
+        assert_eq!(event.queue_id, Some("x9BMEF12345678"));
+        assert_eq!(event.relay(), Some("mx.example.com. [1.2.3.4]"));
+        assert_eq!(event.status(), Some("Deferred: Connection refused"));
+        assert_eq!(event.status_detail(), None);
+    }
+
+    #[test]
+    fn parse_without_queue_id() {
+        let message = parse_message(
+            "<22>Oct 11 22:14:15 mail postfix/smtpd[1234]: connect from unknown[1.2.3.4]",
+            Variant::RFC3164,
+        );
+        let event = message.mail().unwrap();
+        assert_eq!(event.queue_id, None);
+        assert!(event.fields.is_empty());
+
+        let message = parse_message("<22>Oct 11 22:14:15 mail sshd[1]: a=b", Variant::RFC3164);
+        assert_eq!(message.mail(), None);
+    }
+}
@@ -1,19 +1,49 @@
-use crate::pri::{SyslogFacility, SyslogSeverity, compose_pri};
+use crate::pri::{Priority, SyslogFacility, SyslogSeverity, compose_pri};
 use crate::procid::ProcId;
 use crate::structured_data;
 use chrono::prelude::*;
-use std::fmt;
+use std::{
+    fmt,
+    hash::{Hash, Hasher},
+    sync::Arc,
+};
 
-#[derive(Clone, Debug, PartialEq, Eq)]
+/// The protocol a message was sent with, along with the version for RFC5424.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 #[allow(clippy::upper_case_acronyms)]
+#[non_exhaustive]
 pub enum Protocol {
     RFC3164,
     RFC5424(u32),
+    /// An RFC5424 style header with a version other than 1, the only one defined
+    /// so far. The rest of the header is read as it is for version 1.
+    Unknown(u32),
+}
+
+impl Protocol {
+    /// The protocol for an RFC5424 style header with `version`.
+    pub(crate) fn rfc5424(version: u32) -> Self {
+        match version {
+            1 => Protocol::RFC5424(version),
+            version => Protocol::Unknown(version),
+        }
+    }
+
+    /// The version given in the header, if the protocol has one.
+    pub fn version(&self) -> Option<u32> {
+        match *self {
+            Protocol::RFC3164 => None,
+            Protocol::RFC5424(version) | Protocol::Unknown(version) => Some(version),
+        }
+    }
 }
 
 #[derive(Clone, Debug)]
 pub struct Message<S: AsRef<str> + Ord + PartialEq + Clone> {
     pub protocol: Protocol,
+    /// The parsers set the facility and severity together from a [`Priority`],
+    /// so parsed messages have both or neither. They are kept as separate fields, so use
+    /// [`Message::priority`] and [`Message::set_priority`] to keep them together.
     pub facility: Option<SyslogFacility>,
     pub severity: Option<SyslogSeverity>,
     pub timestamp: Option<DateTime<FixedOffset>>,
@@ -36,9 +66,9 @@ impl<S: AsRef<str> + Ord + PartialEq + Clone> fmt::Display for Message<S> {
                 self.facility.unwrap_or(SyslogFacility::LOG_SYSLOG),
                 self.severity.unwrap_or(SyslogSeverity::SEV_DEBUG)
             ),
-            match self.protocol {
-                Protocol::RFC3164 => "".to_string(),
-                Protocol::RFC5424(version) => version.to_string(),
+            match self.protocol.version() {
+                None => "".to_string(),
+                Some(version) => version.to_string(),
             },
             self.timestamp
                 .unwrap_or_else(|| Utc::now().into())
@@ -47,7 +77,7 @@ impl<S: AsRef<str> + Ord + PartialEq + Clone> fmt::Display for Message<S> {
         )?;
 
         match self.protocol {
-            Protocol::RFC5424(_) => {
+            Protocol::RFC5424(_) | Protocol::Unknown(_) => {
                 write!(
                     f,
                     "{} ",
@@ -65,7 +95,7 @@ impl<S: AsRef<str> + Ord + PartialEq + Clone> fmt::Display for Message<S> {
             },
         }
 
-        if let Protocol::RFC5424(_) = self.protocol {
+        if self.protocol != Protocol::RFC3164 {
             write!(
                 f,
                 "{} ",
@@ -74,7 +104,7 @@ impl<S: AsRef<str> + Ord + PartialEq + Clone> fmt::Display for Message<S> {
         }
 
         if self.structured_data.is_empty() {
-            if let Protocol::RFC5424(_) = self.protocol {
+            if self.protocol != Protocol::RFC3164 {
                 write!(f, "- ")?;
             }
         } else {
@@ -97,28 +127,190 @@ impl<S: AsRef<str> + Ord + Clone> PartialEq for Message<S> {
             && self.appname == other.appname
             && self.procid == other.procid
             && self.msgid == other.msgid
-            && self.structured_data == other.structured_data
+            && self.sorted_structured_data() == other.sorted_structured_data()
             && self.msg == other.msg
     }
 }
 
-impl From<Message<&str>> for Message<String> {
-    fn from(message: Message<&str>) -> Self {
+impl<S: AsRef<str> + Ord + Clone> Eq for Message<S> {}
+
+/// Hashes the same fields that are compared for equality, so messages can be used
+/// as keys to deduplicate them. As with equality, the order of the structured data
+/// elements and of their params doesn't matter.
+impl<S: AsRef<str> + Ord + Clone> Hash for Message<S> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.facility.hash(state);
+        self.severity.hash(state);
+        self.timestamp.hash(state);
+        self.hostname.as_ref().map(AsRef::as_ref).hash(state);
+        self.appname.as_ref().map(AsRef::as_ref).hash(state);
+        self.procid.hash(state);
+        self.msgid.as_ref().map(AsRef::as_ref).hash(state);
+        self.sorted_structured_data().hash(state);
+        self.msg.as_ref().hash(state);
+    }
+}
+
+impl<S: AsRef<str> + Ord + Clone> Message<S> {
+    /// The structured data elements and their params in sorted order, to compare
+    /// and hash them regardless of the order they were sent in.
+    fn sorted_structured_data(&self) -> Vec<(&str, Vec<(&str, &str)>)> {
+        let mut elements = self
+            .structured_data
+            .iter()
+            .map(|element| {
+                let mut params = element
+                    .params
+                    .iter()
+                    .map(|(name, value)| (name.as_ref(), value.as_ref()))
+                    .collect::<Vec<_>>();
+                params.sort_unstable();
+                (element.id.as_ref(), params)
+            })
+            .collect::<Vec<_>>();
+        elements.sort_unstable();
+        elements
+    }
+}
+
+impl<S: AsRef<str> + Ord + PartialEq + Clone> Message<S> {
+    /// The facility and severity together, if the message has them.
+    pub fn priority(&self) -> Option<Priority> {
+        Some(Priority::new(self.facility?, self.severity?))
+    }
+
+    /// Set both the facility and the severity, or clear them both with None.
+    pub fn set_priority(&mut self, priority: Option<Priority>) {
+        self.facility = priority.map(Priority::facility);
+        self.severity = priority.map(Priority::severity);
+    }
+
+    /// The process id, if the procid is a number. See [`ProcId::as_pid`].
+    pub fn pid(&self) -> Option<u32> {
+        self.procid.as_ref()?.as_pid()
+    }
+
+    /// The message with its strings converted to `T`, such as `String`, `Arc<str>`
+    /// or `Box<str>`. Use [`Message::to_borrowed`] to go the other way.
+    pub fn into_owned<T>(self) -> Message<T>
+    where
+        T: for<'b> From<&'b str> + AsRef<str> + Ord + PartialEq + Clone,
+    {
+        self.to_borrowed().map(T::from)
+    }
+
+    /// A copy of the message that borrows its string fields from this one.
+    pub fn to_borrowed(&self) -> Message<&str> {
         Message {
-            facility: message.facility,
-            severity: message.severity,
-            timestamp: message.timestamp,
-            hostname: message.hostname.map(|s| s.to_string()),
-            appname: message.appname.map(|s| s.to_string()),
-            procid: message.procid.map(|s| s.into()),
-            msgid: message.msgid.map(|s| s.to_string()),
-            protocol: message.protocol,
-            structured_data: message
+            facility: self.facility,
+            severity: self.severity,
+            timestamp: self.timestamp,
+            hostname: self.hostname.as_ref().map(AsRef::as_ref),
+            appname: self.appname.as_ref().map(AsRef::as_ref),
+            procid: self.procid.as_ref().map(|procid| match procid {
+                ProcId::PID(pid) => ProcId::PID(*pid),
+                ProcId::Name(name) => ProcId::Name(name.as_ref()),
+            }),
+            msgid: self.msgid.as_ref().map(AsRef::as_ref),
+            protocol: self.protocol.clone(),
+            structured_data: self
                 .structured_data
                 .iter()
-                .map(|e| e.clone().into())
+                .map(|element| structured_data::StructuredElement {
+                    id: element.id.as_ref(),
+                    params: element
+                        .params
+                        .iter()
+                        .map(|(name, value)| (name.as_ref(), value.as_ref()))
+                        .collect(),
+                })
+                .collect(),
+            msg: self.msg.as_ref(),
+        }
+    }
+}
+
+impl<'a> Message<&'a str> {
+    /// Check, in debug builds, what holds for any message parsed from `input`: the
+    /// string fields all borrow from it, and there is a severity exactly when there
+    /// is a facility. Fuzzing relies on these to catch the parser going wrong.
+    pub(crate) fn debug_check_invariants(&self, input: &str) {
+        if !cfg!(debug_assertions) {
+            return;
+        }
+
+        let range = input.as_bytes().as_ptr_range();
+        let check = |field: &str| {
+            let bytes = field.as_bytes().as_ptr_range();
+            // Empty fields needn't point anywhere in particular.
+            debug_assert!(
+                bytes.start == bytes.end || (range.start <= bytes.start && bytes.end <= range.end),
+                "{:?} isn't borrowed from {:?}",
+                field,
+                input
+            );
+        };
+        check(self.msg);
+        for field in [self.hostname, self.appname, self.msgid].iter().flatten() {
+            check(field);
+        }
+        if let Some(ProcId::Name(name)) = self.procid {
+            check(name);
+        }
+        for element in &self.structured_data {
+            check(element.id);
+            for (name, value) in element.params.iter() {
+                check(name);
+                check(value);
+            }
+        }
+        debug_assert_eq!(
+            self.facility.is_some(),
+            self.severity.is_some(),
+            "{:?}",
+            input
+        );
+    }
+
+    /// Convert each of the string fields with `f`.
+    pub(crate) fn map<T, F>(self, mut f: F) -> Message<T>
+    where
+        T: AsRef<str> + Ord + PartialEq + Clone,
+        F: FnMut(&'a str) -> T,
+    {
+        Message {
+            facility: self.facility,
+            severity: self.severity,
+            timestamp: self.timestamp,
+            hostname: self.hostname.map(&mut f),
+            appname: self.appname.map(&mut f),
+            procid: self.procid.map(|procid| procid.map(&mut f)),
+            msgid: self.msgid.map(&mut f),
+            protocol: self.protocol,
+            structured_data: self
+                .structured_data
+                .into_iter()
+                .map(|element| element.map(&mut f))
                 .collect(),
-            msg: message.msg.to_string(),
+            msg: f(self.msg),
         }
     }
 }
+
+impl From<Message<&str>> for Message<String> {
+    fn from(message: Message<&str>) -> Self {
+        message.map(str::to_string)
+    }
+}
+
+impl From<Message<&str>> for Message<Arc<str>> {
+    fn from(message: Message<&str>) -> Self {
+        message.map(Arc::from)
+    }
+}
+
+impl From<Message<&str>> for Message<Box<str>> {
+    fn from(message: Message<&str>) -> Self {
+        message.map(Box::from)
+    }
+}
@@ -0,0 +1,171 @@
+//! Pretty reports of why messages didn't parse, using `miette`.
+use crate::{
+    diagnose::ParseWarning,
+    error::{Field, ParseError},
+};
+use miette::{Diagnostic, LabeledSpan, Severity, SourceCode};
+use std::{error, fmt};
+
+fn code(field: Field) -> &'static str {
+    match field {
+        Field::Pri => "syslog_loose::invalid_pri",
+        Field::Version => "syslog_loose::invalid_version",
+        Field::Timestamp => "syslog_loose::invalid_timestamp",
+        Field::Hostname => "syslog_loose::invalid_hostname",
+        Field::Appname => "syslog_loose::invalid_appname",
+        Field::ProcId => "syslog_loose::invalid_procid",
+        Field::MsgId => "syslog_loose::invalid_msgid",
+        Field::StructuredData => "syslog_loose::invalid_structured_data",
+        Field::Empty => "syslog_loose::empty",
+    }
+}
+
+/// The span of the field starting at `offset`, up to the next space.
+fn field_span(input: &str, offset: usize, label: String) -> LabeledSpan {
+    let len = input[offset..]
+        .find(char::is_whitespace)
+        .unwrap_or(input.len() - offset);
+    LabeledSpan::new(Some(label), offset, len)
+}
+
+impl<'a> Diagnostic for ParseError<'a> {
+    fn code<'b>(&'b self) -> Option<Box<dyn fmt::Display + 'b>> {
+        Some(Box::new(code(self.field)))
+    }
+
+    fn help<'b>(&'b self) -> Option<Box<dyn fmt::Display + 'b>> {
+        Some(Box::new(format!("see {}", self.rule)))
+    }
+
+    fn url<'b>(&'b self) -> Option<Box<dyn fmt::Display + 'b>> {
+        Some(Box::new(self.rule.url()))
+    }
+
+    fn source_code(&self) -> Option<&dyn SourceCode> {
+        Some(&self.input)
+    }
+
+    fn labels(&self) -> Option<Box<dyn Iterator<Item = LabeledSpan> + '_>> {
+        let label = format!("expected {}", self.expected);
+        Some(Box::new(std::iter::once(field_span(
+            self.input,
+            self.offset,
+            label,
+        ))))
+    }
+}
+
+/// A [`ParseWarning`] along with the input it was found in, so the report can show
+/// where it is. See [`ParseWarning::report`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct WarningReport<'a> {
+    pub input: &'a str,
+    pub warning: ParseWarning<'a>,
+}
+
+impl<'a> ParseWarning<'a> {
+    /// Pair the warning with the `input` it was found in, to be rendered by `miette`.
+    pub fn report(self, input: &'a str) -> WarningReport<'a> {
+        WarningReport {
+            input,
+            warning: self,
+        }
+    }
+}
+
+impl<'a> fmt::Display for WarningReport<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.warning.fmt(f)
+    }
+}
+
+impl<'a> error::Error for WarningReport<'a> {}
+
+impl<'a> Diagnostic for WarningReport<'a> {
+    fn code<'b>(&'b self) -> Option<Box<dyn fmt::Display + 'b>> {
+        Some(Box::new(match &self.warning {
+            ParseWarning::MissingPri => "syslog_loose::missing_pri",
+            ParseWarning::BadTimestamp { .. } => code(Field::Timestamp),
+            ParseWarning::Unparsed { field, .. } => code(*field),
+            ParseWarning::InvalidSdElement { .. } => "syslog_loose::invalid_sd_element",
+            ParseWarning::Truncated { .. } => "syslog_loose::truncated",
+            ParseWarning::ControlCharacter { .. } => "syslog_loose::control_character",
+        }))
+    }
+
+    fn severity(&self) -> Option<Severity> {
+        Some(Severity::Warning)
+    }
+
+    fn source_code(&self) -> Option<&dyn SourceCode> {
+        Some(&self.input)
+    }
+
+    fn labels(&self) -> Option<Box<dyn Iterator<Item = LabeledSpan> + '_>> {
+        let span = match &self.warning {
+            ParseWarning::MissingPri => {
+                let offset = self.input.len() - self.input.trim_start().len();
+                LabeledSpan::at_offset(offset, "expected a priority such as <34>")
+            }
+            ParseWarning::BadTimestamp { offset } => {
+                field_span(self.input, *offset, "taken as the message".to_string())
+            }
+            ParseWarning::Unparsed { offset, .. } => {
+                field_span(self.input, *offset, "taken as the message".to_string())
+            }
+            ParseWarning::InvalidSdElement { raw } => LabeledSpan::new(
+                Some("dropped".to_string()),
+                raw.as_ptr() as usize - self.input.as_ptr() as usize,
+                raw.len(),
+            ),
+            ParseWarning::ControlCharacter { offset } => {
+                LabeledSpan::new(Some("control character".to_string()), *offset, 1)
+            }
+            ParseWarning::Truncated { offset } => LabeledSpan::new(
+                Some("never closed".to_string()),
+                *offset,
+                self.input.len() - offset,
+            ),
+        };
+        Some(Box::new(std::iter::once(span)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ParseOptions, Variant};
+
+    #[test]
+    fn labels_failed_field() {
+        let input = "<34>1 yesterday mymachine su - - - message";
+        let error = ParseOptions::new(Variant::RFC5424)
+            .parse_exact(input)
+            .unwrap_err();
+
+        assert_eq!(
+            error.code().unwrap().to_string(),
+            "syslog_loose::invalid_timestamp"
+        );
+        let label = error.labels().unwrap().next().unwrap();
+        assert_eq!(
+            &input[label.offset()..label.offset() + label.len()],
+            "yesterday"
+        );
+        assert_eq!(label.label(), Some("expected an RFC3339 timestamp"));
+    }
+
+    #[test]
+    fn labels_warnings() {
+        let input = "<34>1 2003-10-11T22:14:15.003Z mymachine su - - [a b=\"c\"][d e=] message";
+        let (_, warnings) = ParseOptions::default().parse_with_diagnostics(input);
+        let report = warnings[0].clone().report(input);
+
+        assert_eq!(report.severity(), Some(Severity::Warning));
+        let label = report.labels().unwrap().next().unwrap();
+        assert_eq!(
+            &input[label.offset()..label.offset() + label.len()],
+            "[d e=]"
+        );
+    }
+}
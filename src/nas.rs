@@ -0,0 +1,173 @@
+//! Normalising the logs of Synology and QNAP network attached storage devices,
+//! which put the user and IP address in the body as `User [admin] from [10.0.0.1]`
+//! or as a list of `Key: value` pairs.
+use crate::message::Message;
+use std::net::IpAddr;
+
+/// A log from a Synology or QNAP device.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct NasEvent<'a> {
+    /// `synology` or `qnap`.
+    pub vendor: &'static str,
+    /// The kind of log, the appname for Synology, such as `Connection`, or
+    /// `event log` or `conn log` for QNAP.
+    pub category: &'a str,
+    /// The pairs from the body, in the order they were given. For bracketed values
+    /// the key is the word before the bracket, as in `from` for `from [10.0.0.1]`.
+    pub fields: Vec<(&'a str, &'a str)>,
+    pub user: Option<&'a str>,
+    pub ip: Option<IpAddr>,
+}
+
+/// Parse each `word [value]` in `input`.
+fn bracketed(input: &str) -> Vec<(&str, &str)> {
+    let mut fields = Vec::new();
+    let mut rest = input;
+    while let Some(start) = rest.find(" [") {
+        let key = rest[..start].rsplit(' ').next().unwrap_or("");
+        let Some(end) = rest[start + 2..].find(']') else {
+            break;
+        };
+        fields.push((key, &rest[start + 2..start + 2 + end]));
+        rest = &rest[start + 2 + end + 1..];
+    }
+    fields
+}
+
+/// Parse a comma separated list of `Key: value` pairs.
+fn listed(input: &str) -> Vec<(&str, &str)> {
+    input
+        .split(", ")
+        .filter_map(|pair| pair.split_once(": "))
+        .map(|(key, value)| (key.trim(), value.trim()))
+        .collect()
+}
+
+impl<'a> NasEvent<'a> {
+    fn new(
+        vendor: &'static str,
+        category: &'a str,
+        fields: Vec<(&'a str, &'a str)>,
+    ) -> Option<Self> {
+        if fields.is_empty() {
+            return None;
+        }
+        let field = |keys: &[&str]| {
+            fields
+                .iter()
+                .find(|(key, _)| keys.contains(key))
+                .map(|(_, value)| *value)
+        };
+
+        Some(NasEvent {
+            vendor,
+            category,
+            user: field(&["User", "Users"]),
+            ip: field(&["from", "IP", "Source IP"]).and_then(|ip| ip.parse().ok()),
+            fields,
+        })
+    }
+
+    /// Parse a QNAP log body, starting with `event log:` or `conn log:`.
+    pub fn parse_qnap(input: &'a str) -> Option<Self> {
+        let (category, rest) = input.trim_start().split_once(": ")?;
+        if category != "event log" && category != "conn log" {
+            return None;
+        }
+        NasEvent::new("qnap", category, listed(rest))
+    }
+
+    /// Parse a Synology log body, given the appname it was logged with.
+    pub fn parse_synology(appname: &'a str, input: &'a str) -> Option<Self> {
+        let fields = if appname == "WinFileService" {
+            listed(input)
+        } else {
+            bracketed(input)
+        };
+        // The first bracketed value is always preceded by `User`.
+        match fields.first() {
+            Some((key, _)) if *key == "User" || *key == "Event" => {
+                NasEvent::new("synology", appname, fields)
+            }
+            _ => None,
+        }
+    }
+}
+
+impl<S: AsRef<str> + Ord + PartialEq + Clone> Message<S> {
+    /// Parse the body of the message as a Synology or QNAP log.
+    pub fn nas_event(&self) -> Option<NasEvent<'_>> {
+        let msg = self.msg.as_ref();
+        NasEvent::parse_qnap(msg).or_else(|| {
+            let appname = self.appname.as_ref()?.as_ref();
+            NasEvent::parse_synology(appname, msg)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Variant, parse_message};
+
+    #[test]
+    fn synology_connection() {
+        let message = parse_message(
+            "<14>Oct 11 22:14:15 DiskStation Connection: User [admin] from [10.0.0.1] signed in to [DSM] successfully via [password].",
+            Variant::RFC3164,
+        );
+
+        assert_eq!(
+            message.nas_event().unwrap(),
+            NasEvent {
+                vendor: "synology",
+                category: "Connection",
+                fields: vec![
+                    ("User", "admin"),
+                    ("from", "10.0.0.1"),
+                    ("to", "DSM"),
+                    ("via", "password")
+                ],
+                user: Some("admin"),
+                ip: Some("10.0.0.1".parse().unwrap()),
+            }
+        );
+    }
+
+    #[test]
+    fn synology_file_service() {
+        let message = parse_message(
+            "<14>Oct 11 22:14:15 DiskStation WinFileService Event: write, Path: /share/a.txt, File/Folder: File, Size: 1 KB, User: bob, IP: 10.0.0.1",
+            Variant::RFC3164,
+        );
+        let event = message.nas_event().unwrap();
+
+        assert_eq!(event.category, "WinFileService");
+        assert_eq!(event.fields[1], ("Path", "/share/a.txt"));
+        assert_eq!(event.user, Some("bob"));
+        assert_eq!(event.ip, Some("10.0.0.1".parse().unwrap()));
+    }
+
+    #[test]
+    fn qnap_conn_log() {
+        let message = parse_message(
+            "<14>Oct 11 22:14:15 NAS qlogd[123]: conn log: Users: bob, Source IP: 10.0.0.1, Computer name: pc, Connection type: SAMBA, Accessed resources: /share/a, Action: Read",
+            Variant::RFC3164,
+        );
+        let event = message.nas_event().unwrap();
+
+        assert_eq!(event.vendor, "qnap");
+        assert_eq!(event.category, "conn log");
+        assert_eq!(event.user, Some("bob"));
+        assert_eq!(event.fields.last(), Some(&("Action", "Read")));
+    }
+
+    #[test]
+    fn not_nas() {
+        let message = parse_message(
+            "<14>Oct 11 22:14:15 host app: value [x] here",
+            Variant::RFC3164,
+        );
+        assert_eq!(message.nas_event(), None);
+    }
+}
@@ -0,0 +1,412 @@
+use crate::{
+    Variant,
+    dialect::{self, Dialect, DialectFields},
+    error::{Error, InvalidUtf8, ParseError},
+    framing::{Delimiters, Framing, Oversize, Resync},
+    lazy::LazyMessage,
+    message::{Message, Protocol},
+    pri::vendor_priority,
+    timestamp::IncompleteDate,
+};
+use chrono::prelude::*;
+use nom::IResult;
+use std::{cell::Cell, fmt, sync::Arc};
+
+/// The largest frame accepted by default - 64KiB, comfortably above the
+/// 2048 octets that RFC5424 requires receivers to handle.
+pub const DEFAULT_MAX_FRAME_LEN: usize = 64 * 1024;
+
+/// How to treat input that is not valid UTF-8.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Utf8Policy {
+    /// Return an error for any frame containing invalid UTF-8.
+    Strict,
+    /// Replace invalid sequences with U+FFFD and carry on parsing.
+    Lossy,
+}
+
+/// What to do with the control characters, other than tab, in the body of a message.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ControlChars {
+    /// Leave them as they are.
+    Preserve,
+    /// Remove them.
+    Strip,
+    /// Replace each with `#` and its value as three octal digits, as rsyslog does,
+    /// so a newline becomes `#012`.
+    Escape,
+}
+
+fn is_control(c: char) -> bool {
+    c.is_ascii_control() && c != '\t'
+}
+
+/// The byte offset of the first control character, other than tab, in `input`.
+pub(crate) fn find_control(input: &str) -> Option<usize> {
+    input.find(is_control)
+}
+
+impl ControlChars {
+    /// `msg` with the control characters dealt with, or None if it can be left as is.
+    fn apply(self, msg: &str) -> Option<String> {
+        if self == ControlChars::Preserve || find_control(msg).is_none() {
+            return None;
+        }
+
+        let mut clean = String::with_capacity(msg.len());
+        for c in msg.chars() {
+            match self {
+                _ if !is_control(c) => clean.push(c),
+                ControlChars::Escape => clean.push_str(&format!("#{:03o}", c as u8)),
+                ControlChars::Strip | ControlChars::Preserve => {}
+            }
+        }
+        Some(clean)
+    }
+}
+
+/// The number of invalid sequences in `bytes` that [`Utf8Policy::Lossy`] replaces
+/// with U+FFFD.
+pub(crate) fn replacements(bytes: &[u8]) -> u64 {
+    bytes
+        .utf8_chunks()
+        .filter(|chunk| !chunk.invalid().is_empty())
+        .count() as u64
+}
+
+/// Called with the raw bytes of a frame that couldn't be parsed exactly, see
+/// [`ParseOptions::on_malformed`].
+pub type MalformedHook = Arc<dyn Fn(&[u8]) + Send + Sync>;
+
+/// Decides from its header whether to keep a frame, see [`ParseOptions::filter`].
+pub type HeaderFilter = Arc<dyn Fn(&LazyMessage<'_>) -> bool + Send + Sync>;
+
+/// Options used when parsing messages read from a stream of bytes.
+#[derive(Clone)]
+pub struct ParseOptions {
+    /// The variant of message we are expecting to receive.
+    pub variant: Variant,
+    /// A default timezone to use if the parsed timestamp does not specify one.
+    /// If None the local timezone is used.
+    pub tz: Option<FixedOffset>,
+    /// Called if the parsed message contains a date with no year.
+    pub get_year: fn(IncompleteDate) -> i32,
+    /// What to do with frames that are not valid UTF-8.
+    pub utf8: Utf8Policy,
+    /// How individual messages are separated in the stream.
+    pub framing: Framing,
+    /// The bytes that end a frame when using non transparent framing.
+    pub delimiters: Delimiters,
+    /// How to recover when an octet counted frame has an invalid length.
+    pub resync: Resync,
+    /// The longest frame that will be accepted.
+    pub max_frame_len: usize,
+    /// What to do with frames longer than `max_frame_len`.
+    pub oversize: Oversize,
+    /// Called with the raw bytes of every frame that couldn't be parsed exactly
+    /// and so is returned with the whole frame as the message. The hook is shared
+    /// between clones of the options, so can count or forward the frames.
+    pub on_malformed: Option<MalformedHook>,
+    /// If set, a [`Parser`](crate::Parser) replaces each message saying that the
+    /// last message was repeated, see [`Message::repeated_count`], with copies of the
+    /// previous message - at most this many.
+    pub expand_repeated: Option<u32>,
+    /// The dialects tried in turn by [`ParseOptions::extract`]. These start as the
+    /// [`BUILTIN_DIALECTS`](crate::BUILTIN_DIALECTS), and more can be added with
+    /// [`ParseOptions::with_dialect`].
+    pub dialects: Vec<Arc<dyn Dialect>>,
+    /// Called with the header of each frame read from a stream. Frames it returns
+    /// false for are skipped before the structured data and body are parsed or any
+    /// strings are copied, and are counted in [`DecoderStats::filtered`].
+    ///
+    /// [`DecoderStats::filtered`]: crate::DecoderStats::filtered
+    pub filter: Option<HeaderFilter>,
+    /// What to do with control characters in the body of each message read from a
+    /// stream. Messages read by a [`SliceReader`](crate::SliceReader) are never
+    /// copied, so always keep them.
+    pub control_chars: ControlChars,
+    /// Keep a PRI above 191, which has a facility beyond `local7`, as
+    /// [`SyslogFacility::Unknown`] rather than treating it as invalid. Some devices
+    /// use these as vendor extensions. Messages that fail to parse are left
+    /// without a facility.
+    ///
+    /// [`SyslogFacility::Unknown`]: crate::SyslogFacility::Unknown
+    pub vendor_facilities: bool,
+    /// Take four digits following the time of an RFC3164 timestamp as the year,
+    /// as in `Oct 11 22:14:15 2023 switch1`, which Aruba switches send. Otherwise
+    /// they are taken as the hostname.
+    pub year_after_time: bool,
+}
+
+impl fmt::Debug for ParseOptions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ParseOptions")
+            .field("variant", &self.variant)
+            .field("tz", &self.tz)
+            .field("utf8", &self.utf8)
+            .field("framing", &self.framing)
+            .field("delimiters", &self.delimiters)
+            .field("resync", &self.resync)
+            .field("max_frame_len", &self.max_frame_len)
+            .field("oversize", &self.oversize)
+            .field("on_malformed", &self.on_malformed.is_some())
+            .field("expand_repeated", &self.expand_repeated)
+            .field("dialects", &self.dialects)
+            .field("filter", &self.filter.is_some())
+            .field("control_chars", &self.control_chars)
+            .field("vendor_facilities", &self.vendor_facilities)
+            .field("year_after_time", &self.year_after_time)
+            .finish_non_exhaustive()
+    }
+}
+
+thread_local! {
+    /// The current local year, and the time at which the next one starts.
+    static CURRENT_YEAR: Cell<Option<(i32, DateTime<Utc>)>> = const { Cell::new(None) };
+}
+
+/// The current year in the local timezone. Looking up the local time for every
+/// message without a year is relatively slow, so the year is worked out once and
+/// kept until the next new year.
+fn current_year(_: IncompleteDate) -> i32 {
+    let now = Utc::now();
+    if let Some((year, until)) = CURRENT_YEAR.with(Cell::get) {
+        if now < until {
+            return year;
+        }
+    }
+
+    let year = now.with_timezone(&Local).year();
+    let next = Local
+        .with_ymd_and_hms(year + 1, 1, 1, 0, 0, 0)
+        .earliest()
+        .map(|next| next.with_timezone(&Utc));
+    if let Some(next) = next {
+        CURRENT_YEAR.with(|current| current.set(Some((year, next))));
+    }
+    year
+}
+
+impl Default for ParseOptions {
+    fn default() -> Self {
+        ParseOptions {
+            variant: Variant::Either,
+            tz: None,
+            get_year: current_year,
+            utf8: Utf8Policy::Lossy,
+            framing: Framing::NonTransparent,
+            delimiters: Delimiters::NEWLINE,
+            resync: Resync::Scan,
+            max_frame_len: DEFAULT_MAX_FRAME_LEN,
+            oversize: Oversize::Error,
+            on_malformed: None,
+            expand_repeated: None,
+            dialects: dialect::builtin(),
+            filter: None,
+            control_chars: ControlChars::Preserve,
+            vendor_facilities: false,
+            year_after_time: false,
+        }
+    }
+}
+
+impl ParseOptions {
+    /// Options for the given variant, everything else is left as the default.
+    pub fn new(variant: Variant) -> Self {
+        ParseOptions {
+            variant,
+            ..Default::default()
+        }
+    }
+
+    /// Parse a single message using these options.
+    pub fn parse<'a>(&self, input: &'a str) -> Message<&'a str> {
+        match self.parse_preferring(input, None) {
+            Ok((_, message)) => self.vendor_facility(input, message),
+            Err(_) => crate::unparsed(input),
+        }
+    }
+
+    /// Parse a single message exactly, returning an error if it can't be parsed.
+    pub fn parse_exact<'a>(&self, input: &'a str) -> Result<Message<&'a str>, ParseError<'a>> {
+        self.parse_preferring(input, None)
+            .map(|(_, message)| self.vendor_facility(input, message))
+            .map_err(|_| ParseError::diagnose(input, self.variant))
+    }
+
+    fn parse_preferring<'a>(
+        &self,
+        input: &'a str,
+        preferred: Option<Variant>,
+    ) -> IResult<&'a str, Message<&'a str>> {
+        crate::parse_preferring(
+            input,
+            self.get_year,
+            self.tz,
+            self.variant,
+            preferred,
+            self.year_after_time,
+        )
+    }
+
+    /// Set the facility and severity of `message` from a PRI with a vendor facility
+    /// when [`ParseOptions::vendor_facilities`] is set. Only for messages whose
+    /// header was parsed, not those taking the whole input as the body.
+    pub(crate) fn vendor_facility<'a>(
+        &self,
+        input: &str,
+        mut message: Message<&'a str>,
+    ) -> Message<&'a str> {
+        if self.vendor_facilities && message.facility.is_none() {
+            if let Some(priority) = vendor_priority(input) {
+                message.set_priority(Some(priority));
+            }
+        }
+        message
+    }
+
+    /// Parse a single message like [`ParseOptions::parse`], keeping track in `last` of
+    /// the variant of each message parsed from the same source. With
+    /// [`Variant::Either`] that variant is tried first, which saves failing to parse
+    /// every RFC3164 message as RFC5424 when a source only sends RFC3164.
+    ///
+    /// ```
+    /// use syslog_loose::{ParseOptions, Variant};
+    ///
+    /// let options = ParseOptions::default();
+    /// let mut last = None;
+    /// options.parse_with_hint("<34>Oct 11 22:14:15 mymachine su: one", &mut last);
+    /// assert!(matches!(last, Some(Variant::RFC3164)));
+    /// ```
+    pub fn parse_with_hint<'a>(
+        &self,
+        input: &'a str,
+        last: &mut Option<Variant>,
+    ) -> Message<&'a str> {
+        self.parse_exact_with_hint(input, last)
+            .unwrap_or_else(|_| self.parse(input))
+    }
+
+    pub(crate) fn parse_exact_with_hint<'a>(
+        &self,
+        input: &'a str,
+        last: &mut Option<Variant>,
+    ) -> Result<Message<&'a str>, ParseError<'a>> {
+        let (_, message) = self
+            .parse_preferring(input, *last)
+            .map_err(|_| ParseError::diagnose(input, self.variant))?;
+        let message = self.vendor_facility(input, message);
+        *last = Some(match message.protocol {
+            Protocol::RFC3164 => Variant::RFC3164,
+            Protocol::RFC5424(_) | Protocol::Unknown(_) => Variant::RFC5424,
+        });
+        Ok(message)
+    }
+
+    /// Extract the vendor specific fields from the body of a message using the
+    /// first of the dialects that recognises it.
+    pub fn extract<S: AsRef<str> + Ord + PartialEq + Clone>(
+        &self,
+        message: &Message<S>,
+    ) -> Option<DialectFields> {
+        dialect::extract(self.dialects.iter().map(|dialect| &**dialect), message)
+    }
+
+    /// Register `dialect`, to be tried before the dialects already registered.
+    pub fn with_dialect(mut self, dialect: impl Dialect + 'static) -> Self {
+        self.dialects.insert(0, Arc::new(dialect));
+        self
+    }
+
+    /// Parse `input`, which was decoded from the frame `raw`, calling the malformed
+    /// hook if it can't be parsed exactly. Also returns whether the parse fell back
+    /// to taking the whole input as the message. Returns None if the filter skips it.
+    /// `last` is the variant of the last message from the same source, see
+    /// [`ParseOptions::parse_with_hint`].
+    pub(crate) fn parse_raw<'a>(
+        &self,
+        raw: &[u8],
+        input: &'a str,
+        last: &mut Option<Variant>,
+    ) -> Option<(Message<&'a str>, bool)> {
+        // The header is only parsed the once, for both the filter and the message.
+        let (message, fallback) = match self.parse_lazy_with_hint(input, last) {
+            Ok(message) => (message, false),
+            Err(_) => (LazyMessage::parsed(self.parse(input)), true),
+        };
+
+        if let Some(filter) = &self.filter {
+            if !filter(&message) {
+                return None;
+            }
+        }
+
+        if fallback {
+            if let Some(on_malformed) = &self.on_malformed {
+                on_malformed(raw);
+            }
+        }
+        Some((message.into_message(), fallback))
+    }
+
+    /// Pass `message` to `f` with the control characters in the body dealt with as
+    /// set by [`ParseOptions::control_chars`], which copies the body if there are any.
+    pub(crate) fn clean_msg<R>(
+        &self,
+        message: Message<&str>,
+        f: impl FnOnce(Message<&str>) -> R,
+    ) -> R {
+        match self.control_chars.apply(message.msg) {
+            Some(msg) => f(Message {
+                msg: &msg,
+                ..message
+            }),
+            None => f(message),
+        }
+    }
+
+    /// Parse a frame of raw bytes, applying the UTF-8 policy.
+    pub(crate) fn parse_frame(
+        &self,
+        frame: &[u8],
+        last: &mut Option<Variant>,
+    ) -> Result<Option<(Message<String>, bool)>, Error> {
+        self.parse_frame_with(frame, last, &mut 0, |message, _| message.into())
+    }
+
+    /// Parse a frame of raw bytes, applying the UTF-8 policy, and pass the borrowed
+    /// message to `f` along with the decoded input. Only a lossily decoded frame with
+    /// invalid UTF-8 is copied, adding the number of invalid sequences replaced to
+    /// `replaced`.
+    pub(crate) fn parse_frame_with<R>(
+        &self,
+        frame: &[u8],
+        last: &mut Option<Variant>,
+        replaced: &mut u64,
+        f: impl FnOnce(Message<&str>, &str) -> R,
+    ) -> Result<Option<(R, bool)>, Error> {
+        let parsed = match (std::str::from_utf8(frame), self.utf8) {
+            (Ok(input), _) => self
+                .parse_raw(frame, input, last)
+                .map(|(message, fallback)| {
+                    (
+                        self.clean_msg(message, |message| f(message, input)),
+                        fallback,
+                    )
+                }),
+            (Err(err), Utf8Policy::Strict) => return Err(InvalidUtf8::new(frame, err).into()),
+            (Err(_), Utf8Policy::Lossy) => {
+                *replaced += replacements(frame);
+                let input = String::from_utf8_lossy(frame);
+                self.parse_raw(frame, &input, last)
+                    .map(|(message, fallback)| {
+                        (
+                            self.clean_msg(message, |message| f(message, &input)),
+                            fallback,
+                        )
+                    })
+            }
+        };
+        Ok(parsed)
+    }
+}
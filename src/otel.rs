@@ -0,0 +1,118 @@
+//! Conversion to the OpenTelemetry [log data model](https://opentelemetry.io/docs/specs/otel/logs/data-model/).
+//!
+//! This doesn't depend on any particular OpenTelemetry SDK, the fields of
+//! [`OtelLogRecord`] map directly onto those of the SDK's `LogRecord` and `Resource`.
+use crate::{message::Message, pri::SyslogSeverity, procid::ProcId};
+use chrono::{DateTime, FixedOffset};
+
+/// The OpenTelemetry `SeverityNumber` for a syslog severity, as given in the
+/// [mapping table](https://opentelemetry.io/docs/specs/otel/logs/data-model-appendix/#appendix-b-severitynumber-example-mappings).
+pub fn otel_severity_number(severity: SyslogSeverity) -> i32 {
+    match severity {
+        SyslogSeverity::SEV_DEBUG => 5,
+        SyslogSeverity::SEV_INFO => 9,
+        SyslogSeverity::SEV_NOTICE => 10,
+        SyslogSeverity::SEV_WARNING => 13,
+        SyslogSeverity::SEV_ERR => 17,
+        SyslogSeverity::SEV_CRIT => 18,
+        SyslogSeverity::SEV_ALERT => 19,
+        SyslogSeverity::SEV_EMERG => 21,
+    }
+}
+
+/// A log record in the OpenTelemetry data model.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct OtelLogRecord {
+    pub timestamp: Option<DateTime<FixedOffset>>,
+    /// See [`otel_severity_number`].
+    pub severity_number: Option<i32>,
+    pub severity_text: Option<&'static str>,
+    pub body: String,
+    /// The syslog header fields that aren't part of the resource, and the
+    /// structured data flattened into `<SD-ID>.<PARAM-NAME>` keys.
+    pub attributes: Vec<(String, String)>,
+    /// `host.name` and `service.name` from the hostname and appname.
+    pub resource: Vec<(String, String)>,
+}
+
+impl<S: AsRef<str> + Ord + PartialEq + Clone> From<&Message<S>> for OtelLogRecord {
+    fn from(message: &Message<S>) -> Self {
+        let mut resource = Vec::new();
+        if let Some(hostname) = &message.hostname {
+            resource.push(("host.name".to_string(), hostname.as_ref().to_string()));
+        }
+        if let Some(appname) = &message.appname {
+            resource.push(("service.name".to_string(), appname.as_ref().to_string()));
+        }
+
+        let mut attributes = Vec::new();
+        if let Some(facility) = message.facility {
+            attributes.push(("syslog.facility".to_string(), facility.as_str().to_string()));
+        }
+        match &message.procid {
+            Some(ProcId::PID(pid)) => {
+                attributes.push(("syslog.procid".to_string(), pid.to_string()));
+            }
+            Some(ProcId::Name(name)) => {
+                attributes.push(("syslog.procid".to_string(), name.as_ref().to_string()));
+            }
+            None => {}
+        }
+        if let Some(msgid) = &message.msgid {
+            attributes.push(("syslog.msgid".to_string(), msgid.as_ref().to_string()));
+        }
+        for element in &message.structured_data {
+            for (param, value) in element.params() {
+                attributes.push((format!("{}.{}", element.id.as_ref(), param.as_ref()), value));
+            }
+        }
+
+        OtelLogRecord {
+            timestamp: message.timestamp,
+            severity_number: message.severity.map(otel_severity_number),
+            severity_text: message.severity.map(SyslogSeverity::as_str),
+            body: message.msg.as_ref().to_string(),
+            attributes,
+            resource,
+        }
+    }
+}
+
+impl<S: AsRef<str> + Ord + PartialEq + Clone> From<Message<S>> for OtelLogRecord {
+    fn from(message: Message<S>) -> Self {
+        OtelLogRecord::from(&message)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Variant, parse_message};
+
+    #[test]
+    fn message_to_log_record() {
+        let record = OtelLogRecord::from(parse_message(
+            r#"<165>1 2003-10-11T22:14:15.003Z mymachine.example.com evntslog - ID47 [exampleSDID@32473 iut="3"] An application event"#,
+            Variant::RFC5424,
+        ));
+
+        assert_eq!(record.severity_number, Some(10));
+        assert_eq!(record.severity_text, Some("notice"));
+        assert_eq!(record.body, "An application event");
+        assert_eq!(
+            record.resource,
+            vec![
+                ("host.name".to_string(), "mymachine.example.com".to_string()),
+                ("service.name".to_string(), "evntslog".to_string()),
+            ]
+        );
+        assert_eq!(
+            record.attributes,
+            vec![
+                ("syslog.facility".to_string(), "local4".to_string()),
+                ("syslog.msgid".to_string(), "ID47".to_string()),
+                ("exampleSDID@32473.iut".to_string(), "3".to_string()),
+            ]
+        );
+    }
+}
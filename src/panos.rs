@@ -0,0 +1,276 @@
+//! Parsing the CSV bodies of Palo Alto Networks PAN-OS logs. The meaning of each
+//! column depends on the log type in the fourth column, see the
+//! [PAN-OS syslog field descriptions](https://docs.paloaltonetworks.com/pan-os/10-1/pan-os-admin/monitoring/use-syslog-for-monitoring/syslog-field-descriptions).
+use crate::message::Message;
+use std::borrow::Cow;
+
+/// Columns marked `FUTURE_USE` are left empty and skipped.
+const COMMON: &[&str] = &[
+    "",
+    "receive_time",
+    "serial",
+    "type",
+    "subtype",
+    "",
+    "time_generated",
+];
+
+/// The columns following [`COMMON`] that TRAFFIC and THREAT logs share.
+const SESSION: &[&str] = &[
+    "src",
+    "dst",
+    "natsrc",
+    "natdst",
+    "rule",
+    "srcuser",
+    "dstuser",
+    "app",
+    "vsys",
+    "from",
+    "to",
+    "inbound_if",
+    "outbound_if",
+    "logset",
+    "",
+    "sessionid",
+    "repeatcnt",
+    "sport",
+    "dport",
+    "natsport",
+    "natdport",
+    "flags",
+    "proto",
+    "action",
+];
+
+const TRAFFIC: &[&str] = &[
+    "bytes",
+    "bytes_sent",
+    "bytes_received",
+    "packets",
+    "start",
+    "elapsed",
+    "category",
+    "",
+    "seqno",
+    "actionflags",
+    "srcloc",
+    "dstloc",
+    "",
+    "pkts_sent",
+    "pkts_received",
+    "session_end_reason",
+];
+
+const THREAT: &[&str] = &[
+    "misc",
+    "threatid",
+    "category",
+    "severity",
+    "direction",
+    "seqno",
+    "actionflags",
+    "srcloc",
+    "dstloc",
+    "",
+    "contenttype",
+    "pcap_id",
+    "filedigest",
+    "cloud",
+    "url_idx",
+    "user_agent",
+    "filetype",
+    "xff",
+    "referer",
+    "sender",
+    "subject",
+    "recipient",
+    "reportid",
+];
+
+const SYSTEM: &[&str] = &[
+    "vsys",
+    "eventid",
+    "object",
+    "",
+    "",
+    "module",
+    "severity",
+    "opaque",
+    "seqno",
+    "actionflags",
+];
+
+/// Split a line of CSV. Fields may be quoted with `"`, with `""` standing for a
+/// quote within a quoted field.
+fn split_csv(input: &str) -> Vec<Cow<'_, str>> {
+    let mut fields = Vec::new();
+    let mut rest = input;
+    loop {
+        let Some(quoted) = rest.strip_prefix('"') else {
+            let end = rest.find(',').unwrap_or(rest.len());
+            fields.push(Cow::Borrowed(&rest[..end]));
+            match rest[end..].strip_prefix(',') {
+                Some(remaining) => rest = remaining,
+                None => return fields,
+            }
+            continue;
+        };
+
+        let mut value = Cow::Borrowed("");
+        let mut remaining = quoted;
+        loop {
+            let end = remaining.find('"').unwrap_or(remaining.len());
+            value = if value.is_empty() {
+                Cow::Borrowed(&remaining[..end])
+            } else {
+                Cow::Owned(value.into_owned() + &remaining[..end])
+            };
+            remaining = remaining[end..].strip_prefix('"').unwrap_or("");
+            match remaining.strip_prefix('"') {
+                Some(after) => {
+                    value.to_mut().push('"');
+                    remaining = after;
+                }
+                None => break,
+            }
+        }
+        fields.push(value);
+
+        // Skip anything between the closing quote and the next comma.
+        let end = remaining.find(',').unwrap_or(remaining.len());
+        match remaining[end..].strip_prefix(',') {
+            Some(after) => rest = after,
+            None => return fields,
+        }
+    }
+}
+
+/// A PAN-OS log with its columns named according to the log type.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PanOs<'a> {
+    /// The log type, such as `TRAFFIC`, `THREAT` or `SYSTEM`.
+    pub log_type: Cow<'a, str>,
+    /// The named columns in order. Columns for future use and any columns beyond
+    /// the known layout are left out. Other log types only have the columns common
+    /// to all logs named.
+    pub fields: Vec<(&'static str, Cow<'a, str>)>,
+}
+
+impl<'a> PanOs<'a> {
+    /// Parse a PAN-OS CSV log body.
+    pub fn parse(input: &'a str) -> Option<Self> {
+        let columns = split_csv(input.trim());
+        let log_type = columns.get(3)?.clone();
+        let layout: &[&[&str]] = match log_type.as_ref() {
+            "TRAFFIC" => &[COMMON, SESSION, TRAFFIC],
+            "THREAT" => &[COMMON, SESSION, THREAT],
+            "SYSTEM" => &[COMMON, SYSTEM],
+            log_type
+                if !log_type.is_empty()
+                    && log_type
+                        .bytes()
+                        .all(|b| b.is_ascii_uppercase() || b == b'-' || b == b'_') =>
+            {
+                &[COMMON]
+            }
+            _ => return None,
+        };
+
+        let fields = layout
+            .iter()
+            .flat_map(|names| names.iter())
+            .zip(columns)
+            .filter(|(name, _)| !name.is_empty())
+            .map(|(name, value)| (*name, value))
+            .collect();
+
+        Some(PanOs { log_type, fields })
+    }
+
+    /// The value of the named column, if present.
+    pub fn field(&self, name: &str) -> Option<&str> {
+        self.fields
+            .iter()
+            .find(|(field, _)| *field == name)
+            .map(|(_, value)| value.as_ref())
+    }
+
+    /// Copy any borrowed values so the log no longer borrows its input.
+    pub fn into_owned(self) -> PanOs<'static> {
+        PanOs {
+            log_type: Cow::Owned(self.log_type.into_owned()),
+            fields: self
+                .fields
+                .into_iter()
+                .map(|(name, value)| (name, Cow::Owned(value.into_owned())))
+                .collect(),
+        }
+    }
+}
+
+impl<S: AsRef<str> + Ord + PartialEq + Clone> Message<S> {
+    /// Parse the body of the message as a PAN-OS log.
+    ///
+    /// PAN-OS doesn't send a tag, so RFC3164 parsing takes the start of the CSV, up
+    /// to the first space, as the appname. When the appname contains a comma it is
+    /// joined back on to the body before parsing.
+    pub fn panos(&self) -> Option<PanOs<'_>> {
+        match &self.appname {
+            Some(appname) if appname.as_ref().contains(',') => {
+                let input = format!("{} {}", appname.as_ref(), self.msg.as_ref());
+                PanOs::parse(&input).map(PanOs::into_owned)
+            }
+            _ => PanOs::parse(self.msg.as_ref()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Variant, parse_message};
+
+    #[test]
+    fn parse_traffic() {
+        let message = parse_message(
+            "<14>Oct 11 22:14:15 PA-VM 1,2023/10/11 22:14:15,0123,TRAFFIC,end,2049,2023/10/11 22:14:15,10.0.0.1,8.8.8.8,,,allow-dns,,,dns,vsys1,trust,untrust,ethernet1/2,ethernet1/1,fwd,2023/10/11 22:14:15,1234,1,53000,53,0,0,0x19,udp,allow,200,100,100,2",
+            Variant::RFC3164,
+        );
+        let log = message.panos().unwrap();
+
+        assert_eq!(log.log_type, "TRAFFIC");
+        assert_eq!(log.field("receive_time"), Some("2023/10/11 22:14:15"));
+        assert_eq!(log.field("src"), Some("10.0.0.1"));
+        assert_eq!(log.field("rule"), Some("allow-dns"));
+        assert_eq!(log.field("dport"), Some("53"));
+        assert_eq!(log.field("proto"), Some("udp"));
+        assert_eq!(log.field("action"), Some("allow"));
+        assert_eq!(log.field("packets"), Some("2"));
+        assert_eq!(log.field("start"), None);
+    }
+
+    #[test]
+    fn parse_system_quoted() {
+        let log = PanOs::parse(
+            r#"1,2023/10/11 22:14:15,0123,SYSTEM,general,2049,2023/10/11 22:14:15,,general,,0,0,general,informational,"User admin logged in, via ""Web""",1"#,
+        )
+        .unwrap();
+
+        assert_eq!(log.field("eventid"), Some("general"));
+        assert_eq!(log.field("severity"), Some("informational"));
+        assert_eq!(
+            log.field("opaque"),
+            Some(r#"User admin logged in, via "Web""#)
+        );
+        assert_eq!(log.field("seqno"), Some("1"));
+    }
+
+    #[test]
+    fn parse_unknown_type() {
+        let log =
+            PanOs::parse("1,2023/10/11 22:14:15,0123,CONFIG,0,2049,2023/10/11 22:14:15,x").unwrap();
+        assert_eq!(log.fields.len(), 5);
+        assert_eq!(PanOs::parse("just some text"), None);
+    }
+}
@@ -0,0 +1,723 @@
+//! A push style parser that doesn't do any IO itself.
+use crate::{
+    clock::ClockCheck,
+    dialect::DialectFields,
+    error::Error,
+    filter::MessageFilter,
+    framing::{FrameBuffer, Framing},
+    interner::Interner,
+    message::Message,
+    options::ParseOptions,
+    stats::{DecoderStats, ParseStats},
+    timestamp::TimestampCache,
+};
+use chrono::Duration;
+use std::{collections::VecDeque, sync::Arc};
+
+type Queued = Result<Message<String>, Error>;
+
+/// An incremental parser for a stream of messages.
+///
+/// Data is pushed in with [`Parser::feed`] as it arrives, in chunks of any size,
+/// and the parsed messages are pulled out with [`Parser::next_message`]. The parser
+/// does no IO so can be driven by any event loop.
+///
+/// ```
+/// use syslog_loose::{ParseOptions, Parser, Variant};
+///
+/// let mut parser = Parser::new(ParseOptions::new(Variant::RFC3164));
+/// parser.feed(b"<34>Oct 11 22:14:15 mymachine su: one\n<34>Oct 11 22:14:15 myma");
+/// assert_eq!(parser.next_message().unwrap().unwrap().msg, "one");
+/// assert!(parser.next_message().is_none());
+///
+/// parser.feed(b"chine su: two");
+/// parser.finish();
+/// assert_eq!(parser.next_message().unwrap().unwrap().msg, "two");
+/// ```
+///
+/// By default messages are only parsed as they are pulled out. With
+/// [`Parser::with_max_queued`] they are parsed as data is fed, up to the given number,
+/// and [`Parser::is_full`] tells the caller to stop reading until some have been taken.
+#[derive(Debug)]
+pub struct Parser {
+    options: ParseOptions,
+    buf: FrameBuffer,
+    /// Messages already parsed, along with whether they were truncated and how far
+    /// their timestamp went back.
+    queue: VecDeque<(Queued, bool, Option<Duration>)>,
+    max_queued: Option<usize>,
+    truncated: bool,
+    went_back: Option<Duration>,
+    /// The last message returned, kept when expanding repeated messages.
+    last: Option<Message<String>>,
+    /// The number of copies of `last` still to be returned.
+    repeats: u32,
+    interner: Interner,
+    timestamp_cache: Option<TimestampCache>,
+    parse_stats: Option<ParseStats>,
+    clock: Option<ClockCheck>,
+}
+
+impl Parser {
+    pub fn new(options: ParseOptions) -> Self {
+        Parser {
+            buf: FrameBuffer::new(&options),
+            options,
+            queue: VecDeque::new(),
+            max_queued: None,
+            truncated: false,
+            went_back: None,
+            last: None,
+            repeats: 0,
+            interner: Interner::new(),
+            timestamp_cache: None,
+            parse_stats: None,
+            clock: None,
+        }
+    }
+
+    /// Parse messages as data is fed, holding at most `max_queued` of them until they
+    /// are taken with [`Parser::next_message`].
+    pub fn with_max_queued(self, max_queued: usize) -> Self {
+        Parser {
+            max_queued: Some(max_queued),
+            ..self
+        }
+    }
+
+    /// Share the strings of the messages returned by [`Parser::next_message_interned`]
+    /// through `interner`, which may also be used by other parsers.
+    pub fn with_interner(self, interner: Interner) -> Self {
+        Parser { interner, ..self }
+    }
+
+    /// Reuse the previous timestamp when a message has exactly the same RFC3339
+    /// timestamp text as the last one parsed, as happens when a device sends a burst
+    /// of messages within one second.
+    pub fn with_timestamp_cache(self) -> Self {
+        Parser {
+            timestamp_cache: Some(TimestampCache::default()),
+            ..self
+        }
+    }
+
+    /// Keep the counts returned by [`Parser::parse_stats`]. Working out which
+    /// fallbacks were taken adds to the cost of parsing each message.
+    pub fn with_parse_stats(self) -> Self {
+        Parser {
+            parse_stats: Some(ParseStats::default()),
+            ..self
+        }
+    }
+
+    /// The counts of what has been parsed so far, if enabled with
+    /// [`Parser::with_parse_stats`].
+    pub fn parse_stats(&self) -> Option<ParseStats> {
+        self.parse_stats
+    }
+
+    /// Check each message's timestamp against the last one from the same host, for
+    /// [`Parser::went_back`] to report when it is more than `threshold` earlier. See
+    /// [`ClockCheck`].
+    pub fn with_clock_check(self, threshold: Duration) -> Self {
+        Parser {
+            clock: Some(ClockCheck::new(threshold)),
+            ..self
+        }
+    }
+
+    /// Drop the messages that don't match `filter`, counting them in
+    /// [`DecoderStats::filtered`]. The filter is checked with
+    /// [`MessageFilter::matches_lazy`] along with any [`ParseOptions::filter`], so
+    /// frames rejected on their header are never parsed in full.
+    pub fn with_message_filter(mut self, filter: MessageFilter) -> Self {
+        let previous = self.options.filter.take();
+        self.options.filter = Some(Arc::new(move |header| {
+            previous.as_ref().is_none_or(|previous| previous(header)) && filter.matches_lazy(header)
+        }));
+        self
+    }
+
+    /// The options this parser was created with.
+    pub fn options(&self) -> &ParseOptions {
+        &self.options
+    }
+
+    /// Parse a single message with the parser's options, without going through the
+    /// buffer. See [`ParseOptions::parse`].
+    pub fn parse<'a>(&self, input: &'a str) -> Message<&'a str> {
+        self.options.parse(input)
+    }
+
+    /// Extract the vendor specific fields from the body of a message with the
+    /// parser's dialects. See [`ParseOptions::extract`].
+    pub fn extract<S: AsRef<str> + Ord + PartialEq + Clone>(
+        &self,
+        message: &Message<S>,
+    ) -> Option<DialectFields> {
+        self.options.extract(message)
+    }
+
+    /// The framing being used. If the options specify [`Framing::Auto`] this will
+    /// remain `Auto` until enough data has been fed to detect the actual framing.
+    pub fn framing(&self) -> Framing {
+        self.buf.framing()
+    }
+
+    /// Add more data received from the stream.
+    pub fn feed(&mut self, data: &[u8]) {
+        self.buf.extend(data);
+        self.fill_queue();
+    }
+
+    /// Signal that the stream has ended. Any data remaining that hasn't been terminated
+    /// will be returned as the final message.
+    pub fn finish(&mut self) {
+        self.buf.set_eof();
+        self.fill_queue();
+    }
+
+    /// Has [`Parser::finish`] been called?
+    pub fn is_finished(&self) -> bool {
+        self.buf.is_eof()
+    }
+
+    /// Throw away any data that has been fed but not yet returned as a message.
+    pub fn clear(&mut self) {
+        self.buf.clear();
+        self.queue.clear();
+        self.last = None;
+        self.repeats = 0;
+    }
+
+    /// The number of messages parsed and waiting to be taken.
+    pub fn queued(&self) -> usize {
+        self.queue.len()
+    }
+
+    /// Has the limit set by [`Parser::with_max_queued`] been reached? If so the caller
+    /// should stop feeding data until messages have been taken.
+    pub fn is_full(&self) -> bool {
+        self.max_queued
+            .is_some_and(|max_queued| self.queue.len() >= max_queued)
+    }
+
+    /// The counts of what has been decoded so far.
+    pub fn stats(&self) -> DecoderStats {
+        self.buf.stats()
+    }
+
+    /// Was the last message returned cut short by [`Oversize::Truncate`](crate::Oversize::Truncate)?
+    pub fn was_truncated(&self) -> bool {
+        self.truncated
+    }
+
+    /// How far the timestamp of the last message returned went back from the one
+    /// before it from the same host, if enabled with [`Parser::with_clock_check`]
+    /// and it went back more than the threshold.
+    pub fn went_back(&self) -> Option<Duration> {
+        self.went_back
+    }
+
+    /// Returns the next message, or None if more data is needed to complete the
+    /// next frame.
+    pub fn next_message(&mut self) -> Option<Result<Message<String>, Error>> {
+        loop {
+            if self.repeats > 0 {
+                if let Some(last) = &self.last {
+                    self.repeats -= 1;
+                    self.truncated = false;
+                    self.went_back = None;
+                    return Some(Ok(last.clone()));
+                }
+            }
+
+            let (message, truncated, went_back) = match self.queue.pop_front() {
+                Some(queued) => {
+                    self.fill_queue();
+                    queued
+                }
+                None => {
+                    let message = self.parse_next()?;
+                    (message, self.buf.was_truncated(), self.went_back)
+                }
+            };
+            self.truncated = truncated;
+            self.went_back = went_back;
+
+            if let (Some(max), Ok(parsed)) = (self.options.expand_repeated, &message) {
+                match parsed.repeated_count() {
+                    Some(count) if self.last.is_some() => {
+                        self.repeats = count.min(max);
+                        continue;
+                    }
+                    _ => self.last = Some(parsed.clone()),
+                }
+            }
+
+            return Some(message);
+        }
+    }
+
+    /// Returns the next message like [`Parser::next_message`], with the strings that
+    /// repeat between messages shared through the parser's [`Interner`].
+    pub fn next_message_interned(&mut self) -> Option<Result<Message<Arc<str>>, Error>> {
+        let message = self.next_message()?;
+        Some(message.map(|message| self.interner.message(&message)))
+    }
+
+    /// Calls `f` with each of the messages that can be parsed from the data fed so
+    /// far. Unlike [`Parser::next_message`] the messages borrow from the parser's
+    /// buffer, so no strings are allocated for them.
+    ///
+    /// Messages that were already queued, and repeated messages when expanding
+    /// them, are owned by the parser and are passed on borrowed from there.
+    pub fn for_each_message<F>(&mut self, mut f: F)
+    where
+        F: FnMut(Result<Message<&str>, Error>),
+    {
+        loop {
+            if self.repeats > 0 || !self.queue.is_empty() || self.options.expand_repeated.is_some()
+            {
+                match self.next_message() {
+                    Some(Ok(message)) => f(Ok(message.to_borrowed())),
+                    Some(Err(err)) => f(Err(err)),
+                    None => return,
+                }
+                continue;
+            }
+
+            let Some(result) = self.parse_next_with(|message| f(Ok(message))) else {
+                return;
+            };
+            self.truncated = self.buf.was_truncated();
+            if let Err(err) = result {
+                f(Err(err));
+            }
+        }
+    }
+
+    /// Parse the next buffered frame, using the timestamp cache if there is one.
+    fn parse_next(&mut self) -> Option<Result<Message<String>, Error>> {
+        self.parse_next_with(|message| message.into())
+    }
+
+    /// Parse the next buffered frame and pass the borrowed message to `f`.
+    fn parse_next_with<R>(
+        &mut self,
+        mut f: impl FnMut(Message<&str>) -> R,
+    ) -> Option<Result<R, Error>> {
+        let (buf, options, parse_stats) = (&mut self.buf, &self.options, &mut self.parse_stats);
+        let (clock, went_back) = (&mut self.clock, &mut self.went_back);
+        *went_back = None;
+        let f = |message: Message<&str>, input: &str| {
+            if let Some(parse_stats) = parse_stats.as_mut() {
+                parse_stats.record(input, options.variant, &message);
+            }
+            *went_back = clock.as_mut().and_then(|clock| clock.check(&message));
+            f(message)
+        };
+        match &mut self.timestamp_cache {
+            Some(cache) => cache.scope(|| buf.next_message(options, f)),
+            None => buf.next_message(options, f),
+        }
+    }
+
+    /// Parse buffered frames until the queue is full, if it is bounded.
+    fn fill_queue(&mut self) {
+        let Some(max_queued) = self.max_queued else {
+            return;
+        };
+
+        while self.queue.len() < max_queued {
+            match self.parse_next() {
+                Some(message) => {
+                    self.queue
+                        .push_back((message, self.buf.was_truncated(), self.went_back))
+                }
+                None => break,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::FrameError;
+    use std::sync::Mutex;
+
+    #[test]
+    fn feed_byte_at_a_time() {
+        let input = b"56 <34>1 2003-10-11T22:14:15.003Z mymachine su - ID47 - one57 <34>1 2003-10-11T22:14:15.003Z mymachine su - ID47 - two\n";
+        let mut parser = Parser::new(ParseOptions {
+            framing: Framing::OctetCounting,
+            ..Default::default()
+        });
+
+        let mut messages = Vec::new();
+        for byte in input.iter() {
+            parser.feed(&[*byte]);
+            while let Some(message) = parser.next_message() {
+                messages.push(message.unwrap().msg);
+            }
+        }
+
+        assert_eq!(messages, vec!["one", "two"]);
+    }
+
+    #[test]
+    fn detects_framing() {
+        let mut parser = Parser::new(ParseOptions {
+            framing: Framing::Auto,
+            ..Default::default()
+        });
+        assert_eq!(parser.framing(), Framing::Auto);
+
+        parser.feed(b"<34>Oct 11 22:14:15 mymachine su: one\n");
+        assert_eq!(parser.next_message().unwrap().unwrap().msg, "one");
+        assert_eq!(parser.framing(), Framing::NonTransparent);
+    }
+
+    #[test]
+    fn truncates_long_frame() {
+        let mut parser = Parser::new(ParseOptions {
+            max_frame_len: 40,
+            oversize: crate::Oversize::Truncate,
+            ..ParseOptions::new(crate::Variant::RFC3164)
+        });
+        parser.feed(b"<34>Oct 11 22:14:15 mymachine su: one two three\n<34>Oct 11 22:14:15 mymachine su: ok\n");
+
+        assert_eq!(parser.next_message().unwrap().unwrap().msg, "one tw");
+        assert!(parser.was_truncated());
+        assert_eq!(parser.next_message().unwrap().unwrap().msg, "ok");
+        assert!(!parser.was_truncated());
+    }
+
+    #[test]
+    fn counts_stats() {
+        let malformed = Arc::new(Mutex::new(Vec::new()));
+        let mut parser = Parser::new(ParseOptions {
+            framing: Framing::OctetCounting,
+            on_malformed: Some({
+                let malformed = malformed.clone();
+                Arc::new(move |raw| malformed.lock().unwrap().push(raw.to_vec()))
+            }),
+            ..Default::default()
+        });
+        parser.feed(b"4 junk x y 57 <34>1 2003-10-11T22:14:15.003Z mymachine su - ID47 - fine");
+        parser.finish();
+
+        let mut results = Vec::new();
+        while let Some(result) = parser.next_message() {
+            results.push(result.map(|message| message.msg));
+        }
+
+        assert_eq!(results.len(), 3);
+        assert_eq!(results[0].as_deref().unwrap(), "junk");
+        assert!(matches!(
+            results[1],
+            Err(Error::Frame(FrameError::InvalidLength))
+        ));
+        assert_eq!(results[2].as_deref().unwrap(), "fine");
+        assert_eq!(*malformed.lock().unwrap(), vec![b"junk".to_vec()]);
+        assert_eq!(
+            parser.stats(),
+            DecoderStats {
+                frames: 2,
+                fallbacks: 1,
+                resyncs: 1,
+                truncations: 0,
+                errors: 1,
+                filtered: 0,
+                empty: 0,
+                replacements: 0,
+            }
+        );
+    }
+
+    #[test]
+    fn visits_borrowed_messages() {
+        let mut parser = Parser::new(ParseOptions::new(crate::Variant::RFC3164)).with_max_queued(1);
+        parser.feed(
+            b"<34>Oct 11 22:14:15 mymachine su: one\n<34>Oct 11 22:14:15 mymachine su: two\n",
+        );
+        parser.feed(b"<34>Oct 11 22:14:15 mymachine su: three\n<34>Oct 11 22:14:15 mymachine");
+        assert_eq!(parser.queued(), 1);
+
+        let mut messages = Vec::new();
+        parser.for_each_message(|message| messages.push(message.unwrap().msg.to_string()));
+        assert_eq!(messages, vec!["one", "two", "three"]);
+
+        parser.finish();
+        parser.for_each_message(|message| messages.push(message.unwrap().msg.to_string()));
+        assert_eq!(messages.len(), 4);
+        assert!(parser.next_message().is_none());
+    }
+
+    #[test]
+    fn parses_single_messages() {
+        use chrono::{Datelike, Local};
+
+        let parser = Parser::new(ParseOptions::new(crate::Variant::RFC3164));
+        for input in [
+            "<34>Oct 11 22:14:15 mymachine su: one",
+            "<34>Oct 11 22:14:15 mymachine su: two",
+        ] {
+            let message = parser.parse(input);
+            assert_eq!(message.timestamp.unwrap().year(), Local::now().year());
+            assert_eq!(message.appname, Some("su"));
+        }
+        assert!(
+            parser
+                .extract(&parser.parse("<34>Oct 11 22:14:15 mymachine su: x"))
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn counts_parses() {
+        let mut parser = Parser::new(ParseOptions::default()).with_parse_stats();
+        parser.feed(b"<34>Oct 11 22:14:15 mymachine su: one\n");
+        parser.feed(b"<34>1 2003-10-11T22:14:15.003Z mymachine su - ID47 [x][bad x] two\n");
+        parser.feed(b"Oct 41 22:14:15 mymachine su: three\n");
+        while let Some(message) = parser.next_message() {
+            message.unwrap();
+        }
+
+        assert_eq!(
+            parser.parse_stats(),
+            Some(ParseStats {
+                messages: 3,
+                rfc3164: 1,
+                rfc5424: 1,
+                fallbacks: 1,
+                missing_pri: 1,
+                bad_timestamps: 0,
+                unparsed: 1,
+                invalid_sd_elements: 1,
+                truncated: 0,
+                control_characters: 0,
+            })
+        );
+        assert_eq!(Parser::new(ParseOptions::default()).parse_stats(), None);
+    }
+
+    #[test]
+    fn drops_filtered_messages() {
+        let mut parser = Parser::new(ParseOptions::new(crate::Variant::RFC3164))
+            .with_message_filter(MessageFilter::new().with_appname("sshd"));
+        parser.feed(b"<34>Oct 11 22:14:15 host su: one\n<34>Oct 11 22:14:15 host sshd: two\n");
+        parser.feed(b"<34>Oct 11 22:14:15 host cron: three\n<34>Oct 11 22:14:15 host sshd: four\n");
+
+        let mut messages = Vec::new();
+        parser.for_each_message(|message| messages.push(message.unwrap().msg.to_string()));
+        assert_eq!(messages, vec!["two", "four"]);
+        assert_eq!(parser.stats().filtered, 2);
+    }
+
+    #[test]
+    fn filters_before_parsing() {
+        let mut parser = Parser::new(ParseOptions::new(crate::Variant::RFC5424))
+            .with_parse_stats()
+            .with_message_filter(
+                MessageFilter::new().with_severity(..=crate::SyslogSeverity::SEV_WARNING),
+            );
+        parser.feed(b"<37>1 2003-10-11T22:14:15.003Z host su - - [bad one\n");
+        parser.feed(b"<36>1 2003-10-11T22:14:15.003Z host su - - [x a=\"1\"] two\n");
+
+        let mut messages = Vec::new();
+        while let Some(message) = parser.next_message() {
+            messages.push(message.unwrap().msg);
+        }
+        assert_eq!(messages, vec!["two"]);
+        assert_eq!(parser.stats().filtered, 1);
+        assert_eq!(parser.stats().fallbacks, 0);
+        assert_eq!(parser.parse_stats().unwrap().messages, 1);
+    }
+
+    #[test]
+    fn flags_clock_going_back() {
+        let mut parser = Parser::new(ParseOptions::new(crate::Variant::RFC5424))
+            .with_max_queued(2)
+            .with_clock_check(Duration::minutes(1));
+        parser.feed(b"<34>1 2003-10-11T22:14:15Z a su - - - one\n");
+        parser.feed(b"<34>1 2003-10-11T21:14:15Z a su - - - two\n");
+        parser.feed(b"<34>1 2003-10-11T21:14:00Z a su - - - three\n");
+
+        let mut went_back = Vec::new();
+        while let Some(message) = parser.next_message() {
+            went_back.push((message.unwrap().msg, parser.went_back()));
+        }
+        assert_eq!(
+            went_back,
+            vec![
+                ("one".to_string(), None),
+                ("two".to_string(), Some(Duration::hours(1))),
+                ("three".to_string(), None),
+            ]
+        );
+    }
+
+    #[test]
+    fn filters_on_header() {
+        let min_severity = crate::SyslogSeverity::SEV_WARNING;
+        let ignored = ["noisy".to_string()];
+        let mut parser = Parser::new(ParseOptions {
+            filter: Some(Arc::new(move |header| {
+                header.severity() <= Some(min_severity)
+                    && !header
+                        .appname()
+                        .is_some_and(|appname| ignored.iter().any(|ignored| ignored == appname))
+            })),
+            ..Default::default()
+        });
+        parser
+            .feed(b"<36>1 2003-10-11T22:14:15.003Z mymachine su - ID47 [meta seq=\"1\"] warning\n");
+        parser
+            .feed(b"<37>1 2003-10-11T22:14:15.003Z mymachine su - ID47 [meta seq=\"2\"] notice\n");
+        parser.feed(b"<34>1 2003-10-11T22:14:15.003Z mymachine noisy - ID47 - critical\n");
+        parser.feed(b"<34>Oct 11 22:14:15 mymachine su: 3164\n");
+
+        let mut messages = Vec::new();
+        while let Some(message) = parser.next_message() {
+            messages.push(message.unwrap().msg);
+        }
+
+        assert_eq!(messages, vec!["warning", "3164"]);
+        assert_eq!(parser.stats().filtered, 2);
+    }
+
+    #[test]
+    fn prefers_last_variant() {
+        let mut parser = Parser::new(ParseOptions::default());
+        parser.feed(b"<34>Oct 11 22:14:15 mymachine su: one\n");
+        parser.feed(b"<34>1 2003-10-11T22:14:15.003Z mymachine su - ID47 - two\n");
+        parser.feed(b"<34>Oct 11 22:14:15 mymachine su: three\n");
+
+        let mut protocols = Vec::new();
+        while let Some(message) = parser.next_message() {
+            let message = message.unwrap();
+            protocols.push((message.protocol, message.msg));
+        }
+
+        assert_eq!(
+            protocols,
+            vec![
+                (crate::Protocol::RFC3164, "one".to_string()),
+                (crate::Protocol::RFC5424(1), "two".to_string()),
+                (crate::Protocol::RFC3164, "three".to_string()),
+            ]
+        );
+        assert_eq!(parser.stats().fallbacks, 0);
+    }
+
+    #[test]
+    fn bounded_queue() {
+        let mut parser = Parser::new(ParseOptions::new(crate::Variant::RFC3164)).with_max_queued(2);
+        parser.feed(
+            b"<34>Oct 11 22:14:15 mymachine su: one\n<34>Oct 11 22:14:15 mymachine su: two\n",
+        );
+        assert_eq!(parser.queued(), 2);
+        assert!(parser.is_full());
+
+        parser.feed(b"<34>Oct 11 22:14:15 mymachine su: three\n");
+        assert_eq!(parser.queued(), 2);
+
+        assert_eq!(parser.next_message().unwrap().unwrap().msg, "one");
+        assert!(parser.is_full());
+        assert_eq!(parser.next_message().unwrap().unwrap().msg, "two");
+        assert_eq!(parser.next_message().unwrap().unwrap().msg, "three");
+        assert!(!parser.is_full());
+        assert!(parser.next_message().is_none());
+    }
+
+    #[test]
+    fn finish_truncated_frame() {
+        let mut parser = Parser::new(ParseOptions {
+            framing: Framing::OctetCounting,
+            ..Default::default()
+        });
+        parser.feed(b"100 <34>1 2003");
+        assert!(parser.next_message().is_none());
+
+        parser.finish();
+        assert!(matches!(
+            parser.next_message(),
+            Some(Err(Error::Frame(FrameError::Truncated {
+                expected: 100,
+                actual: 10
+            })))
+        ));
+        assert!(parser.next_message().is_none());
+    }
+
+    #[test]
+    fn interns_across_parsers() {
+        let interner = Interner::new();
+        let mut first =
+            Parser::new(ParseOptions::new(crate::Variant::RFC3164)).with_interner(interner.clone());
+        let mut second =
+            Parser::new(ParseOptions::new(crate::Variant::RFC3164)).with_interner(interner.clone());
+        first.feed(b"<34>Oct 11 22:14:15 mymachine su: one\n");
+        second.feed(b"<34>Oct 11 22:14:16 mymachine su: two\n");
+
+        let one = first.next_message_interned().unwrap().unwrap();
+        let two = second.next_message_interned().unwrap().unwrap();
+        assert_eq!(&*two.msg, "two");
+        assert!(Arc::ptr_eq(
+            one.appname.as_ref().unwrap(),
+            two.appname.as_ref().unwrap()
+        ));
+        assert_eq!(interner.len(), 2);
+    }
+
+    #[test]
+    fn caches_timestamps() {
+        let mut parser =
+            Parser::new(ParseOptions::new(crate::Variant::RFC5424)).with_timestamp_cache();
+        parser.feed(b"<34>1 2003-10-11T22:14:15.003+01:00 host su - - - one\n<34>1 2003-10-11T22:14:15.003+01:00 host su - - - two\n<34>1 2003-10-11T22:14:16.003+01:00 host su - - - three\n");
+
+        let mut timestamps = Vec::new();
+        while let Some(message) = parser.next_message() {
+            timestamps.push(message.unwrap().timestamp.unwrap().to_rfc3339());
+        }
+        assert_eq!(
+            timestamps,
+            vec![
+                "2003-10-11T22:14:15.003+01:00",
+                "2003-10-11T22:14:15.003+01:00",
+                "2003-10-11T22:14:16.003+01:00"
+            ]
+        );
+    }
+
+    #[test]
+    fn expands_repeated() {
+        let mut parser = Parser::new(ParseOptions {
+            expand_repeated: Some(3),
+            ..ParseOptions::new(crate::Variant::RFC3164)
+        });
+        parser.feed(b"<13>Oct 11 22:14:15 host sshd: failed\n<13>Oct 11 22:14:45 host last message repeated 2 times\n<13>Oct 11 22:15:45 host last message repeated 100 times\n<13>Oct 11 22:16:00 host sshd: ok\n");
+        parser.finish();
+
+        let mut messages = Vec::new();
+        while let Some(message) = parser.next_message() {
+            messages.push(message.unwrap().msg);
+        }
+        assert_eq!(
+            messages,
+            vec!["failed"; 6]
+                .into_iter()
+                .chain(Some("ok"))
+                .collect::<Vec<_>>()
+        );
+
+        // A repeat after clearing has nothing to repeat.
+        parser.clear();
+        parser.feed(b"<13>Oct 11 22:16:30 host last message repeated 2 times\n");
+        let message = parser.next_message().unwrap().unwrap();
+        assert_eq!(message.msg, "message repeated 2 times");
+        assert!(parser.next_message().is_none());
+    }
+}
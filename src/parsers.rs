@@ -15,6 +15,28 @@ where
     map_res(digit1, FromStr::from_str).parse(input)
 }
 
+/// Parse the decimal number at the start of `input` in a single pass, failing if
+/// there are no digits or the number is larger than `max`. This is quicker than
+/// [`digits`] for the PRI and version that start every message.
+pub(crate) fn decimal(input: &str, max: u32) -> Option<(u32, &str)> {
+    let bytes = input.as_bytes();
+    let mut value: u32 = 0;
+    let mut len = 0;
+    while let Some(&digit) = bytes.get(len).filter(|b| b.is_ascii_digit()) {
+        value = value
+            .checked_mul(10)?
+            .checked_add(u32::from(digit - b'0'))
+            .filter(|value| *value <= max)?;
+        len += 1;
+    }
+
+    if len == 0 {
+        None
+    } else {
+        Some((value, &input[len..]))
+    }
+}
+
 struct ParserOpts {
     has_colons: bool,
     has_trailing_colon: bool,
@@ -0,0 +1,158 @@
+//! Taking a message apart into its fields and putting one together again.
+use crate::{
+    message::{Message, Protocol},
+    pri::{SyslogFacility, SyslogSeverity},
+    procid::ProcId,
+    structured_data::StructuredElement,
+};
+use chrono::{DateTime, FixedOffset};
+
+/// The fields of a [`Message`], for moving messages to and from other log record
+/// types. Unlike a message, the parts have a default, an RFC5424 message with
+/// every field empty, so only the fields that are known need to be named.
+///
+/// ```
+/// use syslog_loose::{Message, MessageParts, SyslogSeverity};
+///
+/// let message = Message::from_parts(MessageParts {
+///     severity: Some(SyslogSeverity::SEV_ERR),
+///     hostname: Some("mymachine"),
+///     msg: "disk full",
+///     ..Default::default()
+/// });
+/// assert_eq!(message.hostname, Some("mymachine"));
+///
+/// let MessageParts { severity, msg, .. } = message.into_parts();
+/// assert_eq!((severity, msg), (Some(SyslogSeverity::SEV_ERR), "disk full"));
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MessageParts<S: AsRef<str> + Ord + PartialEq + Clone> {
+    pub protocol: Protocol,
+    pub facility: Option<SyslogFacility>,
+    pub severity: Option<SyslogSeverity>,
+    pub timestamp: Option<DateTime<FixedOffset>>,
+    pub hostname: Option<S>,
+    pub appname: Option<S>,
+    pub procid: Option<ProcId<S>>,
+    pub msgid: Option<S>,
+    pub structured_data: Vec<StructuredElement<S>>,
+    pub msg: S,
+}
+
+impl<S: AsRef<str> + Ord + PartialEq + Clone + Default> Default for MessageParts<S> {
+    fn default() -> Self {
+        MessageParts {
+            protocol: Protocol::RFC5424(1),
+            facility: None,
+            severity: None,
+            timestamp: None,
+            hostname: None,
+            appname: None,
+            procid: None,
+            msgid: None,
+            structured_data: Vec::new(),
+            msg: S::default(),
+        }
+    }
+}
+
+impl<S: AsRef<str> + Ord + PartialEq + Clone> Message<S> {
+    /// Build a message from its parts.
+    pub fn from_parts(parts: MessageParts<S>) -> Self {
+        let MessageParts {
+            protocol,
+            facility,
+            severity,
+            timestamp,
+            hostname,
+            appname,
+            procid,
+            msgid,
+            structured_data,
+            msg,
+        } = parts;
+        Message {
+            protocol,
+            facility,
+            severity,
+            timestamp,
+            hostname,
+            appname,
+            procid,
+            msgid,
+            structured_data,
+            msg,
+        }
+    }
+
+    /// Take the message apart, the reverse of [`Message::from_parts`].
+    pub fn into_parts(self) -> MessageParts<S> {
+        let Message {
+            protocol,
+            facility,
+            severity,
+            timestamp,
+            hostname,
+            appname,
+            procid,
+            msgid,
+            structured_data,
+            msg,
+        } = self;
+        MessageParts {
+            protocol,
+            facility,
+            severity,
+            timestamp,
+            hostname,
+            appname,
+            procid,
+            msgid,
+            structured_data,
+            msg,
+        }
+    }
+
+    /// A copy of the parts of the message, leaving it as it is.
+    pub fn to_parts(&self) -> MessageParts<S> {
+        self.clone().into_parts()
+    }
+}
+
+impl<S: AsRef<str> + Ord + PartialEq + Clone> From<MessageParts<S>> for Message<S> {
+    fn from(parts: MessageParts<S>) -> Self {
+        Message::from_parts(parts)
+    }
+}
+
+impl<S: AsRef<str> + Ord + PartialEq + Clone> From<Message<S>> for MessageParts<S> {
+    fn from(message: Message<S>) -> Self {
+        message.into_parts()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Variant, parse_message};
+
+    #[test]
+    fn round_trips() {
+        let message = parse_message(
+            r#"<34>1 2003-10-11T22:14:15.003Z mymachine su 12 ID47 [meta a="1"] message"#,
+            Variant::RFC5424,
+        );
+        let parts = message.to_parts();
+        assert_eq!(parts.procid, Some(ProcId::PID(12)));
+        assert_eq!(Message::from_parts(parts), message);
+
+        let message = Message::from(MessageParts {
+            appname: Some("su"),
+            msg: "message",
+            ..Default::default()
+        });
+        assert_eq!(message.protocol, Protocol::RFC5424(1));
+        assert_eq!((message.hostname, message.appname), (None, Some("su")));
+        assert!(message.structured_data.is_empty());
+    }
+}
@@ -0,0 +1,328 @@
+//! Extracting fields from free text message bodies with grok style patterns.
+//!
+//! A pattern is literal text with `%{TYPE:name}` captures, for example
+//! `Accepted %{WORD:method} for %{NOTSPACE:user} from %{IP:ip} port %{INT:port}`.
+//! The types are
+//!
+//! * `WORD` - letters, digits and underscores.
+//! * `NOTSPACE` - anything up to the next whitespace.
+//! * `INT` - an optionally signed integer.
+//! * `NUMBER` - an optionally signed decimal number.
+//! * `IP` - an IPv4 or IPv6 address.
+//! * `DATA` - anything, as little as possible.
+//! * `GREEDYDATA` - anything, as much as possible.
+//!
+//! A capture without a name, `%{INT}`, is matched but not returned.
+use crate::{error::PatternError, message::Message};
+use std::collections::BTreeMap;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Kind {
+    Word,
+    NotSpace,
+    Int,
+    Number,
+    Ip,
+    Data,
+    GreedyData,
+}
+
+impl Kind {
+    fn parse(name: &str) -> Option<Self> {
+        Some(match name {
+            "WORD" => Kind::Word,
+            "NOTSPACE" => Kind::NotSpace,
+            "INT" => Kind::Int,
+            "NUMBER" => Kind::Number,
+            "IP" => Kind::Ip,
+            "DATA" => Kind::Data,
+            "GREEDYDATA" => Kind::GreedyData,
+            _ => return None,
+        })
+    }
+
+    /// The lengths of the prefixes of `input` this could match, in the order they
+    /// should be tried.
+    fn candidates(self, input: &str) -> Vec<usize> {
+        let run = |f: fn(char) -> bool| {
+            input
+                .char_indices()
+                .find(|&(_, c)| !f(c))
+                .map_or(input.len(), |(pos, _)| pos)
+        };
+        let boundaries = || {
+            input
+                .char_indices()
+                .map(|(pos, _)| pos)
+                .chain(std::iter::once(input.len()))
+        };
+
+        match self {
+            Kind::Word => non_empty(run(|c| c.is_alphanumeric() || c == '_')),
+            Kind::NotSpace => non_empty(run(|c| !c.is_whitespace())),
+            Kind::Int => {
+                let sign = usize::from(input.starts_with(['-', '+']));
+                let digits = run_from(input, sign, |c| c.is_ascii_digit());
+                if digits == 0 {
+                    vec![]
+                } else {
+                    vec![sign + digits]
+                }
+            }
+            Kind::Number => {
+                let sign = usize::from(input.starts_with(['-', '+']));
+                let whole = run_from(input, sign, |c| c.is_ascii_digit());
+                let mut len = sign + whole;
+                if input[len..].starts_with('.') {
+                    let fraction = run_from(input, len + 1, |c| c.is_ascii_digit());
+                    if fraction > 0 {
+                        len += 1 + fraction;
+                    }
+                }
+                if whole == 0 && len == sign {
+                    vec![]
+                } else {
+                    vec![len]
+                }
+            }
+            Kind::Ip => {
+                let len = run(|c| c.is_ascii_hexdigit() || c == '.' || c == ':');
+                if input[..len].parse::<std::net::IpAddr>().is_ok() {
+                    vec![len]
+                } else {
+                    vec![]
+                }
+            }
+            Kind::Data => boundaries().collect(),
+            Kind::GreedyData => boundaries().collect::<Vec<_>>().into_iter().rev().collect(),
+        }
+    }
+}
+
+fn non_empty(len: usize) -> Vec<usize> {
+    if len == 0 { vec![] } else { vec![len] }
+}
+
+/// The length of the run of characters matching `f` starting at `start`.
+fn run_from(input: &str, start: usize, f: fn(char) -> bool) -> usize {
+    input[start..]
+        .char_indices()
+        .find(|&(_, c)| !f(c))
+        .map_or(input.len() - start, |(pos, _)| pos)
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Token {
+    Literal(String),
+    Capture(Kind, Option<String>),
+}
+
+/// A compiled pattern.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Pattern {
+    tokens: Vec<Token>,
+}
+
+impl Pattern {
+    pub fn new(pattern: &str) -> Result<Self, PatternError> {
+        let mut tokens = Vec::new();
+        let mut rest = pattern;
+        while let Some(start) = rest.find("%{") {
+            if start > 0 {
+                tokens.push(Token::Literal(rest[..start].to_string()));
+            }
+            let end = rest[start..]
+                .find('}')
+                .ok_or_else(|| PatternError(pattern.to_string()))?;
+            let capture = &rest[start + 2..start + end];
+            let (kind, name) = match capture.split_once(':') {
+                Some((kind, name)) => (kind, Some(name.to_string())),
+                None => (capture, None),
+            };
+            let kind = Kind::parse(kind).ok_or_else(|| PatternError(pattern.to_string()))?;
+            tokens.push(Token::Capture(kind, name));
+            rest = &rest[start + end + 1..];
+        }
+        if !rest.is_empty() {
+            tokens.push(Token::Literal(rest.to_string()));
+        }
+
+        Ok(Pattern { tokens })
+    }
+
+    /// Match the whole of `input`, returning the named captures.
+    pub fn matches<'a>(&self, input: &'a str) -> Option<BTreeMap<&str, &'a str>> {
+        let mut fields = BTreeMap::new();
+        if self.match_tokens(&self.tokens, input, &mut fields) {
+            Some(fields)
+        } else {
+            None
+        }
+    }
+
+    fn match_tokens<'p, 'a>(
+        &self,
+        tokens: &'p [Token],
+        input: &'a str,
+        fields: &mut BTreeMap<&'p str, &'a str>,
+    ) -> bool {
+        let Some((token, rest)) = tokens.split_first() else {
+            return input.is_empty();
+        };
+
+        match token {
+            Token::Literal(literal) => match input.strip_prefix(literal.as_str()) {
+                Some(input) => self.match_tokens(rest, input, fields),
+                None => false,
+            },
+            Token::Capture(kind, name) => {
+                for len in kind.candidates(input) {
+                    if self.match_tokens(rest, &input[len..], fields) {
+                        if let Some(name) = name {
+                            fields.insert(name.as_str(), &input[..len]);
+                        }
+                        return true;
+                    }
+                }
+                false
+            }
+        }
+    }
+}
+
+/// A set of named patterns, each applying either to messages from one appname or
+/// to all messages.
+#[derive(Clone, Debug, Default)]
+pub struct PatternSet {
+    patterns: Vec<(Option<String>, String, Pattern)>,
+}
+
+/// The fields extracted from a message by a [`PatternSet`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Extracted<'p, 'a> {
+    /// The name of the pattern that matched.
+    pub pattern: &'p str,
+    pub fields: BTreeMap<&'p str, &'a str>,
+}
+
+impl PatternSet {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a pattern for messages with the given appname, or for every message if
+    /// `appname` is None. Patterns are tried in the order they were added.
+    pub fn add(
+        &mut self,
+        appname: Option<&str>,
+        name: &str,
+        pattern: &str,
+    ) -> Result<&mut Self, PatternError> {
+        self.patterns.push((
+            appname.map(str::to_string),
+            name.to_string(),
+            Pattern::new(pattern)?,
+        ));
+        Ok(self)
+    }
+
+    /// Match the body of the message against the patterns for its appname,
+    /// returning the fields from the first that matches.
+    pub fn extract<'p, 'a, S: AsRef<str> + Ord + PartialEq + Clone>(
+        &'p self,
+        message: &'a Message<S>,
+    ) -> Option<Extracted<'p, 'a>> {
+        let appname = message.appname.as_ref().map(AsRef::as_ref);
+        self.patterns
+            .iter()
+            .filter(|(pattern_appname, _, _)| {
+                pattern_appname.is_none() || pattern_appname.as_deref() == appname
+            })
+            .find_map(|(_, name, pattern)| {
+                pattern
+                    .matches(message.msg.as_ref())
+                    .map(|fields| Extracted {
+                        pattern: name,
+                        fields,
+                    })
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Variant, parse_message};
+
+    #[test]
+    fn match_pattern() {
+        let pattern = Pattern::new(
+            "Accepted %{WORD:method} for %{NOTSPACE:user} from %{IP:ip} port %{INT:port}%{GREEDYDATA}",
+        )
+        .unwrap();
+        let fields = pattern
+            .matches("Accepted publickey for bob from 10.0.0.1 port 22 ssh2")
+            .unwrap();
+
+        assert_eq!(
+            fields.into_iter().collect::<Vec<_>>(),
+            vec![
+                ("ip", "10.0.0.1"),
+                ("method", "publickey"),
+                ("port", "22"),
+                ("user", "bob")
+            ]
+        );
+        assert_eq!(pattern.matches("Accepted publickey for bob"), None);
+    }
+
+    #[test]
+    fn data_backtracks() {
+        let pattern = Pattern::new("%{DATA:a}: %{GREEDYDATA:b}").unwrap();
+        let fields = pattern.matches("one: two: three").unwrap();
+        assert_eq!(fields["a"], "one");
+        assert_eq!(fields["b"], "two: three");
+
+        let pattern = Pattern::new("took %{NUMBER:took}ms").unwrap();
+        assert_eq!(pattern.matches("took -1.5ms").unwrap()["took"], "-1.5");
+    }
+
+    #[test]
+    fn invalid_pattern() {
+        assert!(Pattern::new("%{NOPE:x}").is_err());
+        assert!(Pattern::new("%{WORD:x").is_err());
+    }
+
+    #[test]
+    fn patterns_by_appname() {
+        let mut patterns = PatternSet::new();
+        patterns
+            .add(
+                Some("sshd"),
+                "ssh_failed",
+                "Failed password for %{NOTSPACE:user} from %{IP:ip}",
+            )
+            .unwrap()
+            .add(None, "any_user", "%{DATA}user=%{WORD:user}%{GREEDYDATA}")
+            .unwrap();
+
+        let message = parse_message(
+            "<38>Oct 11 22:14:15 host sshd[123]: Failed password for root from ::1",
+            Variant::RFC3164,
+        );
+        let extracted = patterns.extract(&message).unwrap();
+        assert_eq!(extracted.pattern, "ssh_failed");
+        assert_eq!(extracted.fields["ip"], "::1");
+
+        let message = parse_message(
+            "<38>Oct 11 22:14:15 host su: login user=bob ok",
+            Variant::RFC3164,
+        );
+        let extracted = patterns.extract(&message).unwrap();
+        assert_eq!(extracted.pattern, "any_user");
+        assert_eq!(extracted.fields["user"], "bob");
+
+        let message = parse_message("<38>Oct 11 22:14:15 host su: nothing", Variant::RFC3164);
+        assert_eq!(patterns.extract(&message), None);
+    }
+}
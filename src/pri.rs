@@ -1,44 +1,46 @@
-use crate::parsers::digits;
-use nom::{
-    IResult, Parser as _,
-    bytes::complete::tag,
-    combinator::{map, opt},
-    sequence::delimited,
+use crate::{
+    error::{FacilityError, SeverityError},
+    parsers::decimal,
 };
+use nom::IResult;
+use std::{convert::TryFrom, fmt, str::FromStr};
 
 // Taken from https://github.com/Roguelazer/rust-syslog-rfc5424/blob/af76363081314f91433e014c76fd834acef756d5/src/facility.rs
 // Many thanks.
 
-#[derive(Copy, Clone, Debug, PartialEq, Eq, Ord, PartialOrd)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Ord, PartialOrd, Hash)]
 #[allow(non_camel_case_types)]
 #[allow(clippy::upper_case_acronyms)]
+#[non_exhaustive]
 /// Syslog facilities. Taken From RFC 5424, but I've heard that some platforms mix these around.
 /// Names are from Linux.
 pub enum SyslogFacility {
-    LOG_KERN = 0,
-    LOG_USER = 1,
-    LOG_MAIL = 2,
-    LOG_DAEMON = 3,
-    LOG_AUTH = 4,
-    LOG_SYSLOG = 5,
-    LOG_LPR = 6,
-    LOG_NEWS = 7,
-    LOG_UUCP = 8,
-    LOG_CRON = 9,
-    LOG_AUTHPRIV = 10,
-    LOG_FTP = 11,
-    LOG_NTP = 12,
-    LOG_AUDIT = 13,
-    LOG_ALERT = 14,
-    LOG_CLOCKD = 15,
-    LOG_LOCAL0 = 16,
-    LOG_LOCAL1 = 17,
-    LOG_LOCAL2 = 18,
-    LOG_LOCAL3 = 19,
-    LOG_LOCAL4 = 20,
-    LOG_LOCAL5 = 21,
-    LOG_LOCAL6 = 22,
-    LOG_LOCAL7 = 23,
+    LOG_KERN,
+    LOG_USER,
+    LOG_MAIL,
+    LOG_DAEMON,
+    LOG_AUTH,
+    LOG_SYSLOG,
+    LOG_LPR,
+    LOG_NEWS,
+    LOG_UUCP,
+    LOG_CRON,
+    LOG_AUTHPRIV,
+    LOG_FTP,
+    LOG_NTP,
+    LOG_AUDIT,
+    LOG_ALERT,
+    LOG_CLOCKD,
+    LOG_LOCAL0,
+    LOG_LOCAL1,
+    LOG_LOCAL2,
+    LOG_LOCAL3,
+    LOG_LOCAL4,
+    LOG_LOCAL5,
+    LOG_LOCAL6,
+    LOG_LOCAL7,
+    /// A code without a name, such as the vendor extensions above 23.
+    Unknown(u8),
 }
 
 impl SyslogFacility {
@@ -100,6 +102,91 @@ impl SyslogFacility {
             SyslogFacility::LOG_LOCAL5 => "local5",
             SyslogFacility::LOG_LOCAL6 => "local6",
             SyslogFacility::LOG_LOCAL7 => "local7",
+            SyslogFacility::Unknown(_) => "unknown",
+        }
+    }
+
+    /// The code of the facility, as used in the wire serialization.
+    pub fn code(self) -> u8 {
+        match self {
+            SyslogFacility::LOG_KERN => 0,
+            SyslogFacility::LOG_USER => 1,
+            SyslogFacility::LOG_MAIL => 2,
+            SyslogFacility::LOG_DAEMON => 3,
+            SyslogFacility::LOG_AUTH => 4,
+            SyslogFacility::LOG_SYSLOG => 5,
+            SyslogFacility::LOG_LPR => 6,
+            SyslogFacility::LOG_NEWS => 7,
+            SyslogFacility::LOG_UUCP => 8,
+            SyslogFacility::LOG_CRON => 9,
+            SyslogFacility::LOG_AUTHPRIV => 10,
+            SyslogFacility::LOG_FTP => 11,
+            SyslogFacility::LOG_NTP => 12,
+            SyslogFacility::LOG_AUDIT => 13,
+            SyslogFacility::LOG_ALERT => 14,
+            SyslogFacility::LOG_CLOCKD => 15,
+            SyslogFacility::LOG_LOCAL0 => 16,
+            SyslogFacility::LOG_LOCAL1 => 17,
+            SyslogFacility::LOG_LOCAL2 => 18,
+            SyslogFacility::LOG_LOCAL3 => 19,
+            SyslogFacility::LOG_LOCAL4 => 20,
+            SyslogFacility::LOG_LOCAL5 => 21,
+            SyslogFacility::LOG_LOCAL6 => 22,
+            SyslogFacility::LOG_LOCAL7 => 23,
+            SyslogFacility::Unknown(code) => code,
+        }
+    }
+}
+
+/// Unknown facilities are shown as their code.
+impl fmt::Display for SyslogFacility {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SyslogFacility::Unknown(code) => write!(f, "{}", code),
+            facility => f.write_str(facility.as_str()),
+        }
+    }
+}
+
+/// Parses the names used by syslog.conf, ignoring case, including `security` for
+/// `auth` and rsyslog's `logaudit` and `logalert`, or the number of the facility as
+/// for [`SyslogFacility::try_from`].
+impl FromStr for SyslogFacility {
+    type Err = FacilityError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        const ALIASES: [(&str, SyslogFacility); 4] = [
+            ("security", SyslogFacility::LOG_AUTH),
+            ("logaudit", SyslogFacility::LOG_AUDIT),
+            ("logalert", SyslogFacility::LOG_ALERT),
+            ("clock", SyslogFacility::LOG_CLOCKD),
+        ];
+
+        (0..24)
+            .filter_map(SyslogFacility::from_int)
+            .map(|facility| (facility.as_str(), facility))
+            .chain(ALIASES.iter().copied())
+            .find(|(name, _)| name.eq_ignore_ascii_case(s))
+            .map(|(_, facility)| facility)
+            .or_else(|| {
+                s.parse::<u8>()
+                    .ok()
+                    .and_then(|code| SyslogFacility::try_from(code).ok())
+            })
+            .ok_or_else(|| FacilityError(s.to_string()))
+    }
+}
+
+/// Codes above 23 that can still be sent in a PRI, up to 31, are
+/// [`SyslogFacility::Unknown`].
+impl TryFrom<u8> for SyslogFacility {
+    type Error = FacilityError;
+
+    fn try_from(facility: u8) -> Result<Self, Self::Error> {
+        match SyslogFacility::from_int(facility.into()) {
+            Some(facility) => Ok(facility),
+            None if facility <= 31 => Ok(SyslogFacility::Unknown(facility)),
+            None => Err(FacilityError(facility.to_string())),
         }
     }
 }
@@ -107,19 +194,24 @@ impl SyslogFacility {
 // Taken from https://github.com/Roguelazer/rust-syslog-rfc5424/blob/af76363081314f91433e014c76fd834acef756d5/src/severity.rs
 // Many thanks!
 
-#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
 #[allow(non_camel_case_types)]
 #[allow(clippy::upper_case_acronyms)]
+#[non_exhaustive]
 /// Syslog Severities from RFC 5424.
+///
+/// Severities are ordered by their number, so the most severe, `SEV_EMERG`, is the
+/// smallest. Use
+/// [`SyslogSeverity::at_least`] to compare by how severe they are.
 pub enum SyslogSeverity {
-    SEV_EMERG = 0,
-    SEV_ALERT = 1,
-    SEV_CRIT = 2,
-    SEV_ERR = 3,
-    SEV_WARNING = 4,
-    SEV_NOTICE = 5,
-    SEV_INFO = 6,
-    SEV_DEBUG = 7,
+    SEV_EMERG,
+    SEV_ALERT,
+    SEV_CRIT,
+    SEV_ERR,
+    SEV_WARNING,
+    SEV_NOTICE,
+    SEV_INFO,
+    SEV_DEBUG,
 }
 
 impl SyslogSeverity {
@@ -141,6 +233,19 @@ impl SyslogSeverity {
         }
     }
 
+    /// Whether this is as severe as `other` or more, to drop anything below a
+    /// threshold.
+    ///
+    /// ```
+    /// use syslog_loose::SyslogSeverity;
+    ///
+    /// assert!(SyslogSeverity::SEV_ERR.at_least(SyslogSeverity::SEV_WARNING));
+    /// assert!(!SyslogSeverity::SEV_INFO.at_least(SyslogSeverity::SEV_WARNING));
+    /// ```
+    pub fn at_least(self, other: SyslogSeverity) -> bool {
+        self <= other
+    }
+
     /// Convert a syslog severity into a unique string representation
     pub fn as_str(self) -> &'static str {
         match self {
@@ -154,6 +259,113 @@ impl SyslogSeverity {
             SyslogSeverity::SEV_DEBUG => "debug",
         }
     }
+
+    /// The code of the severity, as used in the wire serialization.
+    pub fn code(self) -> u8 {
+        match self {
+            SyslogSeverity::SEV_EMERG => 0,
+            SyslogSeverity::SEV_ALERT => 1,
+            SyslogSeverity::SEV_CRIT => 2,
+            SyslogSeverity::SEV_ERR => 3,
+            SyslogSeverity::SEV_WARNING => 4,
+            SyslogSeverity::SEV_NOTICE => 5,
+            SyslogSeverity::SEV_INFO => 6,
+            SyslogSeverity::SEV_DEBUG => 7,
+        }
+    }
+}
+
+impl fmt::Display for SyslogSeverity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Parses the names used by syslog.conf, ignoring case, along with the common
+/// aliases such as `warn`, `error` and `panic`, or the number of the severity.
+impl FromStr for SyslogSeverity {
+    type Err = SeverityError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        const NAMES: [(&str, SyslogSeverity); 13] = [
+            ("emerg", SyslogSeverity::SEV_EMERG),
+            ("emergency", SyslogSeverity::SEV_EMERG),
+            ("panic", SyslogSeverity::SEV_EMERG),
+            ("alert", SyslogSeverity::SEV_ALERT),
+            ("crit", SyslogSeverity::SEV_CRIT),
+            ("critical", SyslogSeverity::SEV_CRIT),
+            ("err", SyslogSeverity::SEV_ERR),
+            ("error", SyslogSeverity::SEV_ERR),
+            ("warning", SyslogSeverity::SEV_WARNING),
+            ("warn", SyslogSeverity::SEV_WARNING),
+            ("notice", SyslogSeverity::SEV_NOTICE),
+            ("info", SyslogSeverity::SEV_INFO),
+            ("debug", SyslogSeverity::SEV_DEBUG),
+        ];
+
+        NAMES
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(s))
+            .map(|(_, severity)| *severity)
+            .or_else(|| s.parse().ok().and_then(SyslogSeverity::from_int))
+            .ok_or_else(|| SeverityError(s.to_string()))
+    }
+}
+
+impl TryFrom<u8> for SyslogSeverity {
+    type Error = SeverityError;
+
+    fn try_from(severity: u8) -> Result<Self, Self::Error> {
+        SyslogSeverity::from_int(severity.into()).ok_or_else(|| SeverityError(severity.to_string()))
+    }
+}
+
+/// A facility and severity together, as sent in the PRI field of a message.
+///
+/// ```
+/// use syslog_loose::{Priority, SyslogFacility, SyslogSeverity};
+///
+/// let priority = Priority::from_pri_value(165).unwrap();
+/// assert_eq!(priority.facility(), SyslogFacility::LOG_LOCAL4);
+/// assert_eq!(priority.severity(), SyslogSeverity::SEV_NOTICE);
+/// assert_eq!(priority.value(), Some(165));
+/// ```
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Priority {
+    facility: SyslogFacility,
+    severity: SyslogSeverity,
+}
+
+impl Priority {
+    pub fn new(facility: SyslogFacility, severity: SyslogSeverity) -> Self {
+        Priority { facility, severity }
+    }
+
+    /// The priority sent as `value`, or None if it is more than 191, as a facility
+    /// above 23 isn't valid. See
+    /// [`ParseOptions::vendor_facilities`](crate::ParseOptions::vendor_facilities)
+    /// to keep them.
+    pub fn from_pri_value(value: u8) -> Option<Self> {
+        match decompose_pri(value) {
+            (Some(facility), Some(severity)) => Some(Priority { facility, severity }),
+            _ => None,
+        }
+    }
+
+    /// The number sent in the PRI field, or None if the facility is an
+    /// [`SyslogFacility::Unknown`] code above 31, which doesn't fit in one.
+    pub fn value(self) -> Option<u8> {
+        let facility = self.facility.code().checked_mul(8)?;
+        Some(facility | self.severity.code())
+    }
+
+    pub fn facility(self) -> SyslogFacility {
+        self.facility
+    }
+
+    pub fn severity(self) -> SyslogSeverity {
+        self.severity
+    }
 }
 
 /// The pri field is composed of both the facility and severity values.
@@ -168,19 +380,42 @@ pub fn decompose_pri(pri: u8) -> (Option<SyslogFacility>, Option<SyslogSeverity>
     )
 }
 
+/// The priority at the start of `input` if it has a facility above 23, which
+/// some devices use as vendor extensions.
+pub(crate) fn vendor_priority(input: &str) -> Option<Priority> {
+    let (pri, _) = input
+        .trim_start()
+        .strip_prefix('<')
+        .and_then(|rest| decimal(rest, u8::MAX.into()))
+        .filter(|(_, rest)| rest.starts_with('>'))?;
+    let (facility, severity) = (pri as u8 >> 3, pri as u8 & 0x7);
+    match SyslogFacility::try_from(facility) {
+        Ok(facility @ SyslogFacility::Unknown(_)) => Some(Priority::new(
+            facility,
+            SyslogSeverity::from_int(severity.into())?,
+        )),
+        _ => None,
+    }
+}
+
 /// Compose the facility and severity as a single integer.
 pub(crate) fn compose_pri(facility: SyslogFacility, severity: SyslogSeverity) -> i32 {
-    ((facility as i32) << 3) + (severity as i32)
+    (i32::from(facility.code()) << 3) + i32::from(severity.code() & 0x7)
 }
 
 // The message priority. An integer surrounded by <>
 // This number contains both the facility and the severity.
-pub(crate) fn pri(input: &str) -> IResult<&str, (Option<SyslogFacility>, Option<SyslogSeverity>)> {
-    map(
-        opt(delimited(tag("<"), map(digits, decompose_pri), tag(">"))),
-        |pri| pri.unwrap_or((None, None)),
-    )
-    .parse(input)
+pub(crate) fn pri(input: &str) -> IResult<&str, Option<Priority>> {
+    let pri = input
+        .strip_prefix('<')
+        .and_then(|rest| decimal(rest, u8::MAX.into()))
+        .and_then(|(pri, rest)| Some((pri, rest.strip_prefix('>')?)));
+
+    // A missing or invalid PRI is skipped over, leaving the input as it is.
+    Ok(match pri {
+        Some((pri, rest)) => (rest, Priority::from_pri_value(pri as u8)),
+        None => (input, None),
+    })
 }
 
 #[test]
@@ -220,16 +455,114 @@ mod tests {
             pri("<34>").unwrap(),
             (
                 "",
-                (
-                    Some(SyslogFacility::LOG_AUTH),
-                    Some(SyslogSeverity::SEV_CRIT)
-                )
+                Some(Priority::new(
+                    SyslogFacility::LOG_AUTH,
+                    SyslogSeverity::SEV_CRIT
+                ))
             )
         );
     }
 
+    #[test]
+    fn priority_values() {
+        for value in 0..192 {
+            assert_eq!(
+                Priority::from_pri_value(value).unwrap().value(),
+                Some(value)
+            );
+        }
+        assert_eq!(Priority::from_pri_value(192), None);
+        let vendor = Priority::new(SyslogFacility::Unknown(31), SyslogSeverity::SEV_DEBUG);
+        assert_eq!(vendor.value(), Some(255));
+        let too_big = Priority::new(SyslogFacility::Unknown(32), SyslogSeverity::SEV_EMERG);
+        assert_eq!(too_big.value(), None);
+
+        let mut message =
+            crate::parse_message("<34>Oct 11 22:14:15 host su: x", crate::Variant::RFC3164);
+        let priority = Priority::new(SyslogFacility::LOG_AUTH, SyslogSeverity::SEV_CRIT);
+        assert_eq!(message.priority(), Some(priority));
+        message.set_priority(None);
+        assert_eq!((message.facility, message.severity), (None, None));
+        assert_eq!(message.priority(), None);
+    }
+
+    #[test]
+    fn facility_conversions() {
+        assert_eq!("daemon".parse(), Ok(SyslogFacility::LOG_DAEMON));
+        assert_eq!("Local7".parse(), Ok(SyslogFacility::LOG_LOCAL7));
+        assert_eq!("security".parse(), Ok(SyslogFacility::LOG_AUTH));
+        assert_eq!("16".parse(), Ok(SyslogFacility::LOG_LOCAL0));
+        assert_eq!(
+            "local8".parse::<SyslogFacility>(),
+            Err(FacilityError("local8".to_string()))
+        );
+
+        assert_eq!(SyslogFacility::try_from(4), Ok(SyslogFacility::LOG_AUTH));
+        assert_eq!(
+            SyslogFacility::try_from(24),
+            Ok(SyslogFacility::Unknown(24))
+        );
+        assert_eq!(
+            SyslogFacility::try_from(32),
+            Err(FacilityError("32".to_string()))
+        );
+        for code in 0..32 {
+            let facility = SyslogFacility::try_from(code).unwrap();
+            assert_eq!(facility.code(), code);
+            assert_eq!(facility.to_string().parse(), Ok(facility));
+        }
+    }
+
+    #[test]
+    fn severity_conversions() {
+        assert_eq!("warning".parse(), Ok(SyslogSeverity::SEV_WARNING));
+        assert_eq!("WARN".parse(), Ok(SyslogSeverity::SEV_WARNING));
+        assert_eq!("panic".parse(), Ok(SyslogSeverity::SEV_EMERG));
+        assert_eq!("3".parse(), Ok(SyslogSeverity::SEV_ERR));
+        assert_eq!(
+            "8".parse::<SyslogSeverity>(),
+            Err(SeverityError("8".to_string()))
+        );
+        assert_eq!(
+            "warnings".parse::<SyslogSeverity>(),
+            Err(SeverityError("warnings".to_string()))
+        );
+
+        assert_eq!(SyslogSeverity::try_from(7), Ok(SyslogSeverity::SEV_DEBUG));
+        assert_eq!(
+            SyslogSeverity::try_from(8),
+            Err(SeverityError("8".to_string()))
+        );
+        assert!(SyslogSeverity::SEV_EMERG < SyslogSeverity::SEV_DEBUG);
+        assert!(SyslogSeverity::SEV_WARNING.at_least(SyslogSeverity::SEV_WARNING));
+        assert!(!SyslogSeverity::SEV_DEBUG.at_least(SyslogSeverity::SEV_INFO));
+        for code in 0..8 {
+            let severity = SyslogSeverity::try_from(code).unwrap();
+            assert_eq!(severity.code(), code);
+            assert_eq!(severity.to_string().parse(), Ok(severity));
+        }
+    }
+
+    #[test]
+    fn vendor_facilities() {
+        assert_eq!(decompose_pri(200), (None, Some(SyslogSeverity::SEV_EMERG)));
+        assert_eq!(Priority::from_pri_value(200), None);
+        assert_eq!(
+            vendor_priority(" <200>1 xxx"),
+            Some(Priority::new(
+                SyslogFacility::Unknown(25),
+                SyslogSeverity::SEV_EMERG
+            ))
+        );
+        assert_eq!(vendor_priority("<34>1 xxx"), None);
+        assert_eq!(vendor_priority("<256>1 xxx"), None);
+    }
+
     #[test]
     fn parse_missing_pri() {
-        assert_eq!(pri("1 xxx").unwrap(), ("1 xxx", (None, None)));
+        assert_eq!(pri("1 xxx").unwrap(), ("1 xxx", None));
+        assert_eq!(pri("<256>1 xxx").unwrap(), ("<256>1 xxx", None));
+        assert_eq!(pri("<>1 xxx").unwrap(), ("<>1 xxx", None));
+        assert_eq!(pri("<34 xxx").unwrap(), ("<34 xxx", None));
     }
 }
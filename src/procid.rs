@@ -1,4 +1,8 @@
-use std::fmt;
+use std::{
+    convert::TryFrom,
+    fmt,
+    hash::{Hash, Hasher},
+};
 
 #[derive(Clone, Debug, PartialEq, Eq)]
 #[allow(clippy::upper_case_acronyms)]
@@ -16,15 +20,54 @@ impl<S: AsRef<str> + Ord + PartialEq + Clone> fmt::Display for ProcId<S> {
     }
 }
 
-impl From<ProcId<&str>> for ProcId<String> {
-    fn from(procid: ProcId<&str>) -> Self {
-        match procid {
+impl<S: AsRef<str> + Ord + PartialEq + Clone> Hash for ProcId<S> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        match self {
+            ProcId::PID(pid) => pid.hash(state),
+            ProcId::Name(name) => name.as_ref().hash(state),
+        }
+    }
+}
+
+impl<S: AsRef<str> + Ord + PartialEq + Clone> ProcId<S> {
+    /// The process id, also parsing a name that is a number, such as one too large
+    /// to have been parsed as a pid. Negative pids aren't process ids.
+    pub fn as_pid(&self) -> Option<u32> {
+        match self {
+            ProcId::PID(pid) => u32::try_from(*pid).ok(),
+            ProcId::Name(name) => name.as_ref().parse().ok(),
+        }
+    }
+
+    /// The name, if the procid isn't a number.
+    pub fn as_name(&self) -> Option<&str> {
+        match self {
+            ProcId::PID(_) => None,
+            ProcId::Name(name) => Some(name.as_ref()),
+        }
+    }
+}
+
+impl<'a> ProcId<&'a str> {
+    /// Convert the name with `f`.
+    pub(crate) fn map<T, F>(self, f: F) -> ProcId<T>
+    where
+        T: AsRef<str> + Ord + PartialEq + Clone,
+        F: FnOnce(&'a str) -> T,
+    {
+        match self {
             ProcId::PID(pid) => ProcId::PID(pid),
-            ProcId::Name(name) => ProcId::Name(name.to_string()),
+            ProcId::Name(name) => ProcId::Name(f(name)),
         }
     }
 }
 
+impl From<ProcId<&str>> for ProcId<String> {
+    fn from(procid: ProcId<&str>) -> Self {
+        procid.map(str::to_string)
+    }
+}
+
 impl<'a> From<&'a str> for ProcId<&'a str> {
     fn from(s: &str) -> ProcId<&str> {
         match s.parse() {
@@ -33,3 +76,18 @@ impl<'a> From<&'a str> for ProcId<&'a str> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accessors() {
+        assert_eq!(ProcId::<&str>::PID(1234).as_pid(), Some(1234));
+        assert_eq!(ProcId::<&str>::PID(-1).as_pid(), None);
+        assert_eq!(ProcId::Name("3000000000").as_pid(), Some(3_000_000_000));
+        assert_eq!(ProcId::Name("worker").as_pid(), None);
+        assert_eq!(ProcId::Name("worker").as_name(), Some("worker"));
+        assert_eq!(ProcId::<&str>::PID(1234).as_name(), None);
+    }
+}
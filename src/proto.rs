@@ -0,0 +1,284 @@
+//! Protocol buffer encoding of messages, using the schema in `proto/syslog.proto`.
+//!
+//! The prost types are written out by hand to match the schema, so building the
+//! crate doesn't need `protoc`.
+use crate::{
+    error::ProtoError,
+    message::{Message, Protocol},
+    pri::{SyslogFacility, SyslogSeverity},
+    procid::ProcId,
+    structured_data::StructuredElement,
+};
+use chrono::{DateTime, FixedOffset};
+use std::convert::TryFrom;
+
+/// A `SyslogParam` in `proto/syslog.proto`.
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct SyslogParam {
+    #[prost(string, tag = "1")]
+    pub name: String,
+    #[prost(string, tag = "2")]
+    pub value: String,
+}
+
+/// A `SyslogElement` in `proto/syslog.proto`.
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct SyslogElement {
+    #[prost(string, tag = "1")]
+    pub id: String,
+    #[prost(message, repeated, tag = "2")]
+    pub params: Vec<SyslogParam>,
+}
+
+/// The `procid` oneof of a `SyslogMessage`.
+#[derive(Clone, PartialEq, prost::Oneof)]
+pub enum SyslogProcId {
+    #[prost(int32, tag = "8")]
+    Pid(i32),
+    #[prost(string, tag = "9")]
+    ProcName(String),
+}
+
+/// A `SyslogMessage` in `proto/syslog.proto`, to encode or decode with
+/// [`prost::Message`]. Convert it from a [`Message`], and back with `TryFrom`,
+/// which fails if a code or the timestamp is out of range.
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct SyslogMessage {
+    #[prost(uint32, optional, tag = "1")]
+    pub rfc5424_version: Option<u32>,
+    #[prost(int32, optional, tag = "2")]
+    pub facility: Option<i32>,
+    #[prost(int32, optional, tag = "3")]
+    pub severity: Option<i32>,
+    #[prost(int64, optional, tag = "4")]
+    pub timestamp_micros: Option<i64>,
+    #[prost(int32, optional, tag = "5")]
+    pub utc_offset_seconds: Option<i32>,
+    #[prost(string, optional, tag = "6")]
+    pub hostname: Option<String>,
+    #[prost(string, optional, tag = "7")]
+    pub appname: Option<String>,
+    #[prost(oneof = "SyslogProcId", tags = "8, 9")]
+    pub procid: Option<SyslogProcId>,
+    #[prost(string, optional, tag = "10")]
+    pub msgid: Option<String>,
+    #[prost(message, repeated, tag = "11")]
+    pub structured_data: Vec<SyslogElement>,
+    #[prost(string, tag = "12")]
+    pub msg: String,
+}
+
+/// Escapes in the structured data param values are removed.
+impl<S: AsRef<str> + Ord + PartialEq + Clone> From<&Message<S>> for SyslogMessage {
+    fn from(message: &Message<S>) -> Self {
+        let string = |s: &Option<S>| s.as_ref().map(|s| s.as_ref().to_string());
+
+        SyslogMessage {
+            rfc5424_version: message.protocol.version(),
+            facility: message.facility.map(|facility| facility.code().into()),
+            severity: message.severity.map(|severity| severity.code().into()),
+            timestamp_micros: message
+                .timestamp
+                .map(|timestamp| timestamp.timestamp_micros()),
+            utc_offset_seconds: message
+                .timestamp
+                .map(|timestamp| timestamp.offset().local_minus_utc()),
+            hostname: string(&message.hostname),
+            appname: string(&message.appname),
+            procid: message.procid.as_ref().map(|procid| match procid {
+                ProcId::PID(pid) => SyslogProcId::Pid(*pid),
+                ProcId::Name(name) => SyslogProcId::ProcName(name.as_ref().to_string()),
+            }),
+            msgid: string(&message.msgid),
+            structured_data: message
+                .structured_data
+                .iter()
+                .map(|element| SyslogElement {
+                    id: element.id.as_ref().to_string(),
+                    params: element
+                        .params()
+                        .map(|(name, value)| SyslogParam {
+                            name: name.as_ref().to_string(),
+                            value,
+                        })
+                        .collect(),
+                })
+                .collect(),
+            msg: message.msg.as_ref().to_string(),
+        }
+    }
+}
+
+/// A facility or severity code, which has to fit in a byte.
+fn code(code: i32) -> Result<u8, ProtoError> {
+    u8::try_from(code).map_err(|_| ProtoError)
+}
+
+impl TryFrom<SyslogMessage> for Message<String> {
+    type Error = ProtoError;
+
+    fn try_from(message: SyslogMessage) -> Result<Self, Self::Error> {
+        let timestamp = match message.timestamp_micros {
+            Some(micros) => {
+                let offset = FixedOffset::east_opt(message.utc_offset_seconds.unwrap_or(0))
+                    .ok_or(ProtoError)?;
+                Some(
+                    DateTime::from_timestamp_micros(micros)
+                        .ok_or(ProtoError)?
+                        .with_timezone(&offset),
+                )
+            }
+            None => None,
+        };
+
+        Ok(Message {
+            protocol: message
+                .rfc5424_version
+                .map_or(Protocol::RFC3164, Protocol::rfc5424),
+            facility: message
+                .facility
+                .map(|facility| SyslogFacility::try_from(code(facility)?).map_err(|_| ProtoError))
+                .transpose()?,
+            severity: message
+                .severity
+                .map(|severity| SyslogSeverity::try_from(code(severity)?).map_err(|_| ProtoError))
+                .transpose()?,
+            timestamp,
+            hostname: message.hostname,
+            appname: message.appname,
+            procid: message.procid.map(|procid| match procid {
+                SyslogProcId::Pid(pid) => ProcId::PID(pid),
+                SyslogProcId::ProcName(name) => ProcId::Name(name),
+            }),
+            msgid: message.msgid,
+            structured_data: message
+                .structured_data
+                .into_iter()
+                .map(|element| StructuredElement {
+                    id: element.id,
+                    params: element
+                        .params
+                        .into_iter()
+                        .map(|param| (param.name, param.value))
+                        .collect(),
+                })
+                .collect(),
+            msg: message.msg,
+        })
+    }
+}
+
+impl<S: AsRef<str> + Ord + PartialEq + Clone> Message<S> {
+    /// Encode the message as a `SyslogMessage` protocol buffer.
+    /// Escapes in the structured data param values are removed.
+    pub fn to_protobuf(&self) -> Vec<u8> {
+        prost::Message::encode_to_vec(&SyslogMessage::from(self))
+    }
+}
+
+impl Message<String> {
+    /// Decode a `SyslogMessage` protocol buffer.
+    pub fn from_protobuf(buf: &[u8]) -> Result<Self, ProtoError> {
+        let message: SyslogMessage = prost::Message::decode(buf).map_err(|_| ProtoError)?;
+        Message::try_from(message)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Variant, parse_message};
+
+    #[test]
+    fn round_trip() {
+        for input in [
+            r#"<165>1 2003-10-11T22:14:15.003-07:00 mymachine evntslog 12 ID47 [exampleSDID@32473 iut="3" eventSource="Application"][examplePriority@32473 class="high"] An application event"#,
+            "<34>Oct 11 22:14:15 mymachine su[postfix]: 'su root' failed",
+            "not syslog at all",
+        ] {
+            let message = parse_message(input, Variant::Either);
+            let decoded = Message::from_protobuf(&message.to_protobuf()).unwrap();
+            assert_eq!(decoded, message.into());
+        }
+    }
+
+    #[test]
+    fn known_encoding() {
+        let message = Message {
+            protocol: Protocol::RFC5424(1),
+            facility: Some(SyslogFacility::LOG_AUTH),
+            severity: Some(SyslogSeverity::SEV_CRIT),
+            timestamp: None,
+            hostname: None,
+            appname: None,
+            procid: None,
+            msgid: None,
+            structured_data: vec![],
+            msg: "hi",
+        };
+        assert_eq!(
+            message.to_protobuf(),
+            vec![0x08, 0x01, 0x10, 0x04, 0x18, 0x02, 0x62, 0x02, b'h', b'i']
+        );
+    }
+
+    #[test]
+    fn prost_types() {
+        let message = parse_message(
+            r#"<165>1 2003-10-11T22:14:15.003-07:00 mymachine evntslog 12 ID47 [meta a="x\]y"] hi"#,
+            Variant::RFC5424,
+        );
+        let proto = SyslogMessage::from(&message);
+        assert_eq!(proto.rfc5424_version, Some(1));
+        assert_eq!((proto.facility, proto.severity), (Some(20), Some(5)));
+        assert_eq!(proto.utc_offset_seconds, Some(-7 * 3600));
+        assert_eq!(proto.procid, Some(SyslogProcId::Pid(12)));
+        assert_eq!(
+            proto.structured_data,
+            vec![SyslogElement {
+                id: "meta".to_string(),
+                params: vec![SyslogParam {
+                    name: "a".to_string(),
+                    value: "x]y".to_string(),
+                }],
+            }]
+        );
+
+        let decoded = Message::try_from(proto.clone()).unwrap();
+        assert_eq!(decoded.to_protobuf(), prost::Message::encode_to_vec(&proto));
+        assert_eq!(
+            Message::try_from(SyslogMessage {
+                severity: Some(8),
+                ..proto
+            }),
+            Err(ProtoError)
+        );
+    }
+
+    #[test]
+    fn unknown_codes() {
+        let message = Message {
+            protocol: Protocol::Unknown(2),
+            facility: Some(SyslogFacility::Unknown(25)),
+            severity: Some(SyslogSeverity::SEV_ERR),
+            timestamp: None,
+            hostname: None,
+            appname: None,
+            procid: None,
+            msgid: None,
+            structured_data: vec![],
+            msg: "hi".to_string(),
+        };
+        assert_eq!(Message::from_protobuf(&message.to_protobuf()), Ok(message));
+    }
+
+    #[test]
+    fn invalid_input() {
+        assert_eq!(Message::from_protobuf(&[0x62, 0x05, b'h']), Err(ProtoError));
+        assert_eq!(Message::from_protobuf(&[0x62, 0x01, 0xff]), Err(ProtoError));
+        // Facilities above 31, including ones that only match once truncated to a byte.
+        assert_eq!(Message::from_protobuf(&[0x10, 0x20]), Err(ProtoError));
+        assert_eq!(Message::from_protobuf(&[0x10, 0x84, 0x02]), Err(ProtoError));
+        assert_eq!(Message::from_protobuf(&[0x18, 0x08]), Err(ProtoError));
+    }
+}
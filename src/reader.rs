@@ -0,0 +1,229 @@
+//! Reading messages from anything implementing `BufRead`.
+use crate::{
+    error::Error, message::Message, options::ParseOptions, parser::Parser, stats::DecoderStats,
+};
+use std::io::{self, BufRead};
+
+/// An iterator over the messages read from a `BufRead`, such as a log file.
+///
+/// Frames that are too long, or that fail the UTF-8 policy are returned as errors,
+/// after which reading carries on with the next frame. An error from the underlying
+/// reader ends the iteration.
+///
+/// ```
+/// use syslog_loose::{MessageReader, ParseOptions, Variant};
+///
+/// let log = "<34>Oct 11 22:14:15 mymachine su: 'su root' failed\n<34>Oct 11 22:14:16 mymachine su: ok\n";
+/// let messages = MessageReader::new(log.as_bytes(), ParseOptions::new(Variant::RFC3164))
+///     .collect::<Result<Vec<_>, _>>()
+///     .unwrap();
+///
+/// assert_eq!(messages.len(), 2);
+/// assert_eq!(messages[1].msg, "ok");
+/// ```
+pub struct MessageReader<R> {
+    reader: R,
+    parser: Parser,
+}
+
+impl<R: BufRead> MessageReader<R> {
+    pub fn new(reader: R, options: ParseOptions) -> Self {
+        MessageReader {
+            reader,
+            parser: Parser::new(options),
+        }
+    }
+
+    /// The counts of what has been read so far.
+    pub fn stats(&self) -> DecoderStats {
+        self.parser.stats()
+    }
+
+    /// Returns the underlying reader. Any data that has been read but not yet
+    /// parsed is lost.
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+
+    /// Calls `f` with each message read until the end of the reader. The messages
+    /// borrow from an internal buffer that is reused, so unlike iterating over the
+    /// reader no strings are allocated for each message.
+    ///
+    /// Frames that can't be read are passed to `f` as errors, an error from the
+    /// underlying reader is returned.
+    pub fn for_each_message<F>(&mut self, mut f: F) -> Result<(), Error>
+    where
+        F: FnMut(Result<Message<&str>, Error>),
+    {
+        loop {
+            self.parser.for_each_message(&mut f);
+
+            if self.parser.is_finished() {
+                return Ok(());
+            }
+
+            if let Err(err) = self.fill() {
+                self.parser.finish();
+                self.parser.clear();
+                return Err(err.into());
+            }
+        }
+    }
+
+    /// Pull the next chunk of data from the reader into our buffer.
+    fn fill(&mut self) -> io::Result<()> {
+        let available = loop {
+            match self.reader.fill_buf() {
+                Ok(available) => break available,
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                Err(err) => return Err(err),
+            }
+        };
+
+        if available.is_empty() {
+            self.parser.finish();
+        } else {
+            let len = available.len();
+            self.parser.feed(available);
+            self.reader.consume(len);
+        }
+
+        Ok(())
+    }
+}
+
+impl<R: BufRead> Iterator for MessageReader<R> {
+    type Item = Result<Message<String>, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(message) = self.parser.next_message() {
+                return Some(message);
+            }
+
+            if self.parser.is_finished() {
+                return None;
+            }
+
+            if let Err(err) = self.fill() {
+                // Don't try to read from a broken reader again.
+                self.parser.finish();
+                self.parser.clear();
+                return Some(Err(err.into()));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        Variant,
+        error::FrameError,
+        framing::Framing,
+        options::{ControlChars, Utf8Policy},
+    };
+
+    #[test]
+    fn read_lines() {
+        let input = "<34>1 2003-10-11T22:14:15.003Z mymachine su - ID47 - one\r\n\n<34>1 2003-10-11T22:14:15.003Z mymachine su - ID47 - two";
+        let messages = MessageReader::new(input.as_bytes(), ParseOptions::new(Variant::RFC5424))
+            .map(|message| message.unwrap().msg)
+            .collect::<Vec<_>>();
+
+        assert_eq!(messages, vec!["one".to_string(), "two".to_string()]);
+    }
+
+    #[test]
+    fn visit_lines() {
+        let input: &[u8] = b"<34>1 2003-10-11T22:14:15.003Z mymachine su - ID47 - one\n\xff\n<34>1 2003-10-11T22:14:15.003Z mymachine su - ID47 - two";
+        let options = ParseOptions {
+            utf8: Utf8Policy::Strict,
+            ..ParseOptions::new(Variant::RFC5424)
+        };
+        let mut reader = MessageReader::new(input, options);
+
+        let mut messages = Vec::new();
+        reader
+            .for_each_message(|message| messages.push(message.map(|message| message.msg.len())))
+            .unwrap();
+
+        assert_eq!(messages.len(), 3);
+        assert_eq!(messages[0].as_ref().unwrap(), &3);
+        assert!(messages[1].is_err());
+        assert_eq!(messages[2].as_ref().unwrap(), &3);
+    }
+
+    #[test]
+    fn read_octet_counted() {
+        let input = "10 <34>1 - -\n9 <34>1 - -";
+        let options = ParseOptions {
+            framing: Framing::OctetCounting,
+            ..Default::default()
+        };
+        let messages = MessageReader::new(input.as_bytes(), options)
+            .map(|message| message.unwrap().msg)
+            .collect::<Vec<_>>();
+
+        assert_eq!(messages.len(), 2);
+    }
+
+    #[test]
+    fn read_oversized_and_invalid_utf8() {
+        let input: &[u8] = b"this line is far too long\nok\n\xff\xfe\n";
+        let options = ParseOptions {
+            max_frame_len: 10,
+            utf8: Utf8Policy::Strict,
+            ..Default::default()
+        };
+        let results = MessageReader::new(input, options).collect::<Vec<_>>();
+
+        assert_eq!(results.len(), 3);
+        assert!(matches!(
+            results[0],
+            Err(Error::Frame(FrameError::TooLong { len: 11, max: 10 }))
+        ));
+        assert_eq!(results[1].as_ref().unwrap().msg, "ok");
+        match &results[2] {
+            Err(Error::InvalidUtf8(err)) => {
+                assert_eq!((err.offset, err.bytes.as_slice()), (0, &[0xff][..]));
+                assert_eq!(err.to_string(), "invalid utf-8 sequence [ff] at byte 0");
+            }
+            result => panic!("expected invalid utf-8, got {:?}", result),
+        }
+    }
+
+    #[test]
+    fn read_control_chars() {
+        let input: &[u8] = b"<34>Oct 11 22:14:15 mymachine su: \x1b[31mred\x1b[0m\tok\n";
+        let read = |control_chars| {
+            let options = ParseOptions {
+                control_chars,
+                ..Default::default()
+            };
+            MessageReader::new(input, options)
+                .next()
+                .unwrap()
+                .unwrap()
+                .msg
+        };
+
+        assert_eq!(read(ControlChars::Preserve), "\x1b[31mred\x1b[0m\tok");
+        assert_eq!(read(ControlChars::Strip), "[31mred[0m\tok");
+        assert_eq!(read(ControlChars::Escape), "#033[31mred#033[0m\tok");
+    }
+
+    #[test]
+    fn read_lossy_utf8() {
+        let input: &[u8] = b"bad \xff byte\ncut \xf0\x9f\n";
+        let mut reader = MessageReader::new(input, ParseOptions::default());
+        let messages = reader
+            .by_ref()
+            .map(|message| message.unwrap().msg)
+            .collect::<Vec<_>>();
+
+        assert_eq!(messages, vec!["bad \u{fffd} byte", "cut \u{fffd}"]);
+        assert_eq!(reader.stats().replacements, 2);
+    }
+}
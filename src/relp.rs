@@ -0,0 +1,231 @@
+//! Framing for the [Reliable Event Logging Protocol](https://www.rsyslog.com/doc/relp.html).
+//!
+//! Each frame is `TXNR SP COMMAND SP DATALEN [SP DATA] LF`. The payload of `syslog`
+//! commands is a syslog message which can be handed on to the message parser.
+use crate::{
+    error::{Error, FrameError, InvalidUtf8},
+    message::Message,
+    options::ParseOptions,
+};
+use std::io::{self, Write};
+
+/// The most digits allowed in the transaction number and data length.
+const MAX_NUMBER_DIGITS: usize = 9;
+
+/// The longest command allowed.
+const MAX_COMMAND_LEN: usize = 32;
+
+/// A single RELP command or response.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RelpFrame<'a> {
+    /// The transaction number, used to match responses to commands.
+    pub txnr: u32,
+    /// The command, such as `open`, `syslog`, `rsp` or `close`.
+    pub command: &'a str,
+    /// The data sent with the command, may be empty.
+    pub data: &'a [u8],
+}
+
+/// Parse a number of at most `MAX_NUMBER_DIGITS` followed by a space or, if
+/// `allow_lf` is set, a newline. Returns the number and the position of the byte
+/// following it, or None if more data is needed.
+fn number(buf: &[u8], start: usize, allow_lf: bool) -> Result<Option<(u32, usize)>, FrameError> {
+    let digits = buf[start..]
+        .iter()
+        .take_while(|b| b.is_ascii_digit())
+        .count();
+    if digits > MAX_NUMBER_DIGITS {
+        return Err(FrameError::InvalidRelp);
+    }
+
+    let end = start + digits;
+    match buf.get(end) {
+        None => Ok(None),
+        Some(b' ') if digits > 0 => Ok(Some((parse_digits(&buf[start..end]), end))),
+        Some(b'\n') if digits > 0 && allow_lf => Ok(Some((parse_digits(&buf[start..end]), end))),
+        Some(_) => Err(FrameError::InvalidRelp),
+    }
+}
+
+/// At most nine ascii digits, so this can't overflow.
+fn parse_digits(digits: &[u8]) -> u32 {
+    digits
+        .iter()
+        .fold(0, |n, digit| n * 10 + u32::from(digit - b'0'))
+}
+
+impl<'a> RelpFrame<'a> {
+    pub fn new(txnr: u32, command: &'a str, data: &'a [u8]) -> Self {
+        RelpFrame {
+            txnr,
+            command,
+            data,
+        }
+    }
+
+    /// Parse the frame at the start of `buf`, returning it along with the number of
+    /// bytes it took up, or None if more data is needed. Frames with more than
+    /// `max_frame_len` bytes of data are rejected.
+    pub fn parse(
+        buf: &'a [u8],
+        max_frame_len: usize,
+    ) -> Result<Option<(RelpFrame<'a>, usize)>, FrameError> {
+        let (txnr, pos) = match number(buf, 0, false)? {
+            Some(number) => number,
+            None => return Ok(None),
+        };
+
+        let command_start = pos + 1;
+        let command_len = buf[command_start..]
+            .iter()
+            .take_while(|b| b.is_ascii_alphabetic())
+            .count();
+        if command_len > MAX_COMMAND_LEN {
+            return Err(FrameError::InvalidRelp);
+        }
+        let command_end = command_start + command_len;
+        match buf.get(command_end) {
+            None => return Ok(None),
+            Some(b' ') if command_len > 0 => {}
+            Some(_) => return Err(FrameError::InvalidRelp),
+        }
+
+        let (len, pos) = match number(buf, command_end + 1, true)? {
+            Some(number) => number,
+            None => return Ok(None),
+        };
+        let len = len as usize;
+        if len > max_frame_len {
+            return Err(FrameError::TooLong {
+                len,
+                max: max_frame_len,
+            });
+        }
+
+        // A space only separates the data if there is any.
+        let data_start = match (len, buf[pos]) {
+            (0, b'\n') => pos,
+            (0, _) => return Err(FrameError::InvalidRelp),
+            (_, b' ') => pos + 1,
+            (_, _) => return Err(FrameError::InvalidRelp),
+        };
+        let data_end = data_start + len;
+        match buf.get(data_end) {
+            None => Ok(None),
+            Some(b'\n') => Ok(Some((
+                RelpFrame {
+                    txnr,
+                    // Only ascii letters, so this can't fail.
+                    command: std::str::from_utf8(&buf[command_start..command_end])
+                        .unwrap_or_default(),
+                    data: &buf[data_start..data_end],
+                },
+                data_end + 1,
+            ))),
+            Some(_) => Err(FrameError::InvalidRelp),
+        }
+    }
+
+    /// Parse the data of a `syslog` command as a message. Returns None for any
+    /// other command.
+    pub fn message(&self, options: &ParseOptions) -> Option<Result<Message<&'a str>, Error>> {
+        if self.command != "syslog" {
+            return None;
+        }
+
+        Some(
+            std::str::from_utf8(self.data)
+                .map(|input| options.parse(input))
+                .map_err(|err| InvalidUtf8::new(self.data, err).into()),
+        )
+    }
+
+    /// Write the frame out.
+    pub fn write_to<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        write!(writer, "{} {} {}", self.txnr, self.command, self.data.len())?;
+        if !self.data.is_empty() {
+            writer.write_all(b" ")?;
+            writer.write_all(self.data)?;
+        }
+        writer.write_all(b"\n")
+    }
+
+    /// The frame as bytes.
+    pub fn encode(&self) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(self.data.len() + 24);
+        // Writing to a Vec can't fail.
+        let _ = self.write_to(&mut buf);
+        buf
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{DEFAULT_MAX_FRAME_LEN, Variant};
+
+    #[test]
+    fn parse_frames() {
+        let buf =
+            b"1 open 5 a=b\nc\n2 syslog 37 <34>Oct 11 22:14:15 mymachine su: one\n3 close 0\n4 rsp";
+
+        let (open, consumed) = RelpFrame::parse(buf, DEFAULT_MAX_FRAME_LEN)
+            .unwrap()
+            .unwrap();
+        assert_eq!(open, RelpFrame::new(1, "open", b"a=b\nc"));
+        assert!(open.message(&ParseOptions::default()).is_none());
+
+        let buf = &buf[consumed..];
+        let (syslog, consumed) = RelpFrame::parse(buf, DEFAULT_MAX_FRAME_LEN)
+            .unwrap()
+            .unwrap();
+        assert_eq!(syslog.txnr, 2);
+        assert_eq!(
+            syslog
+                .message(&ParseOptions::new(Variant::RFC3164))
+                .unwrap()
+                .unwrap()
+                .msg,
+            "one"
+        );
+
+        let buf = &buf[consumed..];
+        let (close, consumed) = RelpFrame::parse(buf, DEFAULT_MAX_FRAME_LEN)
+            .unwrap()
+            .unwrap();
+        assert_eq!(close, RelpFrame::new(3, "close", b""));
+
+        assert_eq!(
+            RelpFrame::parse(&buf[consumed..], DEFAULT_MAX_FRAME_LEN),
+            Ok(None)
+        );
+    }
+
+    #[test]
+    fn parse_invalid_frames() {
+        assert_eq!(
+            RelpFrame::parse(b"x open 0\n", DEFAULT_MAX_FRAME_LEN),
+            Err(FrameError::InvalidRelp)
+        );
+        assert_eq!(
+            RelpFrame::parse(b"1 open 3 abcd\n", DEFAULT_MAX_FRAME_LEN),
+            Err(FrameError::InvalidRelp)
+        );
+        assert_eq!(
+            RelpFrame::parse(b"1 syslog 100 ", 10),
+            Err(FrameError::TooLong { len: 100, max: 10 })
+        );
+    }
+
+    #[test]
+    fn encode_round_trip() {
+        let frame = RelpFrame::new(7, "rsp", b"200 OK");
+        let encoded = frame.encode();
+        assert_eq!(encoded, b"7 rsp 6 200 OK\n");
+        assert_eq!(
+            RelpFrame::parse(&encoded, DEFAULT_MAX_FRAME_LEN),
+            Ok(Some((frame, encoded.len())))
+        );
+        assert_eq!(RelpFrame::new(8, "close", b"").encode(), b"8 close 0\n");
+    }
+}
@@ -0,0 +1,82 @@
+//! Recognising the messages syslog daemons send in place of a run of identical
+//! messages.
+use crate::message::Message;
+
+/// Parse `repeated N times` from the start of `text`, returning N and the rest.
+fn repeated(text: &str) -> Option<(u32, &str)> {
+    let rest = text.strip_prefix("repeated ")?;
+    let (count, rest) = rest.split_once(' ')?;
+    let rest = rest
+        .strip_prefix("times")
+        .or_else(|| rest.strip_prefix("time"))?;
+    Some((count.parse().ok()?, rest))
+}
+
+impl<S: AsRef<str> + Ord + PartialEq + Clone> Message<S> {
+    /// If this message says that the previous message was repeated, the number of
+    /// times it was repeated.
+    ///
+    /// Both the BSD syslogd form, `last message repeated N times`, and the rsyslog
+    /// form, `message repeated N times: [ ... ]`, are recognised. RFC3164 parsing can
+    /// take the first words of the BSD form to be the hostname and appname, so
+    /// these are taken into account.
+    pub fn repeated_count(&self) -> Option<u32> {
+        let msg = self.msg.as_ref();
+        let hostname = self.hostname.as_ref().map(AsRef::as_ref);
+        let appname = self.appname.as_ref().map(AsRef::as_ref);
+
+        let rest = match (hostname, appname) {
+            (Some("last"), Some("message")) => msg,
+            (_, Some("last")) => msg.strip_prefix("message ")?,
+            _ => match msg.strip_prefix("last message ") {
+                Some(rest) => rest,
+                None => {
+                    let (count, rest) = repeated(msg.strip_prefix("message ")?)?;
+                    // The rsyslog form includes the repeated message.
+                    return rest.starts_with(": [").then_some(count);
+                }
+            },
+        };
+
+        let (count, rest) = repeated(rest)?;
+        rest.trim().is_empty().then_some(count)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Variant, parse_message};
+
+    #[test]
+    fn bsd_repeated() {
+        for input in [
+            "<13>Oct 11 22:14:15 host last message repeated 3 times",
+            "<13>Oct 11 22:14:15 last message repeated 3 times",
+            "last message repeated 3 times",
+        ] {
+            let message = parse_message(input, Variant::RFC3164);
+            assert_eq!(message.repeated_count(), Some(3), "{}", input);
+        }
+    }
+
+    #[test]
+    fn rsyslog_repeated() {
+        let message = parse_message(
+            "<13>Oct 11 22:14:15 host sshd[12]: message repeated 2 times: [ Failed password for root ]",
+            Variant::RFC3164,
+        );
+        assert_eq!(message.repeated_count(), Some(2));
+    }
+
+    #[test]
+    fn not_repeated() {
+        for input in [
+            "<13>Oct 11 22:14:15 host app: last message repeated often",
+            "<13>Oct 11 22:14:15 host app: message repeated 2 times",
+            "<13>Oct 11 22:14:15 host last message repeated 3 times today",
+        ] {
+            let message = parse_message(input, Variant::RFC3164);
+            assert_eq!(message.repeated_count(), None, "{}", input);
+        }
+    }
+}
@@ -2,15 +2,16 @@
 use crate::{
     message::{Message, Protocol},
     parsers::{hostname, tagname},
-    pri::pri,
+    pri::{Priority, pri},
     structured_data::structured_data_optional,
-    timestamp::{IncompleteDate, timestamp_3164},
+    timestamp::{IncompleteDate, timestamp_3164_with},
 };
 use chrono::prelude::*;
 use nom::{
     IResult, Parser as _,
-    bytes::complete::{is_not, tag, take_while},
-    character::complete::space0,
+    branch::alt,
+    bytes::complete::{is_not, tag, take_while, take_while1},
+    character::complete::{space0, space1},
     combinator::{map, opt, rest},
     sequence::{delimited, preceded},
 };
@@ -60,12 +61,37 @@ fn resolve_host_and_tag<'a>(
     }
 }
 
+/// Parse the `Message forwarded from host:` prefix that AIX syslogd puts in place
+/// of the hostname when relaying a message.
+fn forwarded_from(input: &str) -> IResult<&str, &str> {
+    delimited(
+        (space1, tag("Message forwarded from"), space1),
+        take_while1(|c: char| !c.is_whitespace() && c != ':'),
+        tag(":"),
+    )
+    .parse(input)
+}
+
 /// Parses the message as per RFC3164.
+#[cfg(test)]
 pub fn parse<F, Tz: TimeZone + Copy>(
     input: &str,
     get_year: F,
     tz: Option<Tz>,
 ) -> IResult<&str, Message<&str>>
+where
+    F: FnOnce(IncompleteDate) -> i32 + Copy,
+{
+    parse_with(input, get_year, tz, false)
+}
+
+/// Like [`parse`], also taking a year after the time if `year_after_time` is set.
+pub(crate) fn parse_with<F, Tz: TimeZone + Copy>(
+    input: &str,
+    get_year: F,
+    tz: Option<Tz>,
+    year_after_time: bool,
+) -> IResult<&str, Message<&str>>
 where
     F: FnOnce(IncompleteDate) -> i32 + Copy,
 {
@@ -73,8 +99,9 @@ where
         (
             pri,
             opt(space0),
-            timestamp_3164(get_year, tz),
-            opt(preceded(tag(" "), hostname)),
+            timestamp_3164_with(get_year, tz, year_after_time),
+            // Older UNIX syslogds can pad the timestamp with extra spaces.
+            opt(alt((map(forwarded_from, Some), preceded(space1, hostname)))),
             opt(preceded(tag(" "), tagname)),
             opt(space0),
             opt(tag(":")),
@@ -88,8 +115,8 @@ where
 
             Message {
                 protocol: Protocol::RFC3164,
-                facility: pri.0,
-                severity: pri.1,
+                facility: pri.map(Priority::facility),
+                severity: pri.map(Priority::severity),
                 timestamp: Some(timestamp),
                 hostname: host,
                 appname,
@@ -352,4 +379,49 @@ mod tests {
             )
         );
     }
+
+    #[test]
+    fn parse_3164_forwarded_from() {
+        assert_eq!(
+            parse::<_, FixedOffset>(
+                "<38>Oct 11 22:14:15 Message forwarded from aixhost: sshd[123]: Accepted password",
+                |_| 2019,
+                Some(Utc.fix())
+            )
+            .unwrap(),
+            (
+                "",
+                Message {
+                    protocol: Protocol::RFC3164,
+                    facility: Some(SyslogFacility::LOG_AUTH),
+                    severity: Some(SyslogSeverity::SEV_INFO),
+                    timestamp: Some(
+                        Utc.with_ymd_and_hms(2019, 10, 11, 22, 14, 15)
+                            .unwrap()
+                            .into()
+                    ),
+                    hostname: Some("aixhost"),
+                    appname: Some("sshd"),
+                    procid: Some(ProcId::PID(123)),
+                    msgid: None,
+                    structured_data: vec![],
+                    msg: "Accepted password",
+                }
+            )
+        );
+    }
+
+    #[test]
+    fn parse_3164_padded_timestamp() {
+        let (_, message) = parse::<_, FixedOffset>(
+            "<38>Oct  1 22:14:15   aixhost sshd[123]: Accepted password",
+            |_| 2019,
+            Some(Utc.fix()),
+        )
+        .unwrap();
+
+        assert_eq!(message.hostname, Some("aixhost"));
+        assert_eq!(message.appname, Some("sshd"));
+        assert_eq!(message.msg, "Accepted password");
+    }
 }
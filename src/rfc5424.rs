@@ -1,11 +1,12 @@
 //! Parsers for rfc 5424 specific formats.
 use crate::{
     message::{Message, Protocol},
-    parsers::{appname, digits, hostname, msgid, procid},
-    pri::pri,
-    structured_data::structured_data,
+    parsers::{appname, decimal, hostname, msgid, procid},
+    pri::{Priority, pri},
+    structured_data::{StructuredElement, structured_data},
     timestamp::timestamp_3339,
 };
+use chrono::{DateTime, FixedOffset, NaiveDate, TimeZone};
 use nom::{
     IResult, Parser as _,
     character::complete::{space0, space1},
@@ -13,12 +14,154 @@ use nom::{
 };
 
 /// Parse the version number - just a simple integer.
-fn version(input: &str) -> IResult<&str, u32> {
-    digits(input)
+pub(crate) fn version(input: &str) -> IResult<&str, u32> {
+    match decimal(input, u32::MAX) {
+        Some((version, rest)) => Ok((rest, version)),
+        None => Err(nom::Err::Error(nom::error::Error::new(
+            input,
+            nom::error::ErrorKind::Digit,
+        ))),
+    }
+}
+
+/// Split off the text up to the next single space.
+fn next_field(input: &str) -> Option<(&str, &str)> {
+    let (field, rest) = input.split_once(' ')?;
+    Some((field, rest))
+}
+
+/// Parse the number made of all of `digits`.
+fn number<T: std::str::FromStr>(digits: &str) -> Option<T> {
+    if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    digits.parse().ok()
+}
+
+/// Parse a UTC timestamp, `yyyy-mm-ddThh:mm:ss[.fraction]Z`.
+fn canonical_timestamp(timestamp: &str) -> Option<DateTime<FixedOffset>> {
+    let bytes = timestamp.as_bytes();
+    // Slicing below relies on every byte being a whole character.
+    if bytes.len() < 20
+        || !timestamp.is_ascii()
+        || bytes[4] != b'-'
+        || bytes[7] != b'-'
+        || bytes[10] != b'T'
+        || bytes[13] != b':'
+        || bytes[16] != b':'
+        || bytes[bytes.len() - 1] != b'Z'
+    {
+        return None;
+    }
+
+    let nanos = match &timestamp[19..timestamp.len() - 1] {
+        "" => 0,
+        fraction => {
+            let digits = fraction.strip_prefix('.')?;
+            if digits.len() > 9 {
+                return None;
+            }
+            number::<u32>(digits)? * 10_u32.pow(9 - digits.len() as u32)
+        }
+    };
+
+    let second = number(&timestamp[17..19])?;
+    if second > 59 {
+        // Leave leap seconds to chrono.
+        return None;
+    }
+    let datetime = NaiveDate::from_ymd_opt(
+        number(&timestamp[..4])?,
+        number(&timestamp[5..7])?,
+        number(&timestamp[8..10])?,
+    )?
+    .and_hms_nano_opt(
+        number(&timestamp[11..13])?,
+        number(&timestamp[14..16])?,
+        second,
+        nanos,
+    )?;
+
+    Some(FixedOffset::east_opt(0)?.from_utc_datetime(&datetime))
+}
+
+/// A header field made up of printable ascii, `-` meaning it is empty.
+fn canonical_field(field: &str) -> Option<Option<&str>> {
+    if field.is_empty() || field.ends_with(':') || !field.bytes().all(|b| b.is_ascii_graphic()) {
+        None
+    } else if field == "-" {
+        Some(None)
+    } else {
+        Some(Some(field))
+    }
+}
+
+/// Parse the message if it has exactly the canonical layout, single spaces
+/// between the fields, a UTC timestamp and no structured data. Machine generated
+/// messages nearly always look like this, so scanning for the spaces directly
+/// avoids the cost of the general parser. Returns None if the message is laid
+/// out any other way.
+pub(crate) fn parse_canonical(input: &str) -> Option<Message<&str>> {
+    let (pri, rest) = input.strip_prefix('<')?.split_once('>')?;
+    let priority = Priority::from_pri_value(number(pri)?);
+    let (version, rest) = next_field(rest)?;
+    let version = number(version)?;
+    let (timestamp, rest) = next_field(rest)?;
+    let (hostname, rest) = next_field(rest)?;
+    let hostname = canonical_field(hostname)?;
+    let (appname, rest) = next_field(rest)?;
+    let appname = canonical_field(appname)?;
+    let (procid, rest) = next_field(rest)?;
+    let procid = canonical_field(procid)?;
+    let (msgid, rest) = next_field(rest)?;
+    let msgid = canonical_field(msgid)?;
+    let msg = match rest.strip_prefix('-')? {
+        "" => "",
+        msg => msg.strip_prefix(' ')?.trim_start_matches([' ', '\t']),
+    };
+
+    Some(Message {
+        protocol: Protocol::rfc5424(version),
+        facility: priority.map(Priority::facility),
+        severity: priority.map(Priority::severity),
+        timestamp: Some(canonical_timestamp(timestamp)?),
+        hostname,
+        appname,
+        procid: procid.map(Into::into),
+        msgid,
+        structured_data: vec![],
+        msg,
+    })
 }
 
 /// Parse the message as per RFC5424
 pub(crate) fn parse(input: &str) -> IResult<&str, Message<&str>> {
+    match parse_canonical(input) {
+        Some(message) => Ok(("", message)),
+        None => {
+            trace_parse!(trace, "not canonical, taking the general path");
+            parse_general(input)
+        }
+    }
+}
+
+/// Parse the message as per RFC5424, allowing for the variations seen in practice.
+fn parse_general(input: &str) -> IResult<&str, Message<&str>> {
+    let (input, message) = header(input)?;
+    let (input, (structured_data, msg)) = body(input)?;
+    Ok((
+        input,
+        Message {
+            structured_data,
+            msg,
+            ..message
+        },
+    ))
+}
+
+/// Parse the fields before the structured data, leaving the structured data and
+/// the body of the message empty.
+fn header(input: &str) -> IResult<&str, Message<&str>> {
     map(
         (
             pri,
@@ -33,49 +176,52 @@ pub(crate) fn parse(input: &str) -> IResult<&str, Message<&str>> {
             procid,
             space1,
             msgid,
-            space0,
-            structured_data,
-            space0,
-            rest,
         ),
-        |(
-            pri,
-            version,
-            _,
-            timestamp,
-            _,
-            hostname,
-            _,
-            appname,
-            _,
-            procid,
-            _,
-            msgid,
-            _,
-            structured_data,
-            _,
-            msg,
-        )| Message {
-            protocol: Protocol::RFC5424(version),
-            facility: pri.0,
-            severity: pri.1,
+        |(pri, version, _, timestamp, _, hostname, _, appname, _, procid, _, msgid)| Message {
+            protocol: Protocol::rfc5424(version),
+            facility: pri.map(Priority::facility),
+            severity: pri.map(Priority::severity),
             timestamp: Some(timestamp),
             hostname,
             appname,
             procid: procid.map(|p| p.into()),
             msgid,
-            structured_data,
-            msg,
+            structured_data: vec![],
+            msg: "",
         },
     )
     .parse(input)
 }
 
+/// Parse the structured data and the message following the header.
+pub(crate) fn body(input: &str) -> IResult<&str, (Vec<StructuredElement<&str>>, &str)> {
+    map(
+        (space0, structured_data, space0, rest),
+        |(_, structured_data, _, msg)| (structured_data, msg),
+    )
+    .parse(input)
+}
+
+/// Parse just the header, returning it along with the unparsed structured data and
+/// message. Returns None unless [`body`] is certain to succeed on the remainder, so
+/// the result is the same as [`parse`] would give.
+pub(crate) fn parse_header(input: &str) -> Option<(Message<&str>, &str)> {
+    let (remaining, message) = header(input).ok()?;
+    // The structured data can only fail to parse if it isn't `-` or there is no
+    // closing bracket, since anything between brackets is accepted.
+    let sd = remaining.trim_start_matches([' ', '\t']);
+    if sd.starts_with('-') || (sd.starts_with('[') && sd.contains(']')) {
+        Some((message, remaining))
+    } else {
+        None
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::pri::{SyslogFacility, SyslogSeverity};
-    use chrono::{Duration, prelude::*};
+    use chrono::Duration;
 
     #[test]
     fn parse_5424() {
@@ -105,4 +251,46 @@ mod tests {
             )
         )
     }
+
+    #[test]
+    fn parse_unknown_version() {
+        for input in [
+            "<34>2 2003-10-11T22:14:15.003Z host su - ID47 - message",
+            "<34>2 2003-10-11T22:14:15.003Z host su - ID47 [id a=\"b\"] message",
+        ] {
+            let (_, message) = parse(input).unwrap();
+            assert_eq!(message.protocol, Protocol::Unknown(2));
+            assert_eq!(message.msg, "message");
+            assert!(
+                message
+                    .to_string()
+                    .starts_with("<34>2 2003-10-11T22:14:15.003+00:00 host su - ID47 ")
+            );
+        }
+    }
+
+    #[test]
+    fn canonical_matches_general() {
+        for input in [
+            "<34>1 2003-10-11T22:14:15.003Z mymachine.example.com su - ID47 - message",
+            "<165>1 2003-10-11T22:14:15Z host app 1234 - -",
+            "<165>1 2003-10-11T22:14:15.123456789Z host app proc-name ID47 -   spaced",
+            "<0>1 2020-02-29T00:00:00.5Z ::1 - - - - BOM",
+        ] {
+            let canonical = parse_canonical(input).unwrap();
+            let (_, general) = parse_general(input).unwrap();
+            assert_eq!(format!("{:?}", canonical), format!("{:?}", general));
+        }
+
+        for input in [
+            "<34>1 2003-10-11T22:14:15.003+01:00 host su - ID47 - offset",
+            "<34>1 2003-10-11T22:14:15.003Z host su - ID47 [id a=\"b\"] sd",
+            "<34>1  2003-10-11T22:14:15.003Z host su - ID47 - double space",
+            "<34>1 2003-10-11T23:59:60Z host su - ID47 - leap second",
+            "<34>1 2003-10-11T22:14:15.003Z host: su - ID47 - colon",
+            "<34>1 2003-10-11T22:14:1éxZ host su - - - msg",
+        ] {
+            assert_eq!(parse_canonical(input), None);
+        }
+    }
 }
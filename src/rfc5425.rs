@@ -0,0 +1,139 @@
+//! Framing for syslog over TLS as per [RFC5425](https://www.rfc-editor.org/rfc/rfc5425#section-4.3).
+//!
+//! Each frame is `MSG-LEN SP SYSLOG-MSG`. The reader and writer here work over any
+//! `Read` or `Write`, so a TLS receiver just needs to supply the decrypted stream.
+use crate::{
+    error::Error,
+    framing::{FrameBuffer, Framing},
+    message::Message,
+    options::{DEFAULT_MAX_FRAME_LEN, ParseOptions},
+};
+use std::io::{self, Read, Write};
+
+/// Reads RFC5425 frames from a stream.
+pub struct FrameReader<R> {
+    reader: R,
+    buf: FrameBuffer,
+    chunk: Box<[u8]>,
+}
+
+impl<R: Read> FrameReader<R> {
+    pub fn new(reader: R) -> Self {
+        Self::with_max_frame_len(reader, DEFAULT_MAX_FRAME_LEN)
+    }
+
+    /// Frames longer than `max_frame_len` are discarded and returned as an error.
+    pub fn with_max_frame_len(reader: R, max_frame_len: usize) -> Self {
+        FrameReader {
+            reader,
+            buf: FrameBuffer::new(&ParseOptions {
+                framing: Framing::OctetCounting,
+                max_frame_len,
+                ..Default::default()
+            }),
+            chunk: vec![0; 8 * 1024].into_boxed_slice(),
+        }
+    }
+
+    /// Read the next frame, returning just the message. Returns None once the
+    /// stream has ended.
+    pub fn read_frame(&mut self) -> Result<Option<Vec<u8>>, Error> {
+        loop {
+            if let Some(frame) = self.buf.next_frame(<[u8]>::to_vec) {
+                return Ok(Some(frame?));
+            }
+
+            if self.buf.is_eof() {
+                return Ok(None);
+            }
+
+            match self.reader.read(&mut self.chunk) {
+                Ok(0) => self.buf.set_eof(),
+                Ok(read) => self.buf.extend(&self.chunk[..read]),
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+                Err(err) => return Err(err.into()),
+            }
+        }
+    }
+
+    /// Returns the underlying reader.
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+/// Writes RFC5425 frames to a stream.
+pub struct FrameWriter<W> {
+    writer: W,
+}
+
+impl<W: Write> FrameWriter<W> {
+    pub fn new(writer: W) -> Self {
+        FrameWriter { writer }
+    }
+
+    /// Write a single frame containing the given message bytes.
+    pub fn write_frame(&mut self, msg: &[u8]) -> io::Result<()> {
+        write!(self.writer, "{} ", msg.len())?;
+        self.writer.write_all(msg)
+    }
+
+    /// Format the message and write it as a single frame.
+    pub fn write_message<S: AsRef<str> + Ord + PartialEq + Clone>(
+        &mut self,
+        message: &Message<S>,
+    ) -> io::Result<()> {
+        self.write_frame(message.to_string().as_bytes())
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+
+    /// Returns the underlying writer.
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{FrameError, Variant, parse_message};
+
+    #[test]
+    fn frames_round_trip() {
+        let mut writer = FrameWriter::new(Vec::new());
+        writer.write_frame(b"<34>1 - - - - - - one").unwrap();
+        writer
+            .write_message(&parse_message(
+                "<34>1 2003-10-11T22:14:15.003Z mymachine su - ID47 - two",
+                Variant::RFC5424,
+            ))
+            .unwrap();
+        let written = writer.into_inner();
+        assert!(written.starts_with(b"21 <34>1 - - - - - - one"));
+
+        let mut reader = FrameReader::new(&written[..]);
+        assert_eq!(
+            reader.read_frame().unwrap().unwrap(),
+            b"<34>1 - - - - - - one"
+        );
+        let two = reader.read_frame().unwrap().unwrap();
+        assert_eq!(
+            parse_message(std::str::from_utf8(&two).unwrap(), Variant::RFC5424).msg,
+            "two"
+        );
+        assert!(reader.read_frame().unwrap().is_none());
+    }
+
+    #[test]
+    fn frame_too_long() {
+        let mut reader = FrameReader::with_max_frame_len(&b"10 0123456789"[..], 5);
+        assert!(matches!(
+            reader.read_frame(),
+            Err(Error::Frame(FrameError::TooLong { len: 10, max: 5 }))
+        ));
+        assert!(reader.read_frame().unwrap().is_none());
+    }
+}
@@ -0,0 +1,112 @@
+//! Parsing the topics that MikroTik RouterOS puts in place of a tag, as in
+//! `firewall,info input: in:ether1 out:(unknown 0), proto TCP (SYN)`.
+use crate::message::Message;
+
+/// The topics that give the level of a message, from least to most severe.
+const LEVELS: &[&str] = &["debug", "info", "warning", "error", "critical"];
+
+/// A RouterOS message split into its topics and text.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RouterOs<'a> {
+    /// The topics other than the level, such as `firewall` or `dhcp`.
+    pub topics: Vec<&'a str>,
+    /// The most severe of the level topics, one of `debug`, `info`, `warning`,
+    /// `error` and `critical`.
+    pub level: &'a str,
+    /// The text following the topics.
+    pub text: &'a str,
+}
+
+impl<'a> RouterOs<'a> {
+    /// Parse a message starting with the comma separated topics.
+    pub fn parse(input: &'a str) -> Option<Self> {
+        let input = input.trim_start();
+        let (topics, text) = input.split_once(' ').unwrap_or((input, ""));
+        Self::parse_topics(topics, text.trim_start())
+    }
+
+    /// Split the topics. At least one must be a level for this to be taken to be
+    /// a RouterOS message.
+    fn parse_topics(topics: &'a str, text: &'a str) -> Option<Self> {
+        let valid = |topic: &str| {
+            !topic.is_empty()
+                && topic
+                    .bytes()
+                    .all(|b| b.is_ascii_lowercase() || b.is_ascii_digit() || b == b'-')
+        };
+        let topics = topics.split(',').collect::<Vec<_>>();
+        if !topics.iter().all(|topic| valid(topic)) {
+            return None;
+        }
+
+        let level = topics
+            .iter()
+            .filter_map(|topic| LEVELS.iter().position(|level| level == topic))
+            .max()
+            .map(|pos| LEVELS[pos])?;
+
+        Some(RouterOs {
+            topics: topics
+                .into_iter()
+                .filter(|topic| !LEVELS.contains(topic))
+                .collect(),
+            level,
+            text,
+        })
+    }
+}
+
+impl<S: AsRef<str> + Ord + PartialEq + Clone> Message<S> {
+    /// Split out the topics of a RouterOS message.
+    ///
+    /// RFC3164 parsing takes the topics to be the appname. If there is no appname
+    /// the topics are looked for at the start of the body.
+    pub fn routeros(&self) -> Option<RouterOs<'_>> {
+        match &self.appname {
+            Some(appname) => RouterOs::parse_topics(appname.as_ref(), self.msg.as_ref()),
+            None => RouterOs::parse(self.msg.as_ref()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Variant, parse_message};
+
+    #[test]
+    fn parse_topics_from_appname() {
+        let message = parse_message(
+            "<30>Oct 11 22:14:15 MikroTik system,error,critical login failure for user admin from 10.0.0.1 via ssh",
+            Variant::RFC3164,
+        );
+
+        assert_eq!(
+            message.routeros().unwrap(),
+            RouterOs {
+                topics: vec!["system"],
+                level: "critical",
+                text: "login failure for user admin from 10.0.0.1 via ssh",
+            }
+        );
+    }
+
+    #[test]
+    fn parse_topics_from_body() {
+        let event = RouterOs::parse("firewall,info input: in:ether1 out:(unknown 0)").unwrap();
+        assert_eq!(event.topics, vec!["firewall"]);
+        assert_eq!(event.level, "info");
+        assert_eq!(event.text, "input: in:ether1 out:(unknown 0)");
+    }
+
+    #[test]
+    fn not_routeros() {
+        let message = parse_message(
+            "<34>Oct 11 22:14:15 mymachine su: 'su root' failed",
+            Variant::RFC3164,
+        );
+        assert_eq!(message.routeros(), None);
+        assert_eq!(RouterOs::parse("Firewall,Info text"), None);
+        assert_eq!(RouterOs::parse("dhcp,wireless text"), None);
+    }
+}
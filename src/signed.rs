@@ -0,0 +1,730 @@
+//! Signed syslog messages as per [RFC5848](https://www.rfc-editor.org/rfc/rfc5848).
+//!
+//! The `ssign` and `ssign-cert` structured data elements are parsed into typed
+//! blocks. Signatures cover the whole message as it was received, so they are
+//! checked against the raw message, see [`SignatureBlock::verify`]. With the
+//! `crypto` feature the OpenPGP DSA signatures can be checked with a key, and
+//! [`SignedLog`] matches messages to the signature blocks covering them.
+#[cfg(feature = "crypto")]
+use crate::{Variant, parse_message};
+use crate::{message::Message, structured_data::StructuredElement};
+use std::{
+    collections::{BTreeMap, VecDeque},
+    convert::TryFrom,
+};
+
+/// The most signature groups [`CertificateAssembler`] keeps fragments for by default.
+const DEFAULT_GROUPS: usize = 16;
+
+/// The longest payload block that is reassembled, as anything longer is not a key.
+const MAX_PAYLOAD: usize = 64 * 1024;
+
+/// Decode standard base64, ignoring padding.
+fn base64(input: &str) -> Option<Vec<u8>> {
+    let mut out = Vec::with_capacity(input.len() * 3 / 4);
+    let mut acc = 0u32;
+    let mut bits = 0;
+    for c in input.trim_end_matches('=').bytes() {
+        let value = match c {
+            b'A'..=b'Z' => c - b'A',
+            b'a'..=b'z' => c - b'a' + 26,
+            b'0'..=b'9' => c - b'0' + 52,
+            b'+' => 62,
+            b'/' => 63,
+            _ => return None,
+        };
+        acc = (acc << 6) | u32::from(value);
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            out.push((acc >> bits) as u8);
+        }
+    }
+    Some(out)
+}
+
+/// The params of the element, looked up by name.
+fn params<S: AsRef<str> + Ord + Clone>(element: &StructuredElement<S>) -> BTreeMap<&str, &str> {
+    element
+        .params
+        .iter()
+        .map(|(name, value)| (name.as_ref(), value.as_ref()))
+        .collect()
+}
+
+fn number<T: std::str::FromStr>(params: &BTreeMap<&str, &str>, name: &str) -> Option<T> {
+    params.get(name)?.parse().ok()
+}
+
+/// The fields shared by signature and certificate blocks that identify the
+/// signature group.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct SignatureGroup {
+    /// The protocol version, hash algorithm and signature scheme, such as `0111`.
+    pub version: String,
+    /// The reboot session id.
+    pub rsid: u64,
+    /// How the signature group is chosen, 0 to 3.
+    pub sg: u8,
+    /// The signature priority, which with `sg` identifies the group.
+    pub spri: u8,
+}
+
+/// The hash algorithm of a signature group, given by the third digit of `VER`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum HashAlgorithm {
+    Sha1,
+    Sha256,
+}
+
+#[cfg(feature = "crypto")]
+impl HashAlgorithm {
+    /// Hash `data`, such as a message as it was received.
+    pub fn digest(self, data: &[u8]) -> Vec<u8> {
+        use sha2::Digest;
+
+        match self {
+            HashAlgorithm::Sha1 => sha1::Sha1::digest(data).to_vec(),
+            HashAlgorithm::Sha256 => sha2::Sha256::digest(data).to_vec(),
+        }
+    }
+}
+
+impl SignatureGroup {
+    /// The hash algorithm, or None if `VER` gives one RFC5848 doesn't define.
+    pub fn hash_algorithm(&self) -> Option<HashAlgorithm> {
+        match self.version.as_bytes().get(2) {
+            Some(b'1') => Some(HashAlgorithm::Sha1),
+            Some(b'2') => Some(HashAlgorithm::Sha256),
+            _ => None,
+        }
+    }
+
+    /// Whether the signature scheme, the fourth digit of `VER`, is OpenPGP DSA,
+    /// the only one RFC5848 defines.
+    pub fn is_openpgp_dsa(&self) -> bool {
+        self.version.as_bytes().get(3) == Some(&b'1')
+    }
+
+    fn parse(params: &BTreeMap<&str, &str>) -> Option<Self> {
+        Some(SignatureGroup {
+            version: params.get("VER")?.to_string(),
+            rsid: number(params, "RSID")?,
+            sg: number(params, "SG")?,
+            spri: number(params, "SPRI")?,
+        })
+    }
+}
+
+/// An `ssign` signature block, holding the hashes of a run of messages.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SignatureBlock {
+    pub group: SignatureGroup,
+    /// The global block counter.
+    pub gbc: u64,
+    /// The number of the first message whose hash is in the block.
+    pub fmn: u64,
+    /// The hashes of messages `fmn` onwards.
+    pub hashes: Vec<Vec<u8>>,
+    pub signature: Vec<u8>,
+}
+
+/// The message as it was signed, which is `raw` with the value of the `SIGN` param
+/// of its `id` element emptied in place.
+fn signed_data(raw: &str, id: &str) -> Option<String> {
+    const SIGN: &str = " SIGN=\"";
+
+    let start = raw.match_indices('[').map(|(idx, _)| idx).find(|&idx| {
+        raw[idx + 1..]
+            .strip_prefix(id)
+            .is_some_and(|rest| rest.starts_with(' '))
+    })?;
+    let value = start + raw[start..].find(SIGN)? + SIGN.len();
+    let end = value + raw[value..].find('"')?;
+    // The params of these elements never hold a `]`, so finding one means the
+    // `SIGN` found belongs to a later element.
+    if raw[start..value].contains(']') {
+        return None;
+    }
+    Some(format!("{}{}", &raw[..value], &raw[end..]))
+}
+
+/// Decode an OpenPGP multiprecision integer, returning it and the rest of `input`.
+#[cfg(feature = "crypto")]
+fn mpi(input: &[u8]) -> Option<(dsa::BigUint, &[u8])> {
+    let (bits, rest) = input.split_at_checked(2)?;
+    let len = usize::from(u16::from_be_bytes([bits[0], bits[1]])).div_ceil(8);
+    let (value, rest) = rest.split_at_checked(len)?;
+    Some((dsa::BigUint::from_bytes_be(value), rest))
+}
+
+/// Check an OpenPGP DSA signature, which is the integers `r` and `s`, of the data
+/// signed for a block of `group`.
+#[cfg(feature = "crypto")]
+fn verify_dsa(
+    group: &SignatureGroup,
+    key: &dsa::VerifyingKey,
+    signed: &[u8],
+    signature: &[u8],
+) -> bool {
+    use dsa::signature::hazmat::PrehashVerifier;
+
+    let signature = mpi(signature)
+        .and_then(|(r, rest)| Some((r, mpi(rest)?)))
+        .filter(|(_, (_, rest))| rest.is_empty())
+        .and_then(|(r, (s, _))| dsa::Signature::from_components(r, s).ok());
+    match (group.hash_algorithm(), signature) {
+        (Some(hash), Some(signature)) if group.is_openpgp_dsa() => {
+            key.verify_prehash(&hash.digest(signed), &signature).is_ok()
+        }
+        _ => false,
+    }
+}
+
+impl SignatureBlock {
+    /// Parse an `ssign` element.
+    pub fn parse<S: AsRef<str> + Ord + Clone>(element: &StructuredElement<S>) -> Option<Self> {
+        if element.id.as_ref() != "ssign" {
+            return None;
+        }
+
+        let params = params(element);
+        let count: usize = number(&params, "CNT")?;
+        let hashes = params
+            .get("HB")?
+            .split_whitespace()
+            .map(base64)
+            .collect::<Option<Vec<_>>>()?;
+        if hashes.len() != count {
+            return None;
+        }
+
+        Some(SignatureBlock {
+            group: SignatureGroup::parse(&params)?,
+            gbc: number(&params, "GBC")?,
+            fmn: number(&params, "FMN")?,
+            hashes,
+            signature: base64(params.get("SIGN")?)?,
+        })
+    }
+
+    /// The hash of message number `number`, if it is covered by this block.
+    pub fn hash_of(&self, number: u64) -> Option<&[u8]> {
+        let idx = usize::try_from(number.checked_sub(self.fmn)?).ok()?;
+        self.hashes.get(idx).map(Vec::as_slice)
+    }
+
+    /// Does this block hold the given message hash?
+    pub fn contains(&self, hash: &[u8]) -> bool {
+        self.hashes.iter().any(|block_hash| block_hash == hash)
+    }
+
+    /// Check the signature with `verify`, which is given the signed data and the
+    /// signature and should check them against the sender's key. `raw` is the
+    /// message the block was parsed from, exactly as it was received.
+    pub fn verify<F: FnOnce(&[u8], &[u8]) -> bool>(&self, raw: &str, verify: F) -> bool {
+        signed_data(raw, "ssign").is_some_and(|signed| verify(signed.as_bytes(), &self.signature))
+    }
+
+    /// Check the signature against the sender's OpenPGP DSA key. `raw` is the
+    /// message the block was parsed from, exactly as it was received.
+    #[cfg(feature = "crypto")]
+    pub fn verify_key(&self, raw: &str, key: &dsa::VerifyingKey) -> bool {
+        self.verify(raw, |signed, signature| {
+            verify_dsa(&self.group, key, signed, signature)
+        })
+    }
+}
+
+/// An `ssign-cert` certificate block, holding a fragment of the payload block
+/// that carries the sender's key.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CertificateBlock {
+    pub group: SignatureGroup,
+    /// The total length of the payload block.
+    pub tbpl: usize,
+    /// The position of this fragment within the payload block, starting at 1.
+    pub index: usize,
+    pub fragment: String,
+    pub signature: Vec<u8>,
+}
+
+impl CertificateBlock {
+    /// Parse an `ssign-cert` element.
+    pub fn parse<S: AsRef<str> + Ord + Clone>(element: &StructuredElement<S>) -> Option<Self> {
+        if element.id.as_ref() != "ssign-cert" {
+            return None;
+        }
+
+        let params = params(element);
+        let fragment = params.get("FRAG")?.to_string();
+        let index: usize = number(&params, "INDEX")?;
+        if index == 0 || number::<usize>(&params, "FLEN")? != fragment.len() {
+            return None;
+        }
+
+        Some(CertificateBlock {
+            group: SignatureGroup::parse(&params)?,
+            tbpl: number(&params, "TBPL")?,
+            index,
+            fragment,
+            signature: base64(params.get("SIGN")?)?,
+        })
+    }
+
+    /// Check the signature with `verify`, as for [`SignatureBlock::verify`].
+    pub fn verify<F: FnOnce(&[u8], &[u8]) -> bool>(&self, raw: &str, verify: F) -> bool {
+        signed_data(raw, "ssign-cert")
+            .is_some_and(|signed| verify(signed.as_bytes(), &self.signature))
+    }
+
+    /// Check the signature against the sender's OpenPGP DSA key, as for
+    /// [`SignatureBlock::verify_key`].
+    #[cfg(feature = "crypto")]
+    pub fn verify_key(&self, raw: &str, key: &dsa::VerifyingKey) -> bool {
+        self.verify(raw, |signed, signature| {
+            verify_dsa(&self.group, key, signed, signature)
+        })
+    }
+}
+
+/// Reassembles payload blocks from the certificate blocks of each signature group.
+///
+/// Fragments are kept for a bounded number of groups, dropping the group that
+/// has waited longest to make room, and a group is dropped once a block from a
+/// later reboot session of the same signature group arrives.
+#[derive(Clone, Debug)]
+pub struct CertificateAssembler {
+    fragments: BTreeMap<SignatureGroup, BTreeMap<usize, String>>,
+    /// The groups with fragments waiting, oldest first.
+    order: VecDeque<SignatureGroup>,
+    capacity: usize,
+}
+
+impl Default for CertificateAssembler {
+    fn default() -> Self {
+        CertificateAssembler {
+            fragments: BTreeMap::new(),
+            order: VecDeque::new(),
+            capacity: DEFAULT_GROUPS,
+        }
+    }
+}
+
+impl CertificateAssembler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Keep fragments for at most `capacity` groups at a time.
+    pub fn with_capacity(self, capacity: usize) -> Self {
+        CertificateAssembler {
+            capacity: capacity.max(1),
+            ..self
+        }
+    }
+
+    /// The number of groups with fragments waiting for the rest.
+    pub fn pending_groups(&self) -> usize {
+        self.order.len()
+    }
+
+    /// Add a fragment, returning the whole payload block once every fragment of it
+    /// has been seen.
+    pub fn add(&mut self, block: CertificateBlock) -> Option<String> {
+        if block.tbpl > MAX_PAYLOAD || block.index + block.fragment.len() > block.tbpl + 1 {
+            return None;
+        }
+
+        let earlier_session = |group: &SignatureGroup| {
+            group.sg == block.group.sg
+                && group.spri == block.group.spri
+                && group.rsid < block.group.rsid
+        };
+        self.order.retain(|group| !earlier_session(group));
+        self.fragments.retain(|group, _| !earlier_session(group));
+
+        if !self.fragments.contains_key(&block.group) {
+            if self.order.len() >= self.capacity {
+                if let Some(oldest) = self.order.pop_front() {
+                    self.fragments.remove(&oldest);
+                }
+            }
+            self.order.push_back(block.group.clone());
+        }
+
+        let fragments = self.fragments.entry(block.group.clone()).or_default();
+        fragments.insert(block.index, block.fragment);
+
+        // Check the fragments cover the payload block with no gaps.
+        let mut next = 1;
+        for (index, fragment) in fragments.iter() {
+            if *index != next {
+                return None;
+            }
+            next += fragment.len();
+        }
+        if next != block.tbpl + 1 {
+            return None;
+        }
+
+        let complete = &block.group;
+        self.order.retain(|group| group != complete);
+        self.fragments
+            .remove(&block.group)
+            .map(|fragments| fragments.into_values().collect())
+    }
+}
+
+/// Matches a sender's messages to the signature blocks covering them, checking the
+/// signature of each block against the sender's key.
+///
+/// Messages are added as they were received. Each one is hashed with the hash
+/// algorithm of the blocks' signature group, so both waiting messages and the
+/// hashes of messages not seen yet are bounded, dropping the oldest.
+#[cfg(feature = "crypto")]
+#[derive(Clone, Debug)]
+pub struct SignedLog {
+    key: dsa::VerifyingKey,
+    capacity: usize,
+    /// Messages waiting for a signature block to cover them, oldest first.
+    pending: VecDeque<String>,
+    /// Hashes from checked signature blocks whose messages haven't been seen yet.
+    hashes: VecDeque<(HashAlgorithm, Vec<u8>)>,
+}
+
+#[cfg(feature = "crypto")]
+impl SignedLog {
+    /// Check the signature blocks against `key`, the sender's OpenPGP DSA key.
+    pub fn new(key: dsa::VerifyingKey) -> Self {
+        SignedLog {
+            key,
+            capacity: 1024,
+            pending: VecDeque::new(),
+            hashes: VecDeque::new(),
+        }
+    }
+
+    /// Keep at most `capacity` waiting messages, and as many unmatched hashes.
+    pub fn with_capacity(self, capacity: usize) -> Self {
+        SignedLog { capacity, ..self }
+    }
+
+    /// Add a message exactly as it was received, returning the messages now known
+    /// to be signed. These are the waiting messages covered by the message's
+    /// signature block, if it has one with a valid signature, or the message itself
+    /// if an earlier block covered it. Anything else waits for a later block.
+    pub fn add(&mut self, raw: &str) -> Vec<String> {
+        let message = parse_message(raw, Variant::RFC5424);
+        if let Some(block) = message.signature_block() {
+            return match block.group.hash_algorithm() {
+                Some(hash) if block.verify_key(raw, &self.key) => self.covered(hash, block.hashes),
+                _ => vec![],
+            };
+        }
+
+        let signed = self
+            .hashes
+            .iter()
+            .position(|(hash, digest)| hash.digest(raw.as_bytes()) == *digest);
+        if let Some(idx) = signed {
+            self.hashes.remove(idx);
+            return vec![raw.to_string()];
+        }
+
+        self.pending.push_back(raw.to_string());
+        if self.pending.len() > self.capacity {
+            self.pending.pop_front();
+        }
+        vec![]
+    }
+
+    /// Take the waiting messages with one of `hashes`, remembering the rest for
+    /// messages still to come.
+    fn covered(&mut self, hash: HashAlgorithm, mut hashes: Vec<Vec<u8>>) -> Vec<String> {
+        let (covered, waiting) = std::mem::take(&mut self.pending)
+            .into_iter()
+            .partition::<VecDeque<_>, _>(|message| {
+                let digest = hash.digest(message.as_bytes());
+                match hashes.iter().position(|block_hash| *block_hash == digest) {
+                    Some(idx) => {
+                        hashes.swap_remove(idx);
+                        true
+                    }
+                    None => false,
+                }
+            });
+        self.pending = waiting;
+
+        self.hashes
+            .extend(hashes.into_iter().map(|digest| (hash, digest)));
+        while self.hashes.len() > self.capacity {
+            self.hashes.pop_front();
+        }
+        covered.into()
+    }
+
+    /// The messages still waiting for a signature block, oldest first.
+    pub fn pending(&self) -> impl Iterator<Item = &str> {
+        self.pending.iter().map(String::as_str)
+    }
+}
+
+impl<S: AsRef<str> + Ord + PartialEq + Clone> Message<S> {
+    /// The signature block carried by the message, if it is a valid one.
+    pub fn signature_block(&self) -> Option<SignatureBlock> {
+        self.structured_data.iter().find_map(SignatureBlock::parse)
+    }
+
+    /// The certificate block carried by the message, if it is a valid one.
+    pub fn certificate_block(&self) -> Option<CertificateBlock> {
+        self.structured_data
+            .iter()
+            .find_map(CertificateBlock::parse)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Variant, parse_message};
+
+    #[test]
+    fn decode_base64() {
+        assert_eq!(base64("aGVsbG8=").unwrap(), b"hello");
+        assert_eq!(base64("aGVsbG8h").unwrap(), b"hello!");
+        assert_eq!(base64("not base64!"), None);
+    }
+
+    #[test]
+    fn signature_block() {
+        let message = parse_message(
+            r#"<110>1 2009-05-03T14:00:39.529966+02:00 host.example.org syslogd 2138 - [ssign VER="0111" RSID="1" SG="0" SPRI="0" GBC="2" FMN="1" CNT="2" HB="aGVsbG8= d29ybGQ=" SIGN="c2lnbg=="]"#,
+            Variant::RFC5424,
+        );
+        let block = message.signature_block().unwrap();
+
+        assert_eq!(block.group.version, "0111");
+        assert_eq!(block.gbc, 2);
+        assert_eq!(block.hash_of(2), Some(&b"world"[..]));
+        assert_eq!(block.hash_of(3), None);
+        assert!(block.contains(b"hello"));
+    }
+
+    #[test]
+    fn verify_raw_message() {
+        // The signature covers the message as it was sent, spacing included.
+        let raw = r#"<110>1 2009-05-03T14:00:39.529966+02:00 host.example.org syslogd 2138 -  [ssign VER="0111" RSID="1" SG="0" SPRI="0" GBC="2" FMN="1" CNT="1" HB="aGVsbG8=" SIGN="c2lnbg=="]"#;
+        let block = parse_message(raw, Variant::RFC5424)
+            .signature_block()
+            .unwrap();
+        assert!(block.verify(raw, |signed, signature| {
+            signed
+                == &br#"<110>1 2009-05-03T14:00:39.529966+02:00 host.example.org syslogd 2138 -  [ssign VER="0111" RSID="1" SG="0" SPRI="0" GBC="2" FMN="1" CNT="1" HB="aGVsbG8=" SIGN=""]"#[..]
+                && signature == b"sign"
+        }));
+        assert!(!block.verify("<110>1 - - - - - -", |_, _| true));
+
+        assert_eq!(
+            signed_data(r#"[ssign-cert SIGN="a"][ssign SIGN="b"]"#, "ssign").as_deref(),
+            Some(r#"[ssign-cert SIGN="a"][ssign SIGN=""]"#)
+        );
+        assert_eq!(signed_data(r#"[ssign A="1"][x SIGN="b"]"#, "ssign"), None);
+    }
+
+    #[test]
+    fn reassemble_certificate() {
+        let fragment = |index: usize, fragment: &str| {
+            parse_message(
+                &format!(
+                    r#"<110>1 2009-05-03T14:00:39.529966+02:00 host.example.org syslogd 2138 - [ssign-cert VER="0111" RSID="1" SG="0" SPRI="0" TBPL="11" INDEX="{}" FLEN="{}" FRAG="{}" SIGN="c2lnbg=="]"#,
+                    index,
+                    fragment.len(),
+                    fragment
+                ),
+                Variant::RFC5424,
+            )
+            .certificate_block()
+            .unwrap()
+        };
+
+        let mut assembler = CertificateAssembler::new();
+        assert_eq!(assembler.add(fragment(7, "world")), None);
+        assert_eq!(
+            assembler.add(fragment(1, "hello ")).as_deref(),
+            Some("hello world")
+        );
+        assert_eq!(assembler.pending_groups(), 0);
+    }
+
+    #[test]
+    fn bound_certificate_groups() {
+        let fragment = |rsid: u64, spri: u8, index: usize, fragment: &str| CertificateBlock {
+            group: SignatureGroup {
+                version: "0111".to_string(),
+                rsid,
+                sg: 0,
+                spri,
+            },
+            tbpl: 11,
+            index,
+            fragment: fragment.to_string(),
+            signature: vec![],
+        };
+
+        let mut assembler = CertificateAssembler::new().with_capacity(2);
+        assert_eq!(assembler.add(fragment(1, 0, 1, "hello ")), None);
+        assert_eq!(assembler.add(fragment(1, 1, 1, "hello ")), None);
+        assert_eq!(assembler.add(fragment(1, 2, 1, "hello ")), None);
+        assert_eq!(assembler.pending_groups(), 2);
+        // The first group was dropped to make room.
+        assert_eq!(assembler.add(fragment(1, 0, 7, "world")), None);
+        assert_eq!(
+            assembler.add(fragment(1, 2, 7, "world")).as_deref(),
+            Some("hello world")
+        );
+
+        // A reboot drops the fragments from the earlier session.
+        assert_eq!(assembler.add(fragment(2, 0, 7, "world")), None);
+        assert_eq!(assembler.pending_groups(), 1);
+
+        // Fragments that run past the payload block are dropped.
+        assert_eq!(assembler.add(fragment(2, 0, 8, "world")), None);
+        assert_eq!(assembler.pending_groups(), 1);
+    }
+
+    /// The 1024 bit key from RFC6979 A.2.1.
+    #[cfg(feature = "crypto")]
+    fn signing_key() -> dsa::SigningKey {
+        let int = |hex: &str| dsa::BigUint::parse_bytes(hex.as_bytes(), 16).unwrap();
+        let components = dsa::Components::from_components(
+            int(concat!(
+                "86F5CA03DCFEB225063FF830A0C769B9DD9D6153AD91D7CE27F787C43278B447",
+                "E6533B86B18BED6E8A48B784A14C252C5BE0DBF60B86D6385BD2F12FB763ED88",
+                "73ABFD3F5BA2E0A8C0A59082EAC056935E529DAF7C610467899C77ADEDFC846C",
+                "881870B7B19B2B58F9BE0521A17002E3BDD6B86685EE90B3D9A1B02B782B1779",
+            )),
+            int("996F967F6C8E388D9E28D01E205FBA957A5698B1"),
+            int(concat!(
+                "07B0F92546150B62514BB771E2A0C0CE387F03BDA6C56B505209FF25FD3C133D",
+                "89BBCD97E904E09114D9A7DEFDEADFC9078EA544D2E401AEECC40BB9FBBF78FD",
+                "87995A10A1C27CB7789B594BA7EFB5C4326A9FE59A070E136DB77175464ADCA4",
+                "17BE5DCE2F40D10A46A3A3943F26AB7FD9C0398FF8C76EE0A56826A8A88F1DBD",
+            )),
+        )
+        .unwrap();
+        let verifying_key = dsa::VerifyingKey::from_components(
+            components,
+            int(concat!(
+                "5DF5E01DED31D0297E274E1691C192FE5868FEF9E19A84776454B100CF16F653",
+                "92195A38B90523E2542EE61871C0440CB87C322FC4B4D2EC5E1E7EC766E1BE8D",
+                "4CE935437DC11C3C8FD426338933EBFE739CB3465F4D3668C5E473508253B1E6",
+                "82F65CBDC4FAE93C2EA212390E54905A86E2223170B44EAA7DA5DD9FFCFB7F3B",
+            )),
+        )
+        .unwrap();
+        dsa::SigningKey::from_components(
+            verifying_key,
+            int("411602CB19A6CCC34494D79D98EF1E7ED5AF25F7"),
+        )
+        .unwrap()
+    }
+
+    #[cfg(feature = "crypto")]
+    fn encode_base64(input: &[u8]) -> String {
+        const CHARS: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+        let mut out = String::new();
+        for chunk in input.chunks(3) {
+            let bytes = [
+                chunk[0],
+                *chunk.get(1).unwrap_or(&0),
+                *chunk.get(2).unwrap_or(&0),
+            ];
+            let acc = u32::from_be_bytes([0, bytes[0], bytes[1], bytes[2]]);
+            for idx in 0..4 {
+                if idx <= chunk.len() {
+                    out.push(CHARS[(acc >> (18 - idx * 6)) as usize & 0x3f] as char);
+                } else {
+                    out.push('=');
+                }
+            }
+        }
+        out
+    }
+
+    /// A signature block for `hashes`, signed with `key`.
+    #[cfg(feature = "crypto")]
+    fn sign(key: &dsa::SigningKey, hashes: &[Vec<u8>]) -> String {
+        let hashes = hashes
+            .iter()
+            .map(|hash| encode_base64(hash))
+            .collect::<Vec<_>>();
+        let unsigned = format!(
+            r#"<110>1 2009-05-03T14:00:39.529966+02:00 host.example.org syslogd 2138 - [ssign VER="0111" RSID="1" SG="0" SPRI="0" GBC="1" FMN="1" CNT="{}" HB="{}" SIGN=""]"#,
+            hashes.len(),
+            hashes.join(" ")
+        );
+
+        let signature = key
+            .sign_prehashed_rfc6979::<sha1::Sha1>(&HashAlgorithm::Sha1.digest(unsigned.as_bytes()))
+            .unwrap();
+        let mut encoded = vec![];
+        for int in [signature.r(), signature.s()] {
+            encoded.extend_from_slice(&(int.bits() as u16).to_be_bytes());
+            encoded.extend_from_slice(&int.to_bytes_be());
+        }
+        unsigned.replace(
+            r#"SIGN="""#,
+            &format!(r#"SIGN="{}""#, encode_base64(&encoded)),
+        )
+    }
+
+    #[cfg(feature = "crypto")]
+    #[test]
+    fn verify_dsa_signature() {
+        let key = signing_key();
+        let raw = sign(&key, &[HashAlgorithm::Sha1.digest(b"hello")]);
+        let block = parse_message(&raw, Variant::RFC5424)
+            .signature_block()
+            .unwrap();
+        assert!(block.verify_key(&raw, key.verifying_key()));
+
+        let tampered = raw.replace(r#"GBC="1""#, r#"GBC="2""#);
+        assert!(!block.verify_key(&tampered, key.verifying_key()));
+    }
+
+    #[cfg(feature = "crypto")]
+    #[test]
+    fn signed_log() {
+        let key = signing_key();
+        let messages = [
+            "<34>1 2009-05-03T14:00:38Z host su - - - one",
+            "<34>1 2009-05-03T14:00:38Z host su - - - two",
+            "<34>1 2009-05-03T14:00:38Z host su - - - three",
+        ];
+        let hashes = messages
+            .iter()
+            .map(|message| HashAlgorithm::Sha1.digest(message.as_bytes()))
+            .collect::<Vec<_>>();
+
+        let mut log = SignedLog::new(key.verifying_key().clone());
+        assert!(log.add(messages[0]).is_empty());
+        assert!(log.add(messages[1]).is_empty());
+        assert!(
+            log.add("<34>1 2009-05-03T14:00:38Z host su - - - unsigned")
+                .is_empty()
+        );
+
+        // A block that fails to verify covers nothing.
+        let forged = sign(&signing_key(), &hashes).replace(r#"RSID="1""#, r#"RSID="2""#);
+        assert!(log.add(&forged).is_empty());
+
+        assert_eq!(log.add(&sign(&key, &hashes)), &messages[..2]);
+        assert_eq!(log.add(messages[2]), &messages[2..]);
+        assert_eq!(
+            log.pending().collect::<Vec<_>>(),
+            ["<34>1 2009-05-03T14:00:38Z host su - - - unsigned"]
+        );
+    }
+}
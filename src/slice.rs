@@ -0,0 +1,188 @@
+//! Parsing every message held in a single buffer, such as a memory mapped file.
+use crate::{
+    error::{Error, InvalidUtf8},
+    framing::{FrameLocator, Step},
+    message::Message,
+    options::ParseOptions,
+    stats::DecoderStats,
+};
+
+/// An iterator over the messages in a buffer that returns each message borrowed
+/// from the buffer along with the byte offset it starts at. No message is copied,
+/// so frames that are not valid UTF-8 are returned as errors whatever the UTF-8 policy.
+///
+/// ```
+/// use syslog_loose::{ParseOptions, SliceReader, Variant};
+///
+/// let log = b"<34>Oct 11 22:14:15 mymachine su: one\n<34>Oct 11 22:14:16 mymachine su: two\n";
+/// let offsets = SliceReader::new(log, ParseOptions::new(Variant::RFC3164))
+///     .map(|(offset, message)| (offset, message.unwrap().msg))
+///     .collect::<Vec<_>>();
+///
+/// assert_eq!(offsets, vec![(0, "one"), (38, "two")]);
+/// ```
+pub struct SliceReader<'a> {
+    buf: &'a [u8],
+    pos: usize,
+    options: ParseOptions,
+    locator: FrameLocator,
+}
+
+impl<'a> SliceReader<'a> {
+    pub fn new(buf: &'a [u8], options: ParseOptions) -> Self {
+        SliceReader {
+            buf,
+            pos: 0,
+            locator: FrameLocator::new(&options),
+            options,
+        }
+    }
+
+    /// The offset of the data that hasn't been read yet.
+    pub fn position(&self) -> usize {
+        self.pos
+    }
+
+    /// The counts of what has been read so far.
+    pub fn stats(&self) -> DecoderStats {
+        self.locator.stats
+    }
+}
+
+impl<'a> Iterator for SliceReader<'a> {
+    type Item = (usize, Result<Message<&'a str>, Error>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let pos = self.pos;
+            let buf: &'a [u8] = self.buf;
+            match self.locator.locate(&buf[pos..], true) {
+                Step::Frame { range, consumed } | Step::Truncated { range, consumed } => {
+                    self.pos += consumed;
+                    let frame = &buf[pos + range.start..pos + range.end];
+                    let input = match std::str::from_utf8(frame) {
+                        Ok(input) => input,
+                        Err(err) => {
+                            let err = InvalidUtf8::new(frame, err);
+                            return Some((pos + range.start, Err(err.into())));
+                        }
+                    };
+                    match self
+                        .options
+                        .parse_raw(frame, input, &mut self.locator.last_variant)
+                    {
+                        Some((message, fallback)) => {
+                            if fallback {
+                                self.locator.stats.fallbacks += 1;
+                            }
+                            return Some((pos + range.start, Ok(message)));
+                        }
+                        None => self.locator.stats.filtered += 1,
+                    }
+                }
+                Step::Skip { consumed, error } => {
+                    self.pos += consumed;
+                    if let Some(error) = error {
+                        return Some((pos, Err(error.into())));
+                    }
+                }
+                Step::Incomplete => return None,
+            }
+        }
+    }
+}
+
+/// A log file mapped into memory.
+#[cfg(feature = "mmap")]
+pub struct MappedLog {
+    map: memmap2::Mmap,
+}
+
+#[cfg(feature = "mmap")]
+impl MappedLog {
+    /// Map the file at `path` into memory.
+    ///
+    /// # Safety
+    ///
+    /// The file must not be modified or truncated while it is mapped, see
+    /// [`memmap2::Mmap::map`].
+    pub unsafe fn open<P: AsRef<std::path::Path>>(path: P) -> std::io::Result<Self> {
+        let file = std::fs::File::open(path)?;
+        let map = unsafe { memmap2::Mmap::map(&file)? };
+        Ok(MappedLog { map })
+    }
+
+    /// The contents of the file.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.map
+    }
+
+    /// Iterate over the messages in the file.
+    pub fn messages(&self, options: ParseOptions) -> SliceReader<'_> {
+        SliceReader::new(&self.map, options)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Variant, error::FrameError};
+
+    #[test]
+    fn slice_offsets_and_errors() {
+        let log = b"<34>Oct 11 22:14:15 mymachine su: one\r\n\xff\n\ntoo long for this\nlast";
+        let results = SliceReader::new(
+            log,
+            ParseOptions {
+                max_frame_len: 40,
+                ..ParseOptions::new(Variant::RFC3164)
+            },
+        )
+        .collect::<Vec<_>>();
+
+        assert_eq!(results.len(), 4);
+        assert_eq!(results[0].0, 0);
+        assert_eq!(results[0].1.as_ref().unwrap().msg, "one");
+        assert_eq!(results[1].0, 39);
+        assert!(matches!(results[1].1, Err(Error::InvalidUtf8(_))));
+        assert_eq!(results[2].0, 42);
+        assert_eq!(results[2].1.as_ref().unwrap().msg, "too long for this");
+        assert_eq!(results[3].0, 60);
+        assert_eq!(results[3].1.as_ref().unwrap().msg, "last");
+    }
+
+    #[test]
+    fn slice_frame_too_long() {
+        let mut reader = SliceReader::new(
+            b"0123456789\nok",
+            ParseOptions {
+                max_frame_len: 5,
+                ..Default::default()
+            },
+        );
+
+        assert!(matches!(
+            reader.next(),
+            Some((0, Err(Error::Frame(FrameError::TooLong { len: 6, max: 5 }))))
+        ));
+        assert_eq!(reader.next().unwrap().1.unwrap().msg, "ok");
+        assert!(reader.next().is_none());
+    }
+
+    #[cfg(feature = "mmap")]
+    #[test]
+    fn mapped_log() {
+        let path = std::env::temp_dir().join("syslog_loose_mapped_log_test.log");
+        std::fs::write(&path, "<34>Oct 11 22:14:15 mymachine su: one\n").unwrap();
+
+        let log = unsafe { MappedLog::open(&path) }.unwrap();
+        let messages = log
+            .messages(ParseOptions::new(Variant::RFC3164))
+            .map(|(_, message)| message.unwrap().msg.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(messages, vec!["one".to_string()]);
+
+        drop(log);
+        std::fs::remove_file(path).unwrap();
+    }
+}
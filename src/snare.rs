@@ -0,0 +1,141 @@
+//! Parsing the Windows event payloads sent by Snare and NXLog agents in the body
+//! of a syslog message.
+use crate::message::Message;
+
+/// A Windows event in the Snare format,
+/// `MSWinEventLog<TAB>Criticality<TAB>Log<TAB>Counter<TAB>Time<TAB>EventID<TAB>Source<TAB>User<TAB>SIDType<TAB>EventType<TAB>Computer<TAB>Category<TAB>Data<TAB>...`
+///
+/// Agents can be set to use a different delimiter to tab, which is detected.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SnareEvent<'a> {
+    /// 0 to 4.
+    pub criticality: &'a str,
+    /// The event log, such as `Security` or `System`.
+    pub log: &'a str,
+    /// Counts the events sent by the agent.
+    pub counter: &'a str,
+    /// When the event was generated, such as `Tue Jun 14 12:00:00 2011`.
+    pub time: &'a str,
+    pub event_id: &'a str,
+    /// The event source or provider.
+    pub source: &'a str,
+    pub user: &'a str,
+    pub sid_type: &'a str,
+    /// Such as `Success Audit` or `Error`.
+    pub event_type: &'a str,
+    pub computer: &'a str,
+    pub category: &'a str,
+    /// The event's message.
+    pub data: &'a str,
+    /// Any fields following the data, such as an expanded string or checksum.
+    pub extra: Vec<&'a str>,
+}
+
+impl<'a> SnareEvent<'a> {
+    /// Parse a Snare event, `input` must start with `MSWinEventLog`.
+    pub fn parse(input: &'a str) -> Option<Self> {
+        let fields = input.trim_start().strip_prefix("MSWinEventLog")?;
+        let delimiter = fields.chars().next()?;
+        Self::parse_fields(&fields[delimiter.len_utf8()..], delimiter)
+    }
+
+    /// Parse the fields following `MSWinEventLog`, detecting the delimiter from the
+    /// character following the criticality.
+    fn parse_body(body: &'a str) -> Option<Self> {
+        let delimiter = body.chars().find(|c| !c.is_ascii_digit())?;
+        Self::parse_fields(body, delimiter)
+    }
+
+    fn parse_fields(fields: &'a str, delimiter: char) -> Option<Self> {
+        let mut fields = fields.trim_end_matches(['\r', '\n']).split(delimiter);
+        let mut next = || fields.next();
+
+        let event = SnareEvent {
+            criticality: next()?,
+            log: next()?,
+            counter: next()?,
+            time: next()?,
+            event_id: next()?,
+            source: next()?,
+            user: next()?,
+            sid_type: next()?,
+            event_type: next()?,
+            computer: next()?,
+            category: next()?,
+            data: next()?,
+            extra: vec![],
+        };
+        if !event.criticality.chars().all(|c| c.is_ascii_digit())
+            || !event.event_id.chars().all(|c| c.is_ascii_digit())
+        {
+            return None;
+        }
+
+        Some(SnareEvent {
+            extra: fields.filter(|field| !field.is_empty()).collect(),
+            ..event
+        })
+    }
+}
+
+impl<S: AsRef<str> + Ord + PartialEq + Clone> Message<S> {
+    /// Parse the body of the message as a Snare Windows event if it is one.
+    ///
+    /// RFC3164 parsing takes `MSWinEventLog` to be the tag, so the message is also
+    /// checked when that is the appname.
+    pub fn snare(&self) -> Option<SnareEvent<'_>> {
+        let msg = self.msg.as_ref();
+        match &self.appname {
+            Some(appname) if appname.as_ref() == "MSWinEventLog" => {
+                SnareEvent::parse_body(msg.trim_start())
+            }
+            _ => SnareEvent::parse(msg),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Variant, parse_message};
+
+    const EVENT: &str = "1\tSecurity\t123\tTue Jun 14 12:00:00 2011\t4624\tMicrosoft-Windows-Security-Auditing\tbob\tN/A\tSuccess Audit\tHOST\tLogon\tAn account was successfully logged on.\t42";
+
+    #[test]
+    fn parse_event() {
+        let input = format!("MSWinEventLog\t{}\n", EVENT);
+        let event = SnareEvent::parse(&input).unwrap();
+        assert_eq!(
+            event,
+            SnareEvent {
+                criticality: "1",
+                log: "Security",
+                counter: "123",
+                time: "Tue Jun 14 12:00:00 2011",
+                event_id: "4624",
+                source: "Microsoft-Windows-Security-Auditing",
+                user: "bob",
+                sid_type: "N/A",
+                event_type: "Success Audit",
+                computer: "HOST",
+                category: "Logon",
+                data: "An account was successfully logged on.",
+                extra: vec!["42"],
+            }
+        );
+
+        let input = format!("MSWinEventLog|{}", EVENT.replace('\t', "|"));
+        let event = SnareEvent::parse(&input).unwrap();
+        assert_eq!(event.event_id, "4624");
+        assert_eq!(SnareEvent::parse("MSWinEventLog\t1\tSecurity"), None);
+    }
+
+    #[test]
+    fn event_from_message() {
+        let input = format!("<13>Jun 14 12:00:00 host MSWinEventLog\t{}", EVENT);
+        let message = parse_message(&input, Variant::RFC3164);
+        let event = message.snare().unwrap();
+        assert_eq!(event.user, "bob");
+        assert_eq!(event.computer, "HOST");
+    }
+}
@@ -0,0 +1,80 @@
+//! Counters for monitoring the quality of the input being decoded.
+use crate::{
+    Variant,
+    diagnose::{ParseWarning, warnings},
+    message::{Message, Protocol},
+};
+
+/// Running totals kept by a decoder, see for example [`Parser::stats`](crate::Parser::stats).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct DecoderStats {
+    /// Frames returned, including truncated frames.
+    pub frames: u64,
+    /// Frames that could not be parsed exactly, so were returned with the whole
+    /// frame as the message.
+    pub fallbacks: u64,
+    /// Times we lost track of the frames and had to scan for the next one.
+    pub resyncs: u64,
+    /// Frames cut short by [`Oversize::Truncate`](crate::Oversize::Truncate).
+    pub truncations: u64,
+    /// Framing errors reported.
+    pub errors: u64,
+    /// Frames skipped by [`ParseOptions::filter`](crate::ParseOptions::filter), or
+    /// dropped by [`Parser::with_message_filter`](crate::Parser::with_message_filter).
+    pub filtered: u64,
+    /// Frames skipped for being empty or nothing but whitespace, such as keepalives.
+    pub empty: u64,
+    /// Invalid UTF-8 sequences replaced with U+FFFD by
+    /// [`Utf8Policy::Lossy`](crate::Utf8Policy::Lossy).
+    pub replacements: u64,
+}
+
+/// Running totals of the messages parsed and the lenient fallbacks taken, kept by
+/// a [`Parser`](crate::Parser) created [`with_parse_stats`](crate::Parser::with_parse_stats).
+/// See [`ParseWarning`] for what each of the warnings means.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ParseStats {
+    /// Messages parsed.
+    pub messages: u64,
+    /// Messages parsed as RFC3164.
+    pub rfc3164: u64,
+    /// Messages parsed as RFC5424.
+    pub rfc5424: u64,
+    /// Messages that couldn't be parsed, so were taken whole as the body.
+    pub fallbacks: u64,
+    /// [`ParseWarning::MissingPri`]
+    pub missing_pri: u64,
+    /// [`ParseWarning::BadTimestamp`]
+    pub bad_timestamps: u64,
+    /// [`ParseWarning::Unparsed`]
+    pub unparsed: u64,
+    /// [`ParseWarning::InvalidSdElement`], the structured data elements dropped.
+    pub invalid_sd_elements: u64,
+    /// [`ParseWarning::Truncated`]
+    pub truncated: u64,
+    /// [`ParseWarning::ControlCharacter`]
+    pub control_characters: u64,
+}
+
+impl ParseStats {
+    /// Count `message`, parsed from `input`.
+    pub(crate) fn record(&mut self, input: &str, variant: Variant, message: &Message<&str>) {
+        self.messages += 1;
+        warnings(input, variant, message, |warning| match warning {
+            ParseWarning::MissingPri => self.missing_pri += 1,
+            ParseWarning::BadTimestamp { .. } => self.bad_timestamps += 1,
+            ParseWarning::Unparsed { .. } => self.unparsed += 1,
+            ParseWarning::InvalidSdElement { .. } => self.invalid_sd_elements += 1,
+            ParseWarning::Truncated { .. } => self.truncated += 1,
+            ParseWarning::ControlCharacter { .. } => self.control_characters += 1,
+        });
+        if message.timestamp.is_none() {
+            self.fallbacks += 1;
+        } else {
+            match message.protocol {
+                Protocol::RFC3164 => self.rfc3164 += 1,
+                Protocol::RFC5424(_) | Protocol::Unknown(_) => self.rfc5424 += 1,
+            }
+        }
+    }
+}
@@ -0,0 +1,238 @@
+//! Adapting an asynchronous stream of bytes into a stream of messages.
+use crate::{
+    error::Error, message::Message, options::ParseOptions, parser::Parser, stats::DecoderStats,
+};
+use futures_core::Stream;
+use std::{
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+/// A stream of the messages parsed from an underlying stream of byte chunks
+/// (`Bytes`, `Vec<u8>` or anything else that is `AsRef<[u8]>`).
+///
+/// The chunks don't need to line up with the messages, they are reframed according
+/// to the framing set in the options. Frames that are too long or that fail the
+/// UTF-8 policy are returned as errors and the stream carries on.
+pub struct MessageStream<St> {
+    stream: St,
+    parser: Parser,
+}
+
+impl<St> MessageStream<St> {
+    pub fn new(stream: St, options: ParseOptions) -> Self {
+        MessageStream {
+            stream,
+            parser: Parser::new(options),
+        }
+    }
+
+    /// The counts of what has been read so far.
+    pub fn stats(&self) -> DecoderStats {
+        self.parser.stats()
+    }
+
+    /// Returns the underlying stream. Any data that has been received but not yet
+    /// parsed is lost.
+    pub fn into_inner(self) -> St {
+        self.stream
+    }
+}
+
+impl<St, B> Stream for MessageStream<St>
+where
+    St: Stream<Item = B> + Unpin,
+    B: AsRef<[u8]>,
+{
+    type Item = Result<Message<String>, Error>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        loop {
+            if let Some(message) = this.parser.next_message() {
+                return Poll::Ready(Some(message));
+            }
+
+            if this.parser.is_finished() {
+                return Poll::Ready(None);
+            }
+
+            match Pin::new(&mut this.stream).poll_next(cx) {
+                Poll::Ready(Some(chunk)) => this.parser.feed(chunk.as_ref()),
+                Poll::Ready(None) => this.parser.finish(),
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+    }
+}
+
+/// A stream of the messages read from a `futures::io::AsyncRead`, so any runtime
+/// (smol, async-std or tokio through a compat layer) can be used.
+///
+/// As with [`MessageReader`](crate::MessageReader), frames that are too long or that
+/// fail the UTF-8 policy are returned as errors and reading carries on, whereas an
+/// error from the reader ends the stream.
+#[cfg(feature = "futures-io")]
+pub struct AsyncMessageReader<R> {
+    reader: R,
+    parser: Parser,
+    chunk: Box<[u8]>,
+}
+
+#[cfg(feature = "futures-io")]
+impl<R> AsyncMessageReader<R> {
+    pub fn new(reader: R, options: ParseOptions) -> Self {
+        AsyncMessageReader {
+            reader,
+            parser: Parser::new(options),
+            chunk: vec![0; 8 * 1024].into_boxed_slice(),
+        }
+    }
+
+    /// The counts of what has been read so far.
+    pub fn stats(&self) -> DecoderStats {
+        self.parser.stats()
+    }
+
+    /// Returns the underlying reader. Any data that has been read but not yet
+    /// parsed is lost.
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+#[cfg(feature = "futures-io")]
+impl<R: futures_io::AsyncRead + Unpin> Stream for AsyncMessageReader<R> {
+    type Item = Result<Message<String>, Error>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        loop {
+            if let Some(message) = this.parser.next_message() {
+                return Poll::Ready(Some(message));
+            }
+
+            if this.parser.is_finished() {
+                return Poll::Ready(None);
+            }
+
+            match Pin::new(&mut this.reader).poll_read(cx, &mut this.chunk) {
+                Poll::Ready(Ok(0)) => this.parser.finish(),
+                Poll::Ready(Ok(read)) => this.parser.feed(&this.chunk[..read]),
+                Poll::Ready(Err(err)) if err.kind() == std::io::ErrorKind::Interrupted => {}
+                Poll::Ready(Err(err)) => {
+                    // Stop here, the reader can't be relied on to carry on.
+                    this.parser.finish();
+                    this.parser.clear();
+                    return Poll::Ready(Some(Err(err.into())));
+                }
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Variant;
+    use std::{collections::VecDeque, task::Waker};
+
+    /// A stream that returns each chunk in turn, pending once before each one.
+    struct Chunks {
+        chunks: VecDeque<&'static [u8]>,
+        ready: bool,
+    }
+
+    impl Stream for Chunks {
+        type Item = Vec<u8>;
+
+        fn poll_next(mut self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Option<Vec<u8>>> {
+            self.ready = !self.ready;
+            if self.ready {
+                Poll::Ready(self.chunks.pop_front().map(|chunk| chunk.to_vec()))
+            } else {
+                Poll::Pending
+            }
+        }
+    }
+
+    fn collect<St: Stream + Unpin>(mut stream: St) -> Vec<St::Item> {
+        let mut cx = Context::from_waker(Waker::noop());
+        let mut items = Vec::new();
+        loop {
+            match Pin::new(&mut stream).poll_next(&mut cx) {
+                Poll::Ready(Some(item)) => items.push(item),
+                Poll::Ready(None) => return items,
+                Poll::Pending => {}
+            }
+        }
+    }
+
+    #[test]
+    fn stream_reframes_chunks() {
+        let chunks = Chunks {
+            chunks: vec![
+                &b"<34>1 2003-10-11T22:14:15.003Z mymachine su - ID47 - o"[..],
+                &b"ne\n<34>1 2003-10-11T22:14:15.003Z mymachine su - ID47 - two\n<34>"[..],
+                &b"1 2003-10-11T22:14:15.003Z mymachine su - ID47 - three"[..],
+            ]
+            .into(),
+            ready: false,
+        };
+        let messages = collect(MessageStream::new(
+            chunks,
+            ParseOptions::new(Variant::RFC5424),
+        ))
+        .into_iter()
+        .map(|message| message.unwrap().msg)
+        .collect::<Vec<_>>();
+
+        assert_eq!(messages, vec!["one", "two", "three"]);
+    }
+
+    /// Reads the chunks in turn, pending once before each one.
+    #[cfg(feature = "futures-io")]
+    impl futures_io::AsyncRead for Chunks {
+        fn poll_read(
+            mut self: Pin<&mut Self>,
+            _cx: &mut Context<'_>,
+            buf: &mut [u8],
+        ) -> Poll<std::io::Result<usize>> {
+            self.ready = !self.ready;
+            if !self.ready {
+                return Poll::Pending;
+            }
+
+            let chunk = self.chunks.pop_front().unwrap_or_default();
+            let len = chunk.len().min(buf.len());
+            buf[..len].copy_from_slice(&chunk[..len]);
+            if len < chunk.len() {
+                self.chunks.push_front(&chunk[len..]);
+            }
+            Poll::Ready(Ok(len))
+        }
+    }
+
+    #[cfg(feature = "futures-io")]
+    #[test]
+    fn async_reader() {
+        let chunks = Chunks {
+            chunks: vec![
+                &b"<34>Oct 11 22:14:15 mymachine su: o"[..],
+                &b"ne\n<34>Oct 11 22:14:15 mymachine su: two"[..],
+            ]
+            .into(),
+            ready: false,
+        };
+        let messages = collect(AsyncMessageReader::new(
+            chunks,
+            ParseOptions::new(Variant::RFC3164),
+        ))
+        .into_iter()
+        .map(|message| message.unwrap().msg)
+        .collect::<Vec<_>>();
+
+        assert_eq!(messages, vec!["one", "two"]);
+    }
+}
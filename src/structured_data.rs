@@ -1,14 +1,29 @@
+use memchr::memchr2;
 use nom::{
     IResult, Parser,
     branch::alt,
-    bytes::complete::{escaped, tag, take_till1, take_until, take_while1},
-    character::complete::{anychar, space0},
+    bytes::complete::{tag, take_till1, take_until},
+    character::complete::space0,
     combinator::map,
     error,
-    multi::{many1, separated_list0},
-    sequence::{delimited, separated_pair, terminated},
+    multi::fold_many1,
+    sequence::{delimited, preceded, separated_pair, terminated},
 };
-use std::fmt;
+use std::{
+    fmt,
+    hash::{Hash, Hasher},
+};
+
+/// Where the params of an element are gathered while parsing.
+///
+/// With the `smallvec` feature up to 8 params are gathered inline and then moved
+/// into a `Vec` of just the right size, rather than growing the `Vec` as they
+/// are found.
+#[cfg(not(feature = "smallvec"))]
+type ParamsBuf<'a> = Vec<(&'a str, &'a str)>;
+
+#[cfg(feature = "smallvec")]
+type ParamsBuf<'a> = smallvec::SmallVec<[(&'a str, &'a str); 8]>;
 
 #[derive(Clone, Debug, Eq)]
 pub struct StructuredElement<S: AsRef<str> + Ord + Clone> {
@@ -18,7 +33,7 @@ pub struct StructuredElement<S: AsRef<str> + Ord + Clone> {
 
 pub struct ParamsIter<'a, S: AsRef<str>> {
     pos: usize,
-    params: &'a Vec<(S, S)>,
+    params: &'a [(S, S)],
 }
 
 impl<S: AsRef<str> + Ord + Clone> StructuredElement<S> {
@@ -33,6 +48,28 @@ impl<S: AsRef<str> + Ord + Clone> StructuredElement<S> {
             params: &self.params,
         }
     }
+
+    /// Append the value of the first param called `name` to `buf` with the escapes
+    /// stripped, so a loop can reuse one buffer rather than allocating for every
+    /// value. Returns false if there is no such param.
+    pub fn write_param_unescaped(&self, name: &str, buf: &mut String) -> bool {
+        match self.params.iter().find(|(param, _)| param.as_ref() == name) {
+            Some((_, value)) => {
+                unescape_into(value.as_ref(), buf);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Iterate over the params with the escapes stripped into `buf`, which is
+    /// cleared for each value.
+    pub fn params_unescaped<'b>(&self, buf: &'b mut String) -> ParamsUnescaped<'_, 'b, S> {
+        ParamsUnescaped {
+            params: self.params.iter(),
+            buf,
+        }
+    }
 }
 
 impl<S: AsRef<str> + Ord + Clone> fmt::Display for StructuredElement<S> {
@@ -49,7 +86,7 @@ impl<S: AsRef<str> + Ord + Clone> fmt::Display for StructuredElement<S> {
 
 impl<S: AsRef<str> + Ord + Clone> PartialEq for StructuredElement<S> {
     fn eq(&self, other: &Self) -> bool {
-        if self.id.as_ref() != other.id.as_ref() {
+        if self.id.as_ref() != other.id.as_ref() || self.params.len() != other.params.len() {
             return false;
         }
 
@@ -68,19 +105,44 @@ impl<S: AsRef<str> + Ord + Clone> PartialEq for StructuredElement<S> {
     }
 }
 
-impl From<StructuredElement<&str>> for StructuredElement<String> {
-    fn from(element: StructuredElement<&str>) -> Self {
+/// Hashes the params in sorted order, as they are compared regardless of order.
+impl<S: AsRef<str> + Ord + Clone> Hash for StructuredElement<S> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.id.as_ref().hash(state);
+        let mut params = self
+            .params
+            .iter()
+            .map(|(name, value)| (name.as_ref(), value.as_ref()))
+            .collect::<Vec<_>>();
+        params.sort_unstable();
+        params.hash(state);
+    }
+}
+
+impl<'a> StructuredElement<&'a str> {
+    /// Convert the id and each param with `f`.
+    pub(crate) fn map<T, F>(self, mut f: F) -> StructuredElement<T>
+    where
+        T: AsRef<str> + Ord + Clone,
+        F: FnMut(&'a str) -> T,
+    {
         StructuredElement {
-            id: element.id.to_string(),
-            params: element
+            id: f(self.id),
+            params: self
                 .params
-                .iter()
-                .map(|(name, value)| (name.to_string(), value.to_string()))
+                .into_iter()
+                .map(|(name, value)| (f(name), f(value)))
                 .collect(),
         }
     }
 }
 
+impl From<StructuredElement<&str>> for StructuredElement<String> {
+    fn from(element: StructuredElement<&str>) -> Self {
+        element.map(str::to_string)
+    }
+}
+
 impl<'a, S: AsRef<str> + Ord + Clone> Iterator for ParamsIter<'a, S> {
     type Item = (&'a S, String);
 
@@ -91,51 +153,113 @@ impl<'a, S: AsRef<str> + Ord + Clone> Iterator for ParamsIter<'a, S> {
             let (key, value) = &self.params[self.pos];
             self.pos += 1;
             let mut trimmed = String::with_capacity(value.as_ref().len());
-            let mut escaped = false;
-            for c in value.as_ref().chars() {
-                if c == '\\' && !escaped {
-                    escaped = true;
-                } else if c == 'n' && escaped {
-                    escaped = false;
-                    trimmed.push('\n');
-                } else if c != '"' && c != ']' && c != '\\' && escaped {
-                    // If the character following the escape isn't a \, " or ] we treat it like an normal unescaped character.
-                    escaped = false;
-                    trimmed.push('\\');
-                    trimmed.push(c);
-                } else {
-                    escaped = false;
-                    trimmed.push(c);
-                }
-            }
+            unescape_into(value.as_ref(), &mut trimmed);
             Some((key, trimmed))
         }
     }
 }
 
+/// The params of an element with the escapes stripped into a buffer that is reused
+/// for every value. Returned by [`StructuredElement::params_unescaped`].
+pub struct ParamsUnescaped<'a, 'b, S: AsRef<str>> {
+    params: std::slice::Iter<'a, (S, S)>,
+    buf: &'b mut String,
+}
+
+impl<'a, S: AsRef<str>> ParamsUnescaped<'a, '_, S> {
+    /// The next param name and its value, which is only valid until this is called
+    /// again.
+    pub fn next_param(&mut self) -> Option<(&'a S, &str)> {
+        let (key, value) = self.params.next()?;
+        self.buf.clear();
+        unescape_into(value.as_ref(), self.buf);
+        Some((key, self.buf.as_str()))
+    }
+}
+
+/// Append `value` to `out` with the escapes stripped out.
+fn unescape_into(value: &str, out: &mut String) {
+    let mut escaped = false;
+    for c in value.chars() {
+        if c == '\\' && !escaped {
+            escaped = true;
+        } else if c == 'n' && escaped {
+            escaped = false;
+            out.push('\n');
+        } else if c != '"' && c != ']' && c != '\\' && escaped {
+            // If the character following the escape isn't a \, " or ] we treat it like an normal unescaped character.
+            escaped = false;
+            out.push('\\');
+            out.push(c);
+        } else {
+            escaped = false;
+            out.push(c);
+        }
+    }
+}
+
+/// The length of the escaped string at the start of `input`, up to the closing '"'.
+fn escaped_len(input: &str) -> Option<usize> {
+    let bytes = input.as_bytes();
+    let mut pos = 0;
+    loop {
+        pos += memchr2(b'\\', b'"', &bytes[pos..])?;
+        if bytes[pos] == b'"' {
+            return Some(pos);
+        }
+        // Skip the backslash and the character it escapes.
+        pos += 1 + input[pos + 1..].chars().next()?.len_utf8();
+    }
+}
+
 /// Parse the param value - a string delimited by '"' - '\' escapes \ and "
 fn param_value(input: &str) -> IResult<&str, &str> {
-    alt((
-        // We need to handle an empty string separately since `escaped`
-        // doesn't work unless it has some input.
-        map(tag(r#""""#), |_| ""),
-        delimited(
-            tag("\""),
-            escaped(take_while1(|c: char| c != '\\' && c != '"'), '\\', anychar),
-            tag("\""),
-        ),
-    ))
-    .parse(input)
+    let fail = || nom::Err::Error(error::Error::new(input, error::ErrorKind::Escaped));
+    let value = input.strip_prefix('"').ok_or_else(fail)?;
+    let len = escaped_len(value).ok_or_else(fail)?;
+    Ok((&value[len + 1..], &value[..len]))
+}
+
+/// Parse the param name, everything up to a '=' or ']'.
+fn param_name(input: &str) -> IResult<&str, &str> {
+    let len = memchr2(b'=', b']', input.as_bytes()).unwrap_or(input.len());
+    if len == 0 {
+        Err(nom::Err::Error(error::Error::new(
+            input,
+            error::ErrorKind::TakeTill1,
+        )))
+    } else {
+        Ok((&input[len..], &input[..len]))
+    }
 }
 
 /// Parse a param name="value"
 fn param(input: &str) -> IResult<&str, (&str, &str)> {
-    separated_pair(
-        take_till1(|c: char| c == ']' || c == '='),
-        terminated(tag("="), space0),
-        param_value,
-    )
-    .parse(input)
+    separated_pair(param_name, terminated(tag("="), space0), param_value).parse(input)
+}
+
+/// Parse the space separated params of an element, gathering them in a [`ParamsBuf`].
+fn params(input: &str) -> IResult<&str, Vec<(&str, &str)>> {
+    let mut params = ParamsBuf::new();
+    let mut input = match param(input) {
+        Ok((rest, first)) => {
+            params.push(first);
+            rest
+        }
+        Err(nom::Err::Error(_)) => return Ok((input, params.into_iter().collect())),
+        Err(err) => return Err(err),
+    };
+
+    loop {
+        match preceded(tag(" "), param).parse(input) {
+            Ok((rest, next)) => {
+                params.push(next);
+                input = rest;
+            }
+            Err(nom::Err::Error(_)) => return Ok((input, params.into_iter().collect())),
+            Err(err) => return Err(err),
+        }
+    }
 }
 
 struct StructuredDatumParser {
@@ -156,7 +280,7 @@ impl StructuredDatumParser {
                 (
                     take_till1(|c: char| c.is_whitespace() || c == ']' || c == '='),
                     space0,
-                    separated_list0(tag(" "), param),
+                    params,
                 ),
                 |(id, _, params)| Some(StructuredElement { id, params }),
             ),
@@ -173,7 +297,18 @@ impl StructuredDatumParser {
         alt((
             |input| self.structured_datum_strict(input),
             // If the element fails to parse, just parse it and return None.
-            delimited(tag("["), map(take_until("]"), |_| None), tag("]")),
+            delimited(
+                tag("["),
+                map(take_until("]"), |_raw| {
+                    trace_parse!(
+                        debug,
+                        raw = _raw,
+                        "dropping invalid structured data element"
+                    );
+                    None
+                }),
+                tag("]"),
+            ),
         ))
         .parse(input)
     }
@@ -217,17 +352,24 @@ fn parse_structured_data(
     allow_empty: bool,
     input: &str,
 ) -> IResult<&str, Vec<StructuredElement<&str>>> {
+    #[cfg(feature = "tracing")]
+    let _span = tracing::trace_span!(target: "syslog_loose", "structured_data").entered();
     alt((
         map(tag("-"), |_| vec![]),
-        map(
-            many1(|input| {
+        // Fold straight into the one Vec, skipping the elements that failed.
+        fold_many1(
+            |input| {
                 StructuredDatumParser {
                     allow_failure,
                     allow_empty,
                 }
                 .parse(input)
-            }),
-            |items| items.iter().filter_map(|item| item.clone()).collect(),
+            },
+            Vec::new,
+            |mut elements, element| {
+                elements.extend(element);
+                elements
+            },
         ),
     ))
     .parse(input)
@@ -238,6 +380,48 @@ pub(crate) fn structured_data(input: &str) -> IResult<&str, Vec<StructuredElemen
     parse_structured_data(true, true, input)
 }
 
+/// The raw text of the elements at the start of `input` that [`structured_data`]
+/// drops because they aren't valid.
+pub(crate) fn invalid_elements(mut input: &str) -> Vec<&str> {
+    let parser = StructuredDatumParser {
+        allow_failure: true,
+        allow_empty: true,
+    };
+    let mut invalid = Vec::new();
+    while let Ok((remaining, element)) = parser.structured_datum_permissive(input) {
+        if element.is_none() {
+            invalid.push(&input[..input.len() - remaining.len()]);
+        }
+        input = remaining;
+    }
+    invalid
+}
+
+/// The rest of `input` from the first of the elements at its start that is never
+/// closed, either by a `]` or by the closing quote of a param value, which suggests
+/// the message was cut short.
+pub(crate) fn unterminated_element(mut input: &str) -> Option<&str> {
+    while input.starts_with('[') {
+        let mut quoted = false;
+        let mut escaped = false;
+        let end = input.bytes().position(|byte| {
+            match byte {
+                _ if escaped => escaped = false,
+                b'\\' if quoted => escaped = true,
+                b'"' => quoted = !quoted,
+                b']' if !quoted => return true,
+                _ => {}
+            }
+            false
+        });
+        match end {
+            Some(end) => input = &input[end + 1..],
+            None => return Some(input),
+        }
+    }
+    None
+}
+
 /// Parse multiple structured data elements.
 pub(crate) fn structured_data_optional(input: &str) -> IResult<&str, Vec<StructuredElement<&str>>> {
     parse_structured_data(false, false, input)
@@ -247,6 +431,22 @@ pub(crate) fn structured_data_optional(input: &str) -> IResult<&str, Vec<Structu
 mod tests {
     use super::*;
 
+    #[test]
+    fn finds_unterminated_elements() {
+        assert_eq!(unterminated_element(r#"[a b="c"] msg"#), None);
+        assert_eq!(unterminated_element(r#"[a b="c]\"]"] msg"#), None);
+        assert_eq!(
+            unterminated_element(r#"[a b="c"][d e="f"#),
+            Some(r#"[d e="f"#)
+        );
+        assert_eq!(
+            unterminated_element(r#"[a b="c][d e="f"]"#),
+            Some(r#"[a b="c][d e="f"]"#)
+        );
+        assert_eq!(unterminated_element("[a b"), Some("[a b"));
+        assert_eq!(unterminated_element("msg [a"), None);
+    }
+
     #[test]
     fn parse_param_value() {
         assert_eq!(
@@ -430,6 +630,22 @@ bye"#
         );
     }
 
+    #[test]
+    fn params_unescaped_into_buffer() {
+        let data = structured_data(r#"[id aa="hullo \"there\"" bb="a\]b"]"#).unwrap();
+        let element = &data.1[0];
+
+        let mut buf = String::new();
+        assert!(element.write_param_unescaped("bb", &mut buf));
+        assert_eq!(buf, "a]b");
+        assert!(!element.write_param_unescaped("cc", &mut buf));
+
+        let mut params = element.params_unescaped(&mut buf);
+        assert_eq!(params.next_param(), Some((&"aa", r#"hullo "there""#)));
+        assert_eq!(params.next_param(), Some((&"bb", "a]b")));
+        assert_eq!(params.next_param(), None);
+    }
+
     #[test]
     fn sd_param_escapes() {
         let (_, value) = param_value(r#""Here are some escaped characters -> \"\\\]""#).unwrap();
@@ -0,0 +1,385 @@
+//! `quickcheck` generators for messages, so code handling them can be property
+//! tested. Generated messages print as text that parses back to the same message.
+use crate::{
+    message::{Message, Protocol},
+    pri::decompose_pri,
+    procid::ProcId,
+    structured_data::StructuredElement,
+};
+use chrono::prelude::*;
+use quickcheck::{Arbitrary, Gen};
+use std::{
+    net::{Ipv4Addr, Ipv6Addr},
+    num::NonZeroU8,
+};
+
+fn gen_string<F>(g: &mut Gen, valid_char: F) -> String
+where
+    F: Fn(char) -> bool,
+{
+    // Limit the size to a u8 to avoid quickcheck trying to make truly huge
+    // allocations.
+    let size = NonZeroU8::arbitrary(g).get() as usize;
+
+    let mut s = String::with_capacity(size);
+    while s.chars().count() < size {
+        let c = char::arbitrary(g);
+        if valid_char(c) {
+            s.push(c);
+        }
+    }
+    s
+}
+
+/// Shrink `s` keeping only the characters still valid for the field.
+fn shrink_string<F>(s: &str, valid_char: F) -> Box<dyn Iterator<Item = String>>
+where
+    F: Fn(char) -> bool + 'static,
+{
+    Box::new(
+        s.to_string()
+            .shrink()
+            .map(move |shrunk| {
+                shrunk
+                    .chars()
+                    .filter(|c| valid_char(*c))
+                    .collect::<String>()
+            })
+            .filter(|shrunk| shrunk.len() > 5),
+    )
+}
+
+fn printable(c: char) -> bool {
+    !c.is_whitespace() && !c.is_control() && c.is_ascii()
+}
+
+/// Structured data names can't contain `]`, `=` or whitespace.
+fn name_char(c: char) -> bool {
+    printable(c) && c != ']' && c != '=' && c != '-'
+}
+
+/// Param values would need `]`, `"` and `\` escaping, so leave them out.
+fn value_char(c: char) -> bool {
+    printable(c) && c != ']' && c != '"' && c != '\\' && c != '-'
+}
+
+/// RFC3164 appnames can't contain a `[`, which starts the procid.
+fn appname_char(c: char) -> bool {
+    printable(c) && c != '[' && c != ':' && c != '-'
+}
+
+fn hostname_char(c: char) -> bool {
+    printable(c) && c != '[' && c != ':'
+}
+
+fn procid_char(c: char) -> bool {
+    printable(c) && c != ']' && c != ':' && c != '-'
+}
+
+/// Header fields can't contain a `:`, which is a sign the message is about to start.
+fn header_char(c: char) -> bool {
+    printable(c) && c != ':' && c != '-'
+}
+
+/// A string that shrinks keeping only the characters valid for its field.
+#[derive(Clone, Debug)]
+struct Field {
+    value: String,
+    valid_char: fn(char) -> bool,
+}
+
+impl Field {
+    fn new(value: String, valid_char: fn(char) -> bool) -> Self {
+        Field { value, valid_char }
+    }
+}
+
+impl Arbitrary for Field {
+    fn arbitrary(g: &mut Gen) -> Self {
+        Field::new(gen_string(g, printable), printable)
+    }
+
+    fn shrink(&self) -> Box<dyn Iterator<Item = Self>> {
+        let valid_char = self.valid_char;
+        Box::new(
+            shrink_string(&self.value, valid_char).map(move |value| Field::new(value, valid_char)),
+        )
+    }
+}
+
+fn gen_field<F>(g: &mut Gen, valid_char: F) -> Option<String>
+where
+    F: Fn(char) -> bool,
+{
+    if bool::arbitrary(g) {
+        Some(gen_string(g, valid_char))
+    } else {
+        None
+    }
+}
+
+impl Arbitrary for ProcId<String> {
+    fn arbitrary(g: &mut Gen) -> Self {
+        if bool::arbitrary(g) {
+            return ProcId::PID(i32::arbitrary(g));
+        }
+        loop {
+            // A name that is all digits would be parsed as a pid.
+            let name = gen_string(g, procid_char);
+            if name.parse::<i32>().is_err() {
+                break ProcId::Name(name);
+            }
+        }
+    }
+
+    fn shrink(&self) -> Box<dyn Iterator<Item = Self>> {
+        match self {
+            ProcId::PID(pid) => Box::new(pid.shrink().map(ProcId::PID)),
+            ProcId::Name(name) => Box::new(
+                shrink_string(name, procid_char)
+                    .filter(|name| name.parse::<i32>().is_err())
+                    .map(ProcId::Name),
+            ),
+        }
+    }
+}
+
+impl Arbitrary for StructuredElement<String> {
+    fn arbitrary(g: &mut Gen) -> Self {
+        let len = usize::arbitrary(g) % 4;
+        StructuredElement {
+            id: gen_string(g, name_char),
+            params: (0..len)
+                .map(|_| (gen_string(g, name_char), gen_string(g, value_char)))
+                .collect(),
+        }
+    }
+
+    fn shrink(&self) -> Box<dyn Iterator<Item = Self>> {
+        let params: Vec<(Field, Field)> = self
+            .params
+            .iter()
+            .map(|(name, value)| {
+                (
+                    Field::new(name.clone(), name_char),
+                    Field::new(value.clone(), value_char),
+                )
+            })
+            .collect();
+
+        Box::new(
+            (Field::new(self.id.clone(), name_char), params)
+                .shrink()
+                .map(|(id, params)| StructuredElement {
+                    id: id.value,
+                    params: params
+                        .into_iter()
+                        .map(|(name, value)| (name.value, value.value))
+                        .collect(),
+                }),
+        )
+    }
+}
+
+/// Generate a message of the given protocol.
+fn message(g: &mut Gen, protocol: Protocol) -> Message<String> {
+    // RFC 5424 defines the priority as being a byte sized numeric from 0 to
+    // 191 inclusive. Modulo will bias the resulting priority toward the
+    // first 65 priorities.
+    let (facility, severity) = decompose_pri(u8::arbitrary(g) % 192);
+    let mut structured_data: Vec<StructuredElement<String>> = Arbitrary::arbitrary(g);
+
+    let (appname, procid, msgid) = match protocol {
+        Protocol::RFC3164 => {
+            // 3164 can't take empty structured data elements.
+            structured_data.retain(|element| !element.params.is_empty());
+            // Nor a procid without an appname, or a msgid.
+            let appname = gen_field(g, appname_char);
+            let procid = appname.as_ref().map(|_| ProcId::arbitrary(g));
+            (appname, procid, None)
+        }
+        _ => (
+            gen_field(g, header_char),
+            Arbitrary::arbitrary(g),
+            gen_field(g, header_char),
+        ),
+    };
+
+    let hostname = match u8::arbitrary(g) % 3 {
+        // A hostname of `-` is parsed as no hostname.
+        0 => gen_field(g, hostname_char).filter(|hostname| hostname != "-"),
+        1 => Some(Ipv4Addr::arbitrary(g).to_string()),
+        _ => Some(Ipv6Addr::arbitrary(g).to_string()),
+    };
+
+    // Limit the seconds to an i32 so the timestamp is always in range.
+    let secs = i32::arbitrary(g);
+    let msg = String::arbitrary(g);
+    Message {
+        facility,
+        severity,
+        timestamp: Some(Utc.timestamp_opt(secs.into(), 0).unwrap().into()),
+        hostname,
+        appname,
+        procid,
+        msgid,
+        protocol,
+        structured_data,
+        msg: msg.trim().into(),
+    }
+}
+
+/// Generates RFC3164 and RFC5424 messages, shrinking the fields while keeping
+/// the message valid.
+impl Arbitrary for Message<String> {
+    fn arbitrary(g: &mut Gen) -> Self {
+        let protocol = if bool::arbitrary(g) {
+            Protocol::RFC3164
+        } else {
+            Protocol::RFC5424(1)
+        };
+        message(g, protocol)
+    }
+
+    fn shrink(&self) -> Box<dyn Iterator<Item = Self>> {
+        let message = self.clone();
+        let appname_valid = match self.protocol {
+            Protocol::RFC3164 => appname_char,
+            _ => header_char,
+        };
+        let field = |value: &Option<String>, valid_char| {
+            value.clone().map(|value| Field::new(value, valid_char))
+        };
+
+        Box::new(
+            (
+                field(&self.hostname, hostname_char),
+                field(&self.appname, appname_valid),
+                self.procid.clone(),
+                field(&self.msgid, header_char),
+                self.structured_data.clone(),
+                self.msg.clone(),
+            )
+                .shrink()
+                .map(
+                    move |(hostname, appname, procid, msgid, structured_data, msg)| {
+                        let structured_data = match message.protocol {
+                            // 3164 can't take empty structured data elements.
+                            Protocol::RFC3164 => structured_data
+                                .into_iter()
+                                .filter(|element| !element.params.is_empty())
+                                .collect(),
+                            _ => structured_data,
+                        };
+
+                        Message {
+                            hostname: hostname.map(|hostname| hostname.value),
+                            // 3164 can't have a procid without an appname.
+                            procid: procid.filter(|_| {
+                                appname.is_some() || message.protocol != Protocol::RFC3164
+                            }),
+                            appname: appname.map(|appname| appname.value),
+                            msgid: msgid.map(|msgid| msgid.value),
+                            structured_data,
+                            msg: msg.trim().into(),
+                            ..message.clone()
+                        }
+                    },
+                ),
+        )
+    }
+}
+
+/// The text of a valid RFC5424 message.
+#[derive(Clone, Debug)]
+pub struct Rfc5424Text(pub String);
+
+impl Arbitrary for Rfc5424Text {
+    fn arbitrary(g: &mut Gen) -> Self {
+        Rfc5424Text(message(g, Protocol::RFC5424(1)).to_string())
+    }
+}
+
+/// The text of a valid RFC3164 message.
+#[derive(Clone, Debug)]
+pub struct Rfc3164Text(pub String);
+
+impl Arbitrary for Rfc3164Text {
+    fn arbitrary(g: &mut Gen) -> Self {
+        Rfc3164Text(message(g, Protocol::RFC3164).to_string())
+    }
+}
+
+/// The text of a message broken in one of the ways seen from real senders: cut
+/// short, with a missing or invalid priority, a mangled timestamp, an unclosed
+/// structured data element, or just arbitrary text.
+#[derive(Clone, Debug)]
+pub struct MalformedText(pub String);
+
+impl Arbitrary for MalformedText {
+    fn arbitrary(g: &mut Gen) -> Self {
+        let text = Message::<String>::arbitrary(g).to_string();
+        let at = |g: &mut Gen, text: &str| {
+            let at = usize::arbitrary(g) % (text.len() + 1);
+            (0..=at)
+                .rev()
+                .find(|at| text.is_char_boundary(*at))
+                .unwrap_or(0)
+        };
+
+        MalformedText(match u8::arbitrary(g) % 6 {
+            0 => {
+                let end = at(g, &text);
+                text[..end].to_string()
+            }
+            1 => text
+                .split_once('>')
+                .map_or("", |(_, rest)| rest)
+                .to_string(),
+            2 => format!(
+                "<{}>{}",
+                u16::arbitrary(g),
+                text.split_once('>').map_or("", |(_, rest)| rest)
+            ),
+            3 => {
+                let start = at(g, &text);
+                let mut text = text;
+                text.insert_str(start, &String::arbitrary(g));
+                text
+            }
+            4 => format!(
+                "<34>1 2003-10-11T22:14:15.003Z host app - - [{} x=\"{}",
+                gen_string(g, name_char),
+                String::arbitrary(g)
+            ),
+            _ => String::arbitrary(g),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ParseOptions, Variant, parse_message, try_parse_message};
+    use quickcheck::QuickCheck;
+
+    #[test]
+    fn generated_text_parses() {
+        fn rfc5424(text: Rfc5424Text) -> bool {
+            try_parse_message(&text.0, Variant::RFC5424).is_ok()
+        }
+        fn rfc3164(text: Rfc3164Text) -> bool {
+            try_parse_message(&text.0, Variant::RFC3164).is_ok()
+        }
+        fn malformed(text: MalformedText) -> bool {
+            let options = ParseOptions::default();
+            let (message, _) = options.parse_with_diagnostics(&text.0);
+            message == parse_message(&text.0, Variant::Either)
+        }
+
+        let mut check = QuickCheck::new().tests(200);
+        check.quickcheck(rfc5424 as fn(Rfc5424Text) -> bool);
+        check.quickcheck(rfc3164 as fn(Rfc3164Text) -> bool);
+        check.quickcheck(malformed as fn(MalformedText) -> bool);
+    }
+}
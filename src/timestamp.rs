@@ -3,44 +3,101 @@ use chrono::prelude::*;
 use nom::{
     IResult, Parser as _,
     branch::alt,
-    bytes::complete::{tag, take, take_until},
+    bytes::complete::{tag, take, take_until, take_while_m_n},
     character::complete::space1,
-    combinator::{map, map_res, opt},
+    combinator::{eof, map, map_opt, map_res, opt, peek},
     error::{self, ErrorKind},
 };
+use std::cell::RefCell;
+
+/// The text of the last RFC3339 timestamp parsed and the result, so a burst of
+/// messages sent in the same second only parses the timestamp once.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct TimestampCache {
+    entry: Option<(String, DateTime<FixedOffset>)>,
+}
+
+thread_local! {
+    /// The cache lent by [`TimestampCache::scope`], if any.
+    static CACHE: RefCell<Option<TimestampCache>> = const { RefCell::new(None) };
+}
+
+impl TimestampCache {
+    /// Call `f` with this cache used by the timestamp parsers on this thread.
+    pub(crate) fn scope<R>(&mut self, f: impl FnOnce() -> R) -> R {
+        CACHE.with(|cache| *cache.borrow_mut() = Some(std::mem::take(self)));
+        let result = f();
+        *self = CACHE
+            .with(|cache| cache.borrow_mut().take())
+            .unwrap_or_default();
+        result
+    }
+}
+
+/// Parse an RFC3339 timestamp, reusing the last result if there is a cache in
+/// scope and the text is the same.
+fn parse_rfc3339(raw: &str) -> chrono::ParseResult<DateTime<FixedOffset>> {
+    CACHE.with(|cache| match cache.borrow_mut().as_mut() {
+        None => DateTime::parse_from_rfc3339(raw),
+        Some(cache) => match &mut cache.entry {
+            Some((text, timestamp)) if text == raw => Ok(*timestamp),
+            entry => {
+                let timestamp = DateTime::parse_from_rfc3339(raw)?;
+                match entry {
+                    Some((text, cached)) => {
+                        text.clear();
+                        text.push_str(raw);
+                        *cached = timestamp;
+                    }
+                    None => *entry = Some((raw.to_string(), timestamp)),
+                }
+                Ok(timestamp)
+            }
+        },
+    })
+}
 
 /// The timestamp for 5424 messages yyyy-mm-ddThh:mm:ss.mmmmZ
 pub(crate) fn timestamp_3339(input: &str) -> IResult<&str, DateTime<FixedOffset>> {
-    map_res(take_until(" "), chrono::DateTime::parse_from_rfc3339).parse(input)
+    map_res(take_until(" "), parse_rfc3339).parse(input)
 }
 
 /// An incomplete date is a tuple of (month, date, hour, minutes, seconds)
 pub type IncompleteDate = (u32, u32, u32, u32, u32);
 
 /// The month as a three letter string. Returns the number.
-fn parse_month(s: &str) -> Result<u32, String> {
-    match s.to_lowercase().as_ref() {
-        "jan" => Ok(1),
-        "feb" => Ok(2),
-        "mar" => Ok(3),
-        "apr" => Ok(4),
-        "may" => Ok(5),
-        "jun" => Ok(6),
-        "jul" => Ok(7),
-        "aug" => Ok(8),
-        "sep" => Ok(9),
-        "oct" => Ok(10),
-        "nov" => Ok(11),
-        "dec" => Ok(12),
-        _ => Err(format!("Invalid month {}", s)),
-    }
+fn parse_month(s: &str) -> Option<u32> {
+    let &[a, b, c] = s.as_bytes() else {
+        return None;
+    };
+    Some(
+        match [
+            a.to_ascii_lowercase(),
+            b.to_ascii_lowercase(),
+            c.to_ascii_lowercase(),
+        ] {
+            [b'j', b'a', b'n'] => 1,
+            [b'f', b'e', b'b'] => 2,
+            [b'm', b'a', b'r'] => 3,
+            [b'a', b'p', b'r'] => 4,
+            [b'm', b'a', b'y'] => 5,
+            [b'j', b'u', b'n'] => 6,
+            [b'j', b'u', b'l'] => 7,
+            [b'a', b'u', b'g'] => 8,
+            [b's', b'e', b'p'] => 9,
+            [b'o', b'c', b't'] => 10,
+            [b'n', b'o', b'v'] => 11,
+            [b'd', b'e', b'c'] => 12,
+            _ => return None,
+        },
+    )
 }
 
 /// The timestamp for 3164 messages. MMM DD HH:MM:SS
 fn timestamp_3164_no_year(input: &str) -> IResult<&str, IncompleteDate> {
     map(
         (
-            map_res(take(3_usize), parse_month),
+            map_opt(take(3_usize), parse_month),
             space1,
             digits,
             space1,
@@ -60,7 +117,7 @@ fn timestamp_3164_no_year(input: &str) -> IResult<&str, IncompleteDate> {
 fn timestamp_3164_with_year(input: &str) -> IResult<&str, NaiveDateTime> {
     map_res(
         (
-            map_res(take(3_usize), parse_month),
+            map_opt(take(3_usize), parse_month),
             space1,
             digits,
             space1,
@@ -83,6 +140,30 @@ fn timestamp_3164_with_year(input: &str) -> IResult<&str, NaiveDateTime> {
     .parse(input)
 }
 
+/// Timestamp with the year following the time, as sent by Aruba switches.
+/// MMM DD HH:MM:SS YYYY
+fn timestamp_3164_year_after_time(input: &str) -> IResult<&str, NaiveDateTime> {
+    map_res(
+        (
+            timestamp_3164_no_year,
+            space1,
+            map_res(
+                take_while_m_n(4, 4, |c: char| c.is_ascii_digit()),
+                str::parse::<i32>,
+            ),
+            // Make sure this is the whole of the year and not the start of the hostname.
+            peek(alt((space1, tag(":"), eof))),
+        ),
+        |((month, date, hour, minute, seconds), _, year, _)| {
+            NaiveDate::from_ymd_opt(year, month, date)
+                .ok_or_else(|| error::Error::new(input, ErrorKind::Fail))?
+                .and_hms_opt(hour, minute, seconds)
+                .ok_or_else(|| error::Error::new(input, ErrorKind::Fail))
+        },
+    )
+    .parse(input)
+}
+
 /// Makes a timestamp given all the fields of the date less the year
 /// and a function to resolve the year.
 fn make_timestamp<F, Tz: TimeZone>(
@@ -125,24 +206,47 @@ pub(crate) fn timestamp_3164<F, Tz: TimeZone + Copy>(
     get_year: F,
     tz: Option<Tz>,
 ) -> impl Fn(&str) -> IResult<&str, DateTime<FixedOffset>>
+where
+    F: FnOnce(IncompleteDate) -> i32 + Copy,
+{
+    timestamp_3164_with(get_year, tz, false)
+}
+
+/// Like [`timestamp_3164`], also taking MMM DD HH:MM:SS YYYY if `year_after_time`
+/// is set. This is only done when asked for, as otherwise a hostname made up of
+/// four digits would be taken as the year.
+pub(crate) fn timestamp_3164_with<F, Tz: TimeZone + Copy>(
+    get_year: F,
+    tz: Option<Tz>,
+    year_after_time: bool,
+) -> impl Fn(&str) -> IResult<&str, DateTime<FixedOffset>>
 where
     F: FnOnce(IncompleteDate) -> i32 + Copy,
 {
     move |input| {
         alt((
+            map(
+                |input| {
+                    if year_after_time {
+                        alt((timestamp_3164_year_after_time, timestamp_3164_with_year)).parse(input)
+                    } else {
+                        timestamp_3164_with_year(input)
+                    }
+                },
+                |naive_date| match tz {
+                    Some(tz) => {
+                        let offset = tz.offset_from_utc_datetime(&naive_date).fix();
+                        DateTime::<FixedOffset>::from_naive_utc_and_offset(naive_date, offset)
+                    }
+                    None => match Local.from_local_datetime(&naive_date).earliest() {
+                        Some(timestamp) => timestamp.into(),
+                        None => Local.from_utc_datetime(&naive_date).into(),
+                    },
+                },
+            ),
             map_res(timestamp_3164_no_year, |ts| {
                 make_timestamp::<_, Tz>(ts, get_year, tz).ok_or("invalid date")
             }),
-            map(timestamp_3164_with_year, |naive_date| match tz {
-                Some(tz) => {
-                    let offset = tz.offset_from_utc_datetime(&naive_date).fix();
-                    DateTime::<FixedOffset>::from_naive_utc_and_offset(naive_date, offset)
-                }
-                None => match Local.from_local_datetime(&naive_date).earliest() {
-                    Some(timestamp) => timestamp.into(),
-                    None => Local.from_utc_datetime(&naive_date).into(),
-                },
-            }),
             timestamp_3339,
         ))
         .parse(input)
@@ -155,6 +259,32 @@ mod tests {
 
     use super::*;
 
+    #[test]
+    fn cache_timestamp() {
+        let mut cache = TimestampCache::default();
+        let first = cache.scope(|| timestamp_3339("2003-10-11T22:14:15.003Z ").unwrap());
+        assert_eq!(
+            cache.entry,
+            Some(("2003-10-11T22:14:15.003Z".to_string(), first.1))
+        );
+
+        let second = cache.scope(|| timestamp_3339("2003-10-11T22:14:15.003Z x").unwrap());
+        assert_eq!(second, (" x", first.1));
+
+        let third = cache.scope(|| timestamp_3339("2003-10-11T22:14:16Z ").unwrap());
+        assert_eq!(third.1 - first.1, chrono::Duration::milliseconds(997));
+        assert_eq!(cache.entry.unwrap().0, "2003-10-11T22:14:16Z");
+    }
+
+    #[test]
+    fn parse_month_names() {
+        assert_eq!(parse_month("Jan"), Some(1));
+        assert_eq!(parse_month("SEP"), Some(9));
+        assert_eq!(parse_month("dec"), Some(12));
+        assert_eq!(parse_month("Foo"), None);
+        assert_eq!(parse_month("Ja"), None);
+    }
+
     #[test]
     fn parse_timestamp_3339() {
         assert_eq!(
@@ -224,6 +354,37 @@ mod tests {
         );
     }
 
+    #[test]
+    fn parse_timestamp_year_after_time_3164() {
+        assert_eq!(
+            timestamp_3164_with(|_| 2019, Some(Utc.fix()), true)("Dec 28 16:49:07 2008 switch1")
+                .unwrap(),
+            (
+                " switch1",
+                FixedOffset::west_opt(0)
+                    .unwrap()
+                    .with_ymd_and_hms(2008, 12, 28, 16, 49, 7)
+                    .unwrap()
+            )
+        );
+
+        // A hostname starting with digits isn't taken to be the year.
+        assert_eq!(
+            timestamp_3164_with(|_| 2019, Some(Utc.fix()), true)("Dec 28 16:49:07 20080 ")
+                .unwrap()
+                .0,
+            " 20080 "
+        );
+
+        // Without asking for it, the digits are left for the hostname.
+        assert_eq!(
+            timestamp_3164(|_| 2019, Some(Utc.fix()))("Dec 28 16:49:07 2008 switch1")
+                .unwrap()
+                .0,
+            " 2008 switch1"
+        );
+    }
+
     #[test]
     fn parse_timestamp_no_year_3164_local_time() {
         let offset = Local
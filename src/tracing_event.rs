@@ -0,0 +1,189 @@
+//! Re-emitting messages as [`tracing`] events.
+use crate::{levels::SeverityMap, message::Message, pri::SyslogSeverity};
+
+/// The `tracing` level for a syslog severity. `tracing` has no level above error,
+/// so `emerg`, `alert` and `crit` are errors too, and `notice` is info. Use a
+/// [`SeverityMap`] for a different mapping.
+pub fn tracing_level(severity: SyslogSeverity) -> tracing::Level {
+    match severity {
+        SyslogSeverity::SEV_EMERG
+        | SyslogSeverity::SEV_ALERT
+        | SyslogSeverity::SEV_CRIT
+        | SyslogSeverity::SEV_ERR => tracing::Level::ERROR,
+        SyslogSeverity::SEV_WARNING => tracing::Level::WARN,
+        SyslogSeverity::SEV_NOTICE | SyslogSeverity::SEV_INFO => tracing::Level::INFO,
+        SyslogSeverity::SEV_DEBUG => tracing::Level::DEBUG,
+    }
+}
+
+impl From<SyslogSeverity> for tracing::Level {
+    fn from(severity: SyslogSeverity) -> Self {
+        tracing_level(severity)
+    }
+}
+
+impl Default for SeverityMap<tracing::Level> {
+    fn default() -> Self {
+        SeverityMap::from_fn(tracing_level)
+    }
+}
+
+/// Emit an event at the given level. Event levels must be known at compile time,
+/// so we have to match on them.
+macro_rules! event {
+    ($level:expr, $($fields:tt)*) => {
+        match $level {
+            tracing::Level::ERROR => tracing::event!(target: "syslog", tracing::Level::ERROR, $($fields)*),
+            tracing::Level::WARN => tracing::event!(target: "syslog", tracing::Level::WARN, $($fields)*),
+            tracing::Level::INFO => tracing::event!(target: "syslog", tracing::Level::INFO, $($fields)*),
+            tracing::Level::DEBUG => tracing::event!(target: "syslog", tracing::Level::DEBUG, $($fields)*),
+            tracing::Level::TRACE => tracing::event!(target: "syslog", tracing::Level::TRACE, $($fields)*),
+        }
+    };
+}
+
+impl<S: AsRef<str> + Ord + PartialEq + Clone> Message<S> {
+    /// Emit the message as a `tracing` event with the `syslog` target. Field names
+    /// must be known at compile time, so the header fields are separate fields and the
+    /// structured data is a single `structured_data` field in its syslog form.
+    /// Messages without a severity are emitted at info.
+    pub fn emit_tracing(&self) {
+        self.emit_tracing_mapped(&SeverityMap::default())
+    }
+
+    /// Emit the message as a `tracing` event at the level `levels` maps its
+    /// severity to, see [`Message::emit_tracing`].
+    pub fn emit_tracing_mapped(&self, levels: &SeverityMap<tracing::Level>) {
+        let hostname = self.hostname.as_ref().map(AsRef::as_ref);
+        let appname = self.appname.as_ref().map(AsRef::as_ref);
+        let procid = self.procid.as_ref().map(ToString::to_string);
+        let msgid = self.msgid.as_ref().map(AsRef::as_ref);
+        let structured_data = self
+            .structured_data
+            .iter()
+            .map(ToString::to_string)
+            .collect::<String>();
+
+        event!(
+            self.severity
+                .map_or(tracing::Level::INFO, |severity| levels.get(severity)),
+            hostname,
+            appname,
+            procid,
+            msgid,
+            structured_data,
+            "{}",
+            self.msg.as_ref()
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Variant, parse_message};
+    use std::sync::{Arc, Mutex};
+    use tracing::{
+        Event, Metadata, Subscriber,
+        field::{Field, Visit},
+        span,
+    };
+
+    type Fields = Vec<(String, String)>;
+
+    /// Records the level and fields of every event.
+    #[derive(Clone, Default)]
+    struct Capture(Arc<Mutex<Vec<(tracing::Level, Fields)>>>);
+
+    impl Subscriber for Capture {
+        fn enabled(&self, _: &Metadata<'_>) -> bool {
+            true
+        }
+
+        fn new_span(&self, _: &span::Attributes<'_>) -> span::Id {
+            span::Id::from_u64(1)
+        }
+
+        fn record(&self, _: &span::Id, _: &span::Record<'_>) {}
+
+        fn record_follows_from(&self, _: &span::Id, _: &span::Id) {}
+
+        fn event(&self, event: &Event<'_>) {
+            struct Collect(Vec<(String, String)>);
+            impl Visit for Collect {
+                fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+                    self.0
+                        .push((field.name().to_string(), format!("{:?}", value)));
+                }
+            }
+
+            let mut collect = Collect(Vec::new());
+            event.record(&mut collect);
+            self.0
+                .lock()
+                .unwrap()
+                .push((*event.metadata().level(), collect.0));
+        }
+
+        fn enter(&self, _: &span::Id) {}
+
+        fn exit(&self, _: &span::Id) {}
+    }
+
+    #[test]
+    fn emit_event() {
+        let capture = Capture::default();
+        let message = parse_message(
+            r#"<164>1 2003-10-11T22:14:15.003Z mymachine app - ID47 [id a="b"] careful"#,
+            Variant::RFC5424,
+        );
+        tracing::subscriber::with_default(capture.clone(), || message.emit_tracing());
+
+        let events = capture.0.lock().unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].0, tracing::Level::WARN);
+        assert_eq!(
+            events[0].1,
+            vec![
+                ("message".to_string(), "careful".to_string()),
+                ("hostname".to_string(), "\"mymachine\"".to_string()),
+                ("appname".to_string(), "\"app\"".to_string()),
+                ("msgid".to_string(), "\"ID47\"".to_string()),
+                (
+                    "structured_data".to_string(),
+                    "\"[id a=\\\"b\\\"]\"".to_string()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn trace_parsing() {
+        let capture = Capture::default();
+        tracing::subscriber::with_default(capture.clone(), || {
+            parse_message(
+                r#"<34>1 2003-10-11T22:14:15+01:00 mymachine su - ID47 [incorrect x] general"#,
+                Variant::Either,
+            );
+            parse_message("junk", Variant::Either);
+        });
+
+        let messages = capture
+            .0
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(_, fields)| fields[0].1.clone())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            messages,
+            vec![
+                "not canonical, taking the general path",
+                "dropping invalid structured data element",
+                "detected variant",
+                "not canonical, taking the general path",
+                "no variant matched",
+                "taking the whole input as the message",
+            ]
+        );
+    }
+}
@@ -0,0 +1,127 @@
+//! Parsing the firewall logs of Ubiquiti EdgeOS and UniFi gateways, such as
+//! `[WAN_LOCAL-default-D]IN=eth0 OUT= SRC=1.2.3.4 DST=5.6.7.8 PROTO=TCP DPT=22`.
+use crate::{logfmt::parse_logfmt, message::Message, procid::ProcId};
+use std::{borrow::Cow, collections::BTreeMap};
+
+/// A firewall log from a Ubiquiti gateway.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UbiquitiFirewall<'a> {
+    /// The rule from the leading bracket, such as `WAN_LOCAL-default-D` from EdgeOS
+    /// or `WAN_LOCAL-D-2000` from UniFi.
+    pub rule: &'a str,
+    /// The `KEY=value` pairs following the rule, such as `SRC` and `DPT`.
+    pub fields: BTreeMap<&'a str, Cow<'a, str>>,
+}
+
+impl<'a> UbiquitiFirewall<'a> {
+    /// Parse a body starting with the rule in brackets. Any kernel timestamp before
+    /// the rule, as in `[ 1234.567890] [WAN_LOCAL-default-D]`, is skipped.
+    pub fn parse(input: &'a str) -> Option<Self> {
+        let mut rest = input.trim_start();
+        loop {
+            let (token, after) = rest.strip_prefix('[')?.split_once(']')?;
+            rest = after;
+            if let Some(firewall) = Self::from_parts(token.trim(), after) {
+                return Some(firewall);
+            }
+            // Only a kernel timestamp may come before the rule.
+            if !token
+                .trim()
+                .bytes()
+                .all(|b| b.is_ascii_digit() || b == b'.')
+            {
+                return None;
+            }
+            rest = rest.trim_start();
+        }
+    }
+
+    fn from_parts(rule: &'a str, body: &'a str) -> Option<Self> {
+        let valid = rule.contains('-')
+            && rule
+                .bytes()
+                .all(|b| b.is_ascii_alphanumeric() || b == b'_' || b == b'-');
+        if !valid {
+            return None;
+        }
+
+        let fields = parse_logfmt(body);
+        if fields.is_empty() {
+            return None;
+        }
+        Some(UbiquitiFirewall { rule, fields })
+    }
+
+    /// The value of the field, if present.
+    pub fn field(&self, key: &str) -> Option<&str> {
+        self.fields.get(key).map(|value| value.as_ref())
+    }
+
+    /// `accept`, `drop` or `reject`, from the `A`, `D` or `R` part of the rule name.
+    pub fn action(&self) -> Option<&'static str> {
+        self.rule.split('-').skip(1).find_map(|part| match part {
+            "A" => Some("accept"),
+            "D" => Some("drop"),
+            "R" => Some("reject"),
+            _ => None,
+        })
+    }
+}
+
+impl<S: AsRef<str> + Ord + PartialEq + Clone> Message<S> {
+    /// Parse the body of the message as a Ubiquiti firewall log.
+    ///
+    /// When the rule directly follows the hostname, RFC3164 parsing takes it to be
+    /// the procid of an empty appname, so this is also checked.
+    pub fn ubiquiti_firewall(&self) -> Option<UbiquitiFirewall<'_>> {
+        match (&self.appname, &self.procid) {
+            (Some(appname), Some(ProcId::Name(rule))) if appname.as_ref().is_empty() => {
+                UbiquitiFirewall::from_parts(rule.as_ref(), self.msg.as_ref())
+            }
+            _ => UbiquitiFirewall::parse(self.msg.as_ref()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Variant, parse_message};
+
+    #[test]
+    fn parse_edgeos() {
+        let message = parse_message(
+            "<4>Oct 11 22:14:15 USG kernel: [ 1234.567890] [WAN_LOCAL-default-D]IN=eth0 OUT= MAC=00:11 SRC=1.2.3.4 DST=5.6.7.8 LEN=40 PROTO=TCP SPT=1234 DPT=22 SYN URGP=0",
+            Variant::RFC3164,
+        );
+        let firewall = message.ubiquiti_firewall().unwrap();
+
+        assert_eq!(firewall.rule, "WAN_LOCAL-default-D");
+        assert_eq!(firewall.action(), Some("drop"));
+        assert_eq!(firewall.field("IN"), Some("eth0"));
+        assert_eq!(firewall.field("OUT"), Some(""));
+        assert_eq!(firewall.field("SRC"), Some("1.2.3.4"));
+        assert_eq!(firewall.field("DPT"), Some("22"));
+    }
+
+    #[test]
+    fn parse_unifi() {
+        let message = parse_message(
+            r#"<4>Oct 11 22:14:15 UDM [WAN_LOCAL-A-2000] DESCR="Allow established" IN=eth8 OUT= SRC=1.2.3.4 DST=5.6.7.8 PROTO=UDP SPT=1 DPT=2"#,
+            Variant::RFC3164,
+        );
+        let firewall = message.ubiquiti_firewall().unwrap();
+
+        assert_eq!(firewall.rule, "WAN_LOCAL-A-2000");
+        assert_eq!(firewall.action(), Some("accept"));
+        assert_eq!(firewall.field("DESCR"), Some("Allow established"));
+        assert_eq!(firewall.field("PROTO"), Some("UDP"));
+    }
+
+    #[test]
+    fn not_ubiquiti() {
+        assert_eq!(UbiquitiFirewall::parse("[info] started"), None);
+        assert_eq!(UbiquitiFirewall::parse("[WAN-IN] nothing here"), None);
+        assert_eq!(UbiquitiFirewall::parse("IN=eth0 OUT="), None);
+    }
+}
@@ -0,0 +1,247 @@
+//! Setters that check values against RFC5424, so messages built in code can't
+//! break the format without anyone noticing.
+use crate::{
+    error::{Field, InvalidField, Rule},
+    message::Message,
+    procid::ProcId,
+    structured_data::StructuredElement,
+};
+
+/// Check a header field is 1 to `max` printable ASCII characters, and isn't the
+/// `-` used for an empty field.
+fn header(
+    value: &str,
+    max: usize,
+    field: Field,
+    expected: &'static str,
+    rule: Rule,
+) -> Result<(), InvalidField> {
+    if value.is_empty()
+        || value.len() > max
+        || value == "-"
+        || !value.bytes().all(|b| b.is_ascii_graphic())
+    {
+        return Err(InvalidField {
+            field,
+            expected,
+            rule,
+        });
+    }
+    Ok(())
+}
+
+/// Check a structured data id or param name is 1 to 32 printable ASCII characters
+/// other than `=`, `]` and `"`.
+fn sd_name(name: &str, rule: Rule) -> Result<(), InvalidField> {
+    let valid = |b: u8| b.is_ascii_graphic() && b != b'=' && b != b']' && b != b'"';
+    if name.is_empty() || name.len() > 32 || !name.bytes().all(valid) {
+        return Err(InvalidField {
+            field: Field::StructuredData,
+            expected: "a name of at most 32 printable ASCII characters other than =, ] and \"",
+            rule,
+        });
+    }
+    Ok(())
+}
+
+/// Check that every `"` and `]` in a param value is escaped, and that it doesn't
+/// end with a `\` that would escape the closing quote.
+fn param_value(value: &str) -> Result<(), InvalidField> {
+    let invalid = || InvalidField {
+        field: Field::StructuredData,
+        expected: "a value with \", \\ and ] escaped",
+        rule: Rule::rfc5424("SD-PARAM/PARAM-VALUE", "6.3.3"),
+    };
+    let mut escaped = false;
+    for c in value.chars() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            '"' | ']' => return Err(invalid()),
+            _ => {}
+        }
+    }
+    if escaped {
+        return Err(invalid());
+    }
+    Ok(())
+}
+
+fn hostname(hostname: &str) -> Result<(), InvalidField> {
+    header(
+        hostname,
+        255,
+        Field::Hostname,
+        "at most 255 printable ASCII characters",
+        Rule::rfc5424("HEADER/HOSTNAME", "6.2.4"),
+    )
+}
+
+fn appname(appname: &str) -> Result<(), InvalidField> {
+    header(
+        appname,
+        48,
+        Field::Appname,
+        "at most 48 printable ASCII characters",
+        Rule::rfc5424("HEADER/APP-NAME", "6.2.5"),
+    )
+}
+
+fn procid<S: AsRef<str> + Ord + PartialEq + Clone>(procid: &ProcId<S>) -> Result<(), InvalidField> {
+    match procid {
+        ProcId::PID(_) => Ok(()),
+        ProcId::Name(name) => header(
+            name.as_ref(),
+            128,
+            Field::ProcId,
+            "at most 128 printable ASCII characters",
+            Rule::rfc5424("HEADER/PROCID", "6.2.6"),
+        ),
+    }
+}
+
+fn msgid(msgid: &str) -> Result<(), InvalidField> {
+    header(
+        msgid,
+        32,
+        Field::MsgId,
+        "at most 32 printable ASCII characters",
+        Rule::rfc5424("HEADER/MSGID", "6.2.7"),
+    )
+}
+
+fn element<S: AsRef<str> + Ord + Clone>(
+    element: &StructuredElement<S>,
+) -> Result<(), InvalidField> {
+    sd_name(
+        element.id.as_ref(),
+        Rule::rfc5424("SD-ELEMENT/SD-ID", "6.3.2"),
+    )?;
+    for (name, value) in element.params.iter() {
+        sd_name(name.as_ref(), Rule::rfc5424("SD-PARAM/PARAM-NAME", "6.3.3"))?;
+        param_value(value.as_ref())?;
+    }
+    Ok(())
+}
+
+/// Setters for the string fields that only accept values RFC5424 allows, leaving
+/// the message unchanged otherwise. The fields can still be set directly, and
+/// [`Message::validate`] checks a whole message.
+impl<S: AsRef<str> + Ord + PartialEq + Clone> Message<S> {
+    /// Check every field of the message against RFC5424, returning the first that
+    /// breaks it.
+    ///
+    /// ```
+    /// use syslog_loose::{Field, Variant, parse_message};
+    ///
+    /// let mut message = parse_message("<34>Oct 11 22:14:15 mymachine su: one", Variant::RFC3164);
+    /// assert_eq!(message.validate(), Ok(()));
+    /// message.appname = Some("an appname far too long to be sent in an RFC5424 message");
+    /// assert_eq!(message.validate().unwrap_err().field, Field::Appname);
+    /// ```
+    pub fn validate(&self) -> Result<(), InvalidField> {
+        self.hostname
+            .as_ref()
+            .map_or(Ok(()), |field| hostname(field.as_ref()))?;
+        self.appname
+            .as_ref()
+            .map_or(Ok(()), |field| appname(field.as_ref()))?;
+        self.procid.as_ref().map_or(Ok(()), procid)?;
+        self.msgid
+            .as_ref()
+            .map_or(Ok(()), |field| msgid(field.as_ref()))?;
+        self.structured_data.iter().try_for_each(element)
+    }
+
+    pub fn set_hostname(&mut self, value: Option<S>) -> Result<(), InvalidField> {
+        value
+            .as_ref()
+            .map_or(Ok(()), |field| hostname(field.as_ref()))?;
+        self.hostname = value;
+        Ok(())
+    }
+
+    pub fn set_appname(&mut self, value: Option<S>) -> Result<(), InvalidField> {
+        value
+            .as_ref()
+            .map_or(Ok(()), |field| appname(field.as_ref()))?;
+        self.appname = value;
+        Ok(())
+    }
+
+    pub fn set_procid(&mut self, value: Option<ProcId<S>>) -> Result<(), InvalidField> {
+        value.as_ref().map_or(Ok(()), procid)?;
+        self.procid = value;
+        Ok(())
+    }
+
+    pub fn set_msgid(&mut self, value: Option<S>) -> Result<(), InvalidField> {
+        value
+            .as_ref()
+            .map_or(Ok(()), |field| msgid(field.as_ref()))?;
+        self.msgid = value;
+        Ok(())
+    }
+
+    /// Replace the structured data, if every element is valid.
+    pub fn set_structured_data(
+        &mut self,
+        value: Vec<StructuredElement<S>>,
+    ) -> Result<(), InvalidField> {
+        value.iter().try_for_each(element)?;
+        self.structured_data = value;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Variant, parse_message};
+
+    #[test]
+    fn checks_fields() {
+        let mut message = parse_message(
+            r#"<34>1 2003-10-11T22:14:15.003Z mymachine su - ID47 [meta a="x\]"] one"#,
+            Variant::RFC5424,
+        );
+        assert_eq!(message.validate(), Ok(()));
+
+        assert_eq!(
+            message.set_appname(Some(
+                "an appname far too long to be sent in an RFC5424 message"
+            )),
+            Err(InvalidField {
+                field: Field::Appname,
+                expected: "at most 48 printable ASCII characters",
+                rule: Rule::rfc5424("HEADER/APP-NAME", "6.2.5"),
+            })
+        );
+        assert_eq!(message.appname, Some("su"));
+        assert!(message.set_hostname(Some("my machine")).is_err());
+        assert!(message.set_msgid(Some("-")).is_err());
+        assert!(message.set_procid(Some(ProcId::Name("é"))).is_err());
+        assert_eq!(message.set_procid(Some(ProcId::PID(12))), Ok(()));
+        assert_eq!(message.set_hostname(None), Ok(()));
+        assert_eq!(message.hostname, None);
+
+        let element = |id, value| StructuredElement {
+            id,
+            params: std::iter::once(("a", value)).collect(),
+        };
+        assert_eq!(
+            message.set_structured_data(vec![element("ok", r#"\"x\\"#)]),
+            Ok(())
+        );
+        for (id, value) in [("a=b", "x"), ("ok", r#"x"y"#), ("ok", "x]"), ("ok", r"x\")].iter() {
+            assert!(
+                message
+                    .set_structured_data(vec![element(id, value)])
+                    .is_err(),
+                "{} {}",
+                id,
+                value
+            );
+        }
+    }
+}
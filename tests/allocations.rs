@@ -0,0 +1,67 @@
+//! Checks that parsing into a `Message<&str>` only allocates where documented.
+use chrono::FixedOffset;
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    cell::Cell,
+};
+use syslog_loose::{Variant, parse_message_with_year_tz};
+
+struct Counting;
+
+thread_local! {
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.with(|count| count.set(count.get() + 1));
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) }
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: Counting = Counting;
+
+/// The number of allocations made parsing `input` on this thread.
+fn allocations(input: &str) -> usize {
+    let tz = FixedOffset::east_opt(3600);
+    let before = ALLOCATIONS.with(Cell::get);
+    let message = parse_message_with_year_tz(input, |_| 2020, tz, Variant::Either);
+    let after = ALLOCATIONS.with(Cell::get);
+    drop(message);
+    after - before
+}
+
+#[test]
+fn no_allocations_without_structured_data() {
+    for input in [
+        "<34>1 2003-10-11T22:14:15.003Z mymachine su - ID47 - canonical",
+        "<34>1 2003-10-11T22:14:15.003+01:00 mymachine su 123 ID47 - offset",
+        "<34>1 2003-10-11T22:14:15.003Z mymachine su - ID47 [incorrect x] failed element",
+        "<34>Oct 11 22:14:15 mymachine su[12]: no year",
+        "<34>Oct 11 2003 22:14:15 mymachine su[12]: with year",
+        "not syslog at all",
+    ] {
+        assert_eq!(allocations(input), 0, "{}", input);
+    }
+}
+
+#[test]
+fn structured_data_allocations() {
+    // One for the Vec of elements, which has room for both, and one for the
+    // params of each element.
+    assert_eq!(
+        allocations(r#"<34>1 2003-10-11T22:14:15.003Z mymachine su - ID47 [a b="c"] one"#),
+        2
+    );
+    assert_eq!(
+        allocations(
+            r#"<34>1 2003-10-11T22:14:15.003Z mymachine su - ID47 [a b="c"][d e="f" g="h"] two"#
+        ),
+        3
+    );
+}
@@ -31,6 +31,7 @@ pub(crate) trait ArbitraryString {
 macro_rules! arbitrary_string {
     ($name: ident, $filter: expr) => {
         #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+        #[allow(dead_code)]
         pub struct $name(pub String);
 
 	impl From<&str> for $name {
@@ -53,10 +53,7 @@ impl Arbitrary for Wrapper<Message<String>> {
 
 	// 3164 can't take empty structured data elements, so filter them out.
         if protocol == Protocol::RFC3164 {
-            structured_data = structured_data
-                .into_iter()
-                .filter(|element| !element.0.params.is_empty())
-                .collect();
+            structured_data.retain(|element| !element.0.params.is_empty());
         }
 
         let (appname, procid, msgid) = match protocol {
@@ -73,7 +70,7 @@ impl Arbitrary for Wrapper<Message<String>> {
                 };
                 (appname, procid, None)
             }
-            Protocol::RFC5424(_) => (
+            _ => (
                 gen_str::<NoColonString>(g),
                 {
                     let procid: Option<Wrapper<ProcId<String>>> = Arbitrary::arbitrary(g);
@@ -218,7 +215,7 @@ impl Arbitrary for Wrapper<StructuredElement<String>> {
                 element
                     .params
                     .iter()
-                    .map(|(name, value)| ((NameString(name.clone()), ValueString(value.clone()))))
+                    .map(|(name, value)| (NameString(name.clone()), ValueString(value.clone())))
                     .collect(),
             )
                 .shrink()
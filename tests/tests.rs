@@ -1,8 +1,8 @@
 use chrono::{Duration, prelude::*};
 use syslog_loose::{
-    IncompleteDate, Message, ProcId, Protocol, StructuredElement, SyslogFacility, SyslogSeverity,
-    Variant, parse_message, parse_message_with_year, parse_message_with_year_exact,
-    parse_message_with_year_exact_tz,
+    Field, IncompleteDate, Message, ProcId, Protocol, StructuredElement, SyslogFacility,
+    SyslogSeverity, Variant, parse_message, parse_message_with_year, parse_message_with_year_exact,
+    parse_message_with_year_exact_tz, try_parse_message,
 };
 
 fn with_year((month, _date, _hour, _min, _sec): IncompleteDate) -> i32 {
@@ -810,10 +810,22 @@ fn parse_exact_error() {
 
     assert_eq!(
         parse_message_with_year_exact(raw, with_year, Variant::Either),
-        Err("unable to parse input as valid syslog message".to_string())
+        Err("invalid priority at byte 0: expected a priority such as <34>".to_string())
     );
 }
 
+#[test]
+fn try_parse_rejects_wrong_variant() {
+    let raw = "<34>Oct 11 22:14:15 mymachine su: 'su root' failed for lonvick on /dev/pts/8";
+
+    assert!(try_parse_message(raw, Variant::RFC3164).is_ok());
+    assert_eq!(
+        try_parse_message(raw, Variant::RFC5424).unwrap_err().field,
+        Field::Version
+    );
+    assert_eq!(parse_message(raw, Variant::RFC5424).msg, raw);
+}
+
 #[test]
 fn parse_exact_with_tz() {
     let raw = r#"<13>Feb 13 20:07:26 74794bfb6795 root[8539]: i am foobar"#;
@@ -846,6 +858,7 @@ fn parse_invalid_date() {
 }
 
 #[test]
+#[allow(clippy::zero_prefixed_literal)]
 fn parse_vrl() {
     let msg = "<13>Feb 13 20:07:26 74794bfb6795 root[8539]:syslog message";
 
@@ -897,6 +910,23 @@ fn parse_ipv4_hostname() {
     )
 }
 
+#[test]
+fn parse_3164_numeric_hostname() {
+    let message = parse_message_with_year(
+        "<34>Oct 11 22:14:15 2001 su: test",
+        with_year,
+        Variant::RFC3164,
+    );
+    assert_eq!(message.timestamp.unwrap().year(), 2020);
+    assert_eq!(message.hostname, Some("2001"));
+    assert_eq!(message.appname, Some("su"));
+
+    let message = parse_message("<34>Oct 11 22:14:15 1234 su[1]: x", Variant::RFC3164);
+    assert_eq!(message.hostname, Some("1234"));
+    assert_eq!(message.appname, Some("su"));
+    assert_eq!(message.procid, Some(ProcId::PID(1)));
+}
+
 #[test]
 fn parse_ipv6_hostname() {
     let msg = "<34>1 2003-10-11T22:14:15.003Z ::FFFF:129.144.52.38 su - ID47 - bananas and peas";
@@ -982,3 +1012,111 @@ fn parse_5424_f5_logs() {
         }
     );
 }
+
+#[test]
+fn convert_owned_strings() {
+    use std::sync::Arc;
+
+    let msg = r#"<34>1 2003-10-11T22:14:15.003Z mymachine su 2800 ID47 [meta seq="1"] message"#;
+    let message = parse_message(msg, Variant::RFC5424);
+    let expected: Message<String> = message.clone().into();
+
+    let shared: Message<Arc<str>> = message.clone().into();
+    let boxed: Message<Box<str>> = message.clone().into();
+    assert_eq!(shared.clone().into_owned::<String>(), expected);
+    assert_eq!(boxed.into_owned::<String>(), expected);
+    assert_eq!(message.into_owned::<Arc<str>>(), shared);
+    assert_eq!(shared.to_borrowed(), parse_message(msg, Variant::RFC5424));
+}
+
+#[test]
+fn parse_pid() {
+    let message = parse_message("<34>Oct 11 22:14:15 host su[1234]: x", Variant::RFC3164);
+    assert_eq!(message.pid(), Some(1234));
+    let message = parse_message("<34>Oct 11 22:14:15 host su[main]: x", Variant::RFC3164);
+    assert_eq!(message.pid(), None);
+    assert_eq!(message.procid.unwrap().as_name(), Some("main"));
+}
+
+#[test]
+fn parse_vendor_facility() {
+    use std::sync::Arc;
+    use syslog_loose::{MessageFilter, MessageReader, ParseOptions};
+
+    let msg = "<200>1 2003-10-11T22:14:15.003Z mymachine su - ID47 - message";
+    let message = parse_message(msg, Variant::RFC5424);
+    assert_eq!((message.facility, message.severity), (None, None));
+    assert_eq!(message.msg, "message");
+
+    let options = ParseOptions {
+        vendor_facilities: true,
+        ..ParseOptions::new(Variant::RFC5424)
+    };
+    let message = options.parse(msg);
+    assert_eq!(message.facility, Some(SyslogFacility::Unknown(25)));
+    assert_eq!(message.severity, Some(SyslogSeverity::SEV_EMERG));
+    assert!(message.to_string().starts_with("<200>1 "));
+
+    let vendor = MessageFilter::new().with_facilities([SyslogFacility::Unknown(25)]);
+    assert!(vendor.matches_lazy(&options.parse_lazy(msg)));
+    let options = ParseOptions {
+        filter: Some(Arc::new(move |header| vendor.matches_lazy(header))),
+        ..options
+    };
+    let input = format!(
+        "{}\n<34>1 2003-10-11T22:14:15.003Z mymachine su - ID47 - other\n",
+        msg
+    );
+    let messages = MessageReader::new(input.as_bytes(), options.clone())
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    assert_eq!(messages.len(), 1);
+    assert_eq!(messages[0].facility, Some(SyslogFacility::Unknown(25)));
+
+    // Only the header of a parsed message is taken as having a PRI.
+    let unparsed = options.parse("<200>");
+    assert_eq!((unparsed.facility, unparsed.severity), (None, None));
+    assert_eq!(unparsed.msg, "<200>");
+}
+
+#[test]
+fn hash_dedups_messages() {
+    use std::collections::HashSet;
+
+    let messages = [
+        r#"<34>1 2003-10-11T22:14:15.003Z mymachine su - ID47 [meta a="1" b="2"] message"#,
+        r#"<34>1 2003-10-11T22:14:15.003Z mymachine su - ID47 [meta b="2" a="1"] message"#,
+        r#"<34>1 2003-10-11T22:14:15.003Z mymachine su - ID47 [meta a="1"] message"#,
+        r#"<34>1 2003-10-11T22:14:16.003Z mymachine su - ID47 [meta a="1"] message"#,
+        r#"<34>1 2003-10-11T22:14:15.003Z mymachine su - ID47 [meta a="1"][origin ip="::1"] message"#,
+        r#"<34>1 2003-10-11T22:14:15.003Z mymachine su - ID47 [origin ip="::1"][meta a="1"] message"#,
+    ];
+    let unique = messages
+        .iter()
+        .map(|msg| parse_message(msg, Variant::RFC5424))
+        .collect::<HashSet<_>>();
+    assert_eq!(unique.len(), 4);
+}
+
+#[test]
+fn eq_ignores_structured_data_order() {
+    use std::collections::hash_map::DefaultHasher;
+    use std::hash::{Hash, Hasher};
+
+    let hash = |message: &Message<&str>| {
+        let mut hasher = DefaultHasher::new();
+        message.hash(&mut hasher);
+        hasher.finish()
+    };
+
+    let first = parse_message(
+        r#"<34>1 2003-10-11T22:14:15.003Z mymachine su - ID47 [meta a="1" b="2"][origin ip="::1"] message"#,
+        Variant::RFC5424,
+    );
+    let second = parse_message(
+        r#"<34>1 2003-10-11T22:14:15.003Z mymachine su - ID47 [origin ip="::1"][meta b="2" a="1"] message"#,
+        Variant::RFC5424,
+    );
+    assert_eq!(first, second);
+    assert_eq!(hash(&first), hash(&second));
+}